@@ -0,0 +1,6 @@
+// Forwards routine registration to Rust so the linker keeps the static library
+void R_init_grid9_extendr(void *dll);
+
+void R_init_grid9(void *dll) {
+    R_init_grid9_extendr(dll);
+}
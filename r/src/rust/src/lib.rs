@@ -0,0 +1,69 @@
+//! R bindings for Grid9
+//!
+//! Thin extendr wrappers over the column helpers of the Rust core
+//! ([`grid9::encode_columns`], [`grid9::decode_columns`] and
+//! [`grid9::distance_columns`]), so whole R vectors are converted in one
+//! call. Rows that cannot be encoded or decoded become `NA` instead of
+//! failing the call; only vectors of different lengths raise an R error.
+
+use extendr_api::prelude::*;
+
+/// Encodes latitude and longitude vectors to Grid9 codes.
+///
+/// @param lat Numeric vector of latitudes in degrees.
+/// @param lon Numeric vector of longitudes in degrees, the same length as `lat`.
+/// @param human_readable Format codes as XXX-XXX-XXX.
+/// @return Character vector of codes; `NA` for missing or out-of-range rows.
+/// @export
+#[extendr]
+fn grid9_encode(lat: Doubles, lon: Doubles, human_readable: bool) -> Result<Strings> {
+    let latitudes: Vec<f64> = lat.iter().map(|value| value.inner()).collect();
+    let longitudes: Vec<f64> = lon.iter().map(|value| value.inner()).collect();
+
+    let codes = grid9::encode_columns(&latitudes, &longitudes, human_readable).map_err(r_error)?;
+    Ok(Strings::from_values(codes.into_iter().map(|code| code.map_or_else(Rstr::na, Rstr::from))))
+}
+
+/// Decodes Grid9 codes to coordinates.
+///
+/// @param codes Character vector of Grid9 codes, with or without dashes.
+/// @return List with numeric vectors `lat` and `lon`; `NA` for missing or invalid codes.
+/// @export
+#[extendr]
+fn grid9_decode(codes: Strings) -> List {
+    let (lats, lons) = grid9::decode_columns(&optional_strs(&codes));
+    list!(lat = na_doubles(lats), lon = na_doubles(lons))
+}
+
+/// Distances in meters between two vectors of Grid9 codes, row by row.
+///
+/// @param from Character vector of Grid9 codes.
+/// @param to Character vector of Grid9 codes, the same length as `from`.
+/// @return Numeric vector of distances; `NA` where either code is missing or invalid.
+/// @export
+#[extendr]
+fn grid9_distance(from: Strings, to: Strings) -> Result<Doubles> {
+    let meters = grid9::distance_columns(&optional_strs(&from), &optional_strs(&to)).map_err(r_error)?;
+    Ok(na_doubles(meters))
+}
+
+/// Borrows an R character vector, with `NA` as `None`.
+fn optional_strs(strings: &Strings) -> Vec<Option<&str>> {
+    strings.iter().map(|value| (!value.is_na()).then(|| value.as_str())).collect()
+}
+
+/// Converts the `NaN` rows of the column helpers to R's `NA`.
+fn na_doubles(values: Vec<f64>) -> Doubles {
+    Doubles::from_values(values.into_iter().map(|value| if value.is_nan() { Rfloat::na() } else { Rfloat::from(value) }))
+}
+
+fn r_error(error: grid9::Grid9Error) -> Error {
+    Error::Other(error.to_string())
+}
+
+extendr_module! {
+    mod grid9;
+    fn grid9_encode;
+    fn grid9_decode;
+    fn grid9_distance;
+}
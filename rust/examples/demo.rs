@@ -52,6 +52,8 @@ fn main() -> Result<()> {
     let encoded_batch = batch_encode(&coordinates, false)?;
     let decoded_batch = batch_decode(&encoded_batch)?;
     
+    assert_eq!(decoded_batch.len(), coordinates.len());
+    
     println!("Batch encoded {} coordinates:", encoded_batch.len());
     for (i, code) in encoded_batch.iter().enumerate() {
         println!("  {}: {}", test_coords[i].2, code);
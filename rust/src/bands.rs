@@ -0,0 +1,180 @@
+//! Concentric distance bands around a center
+//!
+//! [`distance_bands`] and [`distance_bands_of_points`] sort buckets or
+//! points into rings by great-circle distance from a center (0-1km,
+//! 1-5km, ...) in one pass, for drive-radius style reports such as order
+//! counts by distance from a store.
+
+use crate::uniform_precision_compressor::{haversine_distance, validate_coordinates};
+use crate::{Coordinate, Grid9Bucket, Grid9Error, Grid9Region, Result};
+
+/// One ring of [`distance_bands`] or [`distance_bands_of_points`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DistanceBand {
+    /// Inner radius in meters (inclusive)
+    pub inner_m: f64,
+    /// Outer radius in meters (exclusive)
+    pub outer_m: f64,
+    /// Buckets in the band; for points, the level 0 cells they fall in
+    pub region: Grid9Region,
+    /// Number of buckets or points in the band
+    pub count: usize,
+    /// Mean distance of the band's buckets or points from the center, or
+    /// `None` for an empty band
+    pub mean_distance_m: Option<f64>,
+}
+
+/// Groups the buckets of a region into distance bands by the distance of
+/// each bucket's center from `center`.
+///
+/// # Arguments
+/// * `center` - Center of the rings, e.g. a store
+/// * `region` - Buckets to group
+/// * `band_edges_m` - Outer radii of the bands in meters, strictly
+///   increasing; the first band starts at the center and `f64::INFINITY`
+///   closes a last band holding everything further out
+///
+/// # Returns
+/// One band per edge, innermost first. Buckets beyond the last edge are left
+/// out. Fails with `InvalidInput` for no edges or edges that are not
+/// positive and increasing.
+///
+/// # Example
+/// ```rust
+/// use grid9::{distance_bands, BoundingBox, Coordinate, Grid9Region};
+///
+/// let store = Coordinate::new(51.5074, -0.1278);
+/// let area = Grid9Region::from_bbox(&BoundingBox::new(51.45, 51.55, -0.2, -0.05), 8)?;
+///
+/// let bands = distance_bands(&store, &area, &[1_000.0, 3_000.0, f64::INFINITY])?;
+/// assert_eq!(bands.iter().map(|band| band.count).sum::<usize>(), area.len());
+/// assert!(bands[0].count < bands[1].count);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn distance_bands(center: &Coordinate, region: &Grid9Region, band_edges_m: &[f64]) -> Result<Vec<DistanceBand>> {
+    let mut bands = empty_bands(center, band_edges_m, region.level())?;
+    let mut totals = vec![0.0; bands.len()];
+    for bucket in region.buckets() {
+        let position = bucket.center();
+        let distance = haversine_distance(center.lat, center.lon, position.lat, position.lon);
+        if let Some(index) = band_index(band_edges_m, distance) {
+            bands[index].region.insert(bucket)?;
+            bands[index].count += 1;
+            totals[index] += distance;
+        }
+    }
+    Ok(finish(bands, totals))
+}
+
+/// Groups points into distance bands by their distance from `center`.
+///
+/// Each band's region holds the level 0 cells of its points, and its count
+/// the number of points, so repeated points count each time.
+///
+/// # Arguments
+/// * `center` - Center of the rings
+/// * `points` - Points to group, e.g. delivery addresses
+/// * `band_edges_m` - Outer radii of the bands, as for [`distance_bands`]
+///
+/// # Returns
+/// One band per edge, innermost first, or `InvalidLatitude`/
+/// `InvalidLongitude` for points out of range
+pub fn distance_bands_of_points(center: &Coordinate, points: &[Coordinate], band_edges_m: &[f64]) -> Result<Vec<DistanceBand>> {
+    let mut bands = empty_bands(center, band_edges_m, 0)?;
+    let mut totals = vec![0.0; bands.len()];
+    for point in points {
+        validate_coordinates(point.lat, point.lon)?;
+        let distance = haversine_distance(center.lat, center.lon, point.lat, point.lon);
+        if let Some(index) = band_index(band_edges_m, distance) {
+            bands[index].region.insert(Grid9Bucket::from_coordinates(point.lat, point.lon, 0)?)?;
+            bands[index].count += 1;
+            totals[index] += distance;
+        }
+    }
+    Ok(finish(bands, totals))
+}
+
+/// Validates the center and edges and creates one empty band per edge
+fn empty_bands(center: &Coordinate, band_edges_m: &[f64], level: u8) -> Result<Vec<DistanceBand>> {
+    validate_coordinates(center.lat, center.lon)?;
+    if band_edges_m.is_empty() {
+        return Err(Grid9Error::InvalidInput("at least one band edge is required".into()));
+    }
+    let mut inner_m = 0.0;
+    let mut bands = Vec::with_capacity(band_edges_m.len());
+    for &outer_m in band_edges_m {
+        if outer_m.is_nan() || outer_m <= inner_m {
+            return Err(Grid9Error::InvalidInput(format!("band edges must be positive and increasing, got {}", outer_m).into()));
+        }
+        bands.push(DistanceBand { inner_m, outer_m, region: Grid9Region::new(level)?, count: 0, mean_distance_m: None });
+        inner_m = outer_m;
+    }
+    Ok(bands)
+}
+
+/// Band holding a distance, or `None` beyond the last edge
+fn band_index(band_edges_m: &[f64], distance: f64) -> Option<usize> {
+    let index = band_edges_m.partition_point(|&edge| edge <= distance);
+    (index < band_edges_m.len()).then_some(index)
+}
+
+fn finish(mut bands: Vec<DistanceBand>, totals: Vec<f64>) -> Vec<DistanceBand> {
+    for (band, total) in bands.iter_mut().zip(totals) {
+        band.mean_distance_m = (band.count > 0).then(|| total / band.count as f64);
+    }
+    bands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uniform_precision_compressor::destination_point;
+    use crate::{BoundingBox, Grid9Code};
+
+    #[test]
+    fn test_region_bands() {
+        let center = Coordinate::new(10.5, 10.5);
+        let region = Grid9Region::from_bbox(&BoundingBox::new(10.0, 11.0, 10.0, 11.0), 10).unwrap();
+        let bands = distance_bands(&center, &region, &[10_000.0, 30_000.0]).unwrap();
+
+        assert_eq!((bands[0].inner_m, bands[0].outer_m, bands[1].inner_m), (0.0, 10_000.0, 10_000.0));
+        assert!(bands[0].mean_distance_m.unwrap() < 10_000.0);
+        assert!(bands.iter().map(|band| band.count).sum::<usize>() < region.len()); // Corners are beyond 30km
+        for band in &bands {
+            assert_eq!(band.count, band.region.len());
+            for bucket in band.region.buckets() {
+                let c = bucket.center();
+                let distance = haversine_distance(center.lat, center.lon, c.lat, c.lon);
+                assert!(distance >= band.inner_m && distance < band.outer_m);
+            }
+        }
+        // A ring 10-30km wide holds about eight times the area of the inner disc
+        let ratio = bands[1].count as f64 / bands[0].count as f64;
+        assert!((ratio - 8.0).abs() < 0.5, "{}", ratio);
+    }
+
+    #[test]
+    fn test_point_bands() {
+        let center = Coordinate::new(-33.8688, 151.2093);
+        let at = |meters: f64| {
+            let (lat, lon) = destination_point(center.lat, center.lon, 45.0, meters);
+            Coordinate::new(lat, lon)
+        };
+        let points = [at(500.0), at(500.0), at(1_500.0), at(7_000.0), at(20_000.0)];
+        let bands = distance_bands_of_points(&center, &points, &[1_000.0, 5_000.0, 10_000.0]).unwrap();
+
+        assert_eq!(bands.iter().map(|band| band.count).collect::<Vec<_>>(), [2, 1, 1]);
+        assert_eq!(bands[0].region.len(), 1);
+        assert_eq!(bands[0].region.level(), 0);
+        assert!(bands[0].region.contains_code(Grid9Code::encode(points[0].lat, points[0].lon).unwrap()));
+        assert!((bands[2].mean_distance_m.unwrap() - 7_000.0).abs() < 1.0);
+
+        let empty = distance_bands_of_points(&center, &[], &[1_000.0, f64::INFINITY]).unwrap();
+        assert_eq!(empty[1].mean_distance_m, None);
+        for edges in [&[][..], &[0.0], &[5.0, 5.0], &[f64::NAN]] {
+            assert!(matches!(distance_bands_of_points(&center, &points, edges), Err(Grid9Error::InvalidInput(_))));
+        }
+        assert!(distance_bands_of_points(&center, &[Coordinate::new(0.0, 200.0)], &[1.0]).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+//! Grid9 command-line tool
+//!
+//! Usage: `grid9 <COMMAND>`
+//!
+//! Commands:
+//! * `test-vectors [OUTPUT]` - Writes the canonical test vectors as JSON to
+//!   OUTPUT if given, otherwise to stdout. The other ports load this file in
+//!   their test suites to check parity with the Rust core.
+//! * `convert <encode|decode> [INPUT [OUTPUT]]` - Converts newline-delimited
+//!   `lat,lon` pairs to Grid9 codes or back, reading INPUT (or stdin) in
+//!   parallel chunks with [`grid9::process_chunks`] and writing one line per
+//!   input line to OUTPUT (or stdout). Records that fail to convert become
+//!   empty lines and are reported on stderr with their line number.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::process::ExitCode;
+
+use grid9::{decode, encode, parse_lat_lon, process_chunks, Chunk, Grid9Error, ParseOptions, RecordError, TestVectors};
+
+const USAGE: &str = "\
+Usage: grid9 <COMMAND>
+
+Commands:
+  test-vectors [OUTPUT]                     Write the canonical test vectors as JSON to OUTPUT or stdout
+  convert <encode|decode> [INPUT [OUTPUT]]  Convert lat,lon lines to Grid9 codes or back (stdin/stdout by default)
+  help                                      Print this message
+";
+
+/// Bytes of input handed to each conversion worker
+const CHUNK_SIZE: usize = 1 << 20;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["test-vectors"] => write_test_vectors(None),
+        ["test-vectors", output] => write_test_vectors(Some(output)),
+        ["convert", mode @ ("encode" | "decode"), paths @ ..] if paths.len() <= 2 => {
+            convert(*mode == "encode", paths.first().copied(), paths.get(1).copied())
+        }
+        ["help" | "--help" | "-h"] => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        _ => {
+            eprint!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("grid9: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn write_test_vectors(output: Option<&str>) -> std::io::Result<()> {
+    let json = TestVectors::generate().to_json();
+
+    match output {
+        Some(path) => std::fs::write(path, json),
+        None => std::io::stdout().write_all(json.as_bytes()),
+    }
+}
+
+fn convert(to_codes: bool, input: Option<&str>, output: Option<&str>) -> io::Result<()> {
+    let reader: Box<dyn Read> = match input {
+        Some(path) => Box::new(File::open(path)?),
+        None => Box::new(io::stdin()),
+    };
+    let mut writer: BufWriter<Box<dyn Write>> = BufWriter::new(match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    });
+
+    let worker = |chunk: &Chunk<'_>, errors: &mut Vec<RecordError>| {
+        let mut converted = Vec::with_capacity(chunk.data.len() * 2);
+        for (line, record) in chunk.lines() {
+            if !record.is_empty() {
+                match convert_record(record, to_codes) {
+                    Ok(text) => converted.extend_from_slice(text.as_bytes()),
+                    Err(error) => errors.push(RecordError { line, error }),
+                }
+            }
+            converted.push(b'\n');
+        }
+        converted
+    };
+
+    let mut failed = 0;
+    for chunk in process_chunks(reader, CHUNK_SIZE, worker) {
+        let chunk = chunk?;
+        writer.write_all(&chunk.value)?;
+        for error in &chunk.errors {
+            eprintln!("grid9: line {}: {}", error.line, error.error);
+        }
+        failed += chunk.errors.len();
+    }
+    writer.flush()?;
+
+    if failed > 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} records could not be converted", failed)));
+    }
+    Ok(())
+}
+
+fn convert_record(record: &[u8], to_codes: bool) -> grid9::Result<String> {
+    let text = std::str::from_utf8(record).map_err(|_| Grid9Error::InvalidInput("record is not valid UTF-8".into()))?;
+
+    if to_codes {
+        let (lat, lon) = parse_lat_lon(text, ParseOptions::default())?;
+        encode(lat, lon, false)
+    } else {
+        let (lat, lon) = decode(text.trim())?;
+        Ok(format!("{:.7},{:.7}", lat, lon))
+    }
+}
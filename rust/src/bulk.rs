@@ -0,0 +1,512 @@
+//! Memory-mapped bulk conversion of large code and coordinate files
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use memmap2::Mmap;
+
+use crate::chunked::{line_end, trim_line};
+use crate::{
+    process_slice_chunks, Chunk, Codec, Grid9Codec, Grid9Decoder, Grid9Encoder, Grid9Error, Grid9Metrics, RecordError,
+    Result,
+};
+
+/// Input layout understood by [`convert_file`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Format {
+    /// Newline-delimited Grid9 codes, decoded to `lat,lon` lines
+    CodeLines,
+    /// Newline-delimited `lat,lon` pairs, encoded to one code per line
+    CoordinateLines,
+    /// Delimited text with latitude and longitude columns; a code column is
+    /// appended to every row. Quoted fields are not supported.
+    Csv {
+        /// 0-based index of the latitude column
+        lat_column: usize,
+        /// 0-based index of the longitude column
+        lon_column: usize,
+        /// Whether the first line is a header row
+        has_header: bool,
+        /// Field delimiter, usually `b','`
+        delimiter: u8,
+    },
+}
+
+/// Position from which an interrupted conversion can be restarted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResumePoint {
+    /// Input byte offset of the first unprocessed line
+    pub input_offset: u64,
+    /// Output length in bytes corresponding to `input_offset`
+    pub output_len: u64,
+    /// Number of input lines already processed
+    pub lines: u64,
+}
+
+/// Options for [`convert_file`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConvertOptions {
+    /// Write codes in XXX-XXX-XXX format
+    pub human_readable: bool,
+    /// Worker threads (0 uses the available parallelism)
+    pub threads: usize,
+    /// Approximate chunk size in bytes; chunks are extended to the next line break
+    pub chunk_size: usize,
+    /// Continue an interrupted conversion instead of starting from scratch
+    pub resume_from: Option<ResumePoint>,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            human_readable: false,
+            threads: 0,
+            chunk_size: 4 * 1024 * 1024,
+            resume_from: None,
+        }
+    }
+}
+
+/// Progress snapshot passed to the progress callback after each written chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Progress {
+    /// Input bytes processed so far (including any resumed prefix)
+    pub bytes_processed: u64,
+    /// Total input size in bytes
+    pub total_bytes: u64,
+    /// Input lines processed so far
+    pub lines: u64,
+    /// Records that failed to convert so far
+    pub errors: u64,
+    /// Point from which the conversion can be resumed; the output has been
+    /// flushed up to `resume_point.output_len`
+    pub resume_point: ResumePoint,
+}
+
+/// Totals of a finished conversion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConversionSummary {
+    /// Input lines processed by this run
+    pub lines: u64,
+    /// Records that failed to convert in this run
+    pub errors: u64,
+}
+
+/// Converts a large newline-delimited or CSV file between coordinates and Grid9 codes.
+///
+/// The input is memory-mapped and split into line-aligned chunks that are
+/// converted in parallel (see [`process_slice_chunks`]) and written in input order. Each output line
+/// corresponds to exactly one input line: records that fail to convert produce
+/// an empty line (or an empty code column for CSV) and are reported through
+/// the progress callback together with their line number.
+///
+/// After every chunk the output is flushed and `progress` receives
+/// a [`ResumePoint`]; passing it back via [`ConvertOptions::resume_from`]
+/// truncates the output to the matching length and continues from there.
+///
+/// The input file must not be modified while it is being converted.
+///
+/// # Arguments
+/// * `input` - Path of the file to convert
+/// * `output` - Path of the file to write
+/// * `format` - Layout of the input file
+/// * `options` - Conversion options
+/// * `progress` - Called with the current progress and the record errors of the latest chunk
+///
+/// # Returns
+/// Line and error totals for this run
+///
+/// # Example
+/// ```rust,no_run
+/// use grid9::{convert_file, ConvertOptions, Format};
+///
+/// let summary = convert_file("codes.txt", "coords.txt", Format::CodeLines, &ConvertOptions::default(), |progress, errors| {
+///     for e in errors {
+///         eprintln!("line {}: {}", e.line, e.error);
+///     }
+///     eprintln!("{}/{} bytes", progress.bytes_processed, progress.total_bytes);
+/// })?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn convert_file<P, Q, F>(
+    input: P,
+    output: Q,
+    format: Format,
+    options: &ConvertOptions,
+    progress: F,
+) -> io::Result<ConversionSummary>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    F: FnMut(&Progress, &[RecordError]),
+{
+    convert(input, output, format, options, &default_codec(options), None, progress)
+}
+
+/// Converts a file like [`convert_file`], reporting every converted chunk to
+/// `metrics` as a [`crate::BatchOperation::Chunk`] batch of its lines and
+/// failed records.
+///
+/// # Arguments
+/// * `input` - Path of the file to convert
+/// * `output` - Path of the file to write
+/// * `format` - Layout of the input file
+/// * `options` - Conversion options
+/// * `metrics` - Receiver for the per-chunk metrics
+/// * `progress` - Called with the current progress and the record errors of the latest chunk
+///
+/// # Returns
+/// Line and error totals for this run
+pub fn convert_file_with_metrics<P, Q, F>(
+    input: P,
+    output: Q,
+    format: Format,
+    options: &ConvertOptions,
+    metrics: Arc<dyn Grid9Metrics>,
+    progress: F,
+) -> io::Result<ConversionSummary>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    F: FnMut(&Progress, &[RecordError]),
+{
+    convert(input, output, format, options, &default_codec(options), Some(metrics), progress)
+}
+
+/// Converts a file like [`convert_file`], reading and writing codes with a
+/// custom [`Codec`]. `options.human_readable` is ignored; output formatting is
+/// up to the codec.
+///
+/// # Arguments
+/// * `input` - Path of the file to convert
+/// * `output` - Path of the file to write
+/// * `format` - Layout of the input file
+/// * `options` - Conversion options
+/// * `codec` - Text encoding of the codes read or written
+/// * `progress` - Called with the current progress and the record errors of the latest chunk
+///
+/// # Returns
+/// Line and error totals for this run
+pub fn convert_file_with_codec<P, Q, F>(
+    input: P,
+    output: Q,
+    format: Format,
+    options: &ConvertOptions,
+    codec: &dyn Codec,
+    progress: F,
+) -> io::Result<ConversionSummary>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    F: FnMut(&Progress, &[RecordError]),
+{
+    convert(input, output, format, options, codec, None, progress)
+}
+
+/// Codec writing codes as `options.human_readable` asks
+fn default_codec(options: &ConvertOptions) -> Grid9Codec {
+    Grid9Codec::from_parts(
+        Grid9Encoder::builder().human_readable(options.human_readable).build(),
+        Grid9Decoder::default(),
+    )
+}
+
+fn convert<P, Q, F>(
+    input: P,
+    output: Q,
+    format: Format,
+    options: &ConvertOptions,
+    codec: &dyn Codec,
+    metrics: Option<Arc<dyn Grid9Metrics>>,
+    mut progress: F,
+) -> io::Result<ConversionSummary>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    F: FnMut(&Progress, &[RecordError]),
+{
+    let input_file = File::open(input)?;
+    let total_bytes = input_file.metadata()?.len();
+
+    let resume = options.resume_from.unwrap_or_default();
+    if resume.input_offset > total_bytes {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "resume offset beyond end of input"));
+    }
+
+    let mut output_file = OpenOptions::new().create(true).write(true).truncate(false).open(output)?;
+    output_file.set_len(resume.output_len)?;
+    output_file.seek(SeekFrom::End(0))?;
+    let mut writer = BufWriter::new(output_file);
+
+    if total_bytes == 0 {
+        return Ok(ConversionSummary { lines: 0, errors: 0 });
+    }
+
+    // SAFETY: the mapping is only read, and callers are required not to modify
+    // the input file during the conversion.
+    let mmap = unsafe { Mmap::map(&input_file)? };
+    let data: &[u8] = &mmap;
+
+    let mut state = Progress {
+        bytes_processed: resume.input_offset,
+        total_bytes,
+        lines: resume.lines,
+        errors: 0,
+        resume_point: resume,
+    };
+
+    let mut offset = resume.input_offset as usize;
+    if let Format::Csv { has_header: true, delimiter, .. } = format {
+        if offset == 0 {
+            let end = line_end(data, 0);
+            let header = trim_line(&data[..end]);
+            writer.write_all(header)?;
+            writer.write_all(&[delimiter])?;
+            writer.write_all(b"grid9\n")?;
+            offset = end;
+            state.lines = 1;
+            state.resume_point.output_len += (header.len() + 1 + b"grid9\n".len()) as u64;
+        }
+    }
+
+    let worker = |chunk: &Chunk<'_>, errors: &mut Vec<RecordError>| {
+        convert_chunk(chunk, format, codec, errors)
+    };
+    let mut chunks = process_slice_chunks(data, options.chunk_size, worker).resume_at(offset, state.lines);
+    if options.threads > 0 {
+        chunks = chunks.threads(options.threads);
+    }
+    if let Some(metrics) = metrics {
+        chunks = chunks.metrics(metrics);
+    }
+
+    for chunk in chunks {
+        writer.write_all(&chunk.value)?;
+        writer.flush()?;
+
+        state.bytes_processed = chunk.end_offset;
+        state.lines += chunk.line_count;
+        state.errors += chunk.errors.len() as u64;
+        state.resume_point = ResumePoint {
+            input_offset: chunk.end_offset,
+            output_len: state.resume_point.output_len + chunk.value.len() as u64,
+            lines: state.lines,
+        };
+        progress(&state, &chunk.errors);
+    }
+
+    Ok(ConversionSummary {
+        lines: state.lines - resume.lines,
+        errors: state.errors,
+    })
+}
+
+fn convert_chunk(chunk: &Chunk<'_>, format: Format, codec: &dyn Codec, errors: &mut Vec<RecordError>) -> Vec<u8> {
+    let mut output = Vec::with_capacity(chunk.data.len() * 2);
+
+    for (line, record) in chunk.lines() {
+        if !record.is_empty() {
+            if let Err(error) = convert_record(&mut output, record, format, codec) {
+                errors.push(RecordError { line, error });
+            }
+        }
+        output.push(b'\n');
+    }
+
+    output
+}
+
+fn convert_record(out: &mut Vec<u8>, line: &[u8], format: Format, codec: &dyn Codec) -> Result<()> {
+    let text = std::str::from_utf8(line)
+        .map_err(|_| Grid9Error::InvalidInput("record is not valid UTF-8".into()))?;
+
+    match format {
+        Format::CodeLines => {
+            let (lat, lon) = codec.decode(text.trim())?;
+            out.extend_from_slice(format!("{:.7},{:.7}", lat, lon).as_bytes());
+        }
+        Format::CoordinateLines => {
+            let mut fields = text.split(',');
+            let lat = parse_field(fields.next(), "latitude")?;
+            let lon = parse_field(fields.next(), "longitude")?;
+            out.extend_from_slice(codec.encode(lat, lon)?.as_bytes());
+        }
+        Format::Csv { lat_column, lon_column, delimiter, .. } => {
+            // Keep the row even if it cannot be encoded so rows stay aligned
+            out.extend_from_slice(line);
+            out.push(delimiter);
+
+            let fields: Vec<&str> = text.split(delimiter as char).collect();
+            let lat = parse_field(fields.get(lat_column).copied(), "latitude")?;
+            let lon = parse_field(fields.get(lon_column).copied(), "longitude")?;
+            out.extend_from_slice(codec.encode(lat, lon)?.as_bytes());
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_field(field: Option<&str>, name: &str) -> Result<f64> {
+    let field = field.ok_or_else(|| Grid9Error::InvalidInput(format!("missing {} field", name).into()))?;
+    field
+        .trim()
+        .parse()
+        .map_err(|_| Grid9Error::InvalidInput(format!("invalid {}: {:?}", name, field.trim()).into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, encode};
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("grid9-bulk-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_convert_code_lines_in_parallel() {
+        let input = temp_path("codes-in");
+        let output = temp_path("codes-out");
+
+        let codes: Vec<String> = (0..1_000)
+            .map(|i| encode(i as f64 / 20.0, i as f64 / 10.0, false).unwrap())
+            .collect();
+        let mut contents = codes.join("\n");
+        contents.push_str("\r\nBAD\n");
+        std::fs::write(&input, contents).unwrap();
+
+        let options = ConvertOptions { threads: 4, chunk_size: 256, ..Default::default() };
+        let mut errors = Vec::new();
+        let mut callbacks = 0;
+        let summary = convert_file(&input, &output, Format::CodeLines, &options, |progress, errs| {
+            callbacks += 1;
+            errors.extend_from_slice(errs);
+            assert!(progress.bytes_processed <= progress.total_bytes);
+        })
+        .unwrap();
+
+        assert!(callbacks > 1);
+        assert_eq!(summary.lines, 1_001);
+        assert_eq!(summary.errors, 1);
+        assert_eq!(errors[0].line, 1_001);
+
+        let written = std::fs::read_to_string(&output).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 1_001);
+        assert_eq!(lines[1_000], "");
+        for (line, code) in lines.iter().zip(&codes) {
+            let (lat, lon) = decode(code).unwrap();
+            assert_eq!(*line, format!("{:.7},{:.7}", lat, lon));
+        }
+
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+
+    #[test]
+    fn test_convert_csv_and_resume() {
+        let input = temp_path("csv-in");
+        let full = temp_path("csv-full");
+        let resumed = temp_path("csv-resumed");
+
+        let mut contents = String::from("id;lat;lon\n");
+        for i in 0..200 {
+            contents.push_str(&format!("{};{};{}\n", i, i as f64 / 4.0, -(i as f64) / 3.0));
+        }
+        contents.push_str("200;north;0\n");
+        std::fs::write(&input, contents).unwrap();
+
+        let format = Format::Csv { lat_column: 1, lon_column: 2, has_header: true, delimiter: b';' };
+        let options = ConvertOptions { threads: 2, chunk_size: 512, ..Default::default() };
+
+        let mut checkpoints = Vec::new();
+        let mut errors = Vec::new();
+        convert_file(&input, &full, format, &options, |progress, errs| {
+            checkpoints.push(progress.resume_point);
+            errors.extend_from_slice(errs);
+        })
+        .unwrap();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 202);
+
+        let expected = std::fs::read_to_string(&full).unwrap();
+        assert!(expected.starts_with("id;lat;lon;grid9\n0;0;-0;"));
+        assert!(expected.ends_with("200;north;0;\n"));
+
+        // Simulate a crash after the first checkpoint with a partially written tail
+        let checkpoint = checkpoints[0];
+        std::fs::write(&resumed, &expected.as_bytes()[..checkpoint.output_len as usize + 10]).unwrap();
+
+        let options = ConvertOptions { resume_from: Some(checkpoint), ..options };
+        let summary = convert_file(&input, &resumed, format, &options, |_, _| {}).unwrap();
+
+        assert_eq!(summary.lines, 202 - checkpoint.lines);
+        assert_eq!(std::fs::read_to_string(&resumed).unwrap(), expected);
+
+        for path in [input, full, resumed] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_convert_with_codec() {
+        let input = temp_path("codec-in");
+        let output = temp_path("codec-out");
+        std::fs::write(&input, "40.7128,-74.0060\n51.5074,-0.1278\n").unwrap();
+
+        let codec = Grid9Codec::from_parts(
+            Grid9Encoder::builder().checksum(true).build(),
+            Grid9Decoder::builder().checksum(true).build(),
+        );
+        let options = ConvertOptions::default();
+        convert_file_with_codec(&input, &output, Format::CoordinateLines, &options, &codec, |_, _| {}).unwrap();
+
+        let written = std::fs::read_to_string(&output).unwrap();
+        let codes: Vec<&str> = written.lines().collect();
+        assert_eq!(codes.len(), 2);
+        assert!(codes.iter().all(|code| code.len() == 10 && codec.validate(code).is_ok()));
+
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+
+    #[test]
+    fn test_convert_with_metrics() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use crate::BatchOperation;
+
+        #[derive(Default)]
+        struct Totals(AtomicUsize, AtomicUsize);
+
+        impl Grid9Metrics for Totals {
+            fn record_batch(&self, operation: BatchOperation, items: usize, errors: usize, _: std::time::Duration) {
+                assert_eq!(operation, BatchOperation::Chunk);
+                self.0.fetch_add(items, Ordering::Relaxed);
+                self.1.fetch_add(errors, Ordering::Relaxed);
+            }
+        }
+
+        let input = temp_path("metrics-in");
+        let output = temp_path("metrics-out");
+        std::fs::write(&input, "40.7128,-74.0060\nbad\n51.5074,-0.1278\n").unwrap();
+
+        let totals = Arc::new(Totals::default());
+        let options = ConvertOptions { threads: 2, chunk_size: 16, ..Default::default() };
+        let summary = convert_file_with_metrics(&input, &output, Format::CoordinateLines, &options, totals.clone(), |_, _| {}).unwrap();
+
+        assert_eq!(summary.lines, 3);
+        assert_eq!(totals.0.load(Ordering::Relaxed), 3);
+        assert_eq!(totals.1.load(Ordering::Relaxed), 1);
+
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+}
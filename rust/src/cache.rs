@@ -0,0 +1,323 @@
+//! Opt-in memoization of decode results for hot codes
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::{decode, Grid9Metrics, Result};
+
+const SHARD_COUNT: usize = 16;
+const NIL: usize = usize::MAX;
+
+/// A thread-safe LRU cache in front of [`decode`].
+///
+/// Entries are spread over independently locked shards so concurrent readers
+/// rarely contend. Only successful decodes are cached; invalid codes are
+/// re-validated on every call. Keys are the input strings as given, so
+/// `"Q7KH2BBYF"` and `"Q7K-H2B-BYF"` occupy separate entries.
+///
+/// # Example
+/// ```rust
+/// use grid9::CachedDecoder;
+///
+/// let decoder = CachedDecoder::new(10_000);
+/// let first = decoder.decode("Q7KH2BBYF")?;
+/// let second = decoder.decode("Q7KH2BBYF")?; // Served from the cache
+/// assert_eq!(first, second);
+/// assert_eq!(decoder.hits(), 1);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub struct CachedDecoder {
+    shards: Vec<Mutex<LruShard>>,
+    hasher: RandomState,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    metrics: Option<Arc<dyn Grid9Metrics>>,
+}
+
+impl CachedDecoder {
+    /// Creates a cache holding up to roughly `capacity` decoded codes.
+    pub fn new(capacity: usize) -> Self {
+        let shard_capacity = capacity.div_ceil(SHARD_COUNT).max(1);
+        Self {
+            shards: (0..SHARD_COUNT)
+                .map(|_| Mutex::new(LruShard::new(shard_capacity)))
+                .collect(),
+            hasher: RandomState::new(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            metrics: None,
+        }
+    }
+
+    /// Creates a cache that reports every lookup to `metrics`.
+    pub fn with_metrics(capacity: usize, metrics: Arc<dyn Grid9Metrics>) -> Self {
+        Self {
+            metrics: Some(metrics),
+            ..Self::new(capacity)
+        }
+    }
+
+    /// Decodes a Grid9 string, serving repeated codes from the cache.
+    pub fn decode(&self, encoded: &str) -> Result<(f64, f64)> {
+        let mut shard = self.shard(encoded);
+        let cached = shard.get(encoded);
+        drop(shard);
+
+        if let Some(metrics) = &self.metrics {
+            metrics.record_cache_lookup(cached.is_some());
+        }
+        if let Some(value) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(value);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let value = decode(encoded)?;
+        self.shard(encoded).insert(encoded, value);
+        Ok(value)
+    }
+
+    /// Number of lookups served from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of lookups that had to decode
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Fraction of lookups served from the cache (0.0 before the first lookup)
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.hits() as f64;
+        let total = hits + self.misses() as f64;
+        if total == 0.0 { 0.0 } else { hits / total }
+    }
+
+    /// Number of cached codes
+    pub fn len(&self) -> usize {
+        (0..self.shards.len()).map(|i| self.lock(i).map.len()).sum()
+    }
+
+    /// True if nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cached entries and resets the hit/miss counters
+    pub fn clear(&self) {
+        for i in 0..self.shards.len() {
+            self.lock(i).clear();
+        }
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    fn shard(&self, encoded: &str) -> MutexGuard<'_, LruShard> {
+        let index = self.hasher.hash_one(encoded) as usize % self.shards.len();
+        self.lock(index)
+    }
+
+    fn lock(&self, index: usize) -> MutexGuard<'_, LruShard> {
+        // A panic while holding the lock cannot leave the shard inconsistent
+        // in a way that affects correctness, so recover from poisoning.
+        self.shards[index].lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for CachedDecoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedDecoder")
+            .field("len", &self.len())
+            .field("hits", &self.hits())
+            .field("misses", &self.misses())
+            .finish()
+    }
+}
+
+struct LruNode {
+    key: Arc<str>,
+    value: (f64, f64),
+    prev: usize,
+    next: usize,
+}
+
+/// Fixed-capacity LRU list stored in a slab of nodes
+struct LruShard {
+    map: HashMap<Arc<str>, usize>,
+    nodes: Vec<LruNode>,
+    head: usize,
+    tail: usize,
+    capacity: usize,
+}
+
+impl LruShard {
+    fn new(capacity: usize) -> Self {
+        Self {
+            map: HashMap::with_capacity(capacity),
+            nodes: Vec::with_capacity(capacity),
+            head: NIL,
+            tail: NIL,
+            capacity,
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<(f64, f64)> {
+        let index = *self.map.get(key)?;
+        self.unlink(index);
+        self.push_front(index);
+        Some(self.nodes[index].value)
+    }
+
+    fn insert(&mut self, key: &str, value: (f64, f64)) {
+        if let Some(&index) = self.map.get(key) {
+            self.nodes[index].value = value;
+            self.unlink(index);
+            self.push_front(index);
+            return;
+        }
+
+        // The map and the node share one allocation of the key
+        let key: Arc<str> = Arc::from(key);
+        let index = if self.nodes.len() < self.capacity {
+            self.nodes.push(LruNode { key: Arc::clone(&key), value, prev: NIL, next: NIL });
+            self.nodes.len() - 1
+        } else {
+            // Reuse the least recently used node
+            let index = self.tail;
+            self.unlink(index);
+            let evicted = std::mem::replace(&mut self.nodes[index].key, Arc::clone(&key));
+            self.map.remove(&evicted);
+            self.nodes[index].value = value;
+            index
+        };
+
+        self.map.insert(key, index);
+        self.push_front(index);
+    }
+
+    fn clear(&mut self) {
+        self.map.clear();
+        self.nodes.clear();
+        self.head = NIL;
+        self.tail = NIL;
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let (prev, next) = (self.nodes[index].prev, self.nodes[index].next);
+        if prev == NIL { self.head = next } else { self.nodes[prev].next = next }
+        if next == NIL { self.tail = prev } else { self.nodes[next].prev = prev }
+    }
+
+    fn push_front(&mut self, index: usize) {
+        self.nodes[index].prev = NIL;
+        self.nodes[index].next = self.head;
+        if self.head != NIL {
+            self.nodes[self.head].prev = index;
+        }
+        self.head = index;
+        if self.tail == NIL {
+            self.tail = index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode, Grid9Error};
+
+    #[test]
+    fn test_cached_results_match_decode() {
+        let decoder = CachedDecoder::new(100);
+        let code = encode(35.6762, 139.6503, false).unwrap();
+
+        for _ in 0..3 {
+            assert_eq!(decoder.decode(&code).unwrap(), decode(&code).unwrap());
+        }
+        assert_eq!(decoder.misses(), 1);
+        assert_eq!(decoder.hits(), 2);
+        assert!((decoder.hit_rate() - 2.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_errors_are_not_cached() {
+        let decoder = CachedDecoder::new(100);
+
+        assert_eq!(decoder.decode("BAD"), Err(Grid9Error::InvalidLength(3)));
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn test_reports_lookups_to_metrics() {
+        #[derive(Default)]
+        struct Lookups(AtomicU64, AtomicU64);
+
+        impl Grid9Metrics for Lookups {
+            fn record_cache_lookup(&self, hit: bool) {
+                let counter = if hit { &self.0 } else { &self.1 };
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let lookups = Arc::new(Lookups::default());
+        let decoder = CachedDecoder::with_metrics(10, lookups.clone());
+        for _ in 0..3 {
+            decoder.decode("Q7KH2BBYF").unwrap();
+        }
+
+        assert_eq!(lookups.0.load(Ordering::Relaxed), 2);
+        assert_eq!(lookups.1.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut shard = LruShard::new(2);
+        shard.insert("A", (1.0, 1.0));
+        shard.insert("B", (2.0, 2.0));
+        assert!(shard.get("A").is_some()); // B is now least recently used
+        shard.insert("C", (3.0, 3.0));
+
+        assert!(shard.get("B").is_none());
+        assert_eq!(shard.get("A"), Some((1.0, 1.0)));
+        assert_eq!(shard.get("C"), Some((3.0, 3.0)));
+        assert_eq!(shard.map.len(), 2);
+    }
+
+    #[test]
+    fn test_capacity_is_bounded() {
+        let decoder = CachedDecoder::new(32);
+        for i in 0..500 {
+            let code = encode(i as f64 / 10.0, 0.0, false).unwrap();
+            decoder.decode(&code).unwrap();
+        }
+
+        assert!(decoder.len() <= 32);
+        decoder.clear();
+        assert!(decoder.is_empty());
+        assert_eq!(decoder.hits() + decoder.misses(), 0);
+    }
+
+    #[test]
+    fn test_shared_across_threads() {
+        let decoder = std::sync::Arc::new(CachedDecoder::new(1_000));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let decoder = decoder.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        decoder.decode("Q7KH2BBYF").unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(decoder.hits() + decoder.misses(), 400);
+    }
+}
@@ -0,0 +1,216 @@
+//! CBOR encoding of Grid9 types with minicbor
+//!
+//! Values use compact, canonical shapes so constrained CoAP/CBOR devices can
+//! exchange them without a schema:
+//!
+//! | Type | CBOR |
+//! |------|------|
+//! | [`Grid9Code`] | tag [`GRID9_CBOR_TAG`] around a 6-byte big-endian byte string holding the 45-bit payload |
+//! | [`Coordinate`] | `[lat, lon]` as two floats |
+//! | [`Grid9Bucket`] | `[level, key]` with the packed payload of the bucket's south-west cell |
+//! | [`Grid9Region`] | `[level, [key, ...]]` with keys in ascending order |
+//! | [`TrackPoint`] / [`Track`] (`SystemTime`) | `[code, time]` / `[[code, time], ...]`, times as tag 1 epoch seconds (integer when whole, float otherwise) |
+//!
+//! [`GRID9_CBOR_TAG`] is not registered with IANA; it lies in the
+//! first-come-first-served range and spells "G9" in ASCII.
+
+use std::time::SystemTime;
+
+use minicbor::data::{Tag, Type};
+use minicbor::decode::{self, Decoder};
+use minicbor::encode::{self, Encoder, Write};
+use minicbor::{Decode, Encode};
+
+use crate::track::system_time;
+use crate::uniform_precision_compressor::unpack_indices;
+use crate::{Coordinate, Grid9Bucket, Grid9Code, Grid9Region, Timestamp, Track, TrackPoint};
+
+/// CBOR tag of a Grid9 code
+pub const GRID9_CBOR_TAG: u64 = 0x4739;
+
+/// Largest 45-bit payload
+const MAX_PAYLOAD: u64 = (1 << 45) - 1;
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+impl<C> Encode<C> for Grid9Code {
+    fn encode<W: Write>(&self, e: &mut Encoder<W>, _: &mut C) -> Result<(), encode::Error<W::Error>> {
+        e.tag(Tag::Unassigned(GRID9_CBOR_TAG))?.bytes(&self.to_payload_bytes())?.ok()
+    }
+}
+
+impl<'b, C> Decode<'b, C> for Grid9Code {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+        if d.tag()? != Tag::Unassigned(GRID9_CBOR_TAG) {
+            return Err(decode::Error::message("expected a Grid9 code tag"));
+        }
+        let bytes: [u8; 6] = d
+            .bytes()?
+            .try_into()
+            .map_err(|_| decode::Error::message("Grid9 code must be 6 bytes"))?;
+
+        Grid9Code::from_payload_bytes(bytes).map_err(|_| decode::Error::message("Grid9 payload exceeds 45 bits"))
+    }
+}
+
+impl<C> Encode<C> for Coordinate {
+    fn encode<W: Write>(&self, e: &mut Encoder<W>, _: &mut C) -> Result<(), encode::Error<W::Error>> {
+        e.array(2)?.f64(self.lat)?.f64(self.lon)?.ok()
+    }
+}
+
+impl<'b, C> Decode<'b, C> for Coordinate {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+        expect_array(d, 2)?;
+        Ok(Coordinate::new(float(d)?, float(d)?))
+    }
+}
+
+impl<C> Encode<C> for Grid9Bucket {
+    fn encode<W: Write>(&self, e: &mut Encoder<W>, _: &mut C) -> Result<(), encode::Error<W::Error>> {
+        e.array(2)?.u8(self.level())?.u64(self.key())?.ok()
+    }
+}
+
+impl<'b, C> Decode<'b, C> for Grid9Bucket {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+        expect_array(d, 2)?;
+        let level = d.u8()?;
+        bucket_of_key(d.u64()?, level)
+    }
+}
+
+impl<C> Encode<C> for Grid9Region {
+    fn encode<W: Write>(&self, e: &mut Encoder<W>, _: &mut C) -> Result<(), encode::Error<W::Error>> {
+        e.array(2)?.u8(self.level())?.array(self.len() as u64)?;
+        for bucket in self.buckets() {
+            e.u64(bucket.key())?;
+        }
+        Ok(())
+    }
+}
+
+impl<'b, C> Decode<'b, C> for Grid9Region {
+    fn decode(d: &mut Decoder<'b>, _: &mut C) -> Result<Self, decode::Error> {
+        expect_array(d, 2)?;
+        let level = d.u8()?;
+        let count = d.array()?.ok_or_else(|| decode::Error::message("indefinite region arrays are not supported"))?;
+
+        let mut region = Grid9Region::new(level).map_err(|e| decode::Error::message(e.to_string()))?;
+        for _ in 0..count {
+            region
+                .insert(bucket_of_key(d.u64()?, level)?)
+                .map_err(|e| decode::Error::message(e.to_string()))?;
+        }
+        Ok(region)
+    }
+}
+
+impl<C> Encode<C> for TrackPoint<SystemTime> {
+    fn encode<W: Write>(&self, e: &mut Encoder<W>, ctx: &mut C) -> Result<(), encode::Error<W::Error>> {
+        e.array(2)?.encode_with(self.code, ctx)?.tag(Tag::Timestamp)?;
+
+        let nanos = self.time.unix_nanos();
+        if nanos % NANOS_PER_SECOND == 0 {
+            e.i64((nanos / NANOS_PER_SECOND) as i64)?.ok()
+        } else {
+            e.f64(nanos as f64 / NANOS_PER_SECOND as f64)?.ok()
+        }
+    }
+}
+
+impl<'b, C> Decode<'b, C> for TrackPoint<SystemTime> {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, decode::Error> {
+        expect_array(d, 2)?;
+        let code = d.decode_with(ctx)?;
+        if d.tag()? != Tag::Timestamp {
+            return Err(decode::Error::message("expected an epoch timestamp"));
+        }
+
+        let nanos = match d.datatype()? {
+            Type::F32 | Type::F64 => (float(d)? * NANOS_PER_SECOND as f64).round() as i128,
+            _ => d.i64()? as i128 * NANOS_PER_SECOND,
+        };
+        Ok(TrackPoint { code, time: system_time(nanos) })
+    }
+}
+
+impl<C> Encode<C> for Track<SystemTime> {
+    fn encode<W: Write>(&self, e: &mut Encoder<W>, ctx: &mut C) -> Result<(), encode::Error<W::Error>> {
+        e.array(self.len() as u64)?;
+        for point in self.points() {
+            point.encode(e, ctx)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'b, C> Decode<'b, C> for Track<SystemTime> {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, decode::Error> {
+        let count = d.array()?.ok_or_else(|| decode::Error::message("indefinite track arrays are not supported"))?;
+        let points = (0..count).map(|_| TrackPoint::decode(d, ctx)).collect::<Result<Vec<_>, _>>()?;
+        Ok(Track::from_points(points))
+    }
+}
+
+fn expect_array(d: &mut Decoder<'_>, len: u64) -> Result<(), decode::Error> {
+    if d.array()? != Some(len) {
+        return Err(decode::Error::message("unexpected array length"));
+    }
+    Ok(())
+}
+
+/// Reads a single- or double-precision float
+fn float(d: &mut Decoder<'_>) -> Result<f64, decode::Error> {
+    match d.datatype()? {
+        Type::F32 => d.f32().map(f64::from),
+        _ => d.f64(),
+    }
+}
+
+fn bucket_of_key(key: u64, level: u8) -> Result<Grid9Bucket, decode::Error> {
+    if key > MAX_PAYLOAD {
+        return Err(decode::Error::message("Grid9 payload exceeds 45 bits"));
+    }
+    let (lat_index, lon_index) = unpack_indices(key);
+    Grid9Bucket::from_indices(lat_index, lon_index, level).map_err(|e| decode::Error::message(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_code_bytes() {
+        let code = Grid9Code::encode(90.0, 180.0).unwrap();
+        let bytes = minicbor::to_vec(code).unwrap();
+        // Tag 0x4739, byte string of length 6, all-ones payload
+        assert_eq!(bytes, [0xD9, 0x47, 0x39, 0x46, 0x1F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(minicbor::decode::<Grid9Code>(&bytes).unwrap(), code);
+
+        let mut oversized = bytes.clone();
+        oversized[4] = 0xFF;
+        assert!(minicbor::decode::<Grid9Code>(&oversized).is_err());
+        assert!(minicbor::decode::<Grid9Code>(&bytes[3..]).is_err());
+    }
+
+    #[test]
+    fn test_roundtrips() {
+        let coordinate = Coordinate::new(48.8566, 2.3522);
+        assert_eq!(minicbor::decode::<Coordinate>(&minicbor::to_vec(&coordinate).unwrap()).unwrap(), coordinate);
+
+        let ring = [Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.01), Coordinate::new(0.01, 0.0)];
+        let region = Grid9Region::from_polygon(&ring, 6).unwrap();
+        let bucket = region.buckets().next().unwrap();
+        assert_eq!(minicbor::decode::<Grid9Bucket>(&minicbor::to_vec(bucket).unwrap()).unwrap(), bucket);
+        assert_eq!(minicbor::decode::<Grid9Region>(&minicbor::to_vec(&region).unwrap()).unwrap(), region);
+
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut track = Track::new();
+        track.push(Grid9Code::encode(40.7128, -74.0060).unwrap(), start).unwrap();
+        track.push(Grid9Code::encode(40.7129, -74.0061).unwrap(), start + Duration::from_millis(1_500)).unwrap();
+        let bytes = minicbor::to_vec(&track).unwrap();
+        assert_eq!(minicbor::decode::<Track<SystemTime>>(&bytes).unwrap(), track);
+    }
+}
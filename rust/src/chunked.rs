@@ -0,0 +1,544 @@
+//! Multi-threaded processing of newline-delimited data in record-aligned chunks
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+use crate::{BatchOperation, Grid9Error, Grid9Metrics};
+
+/// A record that could not be processed
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordError {
+    /// 1-based input line number
+    pub line: u64,
+    /// Why the record failed
+    pub error: Grid9Error,
+}
+
+/// A block of complete lines handed to a chunk worker
+#[derive(Debug, Clone, Copy)]
+pub struct Chunk<'a> {
+    /// 0-based position of the chunk in the input
+    pub index: u64,
+    /// Byte offset of the chunk in the input
+    pub offset: u64,
+    /// Number of input lines before this chunk
+    pub first_line: u64,
+    /// Raw chunk bytes, always ending at a line break or the end of input
+    pub data: &'a [u8],
+}
+
+impl<'a> Chunk<'a> {
+    /// Iterates over the lines of the chunk as `(line_number, line)` pairs,
+    /// with 1-based input line numbers and `\n` / `\r\n` terminators removed.
+    pub fn lines(&self) -> impl Iterator<Item = (u64, &'a [u8])> + 'a {
+        let data = self.data;
+        let first_line = self.first_line;
+        let mut start = 0;
+        let mut number = first_line;
+
+        std::iter::from_fn(move || {
+            if start >= data.len() {
+                return None;
+            }
+            let end = line_end(data, start);
+            let line = trim_line(&data[start..end]);
+            start = end;
+            number += 1;
+            Some((number, line))
+        })
+    }
+
+    /// Number of lines in the chunk
+    pub fn line_count(&self) -> u64 {
+        count_lines(self.data)
+    }
+}
+
+/// Output of a chunk worker together with the chunk's position
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkResult<T> {
+    /// 0-based position of the chunk in the input
+    pub index: u64,
+    /// Byte offset of the chunk in the input
+    pub offset: u64,
+    /// Byte offset just past the end of the chunk
+    pub end_offset: u64,
+    /// Number of input lines before this chunk
+    pub first_line: u64,
+    /// Number of lines in this chunk
+    pub line_count: u64,
+    /// Value returned by the worker
+    pub value: T,
+    /// Record errors collected by the worker
+    pub errors: Vec<RecordError>,
+}
+
+/// Processes newline-delimited input from a reader in parallel, record-aligned chunks.
+///
+/// The input is cut into chunks of roughly `chunk_size` bytes, each extended
+/// to the next line break so records never straddle chunks. Chunks are
+/// processed by `worker` on scoped threads and yielded in input order, so
+/// memory use stays bounded by `threads * chunk_size` regardless of input size.
+/// Workers report bad records by pushing to the provided error list.
+///
+/// # Arguments
+/// * `reader` - Source of newline-delimited records
+/// * `chunk_size` - Approximate chunk size in bytes
+/// * `worker` - Called once per chunk; returns the per-chunk value
+///
+/// # Returns
+/// An iterator of per-chunk results in input order
+///
+/// # Example
+/// ```rust
+/// use grid9::{is_valid_encoding, process_chunks, Grid9Error, RecordError};
+///
+/// let input = "Q7KH2BBYF\nBAD\nQ7K-H2B-BYF\n".as_bytes();
+/// let mut valid = 0;
+/// let mut errors = Vec::new();
+///
+/// for result in process_chunks(input, 1 << 20, |chunk, errors: &mut Vec<RecordError>| {
+///     let mut count = 0;
+///     for (line, code) in chunk.lines() {
+///         match std::str::from_utf8(code).ok().filter(|c| is_valid_encoding(c)) {
+///             Some(_) => count += 1,
+///             None => errors.push(RecordError { line, error: Grid9Error::InvalidInput("bad code".into()) }),
+///         }
+///     }
+///     count
+/// }).threads(4) {
+///     let result = result?;
+///     valid += result.value;
+///     errors.extend(result.errors);
+/// }
+///
+/// assert_eq!(valid, 2);
+/// assert_eq!(errors[0].line, 2);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn process_chunks<R, T, F>(reader: R, chunk_size: usize, worker: F) -> ProcessChunks<R, T, F>
+where
+    R: Read,
+    T: Send,
+    F: Fn(&Chunk<'_>, &mut Vec<RecordError>) -> T + Sync,
+{
+    ProcessChunks {
+        reader,
+        worker,
+        chunk_size: chunk_size.max(1),
+        threads: default_threads(),
+        carry: Vec::new(),
+        position: Position::default(),
+        eof: false,
+        pending: VecDeque::new(),
+        metrics: None,
+    }
+}
+
+/// Processes an in-memory (e.g. memory-mapped) buffer in parallel, record-aligned chunks.
+///
+/// Behaves like [`process_chunks`] but hands out chunks borrowed directly
+/// from `data`, without copying.
+///
+/// # Arguments
+/// * `data` - Newline-delimited records
+/// * `chunk_size` - Approximate chunk size in bytes
+/// * `worker` - Called once per chunk; returns the per-chunk value
+///
+/// # Returns
+/// An iterator of per-chunk results in input order
+pub fn process_slice_chunks<T, F>(data: &[u8], chunk_size: usize, worker: F) -> SliceChunks<'_, T, F>
+where
+    T: Send,
+    F: Fn(&Chunk<'_>, &mut Vec<RecordError>) -> T + Sync,
+{
+    SliceChunks {
+        data,
+        worker,
+        chunk_size: chunk_size.max(1),
+        threads: default_threads(),
+        position: Position::default(),
+        pending: VecDeque::new(),
+        metrics: None,
+    }
+}
+
+/// Iterator returned by [`process_chunks`]
+pub struct ProcessChunks<R, T, F> {
+    reader: R,
+    worker: F,
+    chunk_size: usize,
+    threads: usize,
+    carry: Vec<u8>,
+    position: Position,
+    eof: bool,
+    pending: VecDeque<ChunkResult<T>>,
+    metrics: Option<Arc<dyn Grid9Metrics>>,
+}
+
+impl<R, T, F> ProcessChunks<R, T, F>
+where
+    R: Read,
+    T: Send,
+    F: Fn(&Chunk<'_>, &mut Vec<RecordError>) -> T + Sync,
+{
+    /// Sets the number of worker threads (at least 1)
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Reports every processed chunk to `metrics` as a
+    /// [`BatchOperation::Chunk`] batch of its lines and record errors.
+    pub fn metrics(mut self, metrics: Arc<dyn Grid9Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Reads the next record-aligned chunk, or `None` at the end of input.
+    fn read_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut buffer = std::mem::take(&mut self.carry);
+        let mut scratch = vec![0u8; self.chunk_size.min(64 * 1024)];
+
+        // Fill to the chunk size, then cut after the first line break from there on
+        let mut searched = self.chunk_size - 1;
+        loop {
+            if buffer.len() > searched {
+                if let Some(i) = buffer[searched..].iter().position(|&b| b == b'\n') {
+                    self.carry = buffer.split_off(searched + i + 1);
+                    return Ok(Some(buffer));
+                }
+                searched = buffer.len();
+            }
+            if self.eof {
+                break;
+            }
+            match self.reader.read(&mut scratch) {
+                Ok(0) => self.eof = true,
+                Ok(n) => buffer.extend_from_slice(&scratch[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(if buffer.is_empty() { None } else { Some(buffer) })
+    }
+}
+
+impl<R, T, F> Iterator for ProcessChunks<R, T, F>
+where
+    R: Read,
+    T: Send,
+    F: Fn(&Chunk<'_>, &mut Vec<RecordError>) -> T + Sync,
+{
+    type Item = io::Result<ChunkResult<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_empty() {
+            let mut buffers = Vec::with_capacity(self.threads);
+            while buffers.len() < self.threads {
+                match self.read_chunk() {
+                    Ok(Some(buffer)) => buffers.push(buffer),
+                    Ok(None) => break,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+
+            let chunks: Vec<Chunk<'_>> = buffers
+                .iter()
+                .map(|buffer| self.position.advance(buffer))
+                .collect();
+            self.pending.extend(run_round(&chunks, &self.worker, self.metrics.as_deref()));
+        }
+
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+/// Iterator returned by [`process_slice_chunks`]
+pub struct SliceChunks<'a, T, F> {
+    data: &'a [u8],
+    worker: F,
+    chunk_size: usize,
+    threads: usize,
+    position: Position,
+    pending: VecDeque<ChunkResult<T>>,
+    metrics: Option<Arc<dyn Grid9Metrics>>,
+}
+
+impl<T, F> SliceChunks<'_, T, F>
+where
+    T: Send,
+    F: Fn(&Chunk<'_>, &mut Vec<RecordError>) -> T + Sync,
+{
+    /// Sets the number of worker threads (at least 1)
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Reports every processed chunk to `metrics` as a
+    /// [`BatchOperation::Chunk`] batch of its lines and record errors.
+    pub fn metrics(mut self, metrics: Arc<dyn Grid9Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Starts processing at byte `offset` (which must be at a line start),
+    /// numbering lines as if `lines` lines came before it.
+    pub fn resume_at(mut self, offset: usize, lines: u64) -> Self {
+        self.position = Position {
+            index: 0,
+            offset: offset.min(self.data.len()) as u64,
+            line: lines,
+        };
+        self
+    }
+}
+
+impl<T, F> Iterator for SliceChunks<'_, T, F>
+where
+    T: Send,
+    F: Fn(&Chunk<'_>, &mut Vec<RecordError>) -> T + Sync,
+{
+    type Item = ChunkResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_empty() {
+            let mut chunks = Vec::with_capacity(self.threads);
+            while chunks.len() < self.threads && (self.position.offset as usize) < self.data.len() {
+                let start = self.position.offset as usize;
+                let cut = (start + self.chunk_size).min(self.data.len()) - 1;
+                let end = line_end(self.data, cut);
+                chunks.push(self.position.advance(&self.data[start..end]));
+            }
+            self.pending.extend(run_round(&chunks, &self.worker, self.metrics.as_deref()));
+        }
+
+        self.pending.pop_front()
+    }
+}
+
+/// Running position of the chunk cutter
+#[derive(Debug, Clone, Copy, Default)]
+struct Position {
+    index: u64,
+    offset: u64,
+    line: u64,
+}
+
+impl Position {
+    fn advance<'a>(&mut self, data: &'a [u8]) -> Chunk<'a> {
+        let chunk = Chunk {
+            index: self.index,
+            offset: self.offset,
+            first_line: self.line,
+            data,
+        };
+        self.index += 1;
+        self.offset += data.len() as u64;
+        self.line += count_lines(data);
+        chunk
+    }
+}
+
+/// Processes one round of chunks in parallel, returning results in chunk order.
+fn run_round<T, F>(chunks: &[Chunk<'_>], worker: &F, metrics: Option<&dyn Grid9Metrics>) -> Vec<ChunkResult<T>>
+where
+    T: Send,
+    F: Fn(&Chunk<'_>, &mut Vec<RecordError>) -> T + Sync,
+{
+    let process = |chunk: &Chunk<'_>| {
+        let start = Instant::now();
+        let mut errors = Vec::new();
+        let value = worker(chunk, &mut errors);
+        let line_count = chunk.line_count();
+        if let Some(metrics) = metrics {
+            metrics.record_batch(BatchOperation::Chunk, line_count as usize, errors.len(), start.elapsed());
+        }
+        ChunkResult {
+            index: chunk.index,
+            offset: chunk.offset,
+            end_offset: chunk.offset + chunk.data.len() as u64,
+            first_line: chunk.first_line,
+            line_count,
+            value,
+            errors,
+        }
+    };
+
+    if chunks.len() <= 1 {
+        return chunks.iter().map(process).collect();
+    }
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = chunks
+            .iter()
+            .map(|chunk| scope.spawn(move || process(chunk)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    })
+}
+
+fn default_threads() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+fn count_lines(data: &[u8]) -> u64 {
+    let breaks = data.iter().filter(|&&b| b == b'\n').count() as u64;
+    match data.last() {
+        Some(&b'\n') | None => breaks,
+        Some(_) => breaks + 1,
+    }
+}
+
+/// Returns the offset just past the line break at or after `from`, or the end of `data`.
+pub(crate) fn line_end(data: &[u8], from: usize) -> usize {
+    data[from..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(data.len(), |i| from + i + 1)
+}
+
+/// Strips the trailing `\n` / `\r\n` of a line.
+pub(crate) fn trim_line(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered_lines(count: usize) -> String {
+        (1..=count).map(|i| format!("line {}\n", i)).collect()
+    }
+
+    fn collect_lines(chunk: &Chunk<'_>, _: &mut Vec<RecordError>) -> Vec<(u64, String)> {
+        chunk
+            .lines()
+            .map(|(n, line)| (n, String::from_utf8(line.to_vec()).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_reader_chunks_are_record_aligned_and_ordered() {
+        let input = numbered_lines(1_000);
+
+        let results: Vec<_> = process_chunks(input.as_bytes(), 100, collect_lines)
+            .threads(3)
+            .collect::<io::Result<_>>()
+            .unwrap();
+
+        assert!(results.len() > 10);
+        let lines: Vec<(u64, String)> = results.iter().flat_map(|r| r.value.clone()).collect();
+        assert_eq!(lines.len(), 1_000);
+        for (i, (n, line)) in lines.iter().enumerate() {
+            assert_eq!(*n, i as u64 + 1);
+            assert_eq!(*line, format!("line {}", i + 1));
+        }
+
+        for pair in results.windows(2) {
+            assert_eq!(pair[0].end_offset, pair[1].offset);
+            assert_eq!(pair[0].first_line + pair[0].line_count, pair[1].first_line);
+            assert_eq!(pair[0].index + 1, pair[1].index);
+        }
+    }
+
+    #[test]
+    fn test_slice_and_reader_agree() {
+        let input = format!("{}last line without break", numbered_lines(57));
+
+        let from_reader: Vec<_> = process_chunks(input.as_bytes(), 64, collect_lines)
+            .map(|r| r.unwrap().value)
+            .collect();
+        let from_slice: Vec<_> = process_slice_chunks(input.as_bytes(), 64, collect_lines)
+            .map(|r| r.value)
+            .collect();
+
+        assert_eq!(from_reader, from_slice);
+        assert_eq!(from_slice.concat().last().unwrap(), &(58, "last line without break".to_string()));
+    }
+
+    #[test]
+    fn test_long_lines_exceed_chunk_size() {
+        let input = format!("{}\nshort\n", "x".repeat(1_000));
+        let results: Vec<_> = process_chunks(input.as_bytes(), 10, collect_lines)
+            .map(|r| r.unwrap())
+            .collect();
+
+        assert_eq!(results[0].value, vec![(1, "x".repeat(1_000))]);
+        assert_eq!(results[1].value, vec![(2, "short".to_string())]);
+    }
+
+    #[test]
+    fn test_errors_carry_absolute_line_numbers() {
+        let input = "ok\nok\nbad\nok\nbad\n";
+        let worker = |chunk: &Chunk<'_>, errors: &mut Vec<RecordError>| {
+            for (line, text) in chunk.lines() {
+                if text == b"bad" {
+                    errors.push(RecordError { line, error: Grid9Error::EmptyInput });
+                }
+            }
+        };
+
+        let lines: Vec<u64> = process_slice_chunks(input.as_bytes(), 4, worker)
+            .threads(2)
+            .flat_map(|r| r.errors)
+            .map(|e| e.line)
+            .collect();
+        assert_eq!(lines, vec![3, 5]);
+
+        let resumed: Vec<u64> = process_slice_chunks(input.as_bytes(), 4, worker)
+            .resume_at(6, 2)
+            .flat_map(|r| r.errors)
+            .map(|e| e.line)
+            .collect();
+        assert_eq!(resumed, vec![3, 5]);
+    }
+
+    #[test]
+    fn test_reports_chunks_to_metrics() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Default)]
+        struct Totals(AtomicUsize, AtomicUsize, AtomicUsize);
+
+        impl Grid9Metrics for Totals {
+            fn record_batch(&self, operation: BatchOperation, items: usize, errors: usize, _: std::time::Duration) {
+                assert_eq!(operation, BatchOperation::Chunk);
+                self.0.fetch_add(1, Ordering::Relaxed);
+                self.1.fetch_add(items, Ordering::Relaxed);
+                self.2.fetch_add(errors, Ordering::Relaxed);
+            }
+        }
+
+        let input = numbered_lines(100).replace("line 7\n", "bad\n");
+        let worker = |chunk: &Chunk<'_>, errors: &mut Vec<RecordError>| {
+            for (line, text) in chunk.lines() {
+                if text == b"bad" {
+                    errors.push(RecordError { line, error: Grid9Error::EmptyInput });
+                }
+            }
+        };
+        let totals = Arc::new(Totals::default());
+        let chunks = process_chunks(input.as_bytes(), 64, worker).threads(3).metrics(totals.clone()).count();
+
+        assert_eq!(totals.0.load(Ordering::Relaxed), chunks);
+        assert_eq!(totals.1.load(Ordering::Relaxed), 100);
+        assert_eq!(totals.2.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!(process_chunks(&b""[..], 16, collect_lines).count(), 0);
+        assert_eq!(process_slice_chunks(b"", 16, collect_lines).count(), 0);
+    }
+}
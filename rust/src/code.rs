@@ -0,0 +1,335 @@
+//! Typed Grid9 codes and raw grid index access
+
+use core::fmt;
+use core::str::FromStr;
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+
+#[cfg(feature = "alloc")]
+use crate::uniform_precision_compressor::format_packed;
+use crate::uniform_precision_compressor::{
+    ascii_str, lat_of_index, lon_of_index, pack_indices, packed_chars, parse_packed, quantize, unpack_indices,
+    validate_coordinates, LAT_BITS, LON_BITS,
+};
+#[cfg(feature = "std")]
+use crate::BoundingBox;
+use crate::{Grid9Error, Result};
+
+/// Largest latitude grid index
+pub const MAX_LAT_INDEX: u32 = (1 << LAT_BITS) - 1;
+
+/// Largest longitude grid index
+pub const MAX_LON_INDEX: u32 = (1 << LON_BITS) - 1;
+
+/// Coarsest available bucket level (a single band of latitude indices), see
+/// `Grid9Bucket`
+pub const MAX_LEVEL: u8 = LAT_BITS as u8;
+
+/// A parsed Grid9 code, stored as its 45-bit payload
+///
+/// Parsing validates the code once, so accessors are infallible. `Display`
+/// writes the compact canonical form. Codes are ordered along a
+/// locality-preserving curve (see [`crate::sort_key`]).
+///
+/// # Example
+/// ```rust
+/// use grid9::Grid9Code;
+///
+/// let code: Grid9Code = "Q7K-H2B-BYF".parse()?;
+/// let (lat_index, lon_index) = code.indices();
+/// assert_eq!(Grid9Code::from_indices(lat_index, lon_index)?, code);
+/// assert_eq!(code.to_string(), "Q7KH2BBYF");
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Grid9Code(u64);
+
+impl Grid9Code {
+    /// Encodes coordinates to a code.
+    pub fn encode(latitude: f64, longitude: f64) -> Result<Self> {
+        validate_coordinates(latitude, longitude)?;
+
+        let (lat_index, lon_index) = quantize(latitude, longitude);
+        Ok(Self(pack_indices(lat_index, lon_index)))
+    }
+
+    /// Builds a code from raw grid indices.
+    ///
+    /// # Arguments
+    /// * `lat_index` - Latitude index (0 to [`MAX_LAT_INDEX`], south to north)
+    /// * `lon_index` - Longitude index (0 to [`MAX_LON_INDEX`], west to east)
+    pub fn from_indices(lat_index: u32, lon_index: u32) -> Result<Self> {
+        if lat_index > MAX_LAT_INDEX {
+            return Err(Grid9Error::InvalidIndex(lat_index));
+        }
+        if lon_index > MAX_LON_INDEX {
+            return Err(Grid9Error::InvalidIndex(lon_index));
+        }
+
+        Ok(Self(pack_indices(lat_index, lon_index)))
+    }
+
+    /// Wraps a 45-bit payload from [`Grid9Code::as_u64`] or
+    /// [`crate::encode_to_u64`]; values of 2^45 and above fail with
+    /// `InvalidIndex`, as their latitude index is beyond [`MAX_LAT_INDEX`].
+    pub fn from_u64(packed: u64) -> Result<Self> {
+        if packed >> (LAT_BITS + LON_BITS) != 0 {
+            return Err(Grid9Error::InvalidIndex(u32::try_from(packed >> LON_BITS).unwrap_or(u32::MAX)));
+        }
+
+        Ok(Self(packed))
+    }
+
+    /// The 45-bit payload, `[22-bit lat index][23-bit lon index]`
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+
+    /// The 45-bit payload as 6 big-endian bytes, the binary form of a code
+    /// in serde binary formats and CBOR.
+    ///
+    /// Comparing these bytes orders codes like their code strings, latitude
+    /// first; for keys ordered like [`Ord`] use [`Grid9Code::to_bytes`].
+    pub fn to_payload_bytes(&self) -> [u8; 6] {
+        let mut bytes = [0u8; 6];
+        bytes.copy_from_slice(&self.0.to_be_bytes()[2..]);
+        bytes
+    }
+
+    /// Returns the code of 6 bytes from [`Grid9Code::to_payload_bytes`];
+    /// fails with `InvalidIndex` if the value exceeds 45 bits.
+    pub fn from_payload_bytes(bytes: [u8; 6]) -> Result<Self> {
+        let mut padded = [0u8; 8];
+        padded[2..].copy_from_slice(&bytes);
+        Self::from_u64(u64::from_be_bytes(padded))
+    }
+
+    /// Latitude grid index
+    pub fn lat_index(&self) -> u32 {
+        unpack_indices(self.0).0
+    }
+
+    /// Longitude grid index
+    pub fn lon_index(&self) -> u32 {
+        unpack_indices(self.0).1
+    }
+
+    /// (latitude, longitude) grid indices
+    pub fn indices(&self) -> (u32, u32) {
+        unpack_indices(self.0)
+    }
+
+    /// Decoded (latitude, longitude) in degrees
+    pub fn coordinates(&self) -> (f64, f64) {
+        let (lat_index, lon_index) = self.indices();
+        (lat_of_index(lat_index as f64), lon_of_index(lon_index as f64))
+    }
+
+    /// Decoded (latitude, longitude) in degrees, like [`crate::decode`] on
+    /// the code string; same as [`Grid9Code::coordinates`]
+    pub fn decode(&self) -> (f64, f64) {
+        self.coordinates()
+    }
+
+    /// Extent of the cell: from its decoded (south-west) corner to the next
+    /// grid line north and east, see [`crate::decode_bounds`]
+    #[cfg(feature = "std")]
+    pub fn bounds(&self) -> BoundingBox {
+        let (lat_index, lon_index) = self.indices();
+        BoundingBox::new(
+            lat_of_index(lat_index as f64),
+            lat_of_index(lat_index as f64 + 1.0).min(90.0),
+            lon_of_index(lon_index as f64),
+            lon_of_index(lon_index as f64 + 1.0).min(180.0),
+        )
+    }
+
+    /// Center of the cell, see [`crate::decode_center`]
+    #[cfg(feature = "std")]
+    pub fn center(&self) -> (f64, f64) {
+        let cell = self.bounds();
+        ((cell.min_lat + cell.max_lat) / 2.0, (cell.min_lon + cell.max_lon) / 2.0)
+    }
+
+    /// Formats the code, optionally as XXX-XXX-XXX
+    #[cfg(feature = "alloc")]
+    pub fn to_code_string(&self, human_readable: bool) -> String {
+        format_packed(self.0, human_readable)
+    }
+
+    /// Formats the code as XXX-XXX-XXX
+    #[cfg(feature = "alloc")]
+    pub fn to_human_readable(&self) -> String {
+        format_packed(self.0, true)
+    }
+
+    /// Wraps a 45-bit payload
+    #[cfg(feature = "std")]
+    pub(crate) fn from_packed(packed: u64) -> Self {
+        Self(packed)
+    }
+}
+
+impl FromStr for Grid9Code {
+    type Err = Grid9Error;
+
+    fn from_str(encoded: &str) -> Result<Self> {
+        parse_packed(encoded).map(Self)
+    }
+}
+
+impl TryFrom<&str> for Grid9Code {
+    type Error = Grid9Error;
+
+    fn try_from(encoded: &str) -> Result<Self> {
+        encoded.parse()
+    }
+}
+
+impl fmt::Display for Grid9Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(ascii_str(&packed_chars(self.0)))
+    }
+}
+
+/// Serializes as the canonical code string in human-readable formats (JSON,
+/// TOML) and as the 6-byte big-endian payload in binary formats, so a code
+/// takes exactly 6 bytes with postcard or bincode.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Grid9Code {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            self.to_payload_bytes().serialize(serializer)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Grid9Code {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        use serde::de::Error;
+
+        if deserializer.is_human_readable() {
+            let text = String::deserialize(deserializer)?;
+            text.parse().map_err(D::Error::custom)
+        } else {
+            let bytes = <[u8; 6]>::deserialize(deserializer)?;
+            Self::from_payload_bytes(bytes).map_err(D::Error::custom)
+        }
+    }
+}
+
+/// Logs the compact canonical form without allocating.
+#[cfg(feature = "defmt")]
+impl defmt::Format for Grid9Code {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "{=str}", ascii_str(&packed_chars(self.0)))
+    }
+}
+
+/// Returns the raw grid indices of a Grid9 code.
+///
+/// Latitude indices grow from south to north and longitude indices from west
+/// to east; neighboring cells differ by one in a single index.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code (dashes are automatically removed)
+///
+/// # Returns
+/// A tuple of (latitude index, longitude index)
+///
+/// # Example
+/// ```rust
+/// use grid9::{from_indices, to_indices};
+///
+/// let (lat_index, lon_index) = to_indices("Q7KH2BBYF")?;
+/// let north = from_indices(lat_index + 1, lon_index, false)?;
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn to_indices(encoded: &str) -> Result<(u32, u32)> {
+    Ok(unpack_indices(parse_packed(encoded)?))
+}
+
+/// Builds a Grid9 code from raw grid indices.
+///
+/// # Arguments
+/// * `lat_index` - Latitude index (0 to [`MAX_LAT_INDEX`])
+/// * `lon_index` - Longitude index (0 to [`MAX_LON_INDEX`])
+/// * `human_readable` - If true, returns XXX-XXX-XXX format with dashes
+///
+/// # Returns
+/// The Grid9 code of the cell
+#[cfg(feature = "alloc")]
+pub fn from_indices(lat_index: u32, lon_index: u32, human_readable: bool) -> Result<String> {
+    Grid9Code::from_indices(lat_index, lon_index).map(|code| code.to_code_string(human_readable))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, encode};
+
+    #[test]
+    fn test_indices_roundtrip() {
+        for (lat, lon) in [(40.7128, -74.0060), (-90.0, -180.0), (90.0, 180.0)] {
+            let code = encode(lat, lon, false).unwrap();
+            let (lat_index, lon_index) = to_indices(&code).unwrap();
+
+            assert_eq!(from_indices(lat_index, lon_index, false).unwrap(), code);
+            assert_eq!(Grid9Code::encode(lat, lon).unwrap().indices(), (lat_index, lon_index));
+        }
+
+        assert_eq!(to_indices("00000-0000").unwrap(), (0, 0));
+        assert_eq!(to_indices("ZZZZZZZZZ").unwrap(), (MAX_LAT_INDEX, MAX_LON_INDEX));
+    }
+
+    #[test]
+    fn test_index_steps_move_one_cell() {
+        let (lat_index, lon_index) = to_indices("Q7KH2BBYF").unwrap();
+        let (lat, lon) = decode("Q7KH2BBYF").unwrap();
+        let (north_lat, north_lon) = decode(&from_indices(lat_index + 1, lon_index, false).unwrap()).unwrap();
+
+        assert!(north_lat > lat && north_lat - lat < 1e-4);
+        assert_eq!(north_lon, lon);
+    }
+
+    #[test]
+    fn test_code_accessors() {
+        let code: Grid9Code = "Q7K-H2B-BYF".parse().unwrap();
+
+        assert_eq!(code.to_string(), "Q7KH2BBYF");
+        assert_eq!(code.to_code_string(true), "Q7K-H2B-BYF");
+        assert_eq!(code.to_human_readable(), "Q7K-H2B-BYF");
+        assert_eq!(Grid9Code::try_from("Q7KH2BBYF"), Ok(code));
+        assert_eq!(Grid9Code::try_from("Q7KH2BBY!"), "Q7KH2BBY!".parse());
+        assert_eq!(code.indices(), (code.lat_index(), code.lon_index()));
+        assert_eq!(code.coordinates(), decode("Q7KH2BBYF").unwrap());
+        assert_eq!(code.decode(), code.coordinates());
+        assert_eq!("BAD".parse::<Grid9Code>(), Err(Grid9Error::InvalidLength(3)));
+    }
+
+    #[test]
+    fn test_out_of_range_indices() {
+        assert_eq!(from_indices(MAX_LAT_INDEX + 1, 0, false), Err(Grid9Error::InvalidIndex(MAX_LAT_INDEX + 1)));
+        assert_eq!(Grid9Code::from_indices(0, MAX_LON_INDEX + 1), Err(Grid9Error::InvalidIndex(MAX_LON_INDEX + 1)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_forms() {
+        let code: Grid9Code = "Q7KH2BBYF".parse().unwrap();
+
+        let bytes = postcard::to_allocvec(&code).unwrap();
+        assert_eq!(bytes.len(), 6);
+        assert_eq!(postcard::from_bytes::<Grid9Code>(&bytes).unwrap(), code);
+        assert!(postcard::from_bytes::<Grid9Code>(&[0x20, 0, 0, 0, 0, 0]).is_err());
+
+        assert_eq!(serde_json::to_string(&code).unwrap(), "\"Q7KH2BBYF\"");
+        assert_eq!(serde_json::from_str::<Grid9Code>("\"Q7K-H2B-BYF\"").unwrap(), code);
+        assert!(serde_json::from_str::<Grid9Code>("\"q7kh2bbyf\"").is_err());
+        assert!(serde_json::from_str::<Grid9Code>("\"Q7KH2BBYU\"").is_err());
+    }
+}
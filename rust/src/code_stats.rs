@@ -0,0 +1,205 @@
+//! Bounding boxes, centers and spread computed directly over Grid9 codes
+//!
+//! Counterparts of [`crate::get_bounding_box`] and [`crate::get_center_point`]
+//! for data that is already encoded, working on grid indices instead of a
+//! decoded `Vec<Coordinate>`.
+
+use std::collections::HashSet;
+
+use crate::uniform_precision_compressor::{haversine_distance, lat_of_index, lon_of_index};
+use crate::{BoundingBox, Coordinate, Grid9Code, Grid9Error, Result, MAX_LAT_INDEX, MAX_LON_INDEX};
+
+/// How widely a set of codes is scattered, from [`spread_stats`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpreadStats {
+    /// Mean of the cell centers (as [`center_of_codes`])
+    pub center: Coordinate,
+    /// Root-mean-square distance of the cell centers from `center`, in meters
+    pub std_dev_radius_m: f64,
+    /// Largest distance between two cell centers, in meters
+    pub max_pairwise_distance_m: f64,
+}
+
+/// Calculates the bounding box covering every cell of the given codes.
+///
+/// Unlike [`crate::get_bounding_box`] over decoded corners, the box extends
+/// to the far edges of the northernmost and easternmost cells.
+///
+/// # Arguments
+/// * `codes` - Codes to enclose
+///
+/// # Returns
+/// Bounding box in degrees
+///
+/// # Example
+/// ```rust
+/// use grid9::{bounding_box_of_codes, Grid9Code};
+///
+/// let codes = [Grid9Code::encode(40.7128, -74.0060)?, Grid9Code::encode(40.7580, -73.9855)?];
+/// let bbox = bounding_box_of_codes(&codes)?;
+/// assert!(bbox.min_lat <= 40.7128 && bbox.max_lat > 40.7580);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn bounding_box_of_codes(codes: &[Grid9Code]) -> Result<BoundingBox> {
+    let first = codes.first().ok_or(Grid9Error::EmptyInput)?.indices();
+    let (mut min_lat, mut min_lon) = first;
+    let (mut max_lat, mut max_lon) = first;
+
+    for code in &codes[1..] {
+        let (lat_index, lon_index) = code.indices();
+        min_lat = min_lat.min(lat_index);
+        max_lat = max_lat.max(lat_index);
+        min_lon = min_lon.min(lon_index);
+        max_lon = max_lon.max(lon_index);
+    }
+
+    Ok(BoundingBox::new(
+        lat_of_index(min_lat as f64),
+        lat_of_index((max_lat + 1).min(MAX_LAT_INDEX) as f64),
+        lon_of_index(min_lon as f64),
+        lon_of_index((max_lon + 1).min(MAX_LON_INDEX) as f64),
+    ))
+}
+
+/// Calculates the mean of the cell centers of the given codes.
+///
+/// Averages latitude and longitude like [`crate::get_center_point`], so it
+/// suits sets that do not span the antimeridian.
+///
+/// # Arguments
+/// * `codes` - Codes to average
+///
+/// # Returns
+/// Center coordinate in degrees
+pub fn center_of_codes(codes: &[Grid9Code]) -> Result<Coordinate> {
+    if codes.is_empty() {
+        return Err(Grid9Error::EmptyInput);
+    }
+
+    let (lat_sum, lon_sum) = codes.iter().fold((0u64, 0u64), |(lat_sum, lon_sum), code| {
+        let (lat_index, lon_index) = code.indices();
+        (lat_sum + lat_index as u64, lon_sum + lon_index as u64)
+    });
+    let count = codes.len() as f64;
+
+    Ok(Coordinate::new(
+        lat_of_index((lat_sum as f64 / count + 0.5).min(MAX_LAT_INDEX as f64)),
+        lon_of_index((lon_sum as f64 / count + 0.5).min(MAX_LON_INDEX as f64)),
+    ))
+}
+
+/// Calculates the center, standard-deviation radius and diameter of a set
+/// of codes.
+///
+/// Distances are great-circle distances between cell centers. The maximum
+/// pairwise distance compares every pair of distinct cells, so it takes time
+/// quadratic in the number of distinct codes.
+///
+/// # Arguments
+/// * `codes` - Codes to measure
+///
+/// # Returns
+/// [`SpreadStats`] for the set
+///
+/// # Example
+/// ```rust
+/// use grid9::{spread_stats, Grid9Code};
+///
+/// let codes = [Grid9Code::encode(40.7128, -74.0060)?, Grid9Code::encode(40.7580, -73.9855)?];
+/// let stats = spread_stats(&codes)?;
+/// assert!((stats.max_pairwise_distance_m - 5_300.0).abs() < 100.0);
+/// assert!((stats.std_dev_radius_m - stats.max_pairwise_distance_m / 2.0).abs() < 10.0);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn spread_stats(codes: &[Grid9Code]) -> Result<SpreadStats> {
+    let center = center_of_codes(codes)?;
+
+    let squared_sum: f64 = codes
+        .iter()
+        .map(|code| {
+            let (lat, lon) = cell_center(code);
+            haversine_distance(center.lat, center.lon, lat, lon).powi(2)
+        })
+        .sum();
+
+    let distinct: HashSet<&Grid9Code> = codes.iter().collect();
+    let centers: Vec<(f64, f64)> = distinct.into_iter().map(cell_center).collect();
+
+    let mut max_pairwise_distance_m = 0.0f64;
+    for (i, &(lat1, lon1)) in centers.iter().enumerate() {
+        for &(lat2, lon2) in &centers[i + 1..] {
+            max_pairwise_distance_m = max_pairwise_distance_m.max(haversine_distance(lat1, lon1, lat2, lon2));
+        }
+    }
+
+    Ok(SpreadStats {
+        center,
+        std_dev_radius_m: (squared_sum / codes.len() as f64).sqrt(),
+        max_pairwise_distance_m,
+    })
+}
+
+fn cell_center(code: &Grid9Code) -> (f64, f64) {
+    let (lat_index, lon_index) = code.indices();
+    // The last index of each axis is the pole or antimeridian itself, not a cell
+    (
+        lat_of_index((lat_index as f64 + 0.5).min(MAX_LAT_INDEX as f64)),
+        lon_of_index((lon_index as f64 + 0.5).min(MAX_LON_INDEX as f64)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_bounding_box, get_center_point};
+
+    fn codes(points: &[(f64, f64)]) -> Vec<Grid9Code> {
+        points.iter().map(|&(lat, lon)| Grid9Code::encode(lat, lon).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_bounding_box_of_codes() {
+        let points = [(40.0, -75.0), (41.0, -73.0), (39.0, -76.0)];
+        let bbox = bounding_box_of_codes(&codes(&points)).unwrap();
+        let corners = get_bounding_box(&points.map(|(lat, lon)| Coordinate::new(lat, lon))).unwrap();
+
+        assert!((bbox.min_lat - corners.min_lat).abs() < 1e-4 && bbox.min_lat <= 39.0);
+        assert!((bbox.max_lat - corners.max_lat).abs() < 1e-4 && bbox.max_lat > 41.0);
+        assert!((bbox.min_lon - corners.min_lon).abs() < 1e-4 && bbox.min_lon <= -76.0);
+        assert!((bbox.max_lon - corners.max_lon).abs() < 1e-4 && bbox.max_lon > -73.0);
+
+        let single = bounding_box_of_codes(&codes(&[(90.0, 180.0)])).unwrap();
+        assert_eq!((single.max_lat, single.max_lon), (90.0, 180.0));
+    }
+
+    #[test]
+    fn test_center_of_codes() {
+        let points = [(40.0, -75.0), (42.0, -73.0)];
+        let center = center_of_codes(&codes(&points)).unwrap();
+        let expected = get_center_point(&points.map(|(lat, lon)| Coordinate::new(lat, lon))).unwrap();
+        assert!((center.lat - expected.lat).abs() < 1e-4);
+        assert!((center.lon - expected.lon).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_spread_stats() {
+        let single = spread_stats(&codes(&[(51.5074, -0.1278); 3])).unwrap();
+        assert_eq!(single.std_dev_radius_m, 0.0);
+        assert_eq!(single.max_pairwise_distance_m, 0.0);
+
+        // Three points on the equator, 1° apart
+        let line = spread_stats(&codes(&[(0.0, 0.0), (0.0, 1.0), (0.0, 2.0)])).unwrap();
+        let degree = haversine_distance(0.0, 0.0, 0.0, 1.0);
+        assert!((line.max_pairwise_distance_m - 2.0 * degree).abs() < 10.0);
+        assert!((line.std_dev_radius_m - degree * (2.0f64 / 3.0).sqrt()).abs() < 10.0);
+        assert!((line.center.lon - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_empty_codes() {
+        assert_eq!(bounding_box_of_codes(&[]), Err(Grid9Error::EmptyInput));
+        assert_eq!(center_of_codes(&[]), Err(Grid9Error::EmptyInput));
+        assert_eq!(spread_stats(&[]), Err(Grid9Error::EmptyInput));
+    }
+}
@@ -0,0 +1,159 @@
+//! Pluggable text encodings of the 45-bit Grid9 payload
+
+use crate::uniform_precision_compressor::{
+    lat_of_index, lon_of_index, pack_indices, quantize, unpack_indices, validate_coordinates,
+};
+use crate::{Grid9Decoder, Grid9Encoder, Result};
+
+/// Mask selecting the 45 payload bits
+pub(crate) const PAYLOAD_MASK: u64 = (1 << 45) - 1;
+
+/// A text encoding of the 45-bit Grid9 payload (`[22-bit lat index][23-bit lon index]`)
+///
+/// Implementors only spell and parse payloads; quantization of coordinates is
+/// shared, so every codec addresses the same cells. Alternative encodings
+/// (Morton order, error-correcting, keyed or word-based codes) implement this
+/// trait to plug into the batch and file conversion functions
+/// (`batch_encode_with_codec`, `convert_file_with_codec`, ...).
+///
+/// # Example
+/// ```rust
+/// use grid9::{Codec, Grid9Codec, Result};
+///
+/// /// Codes written as plain hexadecimal payloads
+/// struct HexCodec;
+///
+/// impl Codec for HexCodec {
+///     fn encode_payload(&self, payload: u64) -> String {
+///         format!("{:012x}", payload)
+///     }
+///
+///     fn decode_payload(&self, encoded: &str) -> Result<u64> {
+///         u64::from_str_radix(encoded, 16)
+///             .map_err(|e| grid9::Grid9Error::InvalidInput(e.to_string().into()))
+///     }
+/// }
+///
+/// let hex = HexCodec.encode(40.7128, -74.0060)?;
+/// let grid9 = Grid9Codec::new().encode(40.7128, -74.0060)?;
+/// assert_eq!(HexCodec.decode(&hex)?, Grid9Codec::new().decode(&grid9)?);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub trait Codec: Send + Sync {
+    /// Spells a payload as text; only the low 45 bits are used.
+    fn encode_payload(&self, payload: u64) -> String;
+
+    /// Parses text back into its payload.
+    fn decode_payload(&self, encoded: &str) -> Result<u64>;
+
+    /// Checks that `encoded` is accepted by this codec.
+    fn validate(&self, encoded: &str) -> Result<()> {
+        self.decode_payload(encoded).map(|_| ())
+    }
+
+    /// Encodes coordinates with this codec.
+    fn encode(&self, latitude: f64, longitude: f64) -> Result<String> {
+        validate_coordinates(latitude, longitude)?;
+
+        let (lat_bits, lon_bits) = quantize(latitude, longitude);
+        Ok(self.encode_payload(pack_indices(lat_bits, lon_bits)))
+    }
+
+    /// Decodes text produced by this codec to coordinates.
+    fn decode(&self, encoded: &str) -> Result<(f64, f64)> {
+        let (lat_bits, lon_bits) = unpack_indices(self.decode_payload(encoded)? & PAYLOAD_MASK);
+
+        Ok((lat_of_index(lat_bits as f64), lon_of_index(lon_bits as f64)))
+    }
+}
+
+/// The standard Grid9 text encoding, optionally customized through
+/// [`Grid9Encoder`] and [`Grid9Decoder`] options
+#[derive(Debug, Clone, Default)]
+pub struct Grid9Codec {
+    encoder: Grid9Encoder,
+    decoder: Grid9Decoder,
+}
+
+impl Grid9Codec {
+    /// The default codec: compact codes, lenient parsing (same as [`crate::encode`] / [`crate::decode`])
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A codec writing with `encoder` and reading with `decoder`
+    pub fn from_parts(encoder: Grid9Encoder, decoder: Grid9Decoder) -> Self {
+        Self { encoder, decoder }
+    }
+}
+
+impl Codec for Grid9Codec {
+    fn encode_payload(&self, payload: u64) -> String {
+        self.encoder.format_packed(payload & PAYLOAD_MASK)
+    }
+
+    fn decode_payload(&self, encoded: &str) -> Result<u64> {
+        self.decoder.parse_packed(encoded)
+    }
+
+    fn encode(&self, latitude: f64, longitude: f64) -> Result<String> {
+        // Honors the encoder's rounding option
+        self.encoder.encode(latitude, longitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, encode, Grid9Error};
+
+    #[test]
+    fn test_grid9_codec_matches_free_functions() {
+        let codec = Grid9Codec::new();
+
+        for (lat, lon) in [(40.7128, -74.0060), (-33.8688, 151.2093), (90.0, -180.0)] {
+            let code = codec.encode(lat, lon).unwrap();
+            assert_eq!(code, encode(lat, lon, false).unwrap());
+            assert_eq!(codec.decode(&code).unwrap(), decode(&code).unwrap());
+            assert_eq!(codec.encode_payload(codec.decode_payload(&code).unwrap()), code);
+        }
+
+        assert_eq!(codec.validate("BAD"), Err(Grid9Error::InvalidLength(3)));
+        assert_eq!(codec.encode(91.0, 0.0), Err(Grid9Error::InvalidLatitude(91.0)));
+    }
+
+    #[test]
+    fn test_codec_from_parts() {
+        let codec = Grid9Codec::from_parts(
+            Grid9Encoder::builder().human_readable(true).checksum(true).build(),
+            Grid9Decoder::builder().checksum(true).build(),
+        );
+        let code = codec.encode(51.5074, -0.1278).unwrap();
+
+        assert_eq!(code.len(), 13);
+        assert!(codec.validate(&code).is_ok());
+        assert!(Grid9Codec::new().validate(&code).is_err());
+    }
+
+    #[test]
+    fn test_trait_objects() {
+        struct Decimal;
+
+        impl Codec for Decimal {
+            fn encode_payload(&self, payload: u64) -> String {
+                payload.to_string()
+            }
+
+            fn decode_payload(&self, encoded: &str) -> Result<u64> {
+                encoded.parse().map_err(|_| Grid9Error::InvalidInput(encoded.to_string().into()))
+            }
+        }
+
+        let codecs: [&dyn Codec; 2] = [&Grid9Codec::new(), &Decimal];
+        let decoded: Vec<(f64, f64)> = codecs
+            .iter()
+            .map(|codec| codec.decode(&codec.encode(35.6762, 139.6503).unwrap()).unwrap())
+            .collect();
+        assert_eq!(decoded[0], decoded[1]);
+    }
+}
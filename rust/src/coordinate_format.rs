@@ -0,0 +1,152 @@
+//! Text formats for coordinates
+//!
+//! `Coordinate` implements `Display` as signed decimal degrees; other
+//! notations are selected with [`Coordinate::display`]. A precision in the
+//! format string (`{:.2}`) sets the number of decimals of the last component.
+
+use std::fmt;
+
+use crate::Coordinate;
+
+/// Notation used by [`Coordinate::display`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CoordinateFormat {
+    /// Signed decimal degrees, `40.712800, -74.006000` (6 decimals by default)
+    #[default]
+    DecimalDegrees,
+    /// Degrees, minutes and seconds, `40°42'46.08"N 74°00'21.60"W` (2 decimals by default)
+    Dms,
+    /// Degrees and decimal minutes, `40°42.7680'N 74°00.3600'W` (4 decimals by default)
+    Ddm,
+}
+
+impl CoordinateFormat {
+    fn default_decimals(self) -> usize {
+        match self {
+            CoordinateFormat::DecimalDegrees => 6,
+            CoordinateFormat::Dms => 2,
+            CoordinateFormat::Ddm => 4,
+        }
+    }
+}
+
+/// A coordinate formatted in a chosen notation, from [`Coordinate::display`]
+#[derive(Debug, Clone, Copy)]
+pub struct CoordinateDisplay<'a> {
+    coordinate: &'a Coordinate,
+    format: CoordinateFormat,
+}
+
+impl Coordinate {
+    /// Formats the coordinate in the given notation.
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::{Coordinate, CoordinateFormat};
+    ///
+    /// let nyc = Coordinate::new(40.7128, -74.0060);
+    /// assert_eq!(nyc.to_string(), "40.712800, -74.006000");
+    /// assert_eq!(nyc.display(CoordinateFormat::Dms).to_string(), "40°42'46.08\"N 74°00'21.60\"W");
+    /// assert_eq!(format!("{:.1}", nyc.display(CoordinateFormat::Ddm)), "40°42.8'N 74°00.4'W");
+    /// ```
+    pub fn display(&self, format: CoordinateFormat) -> CoordinateDisplay<'_> {
+        CoordinateDisplay { coordinate: self, format }
+    }
+}
+
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display(CoordinateFormat::DecimalDegrees).fmt(f)
+    }
+}
+
+impl fmt::Display for CoordinateDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decimals = f.precision().unwrap_or(self.format.default_decimals());
+        let Coordinate { lat, lon } = *self.coordinate;
+
+        match self.format {
+            CoordinateFormat::DecimalDegrees => write!(f, "{:.*}, {:.*}", decimals, lat, decimals, lon),
+            CoordinateFormat::Dms | CoordinateFormat::Ddm => {
+                let with_seconds = self.format == CoordinateFormat::Dms;
+                write_sexagesimal(f, lat, ('N', 'S'), with_seconds, decimals)?;
+                f.write_str(" ")?;
+                write_sexagesimal(f, lon, ('E', 'W'), with_seconds, decimals)
+            }
+        }
+    }
+}
+
+/// Writes `|value|` as degrees and minutes (and seconds), followed by the
+/// hemisphere letter.
+///
+/// Rounds once, in units of the last printed decimal, so values such as
+/// 59.9999" carry into the next minute instead of printing as 60".
+fn write_sexagesimal(
+    f: &mut fmt::Formatter<'_>,
+    value: f64,
+    (positive, negative): (char, char),
+    with_seconds: bool,
+    decimals: usize,
+) -> fmt::Result {
+    if !value.is_finite() {
+        return write!(f, "{}", value);
+    }
+
+    let scale = 10u64.pow(decimals.min(9) as u32);
+    let units_per_degree = if with_seconds { 3600 } else { 60 } * scale;
+    let total = (value.abs() * units_per_degree as f64).round() as u64;
+
+    let degrees = total / units_per_degree;
+    let rest = total % units_per_degree;
+    let hemisphere = if value < 0.0 && total > 0 { negative } else { positive };
+
+    let (minutes, last) = if with_seconds {
+        (rest / (60 * scale), rest % (60 * scale))
+    } else {
+        (0, rest)
+    };
+    if with_seconds {
+        write!(f, "{}°{:02}'", degrees, minutes)?;
+    } else {
+        write!(f, "{}°", degrees)?;
+    }
+
+    let (whole, fraction) = (last / scale, last % scale);
+    match (decimals.min(9), with_seconds) {
+        (0, true) => write!(f, "{:02}\"{}", whole, hemisphere),
+        (0, false) => write!(f, "{:02}'{}", whole, hemisphere),
+        (width, true) => write!(f, "{:02}.{:0width$}\"{}", whole, fraction, hemisphere, width = width),
+        (width, false) => write!(f, "{:02}.{:0width$}'{}", whole, fraction, hemisphere, width = width),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal_degrees() {
+        let sydney = Coordinate::new(-33.8688, 151.2093);
+        assert_eq!(sydney.to_string(), "-33.868800, 151.209300");
+        assert_eq!(format!("{:.2}", sydney), "-33.87, 151.21");
+        assert_eq!(sydney.display(CoordinateFormat::default()).to_string(), sydney.to_string());
+    }
+
+    #[test]
+    fn test_sexagesimal() {
+        let sydney = Coordinate::new(-33.8688, 151.2093);
+        assert_eq!(sydney.display(CoordinateFormat::Dms).to_string(), "33°52'07.68\"S 151°12'33.48\"E");
+        assert_eq!(sydney.display(CoordinateFormat::Ddm).to_string(), "33°52.1280'S 151°12.5580'E");
+        assert_eq!(format!("{:.0}", sydney.display(CoordinateFormat::Dms)), "33°52'08\"S 151°12'33\"E");
+
+        // Rounding carries into minutes and degrees rather than printing 60
+        let edge = Coordinate::new(10.0 - 1e-7, -(20.0 - 1e-7));
+        assert_eq!(edge.display(CoordinateFormat::Dms).to_string(), "10°00'00.00\"N 20°00'00.00\"W");
+        assert_eq!(edge.display(CoordinateFormat::Ddm).to_string(), "10°00.0000'N 20°00.0000'W");
+
+        let origin = Coordinate::new(-0.0, 0.0);
+        assert_eq!(origin.display(CoordinateFormat::Dms).to_string(), "0°00'00.00\"N 0°00'00.00\"E");
+    }
+}
@@ -1,6 +1,10 @@
 //! High-performance coordinate operations with batch processing capabilities
 
-use crate::{decode, encode, calculate_distance, Grid9Error, Result};
+use std::time::Instant;
+
+use crate::locality::validate_bbox;
+use crate::uniform_precision_compressor::{pack_indices, quantize, validate_coordinates, write_packed, METERS_PER_DEGREE};
+use crate::{decode, encode, calculate_distance, BatchOperation, Codec, Grid9Bucket, Grid9Error, Grid9Metrics, NoopMetrics, Result};
 
 /// A coordinate point with latitude and longitude
 #[derive(Debug, Clone, PartialEq)]
@@ -15,6 +19,58 @@ impl Coordinate {
     pub fn new(lat: f64, lon: f64) -> Self {
         Self { lat, lon }
     }
+
+    /// Stable integer key of the coordinate at a bucket level, for
+    /// `HashMap`/`HashSet` membership instead of hashing raw `f64`s.
+    ///
+    /// Coordinates in the same [`Grid9Bucket`] of `level` share a key (level 0
+    /// = the ~3m Grid9 cell); the key is the bucket's [`Grid9Bucket::key`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use std::collections::HashSet;
+    /// use grid9::Coordinate;
+    ///
+    /// let mut seen = HashSet::new();
+    /// assert!(seen.insert(Coordinate::new(40.7128, -74.0060).quantized_key(5)?));
+    /// assert!(!seen.insert(Coordinate::new(40.71281, -74.00601).quantized_key(5)?));
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn quantized_key(&self, level: u8) -> Result<u64> {
+        Ok(Grid9Bucket::from_coordinates(self.lat, self.lon, level)?.key())
+    }
+}
+
+/// `(latitude, longitude)` in degrees, in that order
+impl From<(f64, f64)> for Coordinate {
+    fn from((lat, lon): (f64, f64)) -> Self {
+        Self::new(lat, lon)
+    }
+}
+
+/// `[latitude, longitude]` in degrees, in that order (not GeoJSON's `[lon, lat]`)
+impl From<[f64; 2]> for Coordinate {
+    fn from([lat, lon]: [f64; 2]) -> Self {
+        Self::new(lat, lon)
+    }
+}
+
+impl From<&Coordinate> for Coordinate {
+    fn from(coordinate: &Coordinate) -> Self {
+        coordinate.clone()
+    }
+}
+
+impl From<Coordinate> for (f64, f64) {
+    fn from(coordinate: Coordinate) -> Self {
+        (coordinate.lat, coordinate.lon)
+    }
+}
+
+impl From<Coordinate> for [f64; 2] {
+    fn from(coordinate: Coordinate) -> Self {
+        [coordinate.lat, coordinate.lon]
+    }
 }
 
 /// A bounding box defined by minimum and maximum coordinates
@@ -37,6 +93,114 @@ impl BoundingBox {
             max_lon,
         }
     }
+
+    /// True if a position lies inside the box or on its edges.
+    ///
+    /// Boxes that share an edge both contain positions on it.
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        (self.min_lat..=self.max_lat).contains(&latitude) && (self.min_lon..=self.max_lon).contains(&longitude)
+    }
+
+    /// Splits the box into a grid of `n_rows` by `n_cols` equal boxes (in
+    /// degrees), e.g. as work units for parallel jobs.
+    ///
+    /// # Arguments
+    /// * `n_rows` - Number of rows (latitude bands), at least 1
+    /// * `n_cols` - Number of columns (longitude bands), at least 1
+    ///
+    /// # Returns
+    /// Row-major boxes, south-west first; neighbouring boxes share edges
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::BoundingBox;
+    ///
+    /// let tiles = BoundingBox::new(40.0, 42.0, -75.0, -72.0).split(2, 3)?;
+    /// assert_eq!(tiles.len(), 6);
+    /// assert_eq!(tiles[0], BoundingBox::new(40.0, 41.0, -75.0, -74.0));
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn split(&self, n_rows: usize, n_cols: usize) -> Result<Vec<BoundingBox>> {
+        validate_bbox(self)?;
+        if n_rows == 0 || n_cols == 0 {
+            return Err(Grid9Error::InvalidInput("a box must be split into at least one row and column".into()));
+        }
+
+        let lat_edges = band_edges(self.min_lat, self.max_lat, n_rows);
+        let lon_edges = band_edges(self.min_lon, self.max_lon, n_cols);
+        Ok(lat_edges
+            .windows(2)
+            .flat_map(|lat| lon_edges.windows(2).map(move |lon| BoundingBox::new(lat[0], lat[1], lon[0], lon[1])))
+            .collect())
+    }
+
+    /// Divides the box into tiles of at most `width_m` by `height_m`.
+    ///
+    /// Rows of tiles have equal heights. Each row is split into equal-width
+    /// columns, and the number of columns is chosen from the row's widest
+    /// parallel. Rows nearer the poles therefore have fewer columns.
+    ///
+    /// # Arguments
+    /// * `width_m` - Maximum tile width in meters
+    /// * `height_m` - Maximum tile height in meters
+    ///
+    /// # Returns
+    /// Row-major tiles, south-west first; neighbouring tiles share edges
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::BoundingBox;
+    ///
+    /// let manhattan = BoundingBox::new(40.70, 40.88, -74.02, -73.91);
+    /// let tiles = manhattan.tiles_of_size(5_000.0, 5_000.0)?; // ~20km by ~9km
+    /// assert_eq!(tiles.len(), 5 * 2);
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn tiles_of_size(&self, width_m: f64, height_m: f64) -> Result<Vec<BoundingBox>> {
+        validate_bbox(self)?;
+        for size in [width_m, height_m] {
+            if !size.is_finite() || size <= 0.0 {
+                return Err(Grid9Error::InvalidInput(format!("invalid tile size {}", size).into()));
+            }
+        }
+
+        let n_rows = ((self.max_lat - self.min_lat) * METERS_PER_DEGREE / height_m).ceil().max(1.0) as usize;
+        let mut tiles = Vec::new();
+        for lat in band_edges(self.min_lat, self.max_lat, n_rows).windows(2) {
+            // The row is widest on its parallel closest to the equator
+            let widest_lat = if lat[0] > 0.0 { lat[0] } else { lat[1].min(0.0) };
+            let row_width_m = (self.max_lon - self.min_lon) * METERS_PER_DEGREE * widest_lat.to_radians().cos();
+            let n_cols = (row_width_m / width_m).ceil().max(1.0) as usize;
+            tiles.extend(
+                band_edges(self.min_lon, self.max_lon, n_cols)
+                    .windows(2)
+                    .map(|lon| BoundingBox::new(lat[0], lat[1], lon[0], lon[1])),
+            );
+        }
+        Ok(tiles)
+    }
+}
+
+/// Encodes a coordinate given as a [`Coordinate`], a `(lat, lon)` tuple or a
+/// `[lat, lon]` array.
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, encode_coordinate};
+///
+/// assert_eq!(encode_coordinate((40.7128, -74.0060), false)?, encode(40.7128, -74.0060, false)?);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn encode_coordinate(coordinate: impl Into<Coordinate>, human_readable: bool) -> Result<String> {
+    let coordinate = coordinate.into();
+    encode(coordinate.lat, coordinate.lon, human_readable)
+}
+
+/// `n + 1` evenly spaced values from `min` to exactly `max`
+fn band_edges(min: f64, max: f64, n: usize) -> Vec<f64> {
+    (0..=n)
+        .map(|i| if i == n { max } else { min + (max - min) * i as f64 / n as f64 })
+        .collect()
 }
 
 /// Batch encodes multiple coordinate pairs for high-throughput scenarios.
@@ -60,16 +224,46 @@ impl BoundingBox {
 /// # Ok::<(), grid9::Grid9Error>(())
 /// ```
 pub fn batch_encode(coordinates: &[Coordinate], human_readable: bool) -> Result<Vec<String>> {
-    coordinates
+    batch_encode_with_metrics(coordinates, human_readable, &NoopMetrics)
+}
+
+/// Batch encodes coordinates like [`batch_encode`], reporting the batch size,
+/// failures and elapsed time to `metrics`.
+///
+/// The batch stops at the first invalid coordinate, so a failed batch
+/// reports the coordinates processed up to and including it, with one error.
+///
+/// # Arguments
+/// * `coordinates` - Vector of coordinates to encode
+/// * `human_readable` - If true, returns codes in XXX-XXX-XXX format
+/// * `metrics` - Receiver for the batch metrics
+///
+/// # Returns
+/// Vector of encoded Grid9 strings
+pub fn batch_encode_with_metrics(
+    coordinates: &[Coordinate],
+    human_readable: bool,
+    metrics: &dyn Grid9Metrics,
+) -> Result<Vec<String>> {
+    let start = Instant::now();
+    let mut processed = 0;
+    let result: Result<Vec<String>> = coordinates
         .iter()
-        .map(|coord| encode(coord.lat, coord.lon, human_readable))
-        .collect()
+        .map(|coord| {
+            processed += 1;
+            encode(coord.lat, coord.lon, human_readable)
+        })
+        .collect();
+    
+    let errors = usize::from(result.is_err());
+    metrics.record_batch(BatchOperation::Encode, processed, errors, start.elapsed());
+    result
 }
 
 /// Batch decodes multiple encoded strings for high-throughput scenarios.
 ///
 /// # Arguments
-/// * `encoded` - Vector of encoded Grid9 strings
+/// * `encoded` - Encoded Grid9 strings (`String`s or `&str`s)
 ///
 /// # Returns
 /// Vector of coordinate objects
@@ -85,16 +279,422 @@ pub fn batch_encode(coordinates: &[Coordinate], human_readable: bool) -> Result<
 /// let decoded = batch_decode(&coords)?;
 /// # Ok::<(), grid9::Grid9Error>(())
 /// ```
-pub fn batch_decode(encoded: &[String]) -> Result<Vec<Coordinate>> {
+pub fn batch_decode<S: AsRef<str>>(encoded: &[S]) -> Result<Vec<Coordinate>> {
+    batch_decode_with_metrics(encoded, &NoopMetrics)
+}
+
+/// Batch decodes encoded strings like [`batch_decode`], reporting the batch
+/// size, failures and elapsed time to `metrics`.
+///
+/// The batch stops at the first invalid code, so a failed batch reports the
+/// codes processed up to and including it, with one error.
+///
+/// # Arguments
+/// * `encoded` - Encoded Grid9 strings (`String`s or `&str`s)
+/// * `metrics` - Receiver for the batch metrics
+///
+/// # Returns
+/// Vector of coordinate objects
+pub fn batch_decode_with_metrics<S: AsRef<str>>(encoded: &[S], metrics: &dyn Grid9Metrics) -> Result<Vec<Coordinate>> {
+    let start = Instant::now();
+    let mut processed = 0;
+    let result: Result<Vec<Coordinate>> = encoded
+        .iter()
+        .map(|enc| {
+            processed += 1;
+            let (lat, lon) = decode(enc.as_ref())?;
+            Ok(Coordinate::new(lat, lon))
+        })
+        .collect();
+    
+    let errors = usize::from(result.is_err());
+    metrics.record_batch(BatchOperation::Decode, processed, errors, start.elapsed());
+    result
+}
+
+/// Batch encodes coordinates from any iterator, so callers need not collect
+/// them into a `Vec<Coordinate>` first.
+///
+/// Accepts anything convertible into a [`Coordinate`]: coordinates or
+/// references to them, `(lat, lon)` tuples and `[lat, lon]` arrays. Stops at
+/// the first failure, like [`batch_encode`].
+///
+/// # Arguments
+/// * `coordinates` - Coordinates to encode
+/// * `human_readable` - If true, returns codes in XXX-XXX-XXX format
+///
+/// # Returns
+/// Vector of encoded Grid9 strings
+///
+/// # Example
+/// ```rust
+/// use grid9::batch_encode_iter;
+///
+/// let rows = [(40.7128, -74.0060), (51.5074, -0.1278)];
+/// let encoded = batch_encode_iter(rows, false)?;
+/// assert_eq!(encoded.len(), 2);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn batch_encode_iter<I>(coordinates: I, human_readable: bool) -> Result<Vec<String>>
+where
+    I: IntoIterator,
+    I::Item: Into<Coordinate>,
+{
+    coordinates
+        .into_iter()
+        .map(|coord| encode_coordinate(coord, human_readable))
+        .collect()
+}
+
+/// Batch decodes Grid9 strings from any iterator of `String`s or `&str`s,
+/// stopping at the first failure like [`batch_decode`].
+///
+/// # Arguments
+/// * `encoded` - Encoded Grid9 strings
+///
+/// # Returns
+/// Vector of coordinate objects
+///
+/// # Example
+/// ```rust
+/// use grid9::batch_decode_iter;
+///
+/// let line = "Q7KH2BBYF,S50MBZX2Y";
+/// let decoded = batch_decode_iter(line.split(','))?;
+/// assert_eq!(decoded.len(), 2);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn batch_decode_iter<I>(encoded: I) -> Result<Vec<Coordinate>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    encoded
+        .into_iter()
+        .map(|enc| {
+            let (lat, lon) = decode(enc.as_ref())?;
+            Ok(Coordinate::new(lat, lon))
+        })
+        .collect()
+}
+
+/// Batch encodes coordinates into a caller-owned buffer, reusing both the
+/// vector and the `String`s already in it.
+///
+/// Stream processors that call this once per batch with the same buffer stop
+/// allocating once the buffer has grown to the largest batch size.
+///
+/// # Arguments
+/// * `coordinates` - Coordinates to encode
+/// * `human_readable` - If true, writes codes in XXX-XXX-XXX format
+/// * `out` - Buffer replaced by one code per coordinate, in input order
+///
+/// # Returns
+/// `Ok(())`, or the first failure; `out` then holds the codes encoded
+/// before it
+///
+/// # Example
+/// ```rust
+/// use grid9::{batch_encode_reuse, Coordinate};
+///
+/// let mut codes = Vec::new();
+/// for batch in [[Coordinate::new(40.7128, -74.0060)], [Coordinate::new(51.5074, -0.1278)]] {
+///     batch_encode_reuse(&batch, false, &mut codes)?;
+///     assert_eq!(codes.len(), 1);
+/// }
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn batch_encode_reuse(coordinates: &[Coordinate], human_readable: bool, out: &mut Vec<String>) -> Result<()> {
+    out.truncate(coordinates.len());
+    for (i, coord) in coordinates.iter().enumerate() {
+        if let Err(error) = validate_coordinates(coord.lat, coord.lon) {
+            out.truncate(i);
+            return Err(error);
+        }
+        let (lat_bits, lon_bits) = quantize(coord.lat, coord.lon);
+        if i == out.len() {
+            out.push(String::with_capacity(11));
+        }
+        out[i].clear();
+        write_packed(pack_indices(lat_bits, lon_bits), human_readable, &mut out[i]);
+    }
+    Ok(())
+}
+
+/// Batch decodes encoded strings into a caller-owned buffer, reusing its
+/// capacity instead of allocating a new vector per batch.
+///
+/// # Arguments
+/// * `encoded` - Encoded Grid9 strings (`String`s or `&str`s)
+/// * `out` - Buffer replaced by one coordinate per code, in input order
+///
+/// # Returns
+/// `Ok(())`, or the first failure; `out` then holds the coordinates decoded
+/// before it
+///
+/// # Example
+/// ```rust
+/// use grid9::batch_decode_reuse;
+///
+/// let mut coordinates = Vec::with_capacity(1024);
+/// for line in ["Q7KH2BBYF,S50MBZX2Y", "Q7KH2BBYF"] {
+///     let codes: Vec<&str> = line.split(',').collect();
+///     batch_decode_reuse(&codes, &mut coordinates)?;
+///     assert_eq!(coordinates.len(), codes.len());
+/// }
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn batch_decode_reuse<S: AsRef<str>>(encoded: &[S], out: &mut Vec<Coordinate>) -> Result<()> {
+    out.clear();
+    for enc in encoded {
+        let (lat, lon) = decode(enc.as_ref())?;
+        out.push(Coordinate::new(lat, lon));
+    }
+    Ok(())
+}
+
+/// Batch encodes coordinates without stopping at the first failure.
+///
+/// # Arguments
+/// * `coordinates` - Vector of coordinates to encode
+/// * `human_readable` - If true, returns codes in XXX-XXX-XXX format
+///
+/// # Returns
+/// One result per input coordinate, in input order
+///
+/// # Example
+/// ```rust
+/// use grid9::{batch_encode_partial, Coordinate};
+/// 
+/// let coords = vec![
+///     Coordinate::new(40.7128, -74.0060), // NYC
+///     Coordinate::new(91.0, 0.0),         // Invalid latitude
+/// ];
+/// let results = batch_encode_partial(&coords, false);
+/// assert!(results[0].is_ok() && results[1].is_err());
+/// ```
+pub fn batch_encode_partial(coordinates: &[Coordinate], human_readable: bool) -> Vec<Result<String>> {
+    coordinates
+        .iter()
+        .map(|coord| encode(coord.lat, coord.lon, human_readable))
+        .collect()
+}
+
+/// Batch decodes encoded strings without stopping at the first failure.
+///
+/// # Arguments
+/// * `encoded` - Encoded Grid9 strings (`String`s or `&str`s)
+///
+/// # Returns
+/// One result per input string, in input order
+///
+/// # Example
+/// ```rust
+/// use grid9::batch_decode_partial;
+/// 
+/// let codes = vec!["Q7KH2BBYF".to_string(), "BAD".to_string()];
+/// let results = batch_decode_partial(&codes);
+/// assert!(results[0].is_ok() && results[1].is_err());
+/// ```
+pub fn batch_decode_partial<S: AsRef<str>>(encoded: &[S]) -> Vec<Result<Coordinate>> {
+    encoded
+        .iter()
+        .map(|enc| {
+            let (lat, lon) = decode(enc.as_ref())?;
+            Ok(Coordinate::new(lat, lon))
+        })
+        .collect()
+}
+
+/// Encodes parallel latitude and longitude columns, as used by data-frame
+/// bindings such as R or pandas.
+///
+/// Rows that cannot be encoded (including `NaN`/NA inputs) yield `None`
+/// instead of failing the whole column.
+///
+/// # Arguments
+/// * `latitudes` - Latitude column
+/// * `longitudes` - Longitude column of the same length
+/// * `human_readable` - Format codes as XXX-XXX-XXX
+///
+/// # Returns
+/// One code per row, or an error if the columns differ in length
+///
+/// # Example
+/// ```rust
+/// use grid9::encode_columns;
+///
+/// let codes = encode_columns(&[40.7128, f64::NAN], &[-74.0060, 0.0], false)?;
+/// assert_eq!(codes[0].as_deref(), Some("Q7KH2BBYE"));
+/// assert_eq!(codes[1], None);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn encode_columns(latitudes: &[f64], longitudes: &[f64], human_readable: bool) -> Result<Vec<Option<String>>> {
+    check_column_lengths(latitudes.len(), longitudes.len())?;
+    Ok(latitudes
+        .iter()
+        .zip(longitudes)
+        .map(|(&lat, &lon)| encode(lat, lon, human_readable).ok())
+        .collect())
+}
+
+/// Decodes a column of codes into latitude and longitude columns.
+///
+/// Missing or invalid codes yield `NaN` in both columns, which data-frame
+/// bindings map to NA.
+///
+/// # Example
+/// ```rust
+/// use grid9::decode_columns;
+///
+/// let (lats, lons) = decode_columns(&[Some("Q7KH2BBYF"), None, Some("BAD")]);
+/// assert!((lats[0] - 40.7128).abs() < 0.001 && (lons[0] + 74.006).abs() < 0.001);
+/// assert!(lats[1].is_nan() && lons[2].is_nan());
+/// ```
+pub fn decode_columns<S: AsRef<str>>(encoded: &[Option<S>]) -> (Vec<f64>, Vec<f64>) {
+    encoded
+        .iter()
+        .map(|code| {
+            code.as_ref()
+                .and_then(|code| decode(code.as_ref()).ok())
+                .unwrap_or((f64::NAN, f64::NAN))
+        })
+        .unzip()
+}
+
+/// Distances in meters between two columns of codes, row by row.
+///
+/// Rows with a missing or invalid code yield `NaN`.
+///
+/// # Returns
+/// One distance per row, or an error if the columns differ in length
+///
+/// # Example
+/// ```rust
+/// use grid9::distance_columns;
+///
+/// let meters = distance_columns(&[Some("Q7KH2BBYF"), Some("Q7KH2BBYF")], &[Some("S50MBZX2Y"), None])?;
+/// assert!(meters[0] > 5_500_000.0 && meters[1].is_nan());
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn distance_columns<S: AsRef<str>, T: AsRef<str>>(from: &[Option<S>], to: &[Option<T>]) -> Result<Vec<f64>> {
+    check_column_lengths(from.len(), to.len())?;
+    Ok(from
+        .iter()
+        .zip(to)
+        .map(|(a, b)| match (a, b) {
+            (Some(a), Some(b)) => calculate_distance(a.as_ref(), b.as_ref()).unwrap_or(f64::NAN),
+            _ => f64::NAN,
+        })
+        .collect())
+}
+
+fn check_column_lengths(left: usize, right: usize) -> Result<()> {
+    if left != right {
+        return Err(Grid9Error::InvalidInput(format!("column lengths differ: {} and {}", left, right).into()));
+    }
+    Ok(())
+}
+
+/// Batch validates encoded strings, reporting the error for each invalid row.
+///
+/// # Arguments
+/// * `encoded` - Encoded Grid9 strings (`String`s or `&str`s)
+///
+/// # Returns
+/// One result per input string, in input order (`Ok(())` for valid codes)
+///
+/// # Example
+/// ```rust
+/// use grid9::{batch_validate, Grid9Error};
+/// 
+/// let codes = vec!["Q7KH2BBYF".to_string(), "Q7KH2BB".to_string()];
+/// let results = batch_validate(&codes);
+/// assert_eq!(results[1], Err(Grid9Error::InvalidLength(7)));
+/// ```
+pub fn batch_validate<S: AsRef<str>>(encoded: &[S]) -> Vec<Result<()>> {
+    batch_validate_with_metrics(encoded, &NoopMetrics)
+}
+
+/// Batch validates encoded strings like [`batch_validate`], reporting the
+/// batch size, invalid rows and elapsed time to `metrics`.
+///
+/// # Arguments
+/// * `encoded` - Encoded Grid9 strings (`String`s or `&str`s)
+/// * `metrics` - Receiver for the batch metrics
+///
+/// # Returns
+/// One result per input string, in input order (`Ok(())` for valid codes)
+pub fn batch_validate_with_metrics<S: AsRef<str>>(encoded: &[S], metrics: &dyn Grid9Metrics) -> Vec<Result<()>> {
+    let start = Instant::now();
+    let results: Vec<Result<()>> = encoded
+        .iter()
+        .map(|enc| decode(enc.as_ref()).map(|_| ()))
+        .collect();
+    
+    let errors = results.iter().filter(|r| r.is_err()).count();
+    metrics.record_batch(BatchOperation::Validate, encoded.len(), errors, start.elapsed());
+    results
+}
+
+/// Batch encodes coordinates with a custom [`Codec`].
+///
+/// # Arguments
+/// * `coordinates` - Vector of coordinates to encode
+/// * `codec` - Text encoding to produce
+///
+/// # Returns
+/// Vector of codes in the codec's format
+///
+/// # Example
+/// ```rust
+/// use grid9::{batch_encode_with_codec, Coordinate, Grid9Codec, Grid9Encoder, Grid9Decoder};
+///
+/// let codec = Grid9Codec::from_parts(
+///     Grid9Encoder::builder().checksum(true).build(),
+///     Grid9Decoder::builder().checksum(true).build(),
+/// );
+/// let encoded = batch_encode_with_codec(&[Coordinate::new(40.7128, -74.0060)], &codec)?;
+/// assert_eq!(encoded[0].len(), 10);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn batch_encode_with_codec(coordinates: &[Coordinate], codec: &dyn Codec) -> Result<Vec<String>> {
+    coordinates
+        .iter()
+        .map(|coord| codec.encode(coord.lat, coord.lon))
+        .collect()
+}
+
+/// Batch decodes codes produced by a custom [`Codec`].
+///
+/// # Arguments
+/// * `encoded` - Vector of codes in the codec's format
+/// * `codec` - Text encoding to read
+///
+/// # Returns
+/// Vector of coordinate objects
+pub fn batch_decode_with_codec<S: AsRef<str>>(encoded: &[S], codec: &dyn Codec) -> Result<Vec<Coordinate>> {
     encoded
         .iter()
         .map(|enc| {
-            let (lat, lon) = decode(enc)?;
+            let (lat, lon) = codec.decode(enc.as_ref())?;
             Ok(Coordinate::new(lat, lon))
         })
         .collect()
 }
 
+/// Batch validates codes against a custom [`Codec`].
+///
+/// # Arguments
+/// * `encoded` - Vector of codes in the codec's format
+/// * `codec` - Text encoding to check against
+///
+/// # Returns
+/// One result per input string, in input order (`Ok(())` for valid codes)
+pub fn batch_validate_with_codec<S: AsRef<str>>(encoded: &[S], codec: &dyn Codec) -> Vec<Result<()>> {
+    encoded.iter().map(|enc| codec.validate(enc.as_ref())).collect()
+}
+
 /// Finds all coordinates within a specified radius (in meters) of a center point.
 /// Returns encoded strings of nearby coordinates.
 ///
@@ -202,6 +802,11 @@ pub fn get_bounding_box(coordinates: &[Coordinate]) -> Result<BoundingBox> {
 
 /// Calculates the center point of a set of coordinates.
 ///
+/// Averages latitude and longitude arithmetically: cheap and exact enough for
+/// points within a city or region, but wrong for sets that cross the
+/// antimeridian (the mean of 179° and -179° is 0°) or span large parts of the
+/// globe. Use [`get_spherical_center`] for those.
+///
 /// # Arguments
 /// * `coordinates` - Vector of coordinates
 ///
@@ -233,6 +838,95 @@ pub fn get_center_point(coordinates: &[Coordinate]) -> Result<Coordinate> {
     ))
 }
 
+/// Calculates the geographic center of a set of coordinates on the sphere.
+///
+/// Averages the points as 3D unit vectors and projects the mean back onto the
+/// surface, so the result is correct across the antimeridian, near the poles
+/// and for widely spread points.
+///
+/// # Arguments
+/// * `coordinates` - Vector of coordinates
+///
+/// # Returns
+/// Center coordinate, or [`Grid9Error::InvalidInput`] when the points balance
+/// out (e.g. two antipodal points) and the center is undefined
+///
+/// # Example
+/// ```rust
+/// use grid9::{get_spherical_center, Coordinate};
+///
+/// let coords = vec![Coordinate::new(-17.0, 179.0), Coordinate::new(-17.0, -179.0)]; // Fiji
+/// let center = get_spherical_center(&coords)?;
+/// assert!((center.lon.abs() - 180.0).abs() < 1e-9);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn get_spherical_center(coordinates: &[Coordinate]) -> Result<Coordinate> {
+    if coordinates.is_empty() {
+        return Err(Grid9Error::EmptyInput);
+    }
+
+    spherical_mean(coordinates.iter().map(|coord| (coord, 1.0)))
+}
+
+/// Calculates the center of a set of coordinates weighted by a per-point
+/// value such as order volume or dwell time.
+///
+/// Uses the same unit-vector mean as [`get_spherical_center`], so it is
+/// correct across the antimeridian and near the poles. A point with weight
+/// `2.0` pulls the center as much as two points with weight `1.0`.
+///
+/// # Arguments
+/// * `weighted` - Coordinates paired with finite, non-negative weights
+///
+/// # Returns
+/// Weighted center coordinate, or [`Grid9Error::InvalidInput`] for negative
+/// or non-finite weights, an all-zero total weight, or points that balance out
+///
+/// # Example
+/// ```rust
+/// use grid9::{weighted_center_point, Coordinate};
+///
+/// let orders = vec![
+///     (Coordinate::new(-17.0, 179.9), 3.0),
+///     (Coordinate::new(-17.0, -179.9), 1.0),
+/// ];
+/// let center = weighted_center_point(&orders)?;
+/// assert!((center.lon - 179.95).abs() < 1e-3);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn weighted_center_point(weighted: &[(Coordinate, f64)]) -> Result<Coordinate> {
+    if weighted.is_empty() {
+        return Err(Grid9Error::EmptyInput);
+    }
+    if let Some((_, weight)) = weighted.iter().find(|(_, weight)| !weight.is_finite() || *weight < 0.0) {
+        return Err(Grid9Error::InvalidInput(format!("invalid weight {}", weight).into()));
+    }
+
+    spherical_mean(weighted.iter().map(|(coord, weight)| (coord, *weight)))
+}
+
+/// Weighted mean of unit vectors, projected back to latitude and longitude.
+fn spherical_mean<'a>(weighted: impl Iterator<Item = (&'a Coordinate, f64)>) -> Result<Coordinate> {
+    let (mut x, mut y, mut z, mut total_weight) = (0.0, 0.0, 0.0, 0.0);
+    for (coord, weight) in weighted {
+        let (lat, lon) = (coord.lat.to_radians(), coord.lon.to_radians());
+        x += weight * lat.cos() * lon.cos();
+        y += weight * lat.cos() * lon.sin();
+        z += weight * lat.sin();
+        total_weight += weight;
+    }
+
+    let length = (x * x + y * y + z * z).sqrt();
+    if length.is_nan() || length <= 1e-9 * total_weight {
+        return Err(Grid9Error::InvalidInput("points have no defined center on the sphere".into()));
+    }
+
+    Ok(Coordinate::new(
+        z.atan2(x.hypot(y)).to_degrees(),
+        y.atan2(x).to_degrees(),
+    ))
+}
+
 /// Groups coordinates by their Grid9 codes for spatial indexing.
 ///
 /// # Arguments
@@ -263,15 +957,61 @@ pub fn group_by_grid9(
     
     for coord in coordinates {
         let code = encode(coord.lat, coord.lon, human_readable)?;
-        groups.entry(code).or_insert_with(Vec::new).push(coord.clone());
+        groups.entry(code).or_default().push(coord.clone());
     }
     
     Ok(groups)
 }
 
+/// Groups coordinates by the [`Grid9Bucket`] containing them, at a coarser
+/// resolution than [`group_by_grid9`].
+///
+/// Keys are the buckets' representative codes ([`Grid9Bucket::to_code`]), so
+/// they match the keys of any other hierarchy-based rollup at the same level.
+/// Useful levels are 0 (single ~3m cells, same as [`group_by_grid9`]), 5
+/// (~100m buckets) and 8 (~1km buckets).
+///
+/// # Arguments
+/// * `coordinates` - Vector of coordinates
+/// * `level` - Bucket level (0 to [`crate::MAX_LEVEL`])
+/// * `human_readable` - If true, uses XXX-XXX-XXX format for keys
+///
+/// # Returns
+/// HashMap mapping bucket codes to vectors of coordinates in that bucket
+///
+/// # Example
+/// ```rust
+/// use grid9::{group_by_grid9_at, Coordinate};
+///
+/// let coords = vec![
+///     Coordinate::new(40.7128, -74.0060),
+///     Coordinate::new(40.7133, -74.0055), // ~70m away
+/// ];
+/// let grouped = group_by_grid9_at(&coords, 8, false)?;
+/// assert_eq!(grouped.len(), 1);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn group_by_grid9_at(
+    coordinates: &[Coordinate],
+    level: u8,
+    human_readable: bool,
+) -> Result<std::collections::HashMap<String, Vec<Coordinate>>> {
+    use std::collections::HashMap;
+
+    let mut groups: HashMap<String, Vec<Coordinate>> = HashMap::new();
+
+    for coord in coordinates {
+        let bucket = Grid9Bucket::from_coordinates(coord.lat, coord.lon, level)?;
+        groups.entry(bucket.to_code(human_readable)).or_default().push(coord.clone());
+    }
+
+    Ok(groups)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::MAX_LEVEL;
 
     #[test]
     fn test_batch_encode_decode() {
@@ -293,6 +1033,158 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_generic_batch_inputs() {
+        let coordinates = vec![Coordinate::new(40.7128, -74.0060), Coordinate::new(51.5074, -0.1278)];
+        let encoded = batch_encode(&coordinates, false).unwrap();
+
+        assert_eq!(batch_encode_iter(&coordinates, false).unwrap(), encoded);
+        assert_eq!(batch_encode_iter(coordinates.clone(), false).unwrap(), encoded);
+
+        let borrowed: Vec<&str> = encoded.iter().map(String::as_str).collect();
+        assert_eq!(batch_decode(&borrowed).unwrap(), batch_decode(&encoded).unwrap());
+        assert_eq!(batch_decode_iter(&borrowed).unwrap(), batch_decode(&encoded).unwrap());
+        assert_eq!(batch_decode_iter(encoded.iter()).unwrap(), batch_decode(&encoded).unwrap());
+        assert_eq!(batch_validate(&["Q7KH2BBYF", "BAD"])[1], Err(Grid9Error::InvalidLength(3)));
+        assert!(batch_decode_iter(["Q7KH2BBYF", "BAD"]).is_err());
+    }
+
+    #[test]
+    fn test_batch_reuse() {
+        let coordinates = vec![Coordinate::new(40.7128, -74.0060), Coordinate::new(51.5074, -0.1278)];
+        let mut codes = vec!["stale".to_string(); 3];
+        batch_encode_reuse(&coordinates, true, &mut codes).unwrap();
+        assert_eq!(codes, batch_encode(&coordinates, true).unwrap());
+
+        let buffer = codes[0].as_ptr();
+        batch_encode_reuse(&coordinates[1..], false, &mut codes).unwrap();
+        assert_eq!(codes, batch_encode(&coordinates[1..], false).unwrap());
+        assert_eq!(codes[0].as_ptr(), buffer);
+
+        let mut decoded = Vec::new();
+        batch_decode_reuse(&batch_encode(&coordinates, false).unwrap(), &mut decoded).unwrap();
+        assert_eq!(decoded, batch_decode(&batch_encode(&coordinates, false).unwrap()).unwrap());
+        assert_eq!(batch_decode_reuse(&["Q7KH2BBYF", "BAD"], &mut decoded), Err(Grid9Error::InvalidLength(3)));
+        assert_eq!(decoded.len(), 1);
+
+        let invalid = [coordinates[0].clone(), Coordinate::new(91.0, 0.0)];
+        assert!(batch_encode_reuse(&invalid, false, &mut codes).is_err());
+        assert_eq!(codes.len(), 1);
+    }
+
+    #[test]
+    fn test_tuple_and_array_conversions() {
+        let coordinate = Coordinate::new(40.7128, -74.0060);
+        assert_eq!(Coordinate::from((40.7128, -74.0060)), coordinate);
+        assert_eq!(Coordinate::from([40.7128, -74.0060]), coordinate);
+        assert_eq!(<(f64, f64)>::from(coordinate.clone()), (40.7128, -74.0060));
+        assert_eq!(<[f64; 2]>::from(coordinate.clone()), [40.7128, -74.0060]);
+
+        let expected = encode(40.7128, -74.0060, true).unwrap();
+        assert_eq!(encode_coordinate(&coordinate, true).unwrap(), expected);
+        assert_eq!(encode_coordinate([40.7128, -74.0060], true).unwrap(), expected);
+        assert_eq!(batch_encode_iter(vec![[40.7128, -74.0060]], true).unwrap(), vec![expected]);
+        assert!(encode_coordinate((91.0, 0.0), false).is_err());
+    }
+
+    #[test]
+    fn test_quantized_key() {
+        let coordinate = Coordinate::new(40.7128, -74.0060);
+        let code: crate::Grid9Code = encode(40.7128, -74.0060, false).unwrap().parse().unwrap();
+        let (lat_index, lon_index) = code.indices();
+        assert_eq!(coordinate.quantized_key(0).unwrap(), ((lat_index as u64) << 23) | lon_index as u64);
+
+        let nearby = Coordinate::new(40.7133, -74.0055);
+        assert_ne!(coordinate.quantized_key(0).unwrap(), nearby.quantized_key(0).unwrap());
+        assert_eq!(coordinate.quantized_key(8).unwrap(), nearby.quantized_key(8).unwrap());
+
+        assert_eq!(coordinate.quantized_key(MAX_LEVEL + 1), Err(Grid9Error::InvalidLevel(MAX_LEVEL + 1)));
+        assert!(Coordinate::new(f64::NAN, 0.0).quantized_key(0).is_err());
+    }
+
+    #[test]
+    fn test_partial_batches_continue_after_errors() {
+        let coordinates = vec![
+            Coordinate::new(40.7128, -74.0060),
+            Coordinate::new(0.0, 200.0),
+            Coordinate::new(51.5074, -0.1278),
+        ];
+        
+        let encoded = batch_encode_partial(&coordinates, false);
+        assert!(encoded[0].is_ok());
+        assert_eq!(encoded[1], Err(Grid9Error::InvalidLongitude(200.0)));
+        assert!(encoded[2].is_ok());
+        
+        let codes = vec![
+            encoded[0].clone().unwrap(),
+            "NOT-A-CODE!".to_string(),
+            encoded[2].clone().unwrap(),
+        ];
+        
+        let decoded = batch_decode_partial(&codes);
+        assert_eq!(decoded.len(), 3);
+        assert!(decoded[0].is_ok() && decoded[1].is_err() && decoded[2].is_ok());
+        
+        let validated = batch_validate(&codes);
+        assert_eq!(validated[0], Ok(()));
+        assert!(validated[1].is_err());
+        assert_eq!(validated[2], Ok(()));
+    }
+
+    #[test]
+    fn test_batches_with_codec() {
+        use crate::{Grid9Codec, Grid9Decoder, Grid9Encoder};
+
+        let coordinates = vec![Coordinate::new(40.7128, -74.0060), Coordinate::new(51.5074, -0.1278)];
+        let codec = Grid9Codec::from_parts(
+            Grid9Encoder::builder().human_readable(true).checksum(true).build(),
+            Grid9Decoder::builder().checksum(true).build(),
+        );
+
+        let encoded = batch_encode_with_codec(&coordinates, &codec).unwrap();
+        assert!(encoded.iter().all(|code| code.len() == 13));
+
+        let decoded = batch_decode_with_codec(&encoded, &codec).unwrap();
+        let plain = batch_decode(&batch_encode(&coordinates, false).unwrap()).unwrap();
+        assert_eq!(decoded, plain);
+
+        let validated = batch_validate_with_codec(&batch_encode(&coordinates, false).unwrap(), &codec);
+        assert!(validated.iter().all(|r| r.is_err()));
+    }
+
+    #[test]
+    fn test_batch_metrics() {
+        use std::sync::Mutex;
+        
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<(BatchOperation, usize, usize)>>);
+        
+        impl Grid9Metrics for Recorder {
+            fn record_batch(&self, operation: BatchOperation, items: usize, errors: usize, _: std::time::Duration) {
+                self.0.lock().unwrap().push((operation, items, errors));
+            }
+        }
+        
+        let recorder = Recorder::default();
+        let coordinates = vec![Coordinate::new(40.7128, -74.0060), Coordinate::new(51.5074, -0.1278)];
+        let encoded = batch_encode_with_metrics(&coordinates, false, &recorder).unwrap();
+        batch_decode_with_metrics(&encoded, &recorder).unwrap();
+        batch_validate_with_metrics(&["Q7KH2BBYF".to_string(), "BAD".to_string()], &recorder);
+        
+        // Failed batches stop at the first bad item and count only what they processed
+        let invalid = vec![Coordinate::new(40.7128, -74.0060), Coordinate::new(91.0, 0.0), Coordinate::new(0.0, 0.0)];
+        assert!(batch_encode_with_metrics(&invalid, false, &recorder).is_err());
+        assert!(batch_decode_with_metrics(&["BAD", "Q7KH2BBYF", "Q7KH2BBYF"], &recorder).is_err());
+        
+        assert_eq!(*recorder.0.lock().unwrap(), vec![
+            (BatchOperation::Encode, 2, 0),
+            (BatchOperation::Decode, 2, 0),
+            (BatchOperation::Validate, 2, 1),
+            (BatchOperation::Encode, 2, 1),
+            (BatchOperation::Decode, 1, 1),
+        ]);
+    }
+
     #[test]
     fn test_bounding_box() {
         let coordinates = vec![
@@ -309,6 +1201,40 @@ mod tests {
         assert!((bbox.max_lon - (-73.0)).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn test_split_bounding_box() {
+        let bbox = BoundingBox::new(-10.0, 20.0, 100.0, 101.0);
+        let parts = bbox.split(3, 4).unwrap();
+        assert_eq!(parts.len(), 12);
+        assert_eq!(parts[0], BoundingBox::new(-10.0, 0.0, 100.0, 100.25));
+        assert_eq!(parts[11], BoundingBox::new(10.0, 20.0, 100.75, 101.0));
+        assert_eq!(parts[4].min_lat, parts[0].max_lat);
+
+        assert_eq!(bbox.split(1, 1).unwrap(), vec![bbox.clone()]);
+        assert!(bbox.split(0, 2).is_err());
+        assert!(BoundingBox::new(1.0, 0.0, 0.0, 1.0).split(2, 2).is_err());
+    }
+
+    #[test]
+    fn test_tiles_of_size() {
+        // 1° squares on the equator are ~111km wide; at 60°N half as wide
+        let equator = BoundingBox::new(0.0, 1.0, 0.0, 1.0).tiles_of_size(50_000.0, 50_000.0).unwrap();
+        assert_eq!(equator.len(), 3 * 3);
+        let north = BoundingBox::new(60.0, 60.4, 0.0, 1.0).tiles_of_size(50_000.0, 50_000.0).unwrap();
+        assert_eq!(north.len(), 2);
+
+        // Rows straddling the equator size their columns at the equator
+        let straddling = BoundingBox::new(-0.1, 0.1, 0.0, 1.0).tiles_of_size(10_000.0, 100_000.0).unwrap();
+        assert_eq!(straddling.len(), 12);
+
+        let tiny = BoundingBox::new(0.0, 0.0, 0.0, 0.0).tiles_of_size(1.0, 1.0).unwrap();
+        assert_eq!(tiny.len(), 1);
+        assert!(equator.iter().all(|t| (t.max_lat - t.min_lat) * METERS_PER_DEGREE <= 50_000.0));
+        for size in [0.0, -1.0, f64::NAN] {
+            assert!(BoundingBox::new(0.0, 1.0, 0.0, 1.0).tiles_of_size(size, 1.0).is_err());
+        }
+    }
+
     #[test]
     fn test_center_point() {
         let coordinates = vec![
@@ -322,6 +1248,48 @@ mod tests {
         assert!((center.lon - (-74.0)).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn test_spherical_center() {
+        // Matches the arithmetic mean for nearby points
+        let close = vec![Coordinate::new(40.0, -75.0), Coordinate::new(40.2, -74.8)];
+        let center = get_spherical_center(&close).unwrap();
+        let mean = get_center_point(&close).unwrap();
+        assert!((center.lat - mean.lat).abs() < 1e-3 && (center.lon - mean.lon).abs() < 1e-3);
+
+        // Across the antimeridian and over the pole
+        let dateline = get_spherical_center(&[Coordinate::new(10.0, 170.0), Coordinate::new(10.0, -170.0)]).unwrap();
+        assert!((dateline.lon.abs() - 180.0).abs() < 1e-9);
+        assert!(dateline.lat > 10.0);
+        let polar = get_spherical_center(&[Coordinate::new(80.0, 0.0), Coordinate::new(80.0, 180.0)]).unwrap();
+        assert!((polar.lat - 90.0).abs() < 1e-9);
+
+        let antipodes = [Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 180.0)];
+        assert!(matches!(get_spherical_center(&antipodes), Err(Grid9Error::InvalidInput(_))));
+        assert_eq!(get_spherical_center(&[]), Err(Grid9Error::EmptyInput));
+    }
+
+    #[test]
+    fn test_weighted_center_point() {
+        let equal = [(Coordinate::new(40.0, -75.0), 2.0), (Coordinate::new(40.2, -74.8), 2.0)];
+        let unweighted = get_spherical_center(&[equal[0].0.clone(), equal[1].0.clone()]).unwrap();
+        assert_eq!(weighted_center_point(&equal).unwrap(), unweighted);
+
+        // A zero weight ignores the point; a heavy one pulls the center towards it
+        let ignored = weighted_center_point(&[(Coordinate::new(10.0, 20.0), 1.0), (Coordinate::new(50.0, 60.0), 0.0)]).unwrap();
+        assert!((ignored.lat - 10.0).abs() < 1e-9 && (ignored.lon - 20.0).abs() < 1e-9);
+        let pulled = weighted_center_point(&[(Coordinate::new(0.0, 0.0), 3.0), (Coordinate::new(0.0, 4.0), 1.0)]).unwrap();
+        assert!((pulled.lon - 1.0).abs() < 0.01);
+
+        let dateline = weighted_center_point(&[(Coordinate::new(0.0, 179.0), 1.0), (Coordinate::new(0.0, -179.0), 1.0)]).unwrap();
+        assert!((dateline.lon.abs() - 180.0).abs() < 1e-9);
+
+        assert_eq!(weighted_center_point(&[]), Err(Grid9Error::EmptyInput));
+        for weight in [-1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(weighted_center_point(&[(Coordinate::new(0.0, 0.0), weight)]), Err(Grid9Error::InvalidInput(_))));
+        }
+        assert!(weighted_center_point(&[(Coordinate::new(0.0, 0.0), 0.0)]).is_err());
+    }
+
     #[test]
     fn test_find_nearby() {
         let nearby = find_nearby(40.7128, -74.0060, 1000.0, 5).unwrap();
@@ -352,6 +1320,26 @@ mod tests {
         assert_eq!(total_coords, coordinates.len());
     }
 
+    #[test]
+    fn test_group_by_grid9_at_level() {
+        let coordinates = vec![
+            Coordinate::new(40.7128, -74.0060),
+            Coordinate::new(40.7133, -74.0055), // ~70m away
+            Coordinate::new(51.5074, -0.1278),
+        ];
+
+        let exact = group_by_grid9_at(&coordinates, 0, false).unwrap();
+        assert_eq!(exact, group_by_grid9(&coordinates, false).unwrap());
+
+        let coarse = group_by_grid9_at(&coordinates, 8, true).unwrap();
+        assert_eq!(coarse.len(), 2);
+        let bucket = Grid9Bucket::from_coordinates(40.7128, -74.0060, 8).unwrap();
+        assert_eq!(coarse[&bucket.to_code(true)].len(), 2);
+
+        assert_eq!(group_by_grid9_at(&coordinates, MAX_LEVEL + 1, false), Err(Grid9Error::InvalidLevel(MAX_LEVEL + 1)));
+        assert!(group_by_grid9_at(&[Coordinate::new(91.0, 0.0)], 5, false).is_err());
+    }
+
     #[test]
     fn test_empty_input_errors() {
         let empty_coords = vec![];
@@ -359,4 +1347,20 @@ mod tests {
         assert!(matches!(get_bounding_box(&empty_coords), Err(Grid9Error::EmptyInput)));
         assert!(matches!(get_center_point(&empty_coords), Err(Grid9Error::EmptyInput)));
     }
+
+    #[test]
+    fn test_columns_mark_missing_rows() {
+        let codes = encode_columns(&[48.8566, 91.0, f64::NAN], &[2.3522, 0.0, 0.0], true).unwrap();
+        assert!(codes[0].is_some() && codes[1].is_none() && codes[2].is_none());
+        assert!(encode_columns(&[0.0], &[], false).is_err());
+
+        let (lats, lons) = decode_columns(&codes);
+        assert!((lats[0] - 48.8566).abs() < 0.0001 && (lons[0] - 2.3522).abs() < 0.0001);
+        assert!(lats[1].is_nan() && lons[2].is_nan());
+
+        let distances = distance_columns(&codes, &codes).unwrap();
+        assert_eq!(distances[0], 0.0);
+        assert!(distances[1].is_nan());
+        assert!(distance_columns(&codes, &codes[..1]).is_err());
+    }
 }
\ No newline at end of file
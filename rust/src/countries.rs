@@ -0,0 +1,185 @@
+//! Offline country lookup (feature `countries`)
+//!
+//! The embedded dataset (`data/countries.txt`) holds hand-simplified
+//! outlines with vertices every few tens of kilometers, so lookups are
+//! reliable away from borders and coasts but may be wrong within roughly
+//! 20 km of them. Small island states are approximated by boxes. Use
+//! [`CountryIndex::from_text`] to load a more detailed dataset in the same
+//! format.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::geodata::{parse_regions, smallest_containing, Region};
+use crate::{Grid9Code, Grid9Error, Result};
+
+const EMBEDDED_COUNTRIES: &str = include_str!("../data/countries.txt");
+
+/// ISO 3166-1 alpha-2 country code
+///
+/// # Example
+/// ```rust
+/// use grid9::CountryCode;
+///
+/// let code: CountryCode = "fr".parse()?;
+/// assert_eq!(code.as_str(), "FR");
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CountryCode([u8; 2]);
+
+impl CountryCode {
+    /// The two upper-case letters of the code
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.0).expect("country codes are ASCII")
+    }
+}
+
+impl FromStr for CountryCode {
+    type Err = Grid9Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.as_bytes() {
+            &[a, b] if a.is_ascii_alphabetic() && b.is_ascii_alphabetic() => {
+                Ok(Self([a.to_ascii_uppercase(), b.to_ascii_uppercase()]))
+            }
+            _ => Err(Grid9Error::InvalidInput(format!("invalid country code '{}'", s).into())),
+        }
+    }
+}
+
+impl fmt::Display for CountryCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Point-in-polygon index over country outlines
+#[derive(Debug, Clone)]
+pub struct CountryIndex {
+    regions: Vec<Region<CountryCode>>,
+}
+
+impl CountryIndex {
+    /// The index over the embedded dataset, parsed on first use.
+    pub fn embedded() -> &'static CountryIndex {
+        static INDEX: OnceLock<CountryIndex> = OnceLock::new();
+        INDEX.get_or_init(|| CountryIndex::from_text(EMBEDDED_COUNTRIES).expect("embedded country data is valid"))
+    }
+
+    /// Builds an index from text in the embedded dataset's format: one ring
+    /// per line, a country code followed by `lat,lon` vertices, `#` comments.
+    /// Where rings overlap the smallest one wins.
+    pub fn from_text(text: &str) -> Result<Self> {
+        let regions = parse_regions(text, |labels| match labels {
+            [code] => code.parse(),
+            _ => Err(Grid9Error::InvalidInput("expected a single country code".into())),
+        })?;
+        Ok(Self { regions })
+    }
+
+    /// Country containing the point, or `None` over the ocean.
+    pub fn lookup(&self, latitude: f64, longitude: f64) -> Option<CountryCode> {
+        smallest_containing(&self.regions, latitude, longitude).map(|region| region.label)
+    }
+}
+
+/// Returns the country a Grid9 cell lies in, using the embedded dataset.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code (any accepted form)
+///
+/// # Returns
+/// The ISO 3166-1 alpha-2 code of the country, or `None` over the ocean
+///
+/// # Example
+/// ```rust
+/// use grid9::{country_of, encode};
+///
+/// let paris = encode(48.8566, 2.3522, false)?;
+/// assert_eq!(country_of(&paris)?.unwrap().as_str(), "FR");
+/// let atlantic = encode(35.0, -40.0, false)?;
+/// assert_eq!(country_of(&atlantic)?, None);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn country_of(encoded: &str) -> Result<Option<CountryCode>> {
+    let code: Grid9Code = encoded.parse()?;
+    let (latitude, longitude) = code.coordinates();
+    Ok(CountryIndex::embedded().lookup(latitude, longitude))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode;
+
+    #[test]
+    fn test_capitals() {
+        let capitals = [
+            ("PT", 38.7223, -9.1393),
+            ("ES", 40.4168, -3.7038),
+            ("FR", 48.8566, 2.3522),
+            ("GB", 51.5074, -0.1278),
+            ("IE", 53.3498, -6.2603),
+            ("DE", 52.5200, 13.4050),
+            ("IT", 41.9028, 12.4964),
+            ("VA", 41.9029, 12.4534),
+            ("SM", 43.9424, 12.4578),
+            ("PL", 52.2297, 21.0122),
+            ("UA", 50.4501, 30.5234),
+            ("RU", 55.7558, 37.6173),
+            ("FI", 60.1699, 24.9384),
+            ("TR", 39.9334, 32.8597),
+            ("EG", 30.0444, 31.2357),
+            ("NG", 9.0765, 7.3986),
+            ("KE", -1.2921, 36.8219),
+            ("CD", -4.3217, 15.3126),
+            ("CG", -4.2634, 15.2429),
+            ("ZA", -25.7479, 28.2293),
+            ("LS", -29.3151, 27.4869),
+            ("BW", -24.6282, 25.9231),
+            ("UZ", 41.2995, 69.2401),
+            ("IN", 28.6139, 77.2090),
+            ("CN", 39.9042, 116.4074),
+            ("JP", 35.6762, 139.6503),
+            ("TH", 13.7563, 100.5018),
+            ("ID", -6.2088, 106.8456),
+            ("AU", -35.2809, 149.1300),
+            ("NZ", -41.2865, 174.7762),
+            ("US", 38.9072, -77.0369),
+            ("CA", 45.4215, -75.6972),
+            ("MX", 19.4326, -99.1332),
+            ("CU", 23.1136, -82.3666),
+            ("CO", 4.7110, -74.0721),
+            ("BR", -15.7975, -47.8919),
+            ("AR", -34.6037, -58.3816),
+            ("UY", -34.9011, -56.1645),
+            ("CL", -33.4489, -70.6693),
+            ("GL", 64.1814, -51.6941),
+        ];
+
+        for (expected, lat, lon) in capitals {
+            let code = encode(lat, lon, false).unwrap();
+            assert_eq!(country_of(&code).unwrap().map(|c| c.to_string()), Some(expected.to_string()), "{}", expected);
+        }
+    }
+
+    #[test]
+    fn test_oceans_and_invalid_codes() {
+        for (lat, lon) in [(0.0, -30.0), (-40.0, 90.0), (20.0, -150.0), (60.0, -30.0)] {
+            assert_eq!(country_of(&encode(lat, lon, false).unwrap()).unwrap(), None);
+        }
+        assert!(country_of("INVALID").is_err());
+    }
+
+    #[test]
+    fn test_custom_dataset() {
+        let index = CountryIndex::from_text("XX 0,0 0,10 10,10 10,0\nyy 2,2 2,4 4,4 4,2").unwrap();
+        assert_eq!(index.lookup(5.0, 5.0), Some("XX".parse().unwrap()));
+        assert_eq!(index.lookup(3.0, 3.0).unwrap().as_str(), "YY");
+        assert!(CountryIndex::from_text("XYZ 0,0 0,1 1,1").is_err());
+        assert!("X1".parse::<CountryCode>().is_err());
+    }
+}
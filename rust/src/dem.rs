@@ -0,0 +1,187 @@
+//! SRTM `.hgt` elevation tiles (feature `dem`)
+//!
+//! An `.hgt` file covers one degree square, named after its south-west
+//! corner (`N51E004.hgt` spans 51°N–52°N, 4°E–5°E), and holds a square grid
+//! of big-endian `i16` elevations in meters, north row first. Tiles of 1201
+//! (3 arc-second) and 3601 (1 arc-second) samples per side are accepted.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::ElevationProvider;
+
+/// Sample value marking missing data
+const VOID: i16 = -32768;
+
+#[derive(Debug, Clone)]
+struct HgtTile {
+    size: usize,
+    samples: Vec<i16>,
+}
+
+impl HgtTile {
+    fn sample(&self, row: usize, col: usize) -> Option<f64> {
+        match self.samples[row * self.size + col] {
+            VOID => None,
+            value => Some(value as f64),
+        }
+    }
+}
+
+/// In-memory set of SRTM `.hgt` tiles, bilinearly interpolated
+///
+/// # Example
+/// ```rust,no_run
+/// use grid9::{elevation_of, encode, HgtTiles};
+///
+/// let tiles = HgtTiles::load_dir("/data/srtm")?;
+/// let code = encode(46.5586, 7.8358, false)?;
+/// println!("{:?}", elevation_of(&code, &tiles)?);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HgtTiles {
+    tiles: HashMap<(i32, i32), HgtTile>,
+}
+
+impl HgtTiles {
+    /// An empty tile set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads every `.hgt` file in `dir`; other files are ignored.
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let mut tiles = Self::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("hgt")) {
+                continue;
+            }
+            let (south, west) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(parse_tile_name)
+                .ok_or_else(|| invalid_data(format!("unrecognized tile name {}", path.display())))?;
+            tiles.insert(south, west, &fs::read(&path)?)?;
+        }
+        Ok(tiles)
+    }
+
+    /// Adds the tile whose south-west corner is at (`south`, `west`) from
+    /// the raw contents of its `.hgt` file.
+    pub fn insert(&mut self, south: i32, west: i32, bytes: &[u8]) -> io::Result<()> {
+        let size = match bytes.len() {
+            len if len == 1201 * 1201 * 2 => 1201,
+            len if len == 3601 * 3601 * 2 => 3601,
+            len => return Err(invalid_data(format!("{} bytes is not a 1201 or 3601 sample tile", len))),
+        };
+        if !(-90..90).contains(&south) || !(-180..180).contains(&west) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("no tile at {}, {}", south, west)));
+        }
+
+        let samples = bytes.chunks_exact(2).map(|pair| i16::from_be_bytes([pair[0], pair[1]])).collect();
+        self.tiles.insert((south, west), HgtTile { size, samples });
+        Ok(())
+    }
+
+    /// Number of loaded tiles
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Whether no tiles are loaded
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+}
+
+impl ElevationProvider for HgtTiles {
+    fn elevation(&self, latitude: f64, longitude: f64) -> Option<f64> {
+        // Tiles share their edges; 90°N and 180°E fall in the last tile
+        let south = (latitude.floor() as i32).min(89);
+        let west = (longitude.floor() as i32).min(179);
+        let tile = self.tiles.get(&(south, west))?;
+
+        let last = (tile.size - 1) as f64;
+        let y = (south as f64 + 1.0 - latitude) * last;
+        let x = (longitude - west as f64) * last;
+        let (row, col) = ((y.floor() as usize).min(tile.size - 2), (x.floor() as usize).min(tile.size - 2));
+        let (dy, dx) = (y - row as f64, x - col as f64);
+
+        let top = tile.sample(row, col)? * (1.0 - dx) + tile.sample(row, col + 1)? * dx;
+        let bottom = tile.sample(row + 1, col)? * (1.0 - dx) + tile.sample(row + 1, col + 1)? * dx;
+        Some(top * (1.0 - dy) + bottom * dy)
+    }
+}
+
+/// Parses `N51E004`-style tile names into (south, west) degrees.
+fn parse_tile_name(name: &str) -> Option<(i32, i32)> {
+    let name = name.to_ascii_uppercase();
+    let (lat, lon) = (name.get(..3)?, name.get(3..)?);
+    let degrees = |text: &str, positive: char, negative: char| -> Option<i32> {
+        let value: i32 = text.get(1..)?.parse().ok()?;
+        match text.chars().next()? {
+            c if c == positive => Some(value),
+            c if c == negative => Some(-value),
+            _ => None,
+        }
+    };
+    Some((degrees(lat, 'N', 'S')?, degrees(lon, 'E', 'W')?))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 1201-sample tile whose elevation is `f(row, col)`
+    fn tile(f: impl Fn(usize, usize) -> i16) -> Vec<u8> {
+        (0..1201)
+            .flat_map(|row| (0..1201).map(move |col| (row, col)))
+            .flat_map(|(row, col)| f(row, col).to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let mut tiles = HgtTiles::new();
+        // Rises 1 m per sample eastwards and northwards, 1000 m at the north-west corner
+        tiles.insert(46, 7, &tile(|row, col| (1000 - row as i32 + col as i32) as i16)).unwrap();
+
+        let close = |lat: f64, lon: f64, expected: f64| (tiles.elevation(lat, lon).unwrap() - expected).abs() < 1e-6;
+        assert!(close(46.0, 7.0, -200.0));
+        assert!(close(46.0, 8.0 - 1e-12, 1000.0));
+        assert!(close(46.5, 7.5, 1000.0));
+        assert!(close(46.5, 7.5 + 0.5 / 1200.0, 1000.5));
+        assert!(close(46.5 + 0.25 / 1200.0, 7.5, 1000.25));
+
+        assert_eq!(tiles.elevation(45.5, 7.5), None);
+    }
+
+    #[test]
+    fn test_voids_and_invalid_tiles() {
+        let mut tiles = HgtTiles::new();
+        tiles.insert(-34, 151, &tile(|row, _| if row < 600 { VOID } else { 20 })).unwrap();
+        assert_eq!(tiles.elevation(-33.2, 151.5), None);
+        assert_eq!(tiles.elevation(-33.8, 151.5), Some(20.0));
+
+        assert!(tiles.insert(0, 0, &[0; 100]).is_err());
+        assert!(tiles.insert(90, 0, &tile(|_, _| 0)).is_err());
+        assert_eq!(tiles.len(), 1);
+    }
+
+    #[test]
+    fn test_tile_names() {
+        assert_eq!(parse_tile_name("N51E004"), Some((51, 4)));
+        assert_eq!(parse_tile_name("s34e151"), Some((-34, 151)));
+        assert_eq!(parse_tile_name("N40W074"), Some((40, -74)));
+        assert_eq!(parse_tile_name("X40W074"), None);
+        assert_eq!(parse_tile_name("N40"), None);
+    }
+}
@@ -0,0 +1,378 @@
+//! Distances with explicit units
+//!
+//! [`Distance`] stores meters and converts on access, so callers never
+//! hard-code conversion factors (in particular statute vs nautical miles).
+
+use std::fmt;
+use std::ops::{Add, Sub};
+
+use crate::{calculate_distance, PrecisionInfo, Result};
+
+const METERS_PER_FOOT: f64 = 0.3048;
+const METERS_PER_KILOMETER: f64 = 1_000.0;
+/// International statute mile
+const METERS_PER_MILE: f64 = 1_609.344;
+/// International nautical mile
+const METERS_PER_NAUTICAL_MILE: f64 = 1_852.0;
+
+/// Unit of length
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DistanceUnit {
+    /// Meters
+    #[default]
+    Meters,
+    /// Kilometers
+    Kilometers,
+    /// Statute miles (1,609.344 m)
+    Miles,
+    /// Nautical miles (1,852 m)
+    NauticalMiles,
+}
+
+impl DistanceUnit {
+    /// Length of one unit in meters
+    pub fn meters_per_unit(self) -> f64 {
+        match self {
+            DistanceUnit::Meters => 1.0,
+            DistanceUnit::Kilometers => METERS_PER_KILOMETER,
+            DistanceUnit::Miles => METERS_PER_MILE,
+            DistanceUnit::NauticalMiles => METERS_PER_NAUTICAL_MILE,
+        }
+    }
+
+    /// Unit symbol, e.g. `km` or `nmi`
+    pub fn symbol(self) -> &'static str {
+        match self {
+            DistanceUnit::Meters => "m",
+            DistanceUnit::Kilometers => "km",
+            DistanceUnit::Miles => "mi",
+            DistanceUnit::NauticalMiles => "nmi",
+        }
+    }
+}
+
+/// A distance, stored in meters
+///
+/// # Example
+/// ```rust
+/// use grid9::{Distance, DistanceUnit};
+///
+/// let d = Distance::from_meters(1852.0);
+/// assert_eq!(d.nautical_miles(), 1.0);
+/// assert_eq!(d.km(), 1.852);
+/// assert_eq!(Distance::new(1.0, DistanceUnit::Miles).meters(), 1609.344);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Distance(f64);
+
+impl Distance {
+    /// Zero distance
+    pub const ZERO: Distance = Distance(0.0);
+
+    /// Creates a distance from a value in `unit`
+    pub fn new(value: f64, unit: DistanceUnit) -> Self {
+        Distance(value * unit.meters_per_unit())
+    }
+
+    /// Creates a distance from meters
+    pub const fn from_meters(meters: f64) -> Self {
+        Distance(meters)
+    }
+
+    /// Creates a distance from kilometers
+    pub fn from_km(km: f64) -> Self {
+        Self::new(km, DistanceUnit::Kilometers)
+    }
+
+    /// Creates a distance from statute miles
+    pub fn from_miles(miles: f64) -> Self {
+        Self::new(miles, DistanceUnit::Miles)
+    }
+
+    /// Creates a distance from nautical miles
+    pub fn from_nautical_miles(nautical_miles: f64) -> Self {
+        Self::new(nautical_miles, DistanceUnit::NauticalMiles)
+    }
+
+    /// Distance in meters
+    pub const fn meters(self) -> f64 {
+        self.0
+    }
+
+    /// Distance in kilometers
+    pub fn km(self) -> f64 {
+        self.in_unit(DistanceUnit::Kilometers)
+    }
+
+    /// Distance in statute miles
+    pub fn miles(self) -> f64 {
+        self.in_unit(DistanceUnit::Miles)
+    }
+
+    /// Distance in nautical miles
+    pub fn nautical_miles(self) -> f64 {
+        self.in_unit(DistanceUnit::NauticalMiles)
+    }
+
+    /// Distance in `unit`
+    pub fn in_unit(self, unit: DistanceUnit) -> f64 {
+        self.0 / unit.meters_per_unit()
+    }
+}
+
+impl Add for Distance {
+    type Output = Distance;
+
+    fn add(self, other: Distance) -> Distance {
+        Distance(self.0 + other.0)
+    }
+}
+
+impl Sub for Distance {
+    type Output = Distance;
+
+    fn sub(self, other: Distance) -> Distance {
+        Distance(self.0 - other.0)
+    }
+}
+
+impl std::iter::Sum for Distance {
+    fn sum<I: Iterator<Item = Distance>>(iter: I) -> Distance {
+        Distance(iter.map(Distance::meters).sum())
+    }
+}
+
+impl fmt::Display for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.precision() {
+            Some(precision) => write!(f, "{:.*} m", precision, self.0),
+            None => write!(f, "{} m", self.0),
+        }
+    }
+}
+
+/// Calculates the distance between two Grid9 codes.
+///
+/// Same as [`calculate_distance`], typed as a [`Distance`].
+///
+/// # Example
+/// ```rust
+/// use grid9::{distance_between, encode};
+///
+/// let nyc = encode(40.7128, -74.0060, false)?;
+/// let london = encode(51.5074, -0.1278, false)?;
+/// let distance = distance_between(&nyc, &london)?;
+/// assert!((distance.km() - 5570.0).abs() < 10.0);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn distance_between(encoded1: &str, encoded2: &str) -> Result<Distance> {
+    calculate_distance(encoded1, encoded2).map(Distance::from_meters)
+}
+
+/// Calculates the distance between two Grid9 codes in `unit`.
+///
+/// # Arguments
+/// * `encoded1` - First Grid9 code
+/// * `encoded2` - Second Grid9 code
+/// * `unit` - Unit of the result
+///
+/// # Returns
+/// Haversine distance in `unit`
+pub fn calculate_distance_in(encoded1: &str, encoded2: &str, unit: DistanceUnit) -> Result<f64> {
+    distance_between(encoded1, encoded2).map(|distance| distance.in_unit(unit))
+}
+
+/// Units used by [`format_distance`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnitSystem {
+    /// Meters and kilometers
+    #[default]
+    Metric,
+    /// Feet and statute miles
+    Imperial,
+    /// Meters and nautical miles
+    Nautical,
+}
+
+/// Locale settings for [`format_distance`] and [`format_precision`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FormatOptions {
+    /// Units to display
+    pub units: UnitSystem,
+    /// Digit group separator, e.g. `,` or `.` (`None` disables grouping)
+    pub thousands_separator: Option<char>,
+    /// Decimal separator, e.g. `.` or `,`
+    pub decimal_separator: char,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            units: UnitSystem::Metric,
+            thousands_separator: Some(','),
+            decimal_separator: '.',
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Default separators with the given units
+    pub fn with_units(units: UnitSystem) -> Self {
+        Self { units, ..Self::default() }
+    }
+
+    /// Overrides the digit group and decimal separators
+    pub fn separators(mut self, thousands: Option<char>, decimal: char) -> Self {
+        self.thousands_separator = thousands;
+        self.decimal_separator = decimal;
+        self
+    }
+}
+
+/// Formats a distance for display, e.g. "850 m", "5,570 km" or "3.4 mi".
+///
+/// Values below one kilometer or nautical mile, or a tenth of a mile, use
+/// meters or feet. Values below 10 units get one decimal place, larger
+/// values are rounded to whole units.
+///
+/// # Arguments
+/// * `meters` - Distance in meters
+/// * `options` - Units and separators
+///
+/// # Example
+/// ```rust
+/// use grid9::{format_distance, FormatOptions, UnitSystem};
+///
+/// assert_eq!(format_distance(850.0, &FormatOptions::default()), "850 m");
+/// assert_eq!(format_distance(5_570_224.0, &FormatOptions::default()), "5,570 km");
+/// assert_eq!(format_distance(5_470.0, &FormatOptions::with_units(UnitSystem::Imperial)), "3.4 mi");
+/// ```
+pub fn format_distance(meters: f64, options: &FormatOptions) -> String {
+    // (meters per unit, symbol) of the small and large unit, and the large
+    // value below which the small unit is used
+    let ((small_factor, small_symbol), large, threshold) = match options.units {
+        UnitSystem::Metric => ((1.0, "m"), DistanceUnit::Kilometers, 1.0),
+        UnitSystem::Imperial => ((METERS_PER_FOOT, "ft"), DistanceUnit::Miles, 0.1),
+        UnitSystem::Nautical => ((1.0, "m"), DistanceUnit::NauticalMiles, 1.0),
+    };
+
+    let large_value = meters / large.meters_per_unit();
+    if large_value.abs() >= threshold || !meters.is_finite() {
+        format_value(large_value, large.symbol(), options)
+    } else {
+        format_value(meters / small_factor, small_symbol, options)
+    }
+}
+
+/// Formats the precision of a cell for display, e.g. "±2.4 m".
+///
+/// # Example
+/// ```rust
+/// use grid9::{format_precision, get_actual_precision, FormatOptions};
+///
+/// let precision = get_actual_precision(40.7128, -74.0060)?;
+/// assert!(format_precision(&precision, &FormatOptions::default()).starts_with('±'));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn format_precision(precision: &PrecisionInfo, options: &FormatOptions) -> String {
+    format!("±{}", format_distance(precision.total_error_m, options))
+}
+
+fn format_value(value: f64, symbol: &str, options: &FormatOptions) -> String {
+    if !value.is_finite() {
+        return format!("{} {}", value, symbol);
+    }
+
+    let decimals = if value.abs() < 10.0 { 1 } else { 0 };
+    let rounded = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = rounded.split_once('.').unwrap_or((&rounded, ""));
+
+    let mut out = String::with_capacity(rounded.len() + 8);
+    if value < 0.0 && rounded.bytes().any(|b| b.is_ascii_digit() && b != b'0') {
+        out.push('-');
+    }
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            if let Some(separator) = options.thousands_separator {
+                out.push(separator);
+            }
+        }
+        out.push(digit);
+    }
+    if !fraction.is_empty() {
+        out.push(options.decimal_separator);
+        out.push_str(fraction);
+    }
+    out.push(' ');
+    out.push_str(symbol);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode;
+
+    #[test]
+    fn test_unit_conversions() {
+        let distance = Distance::from_km(10.0);
+        assert_eq!(distance.meters(), 10_000.0);
+        assert!((distance.miles() - 6.213_711_922).abs() < 1e-9);
+        assert!((distance.nautical_miles() - 5.399_568_035).abs() < 1e-9);
+        assert!((Distance::from_nautical_miles(1.0).miles() - 1.150_779_448).abs() < 1e-9);
+        assert_eq!(Distance::from_miles(2.0) - Distance::from_miles(1.0), Distance::from_miles(1.0));
+
+        let total: Distance = [Distance::from_meters(400.0), Distance::from_km(1.0)].into_iter().sum();
+        assert_eq!(total.meters(), 1_400.0);
+        assert!(Distance::ZERO < total);
+        assert_eq!(format!("{:.1}", total), "1400.0 m");
+    }
+
+    #[test]
+    fn test_distance_between_codes() {
+        let nyc = encode(40.7128, -74.0060, false).unwrap();
+        let london = encode(51.5074, -0.1278, false).unwrap();
+
+        let meters = calculate_distance(&nyc, &london).unwrap();
+        assert_eq!(distance_between(&nyc, &london).unwrap().meters(), meters);
+        assert_eq!(
+            calculate_distance_in(&nyc, &london, DistanceUnit::NauticalMiles).unwrap(),
+            meters / 1852.0
+        );
+        assert!(calculate_distance_in("bad", &london, DistanceUnit::Miles).is_err());
+    }
+
+    #[test]
+    fn test_format_distance() {
+        let metric = FormatOptions::default();
+        assert_eq!(format_distance(2.44, &metric), "2.4 m");
+        assert_eq!(format_distance(850.4, &metric), "850 m");
+        assert_eq!(format_distance(3_400.0, &metric), "3.4 km");
+        assert_eq!(format_distance(5_570_224.0, &metric), "5,570 km");
+        assert_eq!(format_distance(12_345_678.0, &metric.separators(Some('.'), ',')), "12.346 km");
+        assert_eq!(format_distance(3_400.0, &metric.separators(None, ',')), "3,4 km");
+
+        let imperial = FormatOptions::with_units(UnitSystem::Imperial);
+        assert_eq!(format_distance(100.0, &imperial), "328 ft");
+        assert_eq!(format_distance(805.0, &imperial), "0.5 mi");
+        assert_eq!(format_distance(5_470.0, &imperial), "3.4 mi");
+
+        let nautical = FormatOptions::with_units(UnitSystem::Nautical);
+        assert_eq!(format_distance(900.0, &nautical), "900 m");
+        assert_eq!(format_distance(18_520.0, &nautical), "10 nmi");
+    }
+
+    #[test]
+    fn test_format_precision() {
+        let precision = PrecisionInfo { lat_error_m: 1.9, lon_error_m: 1.5, total_error_m: 2.42 };
+        assert_eq!(format_precision(&precision, &FormatOptions::default()), "±2.4 m");
+        assert_eq!(
+            format_precision(&precision, &FormatOptions::with_units(UnitSystem::Imperial)),
+            "±7.9 ft"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Terrain elevation from user-supplied data
+//!
+//! Grid9 codes carry no altitude. [`ElevationProvider`] is the hook through
+//! which terrain data (DEM tiles, an elevation service, a test fixture)
+//! plugs into [`elevation_of`] and [`slant_distance`]. Closures
+//! `Fn(f64, f64) -> Option<f64>` implement it directly; behind the `dem`
+//! feature, `HgtTiles` reads SRTM `.hgt` tiles.
+
+use crate::uniform_precision_compressor::haversine_distance;
+use crate::{Grid9Code, Result};
+
+/// Source of terrain elevation
+///
+/// # Example
+/// ```rust
+/// use grid9::{elevation_of, encode, ElevationProvider};
+///
+/// /// A flat plateau 300 m above sea level
+/// struct Plateau;
+///
+/// impl ElevationProvider for Plateau {
+///     fn elevation(&self, _latitude: f64, _longitude: f64) -> Option<f64> {
+///         Some(300.0)
+///     }
+/// }
+///
+/// let code = encode(40.7128, -74.0060, false)?;
+/// assert_eq!(elevation_of(&code, &Plateau)?, Some(300.0));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub trait ElevationProvider {
+    /// Elevation above mean sea level in meters, or `None` where the
+    /// provider has no data.
+    fn elevation(&self, latitude: f64, longitude: f64) -> Option<f64>;
+}
+
+impl<F> ElevationProvider for F
+where
+    F: Fn(f64, f64) -> Option<f64>,
+{
+    fn elevation(&self, latitude: f64, longitude: f64) -> Option<f64> {
+        self(latitude, longitude)
+    }
+}
+
+/// Returns the terrain elevation of a Grid9 cell.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code (any accepted form)
+/// * `provider` - Terrain data
+///
+/// # Returns
+/// Elevation in meters at the cell's decoded coordinates, or `None` where
+/// the provider has no data
+pub fn elevation_of<P: ElevationProvider + ?Sized>(encoded: &str, provider: &P) -> Result<Option<f64>> {
+    let code: Grid9Code = encoded.parse()?;
+    let (latitude, longitude) = code.coordinates();
+    Ok(provider.elevation(latitude, longitude))
+}
+
+/// Calculates the straight-line distance between two Grid9 cells on the
+/// terrain surface.
+///
+/// Combines the haversine distance with the elevation difference, which is
+/// accurate for the short distances (up to tens of kilometers) where the
+/// elevation difference matters at all.
+///
+/// # Arguments
+/// * `encoded1` - First Grid9 code
+/// * `encoded2` - Second Grid9 code
+/// * `provider` - Terrain data
+///
+/// # Returns
+/// Distance in meters, or `None` where the provider lacks data for either cell
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, slant_distance};
+///
+/// // Terrain rising 1 m per 100 m northwards (about 1.1 km per degree)
+/// let slope = |latitude: f64, _longitude: f64| Some(latitude * 1_112.0);
+///
+/// let valley = encode(46.00, 7.00, false)?;
+/// let ridge = encode(46.01, 7.00, false)?;
+/// let distance = slant_distance(&valley, &ridge, &slope)?.unwrap();
+/// assert!((distance - 1_112.0).abs() < 5.0);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn slant_distance<P: ElevationProvider + ?Sized>(
+    encoded1: &str,
+    encoded2: &str,
+    provider: &P,
+) -> Result<Option<f64>> {
+    let (lat1, lon1) = encoded1.parse::<Grid9Code>()?.coordinates();
+    let (lat2, lon2) = encoded2.parse::<Grid9Code>()?.coordinates();
+
+    let (Some(elevation1), Some(elevation2)) = (provider.elevation(lat1, lon1), provider.elevation(lat2, lon2)) else {
+        return Ok(None);
+    };
+    let horizontal = haversine_distance(lat1, lon1, lat2, lon2);
+    Ok(Some(horizontal.hypot(elevation2 - elevation1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calculate_distance, encode};
+
+    #[test]
+    fn test_elevation_of() {
+        let land_only = |latitude: f64, _longitude: f64| (latitude > 0.0).then_some(120.0);
+        let north = encode(10.0, 20.0, false).unwrap();
+        let south = encode(-10.0, 20.0, false).unwrap();
+
+        assert_eq!(elevation_of(&north, &land_only).unwrap(), Some(120.0));
+        assert_eq!(elevation_of(&south, &land_only).unwrap(), None);
+        assert!(elevation_of("INVALID", &land_only).is_err());
+    }
+
+    #[test]
+    fn test_slant_distance() {
+        let flat = |_: f64, _: f64| Some(0.0);
+        let a = encode(46.0, 7.0, false).unwrap();
+        let b = encode(46.0, 7.01, false).unwrap();
+        assert_eq!(slant_distance(&a, &b, &flat).unwrap(), Some(calculate_distance(&a, &b).unwrap()));
+
+        // A cliff: 1 km up over the same horizontal distance
+        let cliff = |_: f64, longitude: f64| Some(if longitude > 7.005 { 1_000.0 } else { 0.0 });
+        let horizontal = calculate_distance(&a, &b).unwrap();
+        let slant = slant_distance(&a, &b, &cliff).unwrap().unwrap();
+        assert!((slant - horizontal.hypot(1_000.0)).abs() < 1e-6);
+
+        let provider: &dyn ElevationProvider = &|_: f64, _: f64| None;
+        assert_eq!(slant_distance(&a, &b, provider).unwrap(), None);
+    }
+}
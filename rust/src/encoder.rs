@@ -0,0 +1,537 @@
+//! Configurable encoders and decoders built from option builders
+
+use crate::uniform_precision_compressor::{
+    lat_of_index, lon_of_index, normalize_unicode, normalize_with, pack_indices, quantize_with, strip_separators_strict,
+    unpack_indices, BASE32_ALPHABET,
+};
+use crate::version::strip_version_tag;
+use crate::{Grid9Error, ParseMode, Result, Rounding, ValidationReport, FORMAT_VERSION, VERSION_PREFIX};
+
+const INVALID: u8 = 0xFF;
+
+/// A 32-symbol alphabet used to spell the 45-bit Grid9 payload
+#[derive(Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    symbols: [u8; 32],
+    values: [u8; 128],
+}
+
+impl Alphabet {
+    /// Creates an alphabet from 32 distinct printable ASCII characters other than `-`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::Alphabet;
+    ///
+    /// let alphabet = Alphabet::new("abcdefghijkmnpqrstuvwxyz23456789")?;
+    /// assert!(Alphabet::new("TOO-SHORT").is_err());
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn new(symbols: &str) -> Result<Self> {
+        let bytes = symbols.as_bytes();
+        if !symbols.is_ascii() || bytes.len() != 32 {
+            return Err(Grid9Error::InvalidInput(
+                "alphabet must contain exactly 32 ASCII characters".into(),
+            ));
+        }
+
+        let mut alphabet = Self {
+            symbols: [0; 32],
+            values: [INVALID; 128],
+        };
+
+        for (value, &symbol) in bytes.iter().enumerate() {
+            if !symbol.is_ascii_graphic() || symbol == b'-' {
+                return Err(Grid9Error::InvalidInput(format!(
+                    "alphabet character {:?} is not allowed",
+                    symbol as char
+                ).into()));
+            }
+            if alphabet.values[symbol as usize] != INVALID {
+                return Err(Grid9Error::InvalidInput(format!(
+                    "alphabet character {:?} appears twice",
+                    symbol as char
+                ).into()));
+            }
+            alphabet.symbols[value] = symbol;
+            alphabet.values[symbol as usize] = value as u8;
+        }
+
+        Ok(alphabet)
+    }
+
+    /// The alphabet's symbols in value order
+    pub fn as_str(&self) -> &str {
+        // Validated as ASCII in `new`
+        std::str::from_utf8(&self.symbols).unwrap_or_default()
+    }
+
+    /// Symbol for a 5-bit value
+    pub(crate) fn symbol(&self, value: u8) -> char {
+        self.symbols[(value & 0x1F) as usize] as char
+    }
+
+    /// 5-bit value of a symbol
+    pub(crate) fn value(&self, ch: char) -> Option<u8> {
+        let value = *self.values.get(ch as usize)?;
+        (value != INVALID).then_some(value)
+    }
+}
+
+impl Default for Alphabet {
+    /// The standard Grid9 alphabet `0123456789ABCDEFGHJKMNPQRSTVWXYZ`
+    fn default() -> Self {
+        Self::new(std::str::from_utf8(BASE32_ALPHABET).unwrap_or_default())
+            .expect("the Grid9 alphabet is valid")
+    }
+}
+
+impl std::fmt::Debug for Alphabet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Alphabet").field(&self.as_str()).finish()
+    }
+}
+
+/// Luhn mod 32 check value over 5-bit digit values; detects every single
+/// substituted character and most adjacent transpositions.
+pub(crate) fn check_value(values: &[u8]) -> u8 {
+    let mut factor = 2;
+    let mut sum = 0u32;
+    for &value in values.iter().rev() {
+        let addend = factor * value as u32;
+        sum += addend / 32 + addend % 32;
+        factor = 3 - factor;
+    }
+    ((32 - sum % 32) % 32) as u8
+}
+
+/// Splits a 45-bit payload into its nine 5-bit digit values, most significant first.
+fn digit_values(packed: u64) -> [u8; 9] {
+    let mut values = [0u8; 9];
+    let mut temp = packed;
+    for value in values.iter_mut().rev() {
+        *value = (temp & 0x1F) as u8;
+        temp >>= 5;
+    }
+    values
+}
+
+/// An encoder with a fixed set of output options
+///
+/// # Example
+/// ```rust
+/// use grid9::{Grid9Decoder, Grid9Encoder, Rounding};
+///
+/// let encoder = Grid9Encoder::builder()
+///     .human_readable(true)
+///     .checksum(true)
+///     .rounding(Rounding::Nearest)
+///     .build();
+/// let code = encoder.encode(40.7128, -74.0060)?;
+/// assert_eq!(code.len(), 13); // XXX-XXX-XXX-C
+///
+/// let decoder = Grid9Decoder::builder().checksum(true).build();
+/// let (lat, lon) = decoder.decode(&code)?;
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Grid9Encoder {
+    alphabet: Alphabet,
+    human_readable: bool,
+    checksum: bool,
+    rounding: Rounding,
+    version_tag: bool,
+    wrap_longitude: bool,
+    clamp_latitude: bool,
+}
+
+impl Grid9Encoder {
+    /// Starts building an encoder; the defaults match [`crate::encode`] with `human_readable = false`.
+    pub fn builder() -> Grid9EncoderBuilder {
+        Grid9EncoderBuilder::default()
+    }
+
+    /// Encodes coordinates using the configured options.
+    pub fn encode(&self, latitude: f64, longitude: f64) -> Result<String> {
+        let (latitude, longitude) = normalize_with(latitude, longitude, self.wrap_longitude, self.clamp_latitude)?;
+
+        let (lat_bits, lon_bits) = quantize_with(latitude, longitude, self.rounding);
+        Ok(self.format_packed(pack_indices(lat_bits, lon_bits)))
+    }
+
+    /// Spells a 45-bit payload using the configured options.
+    pub(crate) fn format_packed(&self, packed: u64) -> String {
+        let values = digit_values(packed);
+        let mut result = String::with_capacity(15);
+
+        if self.version_tag && self.alphabet.value(VERSION_PREFIX).is_none() {
+            result.push(VERSION_PREFIX);
+            result.push(BASE32_ALPHABET[FORMAT_VERSION as usize] as char);
+        }
+
+        for (i, &value) in values.iter().enumerate() {
+            if self.human_readable && (i == 3 || i == 6) {
+                result.push('-');
+            }
+            result.push(self.alphabet.symbol(value));
+        }
+        if self.checksum {
+            if self.human_readable {
+                result.push('-');
+            }
+            result.push(self.alphabet.symbol(check_value(&values)));
+        }
+
+        result
+    }
+}
+
+/// Builder for [`Grid9Encoder`]
+#[derive(Debug, Clone, Default)]
+pub struct Grid9EncoderBuilder {
+    encoder: Grid9Encoder,
+}
+
+impl Grid9EncoderBuilder {
+    /// Output XXX-XXX-XXX formatted codes (default: false)
+    pub fn human_readable(mut self, human_readable: bool) -> Self {
+        self.encoder.human_readable = human_readable;
+        self
+    }
+
+    /// Spell codes with a custom alphabet (default: the Grid9 alphabet)
+    pub fn alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.encoder.alphabet = alphabet;
+        self
+    }
+
+    /// Append a Luhn mod 32 check character (default: false)
+    pub fn checksum(mut self, checksum: bool) -> Self {
+        self.encoder.checksum = checksum;
+        self
+    }
+
+    /// Grid rounding used when quantizing coordinates (default: `Rounding::Truncate`)
+    pub fn rounding(mut self, rounding: Rounding) -> Self {
+        self.encoder.rounding = rounding;
+        self
+    }
+
+    /// Prefix codes with an explicit format version tag such as `U1` (default: false).
+    ///
+    /// Ignored for alphabets containing the tag prefix `U`, which could not be
+    /// told apart from untagged codes.
+    pub fn version_tag(mut self, version_tag: bool) -> Self {
+        self.encoder.version_tag = version_tag;
+        self
+    }
+
+    /// Wrap out-of-range longitudes around the globe instead of failing,
+    /// see [`crate::normalize_coordinates`] (default: false)
+    pub fn wrap_longitude(mut self, wrap_longitude: bool) -> Self {
+        self.encoder.wrap_longitude = wrap_longitude;
+        self
+    }
+
+    /// Clamp latitudes beyond the poles to ±90 instead of failing (default: false)
+    pub fn clamp_latitude(mut self, clamp_latitude: bool) -> Self {
+        self.encoder.clamp_latitude = clamp_latitude;
+        self
+    }
+
+    /// Finishes the encoder
+    pub fn build(self) -> Grid9Encoder {
+        self.encoder
+    }
+}
+
+/// A decoder with a fixed set of input options
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Grid9Decoder {
+    alphabet: Alphabet,
+    checksum: bool,
+    mode: ParseMode,
+}
+
+impl Grid9Decoder {
+    /// Starts building a decoder; the defaults match [`crate::decode`].
+    pub fn builder() -> Grid9DecoderBuilder {
+        Grid9DecoderBuilder::default()
+    }
+
+    /// Decodes a code using the configured options.
+    pub fn decode(&self, encoded: &str) -> Result<(f64, f64)> {
+        let (lat_bits, lon_bits) = unpack_indices(self.parse_packed(encoded)?);
+
+        Ok((lat_of_index(lat_bits as f64), lon_of_index(lon_bits as f64)))
+    }
+
+    /// Returns true if the code decodes under the configured options.
+    pub fn is_valid(&self, encoded: &str) -> bool {
+        self.parse_packed(encoded).is_ok()
+    }
+
+    /// Validates a code against the configured alphabet and checksum setting,
+    /// reporting every problem (see [`crate::validate_detailed`]).
+    pub fn validate_detailed(&self, encoded: &str) -> ValidationReport {
+        crate::validation::validate_with(encoded, self)
+    }
+
+    /// Version tags are only recognized when their prefix is not an alphabet symbol
+    pub(crate) fn reads_version_tags(&self) -> bool {
+        self.alphabet.value(VERSION_PREFIX).is_none()
+    }
+
+    pub(crate) fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    pub(crate) fn checksum(&self) -> bool {
+        self.checksum
+    }
+
+    /// Canonical compact spelling (including any check character) of a payload
+    pub(crate) fn canonical(&self, packed: u64) -> String {
+        Grid9Encoder {
+            alphabet: self.alphabet.clone(),
+            human_readable: false,
+            checksum: self.checksum,
+            rounding: Rounding::default(),
+            version_tag: false,
+            wrap_longitude: false,
+            clamp_latitude: false,
+        }
+        .format_packed(packed)
+    }
+
+    pub(crate) fn parse_packed(&self, encoded: &str) -> Result<u64> {
+        self.parse_packed_with_mode(encoded, self.mode)
+    }
+
+    pub(crate) fn parse_packed_with_mode(&self, encoded: &str, mode: ParseMode) -> Result<u64> {
+        let encoded = if self.reads_version_tags() { strip_version_tag(encoded, mode)? } else { encoded };
+        let clean: String = match mode {
+            ParseMode::Lenient => encoded
+                .trim()
+                .chars()
+                .filter_map(normalize_unicode)
+                .filter(|&ch| ch != '-')
+                .collect(),
+            ParseMode::Strict => strip_separators_strict(encoded, self.checksum)?,
+        };
+        if clean.is_empty() {
+            return Err(Grid9Error::EmptyInput);
+        }
+
+        let expected = if self.checksum { 10 } else { 9 };
+        let len = clean.chars().count();
+        if len != expected {
+            return Err(Grid9Error::InvalidLength(len));
+        }
+
+        let values = clean
+            .chars()
+            .map(|ch| self.alphabet.value(ch).ok_or(Grid9Error::InvalidCharacter(ch)))
+            .collect::<Result<Vec<u8>>>()?;
+        if self.checksum && values[9] != check_value(&values[..9]) {
+            return Err(Grid9Error::InvalidChecksum);
+        }
+
+        Ok(values[..9].iter().fold(0u64, |packed, &value| (packed << 5) | value as u64))
+    }
+}
+
+/// Builder for [`Grid9Decoder`]
+#[derive(Debug, Clone, Default)]
+pub struct Grid9DecoderBuilder {
+    decoder: Grid9Decoder,
+}
+
+impl Grid9DecoderBuilder {
+    /// Read codes spelled with a custom alphabet (default: the Grid9 alphabet)
+    pub fn alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.decoder.alphabet = alphabet;
+        self
+    }
+
+    /// Require and verify a trailing Luhn mod 32 check character (default: false)
+    pub fn checksum(mut self, checksum: bool) -> Self {
+        self.decoder.checksum = checksum;
+        self
+    }
+
+    /// Parsing strictness (default: `ParseMode::Lenient`)
+    pub fn mode(mut self, mode: ParseMode) -> Self {
+        self.decoder.mode = mode;
+        self
+    }
+
+    /// Finishes the decoder
+    pub fn build(self) -> Grid9Decoder {
+        self.decoder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, decode_with_mode, encode};
+
+    const COORDS: [(f64, f64); 5] = [
+        (40.7128, -74.0060),
+        (51.5074, -0.1278),
+        (-33.8688, 151.2093),
+        (90.0, 180.0),
+        (-90.0, -180.0),
+    ];
+
+    #[test]
+    fn test_defaults_match_free_functions() {
+        let encoder = Grid9Encoder::builder().build();
+        let readable = Grid9Encoder::builder().human_readable(true).build();
+        let decoder = Grid9Decoder::builder().build();
+
+        for (lat, lon) in COORDS {
+            let code = encoder.encode(lat, lon).unwrap();
+            assert_eq!(code, encode(lat, lon, false).unwrap());
+            assert_eq!(readable.encode(lat, lon).unwrap(), encode(lat, lon, true).unwrap());
+            assert_eq!(decoder.decode(&code).unwrap(), decode(&code).unwrap());
+        }
+
+        for input in ["q7kh2bbyf", "Q-7KH2BB-YF", "", "BAD", "Q7KH2BBY!"] {
+            assert_eq!(decoder.decode(input), decode(input));
+        }
+        let strict = Grid9Decoder::builder().mode(ParseMode::Strict).build();
+        for input in ["Q7K-H2B-BYF", "Q-7KH2BB-YF", "q7kh2bbyf"] {
+            assert_eq!(strict.decode(input), decode_with_mode(input, ParseMode::Strict));
+        }
+    }
+
+    #[test]
+    fn test_custom_alphabet_roundtrip() {
+        let alphabet = Alphabet::new("abcdefghijkmnpqrstuvwxyz23456789").unwrap();
+        let encoder = Grid9Encoder::builder().alphabet(alphabet.clone()).build();
+        let decoder = Grid9Decoder::builder().alphabet(alphabet).build();
+
+        for (lat, lon) in COORDS {
+            let code = encoder.encode(lat, lon).unwrap();
+            assert!(code.chars().all(|c| c.is_ascii_lowercase() || ('2'..='9').contains(&c)));
+            assert_eq!(decoder.decode(&code).unwrap(), decode(&encode(lat, lon, false).unwrap()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_invalid_alphabets() {
+        assert!(Alphabet::new("0123456789").is_err());
+        assert!(Alphabet::new("0123456789ABCDEFGHJKMNPQRSTVWXY-").is_err());
+        assert!(Alphabet::new("0123456789ABCDEFGHJKMNPQRSTVWXYY").is_err());
+        assert_eq!(Alphabet::default().as_str(), "0123456789ABCDEFGHJKMNPQRSTVWXYZ");
+    }
+
+    #[test]
+    fn test_checksum_detects_typos() {
+        let encoder = Grid9Encoder::builder().checksum(true).build();
+        let decoder = Grid9Decoder::builder().checksum(true).build();
+        let code = encoder.encode(40.7128, -74.0060).unwrap();
+        assert_eq!(code.len(), 10);
+        assert!(decoder.is_valid(&code));
+
+        let alphabet = Alphabet::default();
+        let chars: Vec<char> = code.chars().collect();
+        for i in 0..chars.len() {
+            // Every single-character substitution is caught
+            for symbol in alphabet.as_str().chars().filter(|&s| s != chars[i]) {
+                let mut typo = chars.clone();
+                typo[i] = symbol;
+                let typo: String = typo.into_iter().collect();
+                assert_eq!(decoder.decode(&typo), Err(Grid9Error::InvalidChecksum), "{}", typo);
+            }
+        }
+
+        // Missing check character
+        assert_eq!(decoder.decode(&code[..9]), Err(Grid9Error::InvalidLength(9)));
+    }
+
+    #[test]
+    fn test_checksum_human_readable_and_strict() {
+        let encoder = Grid9Encoder::builder().checksum(true).human_readable(true).build();
+        let code = encoder.encode(51.5074, -0.1278).unwrap();
+        assert_eq!(code.len(), 13);
+        assert_eq!(code.chars().filter(|&c| c == '-').count(), 3);
+
+        let strict = Grid9Decoder::builder().checksum(true).mode(ParseMode::Strict).build();
+        assert!(strict.is_valid(&code));
+        assert!(strict.is_valid(&code.replace('-', "")));
+        assert_eq!(strict.decode(&code[..12]), Err(Grid9Error::InvalidSeparator(3)));
+    }
+
+    #[test]
+    fn test_version_tag() {
+        let encoder = Grid9Encoder::builder().version_tag(true).human_readable(true).build();
+        let code = encoder.encode(40.7128, -74.0060).unwrap();
+
+        assert_eq!(code, format!("U1{}", encode(40.7128, -74.0060, true).unwrap()));
+        assert_eq!(crate::format_version(&code).unwrap(), 1);
+        assert!(Grid9Decoder::builder().mode(ParseMode::Strict).build().is_valid(&code));
+
+        // Alphabets containing the prefix cannot carry tags
+        let alphabet = Alphabet::new("0123456789ABCDEFGHJKMNPQRSTUVWXY").unwrap();
+        let encoder = Grid9Encoder::builder().alphabet(alphabet.clone()).version_tag(true).build();
+        let code = encoder.encode(40.7128, -74.0060).unwrap();
+        assert_eq!(code.len(), 9);
+        assert!(Grid9Decoder::builder().alphabet(alphabet).build().is_valid(&code));
+    }
+
+    #[test]
+    fn test_nearest_rounding_halves_error() {
+        let truncate = Grid9Encoder::builder().build();
+        let nearest = Grid9Encoder::builder().rounding(Rounding::Nearest).build();
+        let lat_step = 180.0 / crate::DefaultGrid::LAT_MAX as f64;
+        let lon_step = 360.0 / crate::DefaultGrid::LON_MAX as f64;
+
+        let mut worst_truncate: f64 = 0.0;
+        for i in 0..1_000 {
+            let lat = -80.0 + i as f64 * 0.16037;
+            let lon = -170.0 + i as f64 * 0.34011;
+            let (lat_n, lon_n) = decode(&nearest.encode(lat, lon).unwrap()).unwrap();
+            let (lat_t, _) = decode(&truncate.encode(lat, lon).unwrap()).unwrap();
+
+            assert!((lat_n - lat).abs() <= lat_step / 2.0 + 1e-9);
+            assert!((lon_n - lon).abs() <= lon_step / 2.0 + 1e-9);
+            worst_truncate = worst_truncate.max((lat_t - lat).abs());
+        }
+        assert!(worst_truncate > lat_step / 2.0);
+    }
+
+    #[test]
+    fn test_normalizing_encoder() {
+        let strict = Grid9Encoder::builder().build();
+        let wrapping = Grid9Encoder::builder().wrap_longitude(true).build();
+        let lenient = Grid9Encoder::builder().wrap_longitude(true).clamp_latitude(true).build();
+
+        assert!(strict.encode(51.5, 190.0).is_err());
+        assert_eq!(wrapping.encode(51.5, 190.0), strict.encode(51.5, -170.0));
+        assert_eq!(wrapping.encode(51.5, -720.0), strict.encode(51.5, 0.0));
+        assert_eq!(wrapping.encode(90.5, 0.0), Err(Grid9Error::InvalidLatitude(90.5)));
+        assert_eq!(lenient.encode(90.5, 370.0), strict.encode(90.0, 10.0));
+        assert!(lenient.encode(f64::NAN, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_checksum_validation_report() {
+        let decoder = Grid9Decoder::builder().checksum(true).build();
+        let code = Grid9Encoder::builder().checksum(true).build().encode(0.0, 0.0).unwrap();
+        assert!(decoder.validate_detailed(&code).is_valid());
+
+        let mut bad = code[..9].to_string();
+        let wrong = Alphabet::default()
+            .as_str()
+            .chars()
+            .find(|&c| !code.ends_with(c))
+            .unwrap();
+        bad.push(wrong);
+        let report = decoder.validate_detailed(&bad);
+        assert_eq!(report.issues, vec![crate::ValidationIssue::ChecksumMismatch]);
+        assert_eq!(report.suggestion, None);
+    }
+}
@@ -0,0 +1,434 @@
+//! Position fixes with altitude, accuracy and time
+//!
+//! A [`Fix`] carries what a GNSS receiver reports beside latitude and
+//! longitude, so accuracy can decide how precisely a position is stored.
+
+use std::time::SystemTime;
+
+use crate::uniform_precision_compressor::{quantize, unit_vector, validate_coordinates, EARTH_RADIUS_M};
+use crate::{blur, encode, Coordinate, Grid9Bucket, Grid9Code, Grid9Error, Result, MAX_LAT_INDEX, MAX_LEVEL, MAX_LON_INDEX};
+
+/// Share of fixes within the reported accuracy radius, as for Android's
+/// `Location.getAccuracy` (one standard deviation of a circular error)
+const ACCURACY_CONFIDENCE: f64 = 0.68;
+
+/// Most cells searched for alternates either side of the fix's cell in
+/// longitude, bounding the work for fixes right next to a pole
+const MAX_ALTERNATE_COLUMNS: i64 = 2048;
+
+/// Most cells searched for alternates either side of the fix's cell in
+/// latitude
+const MAX_ALTERNATE_ROWS: i64 = 2048;
+
+/// Most alternates returned; the least likely are dropped beyond this
+const MAX_ALTERNATES: usize = 1024;
+
+/// Rows of cells the accuracy radius may span before alternates move up to
+/// coarser buckets
+const ALTERNATE_ROWS_PER_RADIUS: f64 = 16.0;
+
+/// A position fix: a coordinate with optional altitude, horizontal accuracy
+/// and timestamp
+///
+/// # Example
+/// ```rust
+/// use std::time::SystemTime;
+/// use grid9::Fix;
+///
+/// let fix = Fix::builder(40.7128, -74.0060)
+///     .altitude_m(10.0)
+///     .horizontal_accuracy_m(25.0)
+///     .timestamp(SystemTime::now())
+///     .build()?;
+///
+/// // A ±25m fix is stored at a level whose buckets span its error circle
+/// let bucket = fix.precision_bucket()?;
+/// assert!(bucket.dimensions_m().0 >= 50.0);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fix<T = SystemTime> {
+    /// Latitude and longitude in degrees
+    pub coordinate: Coordinate,
+    /// Altitude in meters
+    pub altitude_m: Option<f64>,
+    /// Radius of the horizontal error circle in meters
+    pub horizontal_accuracy_m: Option<f64>,
+    /// Time of the fix
+    pub timestamp: Option<T>,
+}
+
+impl Fix {
+    /// Starts a fix at the given coordinates; add a timestamp of any type
+    /// with [`FixBuilder::timestamp`].
+    pub fn builder(latitude: f64, longitude: f64) -> FixBuilder {
+        FixBuilder {
+            fix: Fix {
+                coordinate: Coordinate::new(latitude, longitude),
+                altitude_m: None,
+                horizontal_accuracy_m: None,
+                timestamp: None,
+            },
+        }
+    }
+}
+
+impl<T> Fix<T> {
+    /// Grid9 code of the fix's position
+    pub fn code(&self) -> Result<Grid9Code> {
+        Grid9Code::encode(self.coordinate.lat, self.coordinate.lon)
+    }
+
+    /// Encodes the fix's position at full precision.
+    pub fn encode(&self, human_readable: bool) -> Result<String> {
+        encode(self.coordinate.lat, self.coordinate.lon, human_readable)
+    }
+
+    /// The finest bucket that still covers the fix's error circle (see
+    /// [`blur`]), or the single cell when the accuracy is unknown.
+    ///
+    /// Storing this instead of the full code avoids claiming ~3m precision
+    /// for a fix that is only good to tens of meters.
+    pub fn precision_bucket(&self) -> Result<Grid9Bucket> {
+        let code = self.encode(false)?;
+        match self.horizontal_accuracy_m {
+            Some(accuracy) => blur(&code, accuracy),
+            None => Grid9Bucket::from_code(&code, 0),
+        }
+    }
+
+    /// True if the fix's accuracy is known and no worse than `max_error_m`
+    pub fn is_accurate_to(&self, max_error_m: f64) -> bool {
+        self.horizontal_accuracy_m.is_some_and(|accuracy| accuracy <= max_error_m)
+    }
+
+    /// Absolute altitude difference to another fix in meters, or `None` if
+    /// either altitude is unknown.
+    pub fn vertical_separation<U>(&self, other: &Fix<U>) -> Option<f64> {
+        Some((other.altitude_m? - self.altitude_m?).abs())
+    }
+
+    /// Straight-line distance to another fix in meters, through both
+    /// positions at their altitudes above a spherical Earth, or `None` if
+    /// either altitude is unknown.
+    ///
+    /// Grid9 codes carry no altitude, so this works on fixes, which pair a
+    /// position with one.
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::Fix;
+    ///
+    /// let drone = Fix::builder(47.3769, 8.5417).altitude_m(120.0).build()?;
+    /// let other = Fix::builder(47.3772, 8.5417).altitude_m(150.0).build()?;
+    ///
+    /// assert_eq!(drone.vertical_separation(&other), Some(30.0));
+    /// let distance = drone.distance_3d(&other).unwrap();
+    /// assert!(distance > 40.0 && distance < 50.0); // ~33m apart, 30m higher
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn distance_3d<U>(&self, other: &Fix<U>) -> Option<f64> {
+        let position = |lat: f64, lon: f64, altitude: f64| {
+            unit_vector(lat, lon).map(|component| component * (EARTH_RADIUS_M + altitude))
+        };
+        let a = position(self.coordinate.lat, self.coordinate.lon, self.altitude_m?);
+        let b = position(other.coordinate.lat, other.coordinate.lon, other.altitude_m?);
+        Some(a.iter().zip(&b).map(|(a, b)| (a - b).powi(2)).sum::<f64>().sqrt())
+    }
+}
+
+impl<T> From<Coordinate> for Fix<T> {
+    fn from(coordinate: Coordinate) -> Self {
+        Self {
+            coordinate,
+            altitude_m: None,
+            horizontal_accuracy_m: None,
+            timestamp: None,
+        }
+    }
+}
+
+/// Builder for [`Fix`], created by [`Fix::builder`]
+#[derive(Debug, Clone)]
+pub struct FixBuilder<T = SystemTime> {
+    fix: Fix<T>,
+}
+
+impl<T> FixBuilder<T> {
+    /// Altitude in meters
+    pub fn altitude_m(mut self, altitude_m: f64) -> Self {
+        self.fix.altitude_m = Some(altitude_m);
+        self
+    }
+
+    /// Radius of the horizontal error circle in meters
+    pub fn horizontal_accuracy_m(mut self, accuracy_m: f64) -> Self {
+        self.fix.horizontal_accuracy_m = Some(accuracy_m);
+        self
+    }
+
+    /// Time of the fix, as any timestamp type (e.g. `SystemTime` or, with
+    /// the `chrono` feature, `chrono::DateTime`)
+    pub fn timestamp<U>(self, timestamp: U) -> FixBuilder<U> {
+        let Fix { coordinate, altitude_m, horizontal_accuracy_m, .. } = self.fix;
+        FixBuilder {
+            fix: Fix { coordinate, altitude_m, horizontal_accuracy_m, timestamp: Some(timestamp) },
+        }
+    }
+
+    /// Validates the coordinates, altitude and accuracy and returns the fix.
+    pub fn build(self) -> Result<Fix<T>> {
+        validate_coordinates(self.fix.coordinate.lat, self.fix.coordinate.lon)?;
+        if let Some(altitude) = self.fix.altitude_m.filter(|altitude| !altitude.is_finite()) {
+            return Err(Grid9Error::InvalidInput(format!("invalid altitude {}", altitude).into()));
+        }
+        if let Some(accuracy) = self.fix.horizontal_accuracy_m.filter(|accuracy| !accuracy.is_finite() || *accuracy < 0.0) {
+            return Err(Grid9Error::InvalidRadius(accuracy));
+        }
+
+        Ok(self.fix)
+    }
+}
+
+/// A position's cell with the chance that the true position lies in it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CellEstimate {
+    /// Cell containing the measured position
+    pub code: Grid9Code,
+    /// Probability that the true position lies in `code`'s cell
+    pub confidence: f64,
+    /// Other buckets with their centers inside the accuracy circle, most
+    /// likely first and at most 1024 of them. They are single cells (level
+    /// 0) unless the accuracy radius spans more than 16 rows of cells, in
+    /// which case all share the finest level that brings it back within that.
+    pub alternates: Vec<Grid9Bucket>,
+}
+
+/// Encodes a position along with how much its cell can be trusted.
+///
+/// The error of the measured position is modelled as a circular normal
+/// distribution in which `accuracy_m` is the radius holding 68% of fixes,
+/// the convention of Android and most GNSS receivers. A 30m fix lands in
+/// its ~3m cell less than 1% of the time; the alternates list the cells
+/// the true position is likely in instead, or coarser buckets once the
+/// circle is much larger than a cell. [`Fix::precision_bucket`] gives a
+/// single bucket covering the same circle.
+///
+/// # Arguments
+/// * `latitude` - Measured latitude in degrees
+/// * `longitude` - Measured longitude in degrees
+/// * `accuracy_m` - Horizontal accuracy in meters (finite, non-negative)
+///
+/// # Returns
+/// The cell, its confidence and the alternates, or `InvalidRadius` for an
+/// invalid accuracy
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode_with_accuracy, Grid9Code};
+///
+/// let survey = encode_with_accuracy(40.7128, -74.0060, 0.5)?;
+/// assert!(survey.confidence > 0.5);
+///
+/// let phone = encode_with_accuracy(40.7128, -74.0060, 30.0)?;
+/// assert_eq!(phone.code, Grid9Code::encode(40.7128, -74.0060)?);
+/// assert!(phone.confidence < 0.02);
+/// assert!(phone.alternates.len() > 100);
+///
+/// // A cell-tower fix gets ~10km buckets instead of millions of cells
+/// let tower = encode_with_accuracy(40.7128, -74.0060, 100_000.0)?;
+/// assert!(tower.alternates.iter().all(|bucket| bucket.level() > 0));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn encode_with_accuracy(latitude: f64, longitude: f64, accuracy_m: f64) -> Result<CellEstimate> {
+    validate_coordinates(latitude, longitude)?;
+    if !accuracy_m.is_finite() || accuracy_m < 0.0 {
+        return Err(Grid9Error::InvalidRadius(accuracy_m));
+    }
+    let (lat_index, lon_index) = quantize(latitude, longitude);
+    let code = Grid9Code::from_indices(lat_index, lon_index)?;
+    if accuracy_m == 0.0 {
+        return Ok(CellEstimate { code, confidence: 1.0, alternates: Vec::new() });
+    }
+
+    // Local plane around the position, in meters east and north
+    let meters_per_degree = EARTH_RADIUS_M.to_radians();
+    let cos_lat = latitude.to_radians().cos();
+    let north = |lat: f64| (lat - latitude) * meters_per_degree;
+    let east = |lon: f64| ((lon - longitude + 540.0).rem_euclid(360.0) - 180.0) * meters_per_degree * cos_lat;
+    // Per-axis standard deviation of a circular normal with ACCURACY_CONFIDENCE inside accuracy_m
+    let sigma = accuracy_m / (-2.0 * (1.0 - ACCURACY_CONFIDENCE).ln()).sqrt();
+    let mass = |low: f64, high: f64| normal_cdf(high / sigma) - normal_cdf(low / sigma);
+
+    // Probability of a bucket, and its center's distance from the position
+    let estimate = |bucket: &Grid9Bucket| -> (f64, f64) {
+        let bounds = bucket.bounds();
+        let x = east((bounds.min_lon + bounds.max_lon) / 2.0);
+        let half_width = (bounds.max_lon - bounds.min_lon) / 2.0 * meters_per_degree * cos_lat;
+        let (bottom, top) = (north(bounds.min_lat), north(bounds.max_lat));
+        (mass(bottom, top) * mass(x - half_width, x + half_width), x.hypot((bottom + top) / 2.0))
+    };
+    let (confidence, _) = estimate(&Grid9Bucket::from_indices(lat_index, lon_index, 0)?);
+
+    // Coarsen the alternates until the circle spans few enough rows
+    let cell_height_m = 180.0 / (MAX_LAT_INDEX as f64 + 1.0) * meters_per_degree;
+    let level = (0..MAX_LEVEL)
+        .find(|&level| accuracy_m <= ALTERNATE_ROWS_PER_RADIUS * cell_height_m * f64::from(1u32 << level))
+        .unwrap_or(MAX_LEVEL);
+    let scale = f64::from(1u32 << level);
+    let height_m = cell_height_m * scale;
+    let width_m = 360.0 / (MAX_LON_INDEX as f64 + 1.0) * meters_per_degree * cos_lat * scale;
+    let (lat_bucket, lon_bucket) = ((lat_index >> level) as i64, (lon_index >> level) as i64);
+    let lon_buckets = (MAX_LON_INDEX as i64 + 1) >> level;
+    let rows = ((accuracy_m / height_m).ceil() + 1.0).min(MAX_ALTERNATE_ROWS as f64) as i64;
+    let columns = ((accuracy_m / width_m).ceil() + 1.0).min(MAX_ALTERNATE_COLUMNS as f64) as i64;
+
+    let mut alternates: Vec<(Grid9Bucket, f64)> = Vec::new();
+    for row in -rows..=rows {
+        let lat = lat_bucket + row;
+        if !(0..=(MAX_LAT_INDEX >> level) as i64).contains(&lat) {
+            continue;
+        }
+        // A narrow circle can wrap onto the same buckets from both sides
+        for column in -columns.min(lon_buckets / 2)..=columns.min((lon_buckets - 1) / 2) {
+            if row == 0 && column == 0 {
+                continue;
+            }
+            let lon = (lon_bucket + column).rem_euclid(lon_buckets);
+            let bucket = Grid9Bucket::from_indices((lat as u32) << level, (lon as u32) << level, level)?;
+            let (probability, distance) = estimate(&bucket);
+            if distance <= accuracy_m {
+                alternates.push((bucket, probability));
+            }
+        }
+    }
+    alternates.sort_by(|a, b| b.1.total_cmp(&a.1));
+    alternates.truncate(MAX_ALTERNATES);
+
+    Ok(CellEstimate { code, confidence, alternates: alternates.into_iter().map(|(bucket, _)| bucket).collect() })
+}
+
+/// Standard normal cumulative distribution function
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+/// Error function (Abramowitz and Stegun 7.1.26, absolute error below 1.5e-7)
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let poly = t * (0.254_829_592 + t * (-0.284_496_736 + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    (1.0 - poly * (-x * x).exp()).copysign(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculate_distance;
+    use crate::uniform_precision_compressor::haversine_distance;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_builder() {
+        let fix = Fix::builder(51.5074, -0.1278).altitude_m(35.0).build().unwrap();
+        assert_eq!(fix.coordinate, Coordinate::new(51.5074, -0.1278));
+        assert_eq!(fix.altitude_m, Some(35.0));
+        assert_eq!(fix.timestamp, None);
+        assert_eq!(fix.encode(false).unwrap(), encode(51.5074, -0.1278, false).unwrap());
+
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let timed = Fix::builder(0.0, 0.0).timestamp(time).horizontal_accuracy_m(3.0).build().unwrap();
+        assert_eq!(timed.timestamp, Some(time));
+        assert!(timed.is_accurate_to(5.0) && !timed.is_accurate_to(2.0));
+        assert!(!fix.is_accurate_to(100.0));
+
+        assert_eq!(Fix::builder(91.0, 0.0).build(), Err(Grid9Error::InvalidLatitude(91.0)));
+        assert_eq!(Fix::builder(0.0, 0.0).horizontal_accuracy_m(-1.0).build(), Err(Grid9Error::InvalidRadius(-1.0)));
+        assert!(Fix::builder(0.0, 0.0).altitude_m(f64::NAN).build().is_err());
+    }
+
+    #[test]
+    fn test_vertical_and_3d_distance() {
+        let ground = Fix::builder(40.7128, -74.0060).altitude_m(0.0).build().unwrap();
+        let above = Fix::builder(40.7128, -74.0060).altitude_m(400.0).build().unwrap();
+        let unknown: Fix = Coordinate::new(40.7128, -74.0060).into();
+
+        assert_eq!(ground.vertical_separation(&above), Some(400.0));
+        assert_eq!(above.vertical_separation(&ground), Some(400.0));
+        assert!((ground.distance_3d(&above).unwrap() - 400.0).abs() < 1e-6);
+        assert_eq!(ground.distance_3d(&unknown), None);
+        assert_eq!(unknown.vertical_separation(&ground), None);
+
+        // 300m east and 400m up: the 3-4-5 triangle, up to Earth curvature
+        let east = Fix::builder(40.7128, -74.0060 + 300.0 / (111_194.9 * 40.7128f64.to_radians().cos()))
+            .altitude_m(400.0)
+            .build()
+            .unwrap();
+        let distance = ground.distance_3d(&east).unwrap();
+        assert!((distance - 500.0).abs() < 0.5, "{}", distance);
+    }
+
+    #[test]
+    fn test_precision_bucket() {
+        let precise: Fix = Coordinate::new(40.7128, -74.0060).into();
+        assert_eq!(precise.precision_bucket().unwrap().level(), 0);
+
+        let coarse = Fix::builder(40.7128, -74.0060).horizontal_accuracy_m(500.0).build().unwrap();
+        let bucket = coarse.precision_bucket().unwrap();
+        assert_eq!(bucket, blur(&coarse.encode(false).unwrap(), 500.0).unwrap());
+        assert!(bucket.contains(&precise.encode(false).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_encode_with_accuracy() {
+        let exact = encode_with_accuracy(40.7128, -74.0060, 0.0).unwrap();
+        assert_eq!((exact.code, exact.confidence, exact.alternates.len()), (Grid9Code::encode(40.7128, -74.0060).unwrap(), 1.0, 0));
+
+        // Confidence falls as accuracy worsens, and alternates cover the circle
+        let mut previous = 1.0;
+        for accuracy in [0.5, 3.0, 10.0, 30.0] {
+            let estimate = encode_with_accuracy(40.7128, -74.0060, accuracy).unwrap();
+            assert!(estimate.confidence > 0.0 && estimate.confidence < previous, "{}", accuracy);
+            previous = estimate.confidence;
+            let (height, width) = Grid9Bucket::from_code(&estimate.code.to_string(), 0).unwrap().dimensions_m();
+            let expected = std::f64::consts::PI * accuracy * accuracy / (height * width);
+            assert!((estimate.alternates.len() as f64 - expected).abs() < expected * 0.2 + 8.0, "{} {}", accuracy, estimate.alternates.len());
+        }
+        let phone = encode_with_accuracy(40.7128, -74.0060, 30.0).unwrap();
+        assert!(phone.alternates.iter().all(|alternate| alternate.level() == 0));
+        assert!(phone.alternates.iter().all(|alternate| calculate_distance(&alternate.to_code(false), &phone.code.to_string()).unwrap() < 35.0));
+        // The most likely alternate borders the measured cell
+        assert!(calculate_distance(&phone.alternates[0].to_code(false), &phone.code.to_string()).unwrap() < 5.0);
+
+        let antimeridian = encode_with_accuracy(0.0, 179.99999, 10.0).unwrap();
+        assert!(antimeridian.alternates.iter().any(|alternate| alternate.center().lon < 0.0));
+        let pole = encode_with_accuracy(90.0, 0.0, 10.0).unwrap();
+        assert!(!pole.alternates.is_empty() && pole.alternates.len() <= MAX_ALTERNATES);
+
+        // A 100km circle is searched in coarse buckets, still centered on the fix
+        let tower = encode_with_accuracy(40.7128, -74.0060, 100_000.0).unwrap();
+        let level = tower.alternates[0].level();
+        assert!(level > 0 && tower.alternates.iter().all(|alternate| alternate.level() == level));
+        assert!(tower.alternates.len() > 100 && tower.alternates.len() <= MAX_ALTERNATES);
+        assert!(tower.alternates.iter().all(|alternate| {
+            let center = alternate.center();
+            haversine_distance(40.7128, -74.0060, center.lat, center.lon) < 101_000.0
+        }));
+        let home = Grid9Bucket::from_code(&tower.code.to_string(), level).unwrap();
+        assert!(!tower.alternates.contains(&home));
+        assert!(tower.confidence < 1e-6);
+        let nearest = tower.alternates[0].center();
+        assert!(haversine_distance(40.7128, -74.0060, nearest.lat, nearest.lon) < 2.0 * home.dimensions_m().0);
+
+        // Circles larger than the Earth stay bounded too
+        let world = encode_with_accuracy(0.0, 0.0, 1e9).unwrap();
+        assert!(world.alternates.len() <= MAX_ALTERNATES);
+        let pole = encode_with_accuracy(89.99, 0.0, 1_000.0).unwrap();
+        assert!(pole.alternates.len() <= MAX_ALTERNATES);
+
+        assert_eq!(encode_with_accuracy(0.0, 0.0, -1.0), Err(Grid9Error::InvalidRadius(-1.0)));
+        assert!(encode_with_accuracy(0.0, 0.0, f64::NAN).is_err());
+        assert!(encode_with_accuracy(91.0, 0.0, 1.0).is_err());
+    }
+}
@@ -0,0 +1,250 @@
+//! `arbitrary` implementations for structure-aware fuzzing
+//!
+//! Generated values are always valid (coordinates in range, ordered boxes,
+//! tracks in time order) but lean towards the inputs that tend to break
+//! geometry code: the poles and the antimeridian, grid cell edges and the
+//! exact midpoints between cells, power-of-two index boundaries of bucket
+//! levels, duplicate timestamps and times before the Unix epoch.
+//!
+//! ```rust,ignore
+//! // fuzz/fuzz_targets/roundtrip.rs
+//! libfuzzer_sys::fuzz_target!(|code: grid9::Grid9Code| {
+//!     assert_eq!(code.to_string().parse::<grid9::Grid9Code>(), Ok(code));
+//! });
+//! ```
+
+use std::time::SystemTime;
+
+use arbitrary::{Arbitrary, Error, Result, Unstructured};
+
+use crate::track::system_time;
+use crate::uniform_precision_compressor::{lat_of_index, lon_of_index};
+use crate::{
+    BoundingBox, Coordinate, Grid9Bucket, Grid9Code, Grid9Region, Track, TrackPoint, MAX_LAT_INDEX, MAX_LEVEL,
+    MAX_LON_INDEX,
+};
+
+/// Latitudes worth hitting on purpose
+const EDGE_LATITUDES: [f64; 7] = [-90.0, 90.0, 0.0, -0.0, f64::MIN_POSITIVE, -89.999_999_9, 89.999_999_9];
+
+/// Longitudes worth hitting on purpose
+const EDGE_LONGITUDES: [f64; 7] = [-180.0, 180.0, 0.0, -0.0, f64::MIN_POSITIVE, -179.999_999_9, 179.999_999_9];
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+/// Track start times range over roughly ±100 years around the Unix epoch
+const MAX_START_SECONDS: i64 = 100 * 366 * 86_400;
+
+impl<'a> Arbitrary<'a> for Grid9Code {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let lat_index = tricky_index(u, MAX_LAT_INDEX)?;
+        let lon_index = tricky_index(u, MAX_LON_INDEX)?;
+        Grid9Code::from_indices(lat_index, lon_index).map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Coordinate {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Coordinate::new(latitude(u)?, longitude(u)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for BoundingBox {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let corner = Coordinate::arbitrary(u)?;
+        // Degenerate boxes (a single point) are common in practice
+        let other = if u.ratio(1, 8)? { corner.clone() } else { Coordinate::arbitrary(u)? };
+
+        Ok(BoundingBox::new(
+            corner.lat.min(other.lat),
+            corner.lat.max(other.lat),
+            corner.lon.min(other.lon),
+            corner.lon.max(other.lon),
+        ))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Grid9Bucket {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let level = u.int_in_range(0..=MAX_LEVEL)?;
+        bucket_at(u, level)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Grid9Region {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let level = u.int_in_range(0..=MAX_LEVEL)?;
+        let mut region = Grid9Region::new(level).map_err(|_| Error::IncorrectFormat)?;
+
+        u.arbitrary_loop(None, Some(256), |u| {
+            region.insert(bucket_at(u, level)?).map_err(|_| Error::IncorrectFormat)?;
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        Ok(region)
+    }
+}
+
+impl<'a> Arbitrary<'a> for TrackPoint<SystemTime> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(TrackPoint { code: Grid9Code::arbitrary(u)?, time: system_time(start_nanos(u)?) })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Track<SystemTime> {
+    /// Tracks mostly move between neighbouring cells, with occasional
+    /// stationary fixes, jumps and repeated timestamps.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut track = Track::new();
+        let mut code = Grid9Code::arbitrary(u)?;
+        let mut nanos = start_nanos(u)?;
+
+        u.arbitrary_loop(None, Some(1024), |u| {
+            track.push(code, system_time(nanos)).map_err(|_| Error::IncorrectFormat)?;
+
+            code = match u.int_in_range(0..=3)? {
+                0 => code,
+                1 => Grid9Code::arbitrary(u)?,
+                _ => neighbor(u, code)?,
+            };
+            nanos += match u.int_in_range(0..=3)? {
+                0 => 0,
+                1 => u.int_in_range(1..=NANOS_PER_SECOND)?,
+                2 => u.int_in_range(1..=3_600)? * NANOS_PER_SECOND,
+                _ => u.int_in_range(0..=30 * 86_400 * NANOS_PER_SECOND)?,
+            };
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        Ok(track)
+    }
+}
+
+/// A grid index biased towards the ends, the middle and power-of-two boundaries
+fn tricky_index(u: &mut Unstructured<'_>, max: u32) -> Result<u32> {
+    Ok(match u.int_in_range(0..=3)? {
+        0 => *u.choose(&[0, 1, max / 2, max / 2 + 1, max - 1, max])?,
+        1 => {
+            let power = 1u32 << u.int_in_range(1..=max.count_ones() - 1)?;
+            (power - u.int_in_range(0..=1)?).min(max)
+        }
+        _ => u.int_in_range(0..=max)?,
+    })
+}
+
+fn latitude(u: &mut Unstructured<'_>) -> Result<f64> {
+    coordinate(u, &EDGE_LATITUDES, MAX_LAT_INDEX, lat_of_index, 90.0)
+}
+
+fn longitude(u: &mut Unstructured<'_>) -> Result<f64> {
+    coordinate(u, &EDGE_LONGITUDES, MAX_LON_INDEX, lon_of_index, 180.0)
+}
+
+/// A coordinate in `-limit..=limit`: an edge value, a cell edge or cell
+/// midpoint, one of those nudged by one ULP, or a uniform value
+fn coordinate(
+    u: &mut Unstructured<'_>,
+    edges: &[f64],
+    max_index: u32,
+    of_index: impl Fn(f64) -> f64,
+    limit: f64,
+) -> Result<f64> {
+    let value = match u.int_in_range(0..=4)? {
+        0 => *u.choose(edges)?,
+        1 | 2 => {
+            let index = tricky_index(u, max_index)? as f64;
+            let value = of_index(if u.arbitrary()? { index + 0.5 } else { index });
+            match u.int_in_range(0..=2)? {
+                0 => value.next_down(),
+                1 => value.next_up(),
+                _ => value,
+            }
+        }
+        _ => u.arbitrary::<u32>()? as f64 / u32::MAX as f64 * 2.0 * limit - limit,
+    };
+    Ok(value.clamp(-limit, limit))
+}
+
+/// A bucket of `level`, found from a tricky cell
+fn bucket_at(u: &mut Unstructured<'_>, level: u8) -> Result<Grid9Bucket> {
+    let (lat_index, lon_index) = Grid9Code::arbitrary(u)?.indices();
+    Grid9Bucket::from_indices(lat_index, lon_index, level).map_err(|_| Error::IncorrectFormat)
+}
+
+/// One of the eight cells around `code`, wrapping across the antimeridian
+/// and staying put at the poles
+fn neighbor(u: &mut Unstructured<'_>, code: Grid9Code) -> Result<Grid9Code> {
+    let (lat_index, lon_index) = code.indices();
+    let lat_index = match u.int_in_range(0..=2)? {
+        0 => lat_index.saturating_sub(1),
+        1 => (lat_index + 1).min(MAX_LAT_INDEX),
+        _ => lat_index,
+    };
+    let lon_index = match u.int_in_range(0..=2)? {
+        0 => lon_index.checked_sub(1).unwrap_or(MAX_LON_INDEX),
+        1 => if lon_index == MAX_LON_INDEX { 0 } else { lon_index + 1 },
+        _ => lon_index,
+    };
+    Grid9Code::from_indices(lat_index, lon_index).map_err(|_| Error::IncorrectFormat)
+}
+
+fn start_nanos(u: &mut Unstructured<'_>) -> Result<i128> {
+    let seconds = match u.int_in_range(0..=2)? {
+        0 => *u.choose(&[0, -1, 1])?,
+        _ => u.int_in_range(-MAX_START_SECONDS..=MAX_START_SECONDS)?,
+    };
+    let subsec = if u.arbitrary()? { u.int_in_range(0..=NANOS_PER_SECOND - 1)? } else { 0 };
+    Ok(seconds as i128 * NANOS_PER_SECOND + subsec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uniform_precision_compressor::validate_coordinates;
+
+    /// Deterministic pseudo-random fuzzer input
+    fn input(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_values_are_valid() {
+        let mut poles = 0;
+        for seed in 0..200 {
+            let data = input(seed, 4096);
+            let mut u = Unstructured::new(&data);
+
+            let code = Grid9Code::arbitrary(&mut u).unwrap();
+            assert_eq!(code.to_string().parse::<Grid9Code>(), Ok(code));
+
+            let coordinate = Coordinate::arbitrary(&mut u).unwrap();
+            assert!(validate_coordinates(coordinate.lat, coordinate.lon).is_ok(), "{:?}", coordinate);
+            poles += (coordinate.lat.abs() == 90.0) as u32;
+
+            let bbox = BoundingBox::arbitrary(&mut u).unwrap();
+            assert!(bbox.min_lat <= bbox.max_lat && bbox.min_lon <= bbox.max_lon, "{:?}", bbox);
+            assert!(validate_coordinates(bbox.min_lat, bbox.min_lon).is_ok());
+            assert!(validate_coordinates(bbox.max_lat, bbox.max_lon).is_ok());
+
+            let region = Grid9Region::arbitrary(&mut u).unwrap();
+            assert!(region.buckets().all(|bucket| bucket.level() == region.level()));
+
+            let track = Track::<SystemTime>::arbitrary(&mut u).unwrap();
+            assert!(track.points().windows(2).all(|pair| pair[0].time <= pair[1].time));
+        }
+        // Edge values show up far more often than uniform sampling would give
+        assert!(poles > 5, "{}", poles);
+    }
+
+    #[test]
+    fn test_exhausted_input() {
+        let mut u = Unstructured::new(&[]);
+        assert!(validate_coordinates(Coordinate::arbitrary(&mut u).unwrap().lat, 0.0).is_ok());
+        assert!(Grid9Region::arbitrary(&mut u).unwrap().is_empty());
+        assert_eq!(Track::<SystemTime>::arbitrary(&mut u).unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,150 @@
+//! Offline nearest-city lookup (feature `gazetteer`)
+//!
+//! The embedded gazetteer (`data/cities.txt`) lists about 1,600 places:
+//! every capital, most cities above half a million inhabitants and the
+//! major ports and regional hubs in between. It is meant for readable logs
+//! and notifications ("asset near Rotterdam"), not for addressing: in
+//! sparsely populated areas the nearest listed city can be hundreds of
+//! kilometers away, so check [`NearestCity::distance_m`] before showing it.
+
+use std::sync::OnceLock;
+
+use crate::uniform_precision_compressor::haversine_distance;
+use crate::{CountryCode, Grid9Code, Grid9Error, Result};
+
+const EMBEDDED_CITIES: &str = include_str!("../data/cities.txt");
+
+/// The gazetteer entry closest to a Grid9 cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearestCity {
+    /// English name of the city
+    pub name: &'static str,
+    /// Country the city lies in
+    pub country: CountryCode,
+    /// Great-circle distance from the cell to the city center in meters
+    pub distance_m: f64,
+}
+
+struct City {
+    name: &'static str,
+    country: CountryCode,
+    latitude: f64,
+    longitude: f64,
+}
+
+fn embedded_cities() -> &'static [City] {
+    static CITIES: OnceLock<Vec<City>> = OnceLock::new();
+    CITIES.get_or_init(|| parse_cities(EMBEDDED_CITIES).expect("embedded gazetteer is valid"))
+}
+
+/// Parses `CC lat lon Name` lines; `#` starts a comment line.
+fn parse_cities(text: &'static str) -> Result<Vec<City>> {
+    let mut cities = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |message: String| Grid9Error::InvalidInput(format!("line {}: {}", number + 1, message).into());
+
+        let mut rest = line;
+        let mut next_field = || {
+            let (field, tail) = rest.split_once(char::is_whitespace)?;
+            rest = tail.trim_start();
+            Some(field)
+        };
+        let (Some(country), Some(latitude), Some(longitude)) = (next_field(), next_field(), next_field()) else {
+            return Err(invalid("expected a country code, latitude, longitude and name".to_string()));
+        };
+
+        let country = country.parse().map_err(|e: Grid9Error| invalid(e.to_string()))?;
+        let (latitude, longitude) = match (latitude.parse::<f64>(), longitude.parse::<f64>()) {
+            (Ok(lat), Ok(lon)) if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) => (lat, lon),
+            _ => return Err(invalid(format!("invalid coordinates '{} {}'", latitude, longitude))),
+        };
+
+        cities.push(City { name: rest, country, latitude, longitude });
+    }
+
+    Ok(cities)
+}
+
+/// Returns the embedded gazetteer's city closest to a Grid9 cell.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code (any accepted form)
+///
+/// # Returns
+/// The city's name and country with its distance from the cell
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, nearest_city};
+///
+/// let city = nearest_city(&encode(51.90, 4.45, false)?)?;
+/// assert_eq!((city.name, city.country.as_str()), ("Rotterdam", "NL"));
+/// assert!(city.distance_m < 5_000.0);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn nearest_city(encoded: &str) -> Result<NearestCity> {
+    let code: Grid9Code = encoded.parse()?;
+    let (latitude, longitude) = code.coordinates();
+
+    let nearest = embedded_cities()
+        .iter()
+        .map(|city| (city, haversine_distance(latitude, longitude, city.latitude, city.longitude)))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(city, distance_m)| NearestCity { name: city.name, country: city.country, distance_m })
+        .expect("embedded gazetteer is not empty");
+    Ok(nearest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode;
+
+    fn nearest(latitude: f64, longitude: f64) -> NearestCity {
+        nearest_city(&encode(latitude, longitude, false).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_nearby_cities() {
+        let places = [
+            ("Rotterdam", "NL", 51.95, 4.40),
+            ("Lisbon", "PT", 38.74, -9.15),
+            ("Tokyo", "JP", 35.69, 139.70),
+            ("Nairobi", "KE", -1.30, 36.80),
+            ("Punta Arenas", "CL", -53.10, -70.90),
+            ("Portland, Maine", "US", 43.66, -70.26),
+            ("St. John's", "CA", 47.56, -52.71),
+        ];
+
+        for (name, country, lat, lon) in places {
+            let city = nearest(lat, lon);
+            assert_eq!((city.name, city.country.as_str()), (name, country));
+            assert!(city.distance_m < 10_000.0, "{} is {} m away", name, city.distance_m);
+        }
+    }
+
+    #[test]
+    fn test_remote_points() {
+        let city = nearest(-30.0, -140.0);
+        assert!(city.distance_m > 500_000.0);
+        assert!(nearest_city("INVALID").is_err());
+    }
+
+    #[test]
+    fn test_parse_cities() {
+        let cities = parse_cities("# comment\n\nNL 51.9244 4.4777 Rotterdam\nUS 43.6591 -70.2568  Portland, Maine\n")
+            .unwrap();
+        assert_eq!(cities.len(), 2);
+        assert_eq!((cities[0].name, cities[0].country.as_str()), ("Rotterdam", "NL"));
+        assert_eq!(cities[1].name, "Portland, Maine");
+
+        assert!(parse_cities("NL 51.9244 4.4777").is_err());
+        assert!(parse_cities("NL 91.0 4.4777 Nowhere").is_err());
+        assert!(parse_cities("NLD 51.9244 4.4777 Rotterdam").is_err());
+    }
+}
@@ -0,0 +1,399 @@
+//! Online reverse geocoding with a per-cell cache (feature `http`)
+//!
+//! [`ReverseGeocoder`] asks a Nominatim or Photon server for the address of a
+//! code. Answers are cached per [`Grid9Bucket`], so repeated lookups in the
+//! same cell (or coarser bucket) never reach the network, and requests are
+//! spaced out to respect the public servers' usage policies (at most one
+//! request per second for `nominatim.openstreetmap.org`).
+
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::{Grid9Bucket, Grid9Code, Grid9Error, Result, MAX_LEVEL};
+
+/// Public Nominatim server
+pub const NOMINATIM_URL: &str = "https://nominatim.openstreetmap.org";
+
+/// Public Photon server
+pub const PHOTON_URL: &str = "https://photon.komoot.io";
+
+/// Reverse geocoding service protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GeocoderProvider {
+    /// Nominatim `/reverse` API (`format=jsonv2`)
+    Nominatim,
+    /// Photon `/reverse` API (GeoJSON)
+    Photon,
+}
+
+impl GeocoderProvider {
+    /// Public server of the provider
+    pub fn default_url(&self) -> &'static str {
+        match self {
+            GeocoderProvider::Nominatim => NOMINATIM_URL,
+            GeocoderProvider::Photon => PHOTON_URL,
+        }
+    }
+}
+
+/// Address returned by a reverse geocoder; fields the service did not
+/// report are `None`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Address {
+    /// Full address line (Nominatim) or place name (Photon)
+    pub display_name: Option<String>,
+    /// House number
+    pub house_number: Option<String>,
+    /// Street
+    pub road: Option<String>,
+    /// City, town or village
+    pub city: Option<String>,
+    /// Postal code
+    pub postcode: Option<String>,
+    /// State or region
+    pub state: Option<String>,
+    /// Country name
+    pub country: Option<String>,
+    /// ISO 3166-1 alpha-2 country code, upper case
+    pub country_code: Option<String>,
+}
+
+/// Rate-limited reverse geocoding client with a per-cell cache
+///
+/// The client is `Sync`; share one instance so the cache and the rate limit
+/// apply across threads.
+///
+/// # Example
+/// ```rust,no_run
+/// use grid9::{GeocoderProvider, Grid9Code, ReverseGeocoder};
+///
+/// let geocoder = ReverseGeocoder::new(GeocoderProvider::Nominatim, "delivery-app/1.0 (ops@example.com)");
+/// let code: Grid9Code = "Q7KH2BBYF".parse()?;
+/// if let Some(address) = geocoder.reverse_geocode(code)? {
+///     println!("{:?}", address.display_name);
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct ReverseGeocoder {
+    agent: ureq::Agent,
+    provider: GeocoderProvider,
+    base_url: String,
+    user_agent: String,
+    level: u8,
+    min_interval: Duration,
+    next_request: Mutex<Instant>,
+    cache: Mutex<AddressCache>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ReverseGeocoder {
+    /// Creates a client for the provider's public server.
+    ///
+    /// Public servers require a `user_agent` identifying the application.
+    /// Defaults: one request per second, cache keyed by single cells (level
+    /// 0) holding up to 10 000 answers.
+    pub fn new(provider: GeocoderProvider, user_agent: impl Into<String>) -> Self {
+        Self {
+            agent: ureq::AgentBuilder::new().timeout(Duration::from_secs(10)).build(),
+            provider,
+            base_url: provider.default_url().to_string(),
+            user_agent: user_agent.into(),
+            level: 0,
+            min_interval: Duration::from_secs(1),
+            next_request: Mutex::new(Instant::now()),
+            cache: Mutex::new(AddressCache::new(10_000)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Uses a self-hosted server instead of the public one
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Sets the minimum time between two network requests
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Shares cached answers across buckets of `level` instead of single
+    /// cells; lookups then query the bucket center.
+    pub fn with_cache_level(mut self, level: u8) -> Result<Self> {
+        if level > MAX_LEVEL {
+            return Err(Grid9Error::InvalidLevel(level));
+        }
+        self.level = level;
+        Ok(self)
+    }
+
+    /// Limits the cache to `capacity` answers, dropping the oldest first
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache = Mutex::new(AddressCache::new(capacity));
+        self
+    }
+
+    /// Looks up the address of a code.
+    ///
+    /// # Returns
+    /// The address, `None` where the service has none (e.g. at sea), or an
+    /// I/O error for network failures, HTTP errors and malformed responses.
+    /// Only successful answers are cached.
+    pub fn reverse_geocode(&self, code: Grid9Code) -> io::Result<Option<Address>> {
+        let (lat_index, lon_index) = code.indices();
+        let bucket = Grid9Bucket::from_indices(lat_index, lon_index, self.level)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        if let Some(address) = self.cache.lock().unwrap_or_else(|e| e.into_inner()).get(bucket.key()) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(address);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let (lat, lon) = match self.level {
+            0 => code.coordinates(),
+            _ => bucket.center().into(),
+        };
+        let body = self.request(lat, lon)?;
+        let address = match self.provider {
+            GeocoderProvider::Nominatim => parse_nominatim(&body),
+            GeocoderProvider::Photon => parse_photon(&body),
+        }
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).insert(bucket.key(), address.clone());
+        Ok(address)
+    }
+
+    /// Lookups answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Lookups that went to the network
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Drops all cached answers
+    pub fn clear_cache(&self) {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    fn request(&self, latitude: f64, longitude: f64) -> io::Result<String> {
+        self.wait_for_slot();
+
+        let url = format!("{}/reverse", self.base_url);
+        let request = self.agent.get(&url).set("User-Agent", &self.user_agent);
+        let request = match self.provider {
+            GeocoderProvider::Nominatim => request.query("format", "jsonv2").query("addressdetails", "1"),
+            GeocoderProvider::Photon => request,
+        };
+        request
+            .query("lat", &format!("{:.7}", latitude))
+            .query("lon", &format!("{:.7}", longitude))
+            .call()
+            .map_err(|e| io::Error::other(e.to_string()))?
+            .into_string()
+    }
+
+    /// Blocks until the rate limit allows another request and reserves it
+    fn wait_for_slot(&self) {
+        let wait = {
+            let mut next_request = self.next_request.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let start = (*next_request).max(now);
+            *next_request = start + self.min_interval;
+            start - now
+        };
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+/// Parses a Nominatim `format=jsonv2` reverse response.
+///
+/// # Returns
+/// The address, `None` for Nominatim's "Unable to geocode" answer, or
+/// `InvalidInput` for malformed responses
+pub fn parse_nominatim(body: &str) -> Result<Option<Address>> {
+    let value = parse_json(body)?;
+    if value.get("error").is_some() {
+        return Ok(None);
+    }
+    let details = value.get("address").ok_or_else(|| invalid("Nominatim response without address"))?;
+    let field = |keys: &[&str]| keys.iter().find_map(|key| text(details, key));
+
+    Ok(Some(Address {
+        display_name: text(&value, "display_name"),
+        house_number: field(&["house_number"]),
+        road: field(&["road", "pedestrian", "footway", "path"]),
+        city: field(&["city", "town", "village", "hamlet", "municipality"]),
+        postcode: field(&["postcode"]),
+        state: field(&["state", "region"]),
+        country: field(&["country"]),
+        country_code: field(&["country_code"]).map(|code| code.to_ascii_uppercase()),
+    }))
+}
+
+/// Parses a Photon reverse response (a GeoJSON `FeatureCollection`).
+///
+/// # Returns
+/// The address of the first feature, `None` if there is none, or
+/// `InvalidInput` for malformed responses
+pub fn parse_photon(body: &str) -> Result<Option<Address>> {
+    let value = parse_json(body)?;
+    let features = value.get("features").and_then(Value::as_array).ok_or_else(|| invalid("Photon response without features"))?;
+    let Some(properties) = features.first().and_then(|feature| feature.get("properties")) else {
+        return Ok(None);
+    };
+
+    Ok(Some(Address {
+        display_name: text(properties, "name"),
+        house_number: text(properties, "housenumber"),
+        road: text(properties, "street"),
+        city: text(properties, "city"),
+        postcode: text(properties, "postcode"),
+        state: text(properties, "state"),
+        country: text(properties, "country"),
+        country_code: text(properties, "countrycode").map(|code| code.to_ascii_uppercase()),
+    }))
+}
+
+fn parse_json(body: &str) -> Result<Value> {
+    serde_json::from_str(body).map_err(|e| Grid9Error::InvalidInput(format!("malformed geocoder response: {}", e).into()))
+}
+
+fn text(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+fn invalid(message: &str) -> Grid9Error {
+    Grid9Error::InvalidInput(message.to_string().into())
+}
+
+/// Answers keyed by bucket, evicting the oldest entry when full
+struct AddressCache {
+    capacity: usize,
+    entries: HashMap<u64, Option<Address>>,
+    order: VecDeque<u64>,
+}
+
+impl AddressCache {
+    fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), entries: HashMap::new(), order: VecDeque::new() }
+    }
+
+    fn get(&self, key: u64) -> Option<Option<Address>> {
+        self.entries.get(&key).cloned()
+    }
+
+    fn insert(&mut self, key: u64, address: Option<Address>) {
+        if self.entries.insert(key, address).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    const NOMINATIM_BODY: &str = r#"{"place_id":1,"display_name":"20, Rue de Rivoli, Paris, France",
+        "address":{"house_number":"20","road":"Rue de Rivoli","town":"Paris","postcode":"75004","country":"France","country_code":"fr"}}"#;
+
+    /// Serves canned HTTP responses, reporting each request line
+    fn serve(body: &'static str) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let _ = sender.send(line.trim().to_string());
+                loop {
+                    let mut header = String::new();
+                    if reader.read_line(&mut header).unwrap() == 0 || header == "\r\n" {
+                        break;
+                    }
+                }
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, receiver)
+    }
+
+    #[test]
+    fn test_parse_responses() {
+        let address = parse_nominatim(NOMINATIM_BODY).unwrap().unwrap();
+        assert_eq!(address.city.as_deref(), Some("Paris"));
+        assert_eq!(address.country_code.as_deref(), Some("FR"));
+        assert_eq!(parse_nominatim(r#"{"error":"Unable to geocode"}"#).unwrap(), None);
+        assert!(parse_nominatim("{}").is_err());
+
+        let photon = r#"{"type":"FeatureCollection","features":[{"type":"Feature","properties":
+            {"name":"Empire State Building","housenumber":"350","street":"5th Avenue","city":"New York","countrycode":"US"}}]}"#;
+        let address = parse_photon(photon).unwrap().unwrap();
+        assert_eq!(address.road.as_deref(), Some("5th Avenue"));
+        assert_eq!(address.postcode, None);
+        assert_eq!(parse_photon(r#"{"type":"FeatureCollection","features":[]}"#).unwrap(), None);
+        assert!(parse_photon("<html>").is_err());
+    }
+
+    #[test]
+    fn test_cache_and_rate_limit() {
+        let (url, requests) = serve(NOMINATIM_BODY);
+        let interval = Duration::from_millis(100);
+        let geocoder = ReverseGeocoder::new(GeocoderProvider::Nominatim, "grid9-test")
+            .with_base_url(url)
+            .with_min_interval(interval)
+            .with_cache_level(4)
+            .unwrap();
+
+        let start = Instant::now();
+        let first = geocoder.reverse_geocode(Grid9Code::encode(48.8556, 2.3600).unwrap()).unwrap();
+        // Same level 4 bucket (~76m), then a different one
+        let second = geocoder.reverse_geocode(Grid9Code::encode(48.85561, 2.36001).unwrap()).unwrap();
+        geocoder.reverse_geocode(Grid9Code::encode(48.8600, 2.3400).unwrap()).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!((geocoder.hits(), geocoder.misses()), (1, 2));
+        assert!(start.elapsed() >= interval);
+
+        let line = requests.recv().unwrap();
+        assert!(line.starts_with("GET /reverse?format=jsonv2&addressdetails=1&lat=48.85"), "{}", line);
+        assert_eq!(requests.try_iter().count() + 1, 2);
+    }
+}
@@ -0,0 +1,147 @@
+//! Simplified polygon datasets shared by the offline lookup features
+//!
+//! Datasets are plain text with one ring per line: one or more labels
+//! followed by `lat,lon` vertices. `#` starts a comment line.
+
+use crate::{BoundingBox, Grid9Error, Result};
+
+/// A labelled ring with its bounding box and (planar) area
+#[derive(Debug, Clone)]
+pub(crate) struct Region<L> {
+    pub label: L,
+    bounds: BoundingBox,
+    area: f64,
+    ring: Vec<(f64, f64)>,
+}
+
+impl<L> Region<L> {
+    fn new(label: L, ring: Vec<(f64, f64)>) -> Self {
+        let mut bounds = BoundingBox::new(f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+        for &(lat, lon) in &ring {
+            bounds.min_lat = bounds.min_lat.min(lat);
+            bounds.max_lat = bounds.max_lat.max(lat);
+            bounds.min_lon = bounds.min_lon.min(lon);
+            bounds.max_lon = bounds.max_lon.max(lon);
+        }
+
+        let twice_area: f64 = ring
+            .iter()
+            .zip(ring.iter().cycle().skip(1))
+            .map(|(&(lat1, lon1), &(lat2, lon2))| lon1 * lat2 - lon2 * lat1)
+            .sum();
+
+        Self { label, bounds, area: twice_area.abs() / 2.0, ring }
+    }
+
+    /// Even-odd point-in-polygon test.
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        if latitude < self.bounds.min_lat
+            || latitude > self.bounds.max_lat
+            || longitude < self.bounds.min_lon
+            || longitude > self.bounds.max_lon
+        {
+            return false;
+        }
+
+        let mut inside = false;
+        let mut previous = self.ring[self.ring.len() - 1];
+        for &current in &self.ring {
+            let ((lat1, lon1), (lat2, lon2)) = (previous, current);
+            if (lat1 > latitude) != (lat2 > latitude) {
+                let crossing = lon1 + (latitude - lat1) / (lat2 - lat1) * (lon2 - lon1);
+                if longitude < crossing {
+                    inside = !inside;
+                }
+            }
+            previous = current;
+        }
+        inside
+    }
+}
+
+/// Returns the smallest region containing the point, so enclaves and
+/// islands take precedence over the rings drawn around them.
+pub(crate) fn smallest_containing<'r, L: 'r>(
+    regions: impl IntoIterator<Item = &'r Region<L>>,
+    latitude: f64,
+    longitude: f64,
+) -> Option<&'r Region<L>> {
+    regions
+        .into_iter()
+        .filter(|region| region.contains(latitude, longitude))
+        .min_by(|a, b| a.area.total_cmp(&b.area))
+}
+
+/// Parses a dataset, turning each line's labels into `L` with `label`.
+pub(crate) fn parse_regions<'a, L>(
+    text: &'a str,
+    mut label: impl FnMut(&[&'a str]) -> Result<L>,
+) -> Result<Vec<Region<L>>> {
+    let mut regions = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |message: String| Grid9Error::InvalidInput(format!("line {}: {}", number + 1, message).into());
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let vertices_start = tokens.iter().position(|token| token.contains(',')).unwrap_or(tokens.len());
+        let label = label(&tokens[..vertices_start]).map_err(|e| invalid(e.to_string()))?;
+
+        let ring = tokens[vertices_start..]
+            .iter()
+            .map(|vertex| {
+                let (lat, lon) = vertex.split_once(',').ok_or_else(|| invalid(format!("invalid vertex '{}'", vertex)))?;
+                match (lat.parse::<f64>(), lon.parse::<f64>()) {
+                    (Ok(lat), Ok(lon)) if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) => {
+                        Ok((lat, lon))
+                    }
+                    _ => Err(invalid(format!("invalid vertex '{}'", vertex))),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if ring.len() < 3 {
+            return Err(invalid(format!("ring has {} vertices, expected at least 3", ring.len())));
+        }
+
+        regions.push(Region::new(label, ring));
+    }
+
+    Ok(regions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(tokens: &[&str]) -> Result<String> {
+        Ok(tokens.join(" "))
+    }
+
+    #[test]
+    fn test_smallest_region_wins() {
+        let text = "# comment\nouter 0,0 0,10 10,10 10,0\n\ninner 4,4 4,6 6,6 6,4\n";
+        let regions = parse_regions(text, labels).unwrap();
+
+        assert_eq!(smallest_containing(&regions, 5.0, 5.0).unwrap().label, "inner");
+        assert_eq!(smallest_containing(&regions, 1.0, 1.0).unwrap().label, "outer");
+        assert!(smallest_containing(&regions, 11.0, 5.0).is_none());
+    }
+
+    #[test]
+    fn test_concave_ring() {
+        let regions = parse_regions("u 0,0 10,0 10,3 3,3 3,7 10,7 10,10 0,10", labels).unwrap();
+        assert!(regions[0].contains(5.0, 1.0));
+        assert!(!regions[0].contains(5.0, 5.0));
+        assert!(regions[0].contains(5.0, 9.0));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_regions("x 0,0 1,1", labels).is_err());
+        assert!(parse_regions("x 0,0 1,1 1;2", labels).is_err());
+        assert!(parse_regions("x 0,0 1,1 95,2", labels).is_err());
+    }
+}
@@ -0,0 +1,807 @@
+//! Named polygon fences and their storage formats
+//!
+//! A [`GeofenceSet`] holds fences keyed by id and answers which fences
+//! contain a code. Sets can be saved in two formats:
+//!
+//! * GeoJSON (feature `geojson`): a `FeatureCollection` of `Polygon` and
+//!   `MultiPolygon` features with the fence id as feature `id` and the label
+//!   as the `label` property, for editing in GIS tools and review in diffs.
+//! * A compact binary format ([`GeofenceSet::to_bytes`]) for services that
+//!   reload fences often. All integers are big-endian:
+//!
+//! | Field | Bytes |
+//! |-------|-------|
+//! | Magic `G9FS` | 4 |
+//! | Format version ([`GEOFENCE_SET_VERSION`]) | 1 |
+//! | Fence count | 4 |
+//! | Per fence: id length, id (UTF-8) | 2 + n |
+//! | Per fence: label flag (0 or 1), then label length and label if set | 1 (+ 2 + n) |
+//! | Per fence: polygon count | 4 |
+//! | Per polygon: ring count (outer ring first, then holes) | 4 |
+//! | Per ring: vertex count, vertices as 45-bit Grid9 payloads | 4 + 6 × n |
+//!
+//! Version 1 files, which held a single ring per fence in place of the
+//! polygon and ring counts, are still read.
+//! The binary format stores vertices as Grid9 cells, so loading moves them
+//! by up to ~3m onto their cell's grid point. Saving a loaded set again
+//! produces the same bytes.
+//!
+//! [`dwell_times`] turns a [`Track`] into visits to the fences of a set,
+//! for time-on-site reports.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::uniform_precision_compressor::{pack_indices, quantize, unpack_indices, validate_coordinates};
+use crate::track::elapsed;
+use crate::{Coordinate, Grid9Code, Grid9Error, Grid9Region, Result, Timestamp, Track};
+
+/// Version written into the binary geofence format
+pub const GEOFENCE_SET_VERSION: u8 = 2;
+
+const MAGIC: &[u8; 4] = b"G9FS";
+
+/// A named area: one or more polygons, each possibly with holes
+///
+/// Rings may be open or closed; edges are straight lines in latitude and
+/// longitude and must not cross the antimeridian.
+///
+/// # Example
+/// ```rust
+/// use grid9::{Coordinate, Geofence};
+///
+/// let square = |min: f64, max: f64| {
+///     vec![Coordinate::new(min, min), Coordinate::new(min, max), Coordinate::new(max, max), Coordinate::new(max, min)]
+/// };
+/// let campus = Geofence::new("campus", square(0.0, 0.03))
+///     .with_hole(square(0.01, 0.02)) // Courtyard
+///     .with_polygon(square(0.05, 0.06)); // Annex across the road
+///
+/// assert!(campus.contains(0.005, 0.005));
+/// assert!(!campus.contains(0.015, 0.015));
+/// assert!(campus.contains(0.055, 0.055));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Geofence {
+    /// Identifier, unique within a set
+    pub id: String,
+    /// Optional display name
+    pub label: Option<String>,
+    /// Polygons, each an outer ring followed by any holes
+    pub polygons: Vec<Vec<Vec<Coordinate>>>,
+}
+
+impl Geofence {
+    /// Creates an unlabelled fence bounded by a single ring
+    pub fn new(id: impl Into<String>, ring: Vec<Coordinate>) -> Self {
+        Self::from_polygons(id, vec![vec![ring]])
+    }
+
+    /// Creates an unlabelled fence from polygons, each an outer ring
+    /// followed by any holes (the layout of a GeoJSON `MultiPolygon`)
+    pub fn from_polygons(id: impl Into<String>, polygons: Vec<Vec<Vec<Coordinate>>>) -> Self {
+        Self { id: id.into(), label: None, polygons }
+    }
+
+    /// Sets the display name
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Cuts a hole into the most recently added polygon
+    pub fn with_hole(mut self, ring: Vec<Coordinate>) -> Self {
+        match self.polygons.last_mut() {
+            Some(polygon) => polygon.push(ring),
+            None => self.polygons.push(vec![ring]),
+        }
+        self
+    }
+
+    /// Adds another polygon bounded by `ring`
+    pub fn with_polygon(mut self, ring: Vec<Coordinate>) -> Self {
+        self.polygons.push(vec![ring]);
+        self
+    }
+
+    /// True if a position lies inside any polygon of the fence and outside
+    /// its holes (even-odd rule within each polygon).
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        self.polygons.iter().any(|rings| {
+            rings.iter().fold(false, |inside, ring| inside ^ ring_contains(ring, latitude, longitude))
+        })
+    }
+
+    /// True if the grid point of a code lies inside the fence
+    pub fn contains_code(&self, code: Grid9Code) -> bool {
+        let (lat, lon) = code.coordinates();
+        self.contains(lat, lon)
+    }
+
+    /// The buckets of `level` whose centers lie inside the fence, see
+    /// [`Grid9Region::from_multipolygon`].
+    pub fn region(&self, level: u8) -> Result<Grid9Region> {
+        Grid9Region::from_multipolygon(&self.polygons, level)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.id.is_empty() || self.id.len() > u16::MAX as usize {
+            return Err(Grid9Error::InvalidInput(format!("invalid geofence id {:?}", self.id).into()));
+        }
+        if self.label.as_ref().is_some_and(|label| label.len() > u16::MAX as usize) {
+            return Err(Grid9Error::InvalidInput(format!("label of geofence {} is too long", self.id).into()));
+        }
+        if self.polygons.is_empty() || self.polygons.iter().any(Vec::is_empty) {
+            return Err(Grid9Error::InvalidInput(format!("geofence {} has an empty polygon", self.id).into()));
+        }
+        if self.polygons.len() > u32::MAX as usize || self.polygons.iter().any(|rings| rings.len() > u32::MAX as usize) {
+            return Err(Grid9Error::InvalidInput(format!("geofence {} has too many rings", self.id).into()));
+        }
+        for ring in self.polygons.iter().flatten() {
+            for point in ring {
+                validate_coordinates(point.lat, point.lon)?;
+            }
+            let closed = ring.len() > 1 && ring.first() == ring.last();
+            if ring.len() < 3 + closed as usize {
+                return Err(Grid9Error::InvalidInput(format!("rings of geofence {} need at least three vertices", self.id).into()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// True if a position lies inside a ring (even-odd rule)
+fn ring_contains(ring: &[Coordinate], latitude: f64, longitude: f64) -> bool {
+    let mut inside = false;
+    let mut previous = match ring.last() {
+        Some(last) => last,
+        None => return false,
+    };
+    for current in ring {
+        if (previous.lat > latitude) != (current.lat > latitude) {
+            let crossing =
+                previous.lon + (latitude - previous.lat) / (current.lat - previous.lat) * (current.lon - previous.lon);
+            if longitude < crossing {
+                inside = !inside;
+            }
+        }
+        previous = current;
+    }
+    inside
+}
+
+/// Geofences keyed by id
+///
+/// # Example
+/// ```rust
+/// use grid9::{Coordinate, Geofence, GeofenceSet, Grid9Code};
+///
+/// let mut fences = GeofenceSet::new();
+/// fences.insert(
+///     Geofence::new("depot-7", vec![
+///         Coordinate::new(40.70, -74.02),
+///         Coordinate::new(40.70, -73.98),
+///         Coordinate::new(40.73, -73.98),
+///         Coordinate::new(40.73, -74.02),
+///     ])
+///     .with_label("Lower Manhattan depot"),
+/// )?;
+///
+/// let bytes = fences.to_bytes();
+/// let reloaded = GeofenceSet::from_bytes(&bytes)?;
+/// let code = Grid9Code::encode(40.7128, -74.0060)?;
+/// let ids: Vec<&str> = reloaded.containing(code).map(|fence| fence.id.as_str()).collect();
+/// assert_eq!(ids, ["depot-7"]);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeofenceSet {
+    fences: BTreeMap<String, Geofence>,
+}
+
+impl GeofenceSet {
+    /// An empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a fence, returning the fence it replaces if the id was taken.
+    ///
+    /// Fails with `InvalidInput` for an empty id, a fence without polygons, a
+    /// ring of fewer than three vertices, or an id or label longer than 65535
+    /// bytes, and with
+    /// `InvalidLatitude`/`InvalidLongitude` for vertices out of range.
+    pub fn insert(&mut self, fence: Geofence) -> Result<Option<Geofence>> {
+        fence.validate()?;
+        Ok(self.fences.insert(fence.id.clone(), fence))
+    }
+
+    /// Fence with the given id
+    pub fn get(&self, id: &str) -> Option<&Geofence> {
+        self.fences.get(id)
+    }
+
+    /// Removes and returns the fence with the given id
+    pub fn remove(&mut self, id: &str) -> Option<Geofence> {
+        self.fences.remove(id)
+    }
+
+    /// Number of fences
+    pub fn len(&self) -> usize {
+        self.fences.len()
+    }
+
+    /// True if the set has no fences
+    pub fn is_empty(&self) -> bool {
+        self.fences.is_empty()
+    }
+
+    /// Fences ordered by id
+    pub fn iter(&self) -> impl Iterator<Item = &Geofence> {
+        self.fences.values()
+    }
+
+    /// Fences containing the grid point of a code, ordered by id
+    pub fn containing(&self, code: Grid9Code) -> impl Iterator<Item = &Geofence> {
+        let (lat, lon) = code.coordinates();
+        self.iter().filter(move |fence| fence.contains(lat, lon))
+    }
+
+    /// Encodes the set in the binary format described in the module docs
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(GEOFENCE_SET_VERSION);
+        bytes.extend_from_slice(&(self.fences.len() as u32).to_be_bytes());
+
+        for fence in self.iter() {
+            write_text(&mut bytes, &fence.id);
+            match &fence.label {
+                Some(label) => {
+                    bytes.push(1);
+                    write_text(&mut bytes, label);
+                }
+                None => bytes.push(0),
+            }
+            bytes.extend_from_slice(&(fence.polygons.len() as u32).to_be_bytes());
+            for rings in &fence.polygons {
+                bytes.extend_from_slice(&(rings.len() as u32).to_be_bytes());
+                for ring in rings {
+                    write_ring(&mut bytes, ring);
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Decodes a set written by [`GeofenceSet::to_bytes`].
+    ///
+    /// # Returns
+    /// The set, `UnsupportedVersion` for an unknown format version, or
+    /// `InvalidInput` for truncated or malformed data
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(Grid9Error::InvalidInput("not a Grid9 geofence set".into()));
+        }
+        let version = reader.take(1)?[0];
+        if !(1..=GEOFENCE_SET_VERSION).contains(&version) {
+            return Err(Grid9Error::UnsupportedVersion(version));
+        }
+
+        let mut set = Self::new();
+        for _ in 0..reader.u32()? {
+            let id = reader.text()?;
+            let label = match reader.take(1)?[0] {
+                0 => None,
+                1 => Some(reader.text()?),
+                other => return Err(Grid9Error::InvalidInput(format!("invalid label flag {}", other).into())),
+            };
+            let polygons = if version == 1 {
+                vec![vec![reader.ring()?]]
+            } else {
+                (0..reader.u32()?)
+                    .map(|_| (0..reader.u32()?).map(|_| reader.ring()).collect())
+                    .collect::<Result<_>>()?
+            };
+
+            if set.insert(Geofence { id, label, polygons })?.is_some() {
+                return Err(Grid9Error::InvalidInput("duplicate geofence id".into()));
+            }
+        }
+        if !reader.bytes.is_empty() {
+            return Err(Grid9Error::InvalidInput("trailing bytes after geofence set".into()));
+        }
+        Ok(set)
+    }
+
+    /// Writes the set as a GeoJSON `FeatureCollection`.
+    ///
+    /// Each fence becomes a `Polygon` feature, or a `MultiPolygon` feature
+    /// if it has several polygons, with the fence id as feature `id` and, if
+    /// set, a `label` property. Rings are written closed.
+    #[cfg(feature = "geojson")]
+    pub fn to_geojson(&self) -> String {
+        use serde_json::{json, Value};
+
+        let features: Vec<String> = self
+            .iter()
+            .map(|fence| {
+                let closed = |ring: &Vec<Coordinate>| {
+                    let mut positions: Vec<Value> = ring.iter().map(|point| json!([point.lon, point.lat])).collect();
+                    if ring.first() != ring.last() {
+                        positions.push(positions[0].clone());
+                    }
+                    Value::Array(positions)
+                };
+                let polygons: Vec<Value> =
+                    fence.polygons.iter().map(|rings| Value::Array(rings.iter().map(closed).collect())).collect();
+                let geometry = match polygons.as_slice() {
+                    [polygon] => json!({ "type": "Polygon", "coordinates": polygon }),
+                    _ => json!({ "type": "MultiPolygon", "coordinates": polygons }),
+                };
+                let properties = match &fence.label {
+                    Some(label) => json!({ "label": label }),
+                    None => json!({}),
+                };
+                format!(
+                    r#"{{"type":"Feature","id":{},"properties":{},"geometry":{}}}"#,
+                    Value::from(fence.id.as_str()),
+                    properties,
+                    geometry
+                )
+            })
+            .collect();
+
+        format!(r#"{{"type":"FeatureCollection","features":[{}]}}"#, features.join(","))
+    }
+
+    /// Reads fences from a GeoJSON `FeatureCollection` of `Polygon` and
+    /// `MultiPolygon` features.
+    ///
+    /// Fence ids come from the feature `id` (a string or number) or else an
+    /// `id` property; labels from a `label` or else a `name` property.
+    /// Other properties are ignored.
+    ///
+    /// # Returns
+    /// The set, or `InvalidInput` for malformed GeoJSON, features without an
+    /// id, duplicate ids and other geometry types
+    #[cfg(feature = "geojson")]
+    pub fn from_geojson(geojson: &str) -> Result<Self> {
+        use crate::geojson::{invalid, positions};
+        use serde_json::Value;
+
+        let value: Value =
+            serde_json::from_str(geojson).map_err(|e| Grid9Error::InvalidInput(format!("malformed GeoJSON: {}", e).into()))?;
+        if value.get("type").and_then(Value::as_str) != Some("FeatureCollection") {
+            return Err(invalid("expected a FeatureCollection"));
+        }
+        let features = value.get("features").and_then(Value::as_array).ok_or_else(|| invalid("missing features"))?;
+
+        let mut set = Self::new();
+        for feature in features {
+            let properties = feature.get("properties");
+            let property = |key: &str| properties.and_then(|p| p.get(key)).filter(|v| !v.is_null());
+            let id = match feature.get("id").filter(|v| !v.is_null()).or_else(|| property("id")) {
+                Some(Value::String(id)) => id.clone(),
+                Some(Value::Number(id)) => id.to_string(),
+                _ => return Err(invalid("geofence feature without an id")),
+            };
+            let label = match property("label").or_else(|| property("name")) {
+                Some(Value::String(label)) => Some(label.clone()),
+                Some(_) => return Err(Grid9Error::InvalidInput(format!("label of geofence {} is not a string", id).into())),
+                None => None,
+            };
+
+            let geometry = feature.get("geometry").ok_or_else(|| invalid("feature without geometry"))?;
+            let coordinates = geometry.get("coordinates").ok_or_else(|| invalid("geometry without coordinates"))?;
+            let rings = |polygon: &Value| -> Result<Vec<Vec<Coordinate>>> {
+                let rings = polygon.as_array().ok_or_else(|| invalid("polygon coordinates must be an array of rings"))?;
+                rings.iter().map(positions).collect()
+            };
+            let polygons = match geometry.get("type").and_then(Value::as_str) {
+                Some("Polygon") => vec![rings(coordinates)?],
+                Some("MultiPolygon") => coordinates
+                    .as_array()
+                    .ok_or_else(|| invalid("multipolygon coordinates must be an array of polygons"))?
+                    .iter()
+                    .map(rings)
+                    .collect::<Result<_>>()?,
+                _ => return Err(Grid9Error::InvalidInput(format!("geofence {} is not a Polygon or MultiPolygon", id).into())),
+            };
+
+            if set.insert(Geofence { id, label, polygons })?.is_some() {
+                return Err(invalid("duplicate geofence id"));
+            }
+        }
+        Ok(set)
+    }
+}
+
+/// A visit to a geofence
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DwellRecord<T> {
+    /// Id of the fence visited
+    pub fence_id: String,
+    /// Time of the first fix inside the fence
+    pub enter: T,
+    /// Time of the last fix inside the fence
+    pub exit: T,
+    /// `exit - enter`
+    pub duration: Duration,
+}
+
+/// How [`dwell_times_with`] splits a track into visits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DwellOptions {
+    /// Longest time between consecutive fixes that a visit spans. A longer
+    /// gap in the track ends every open visit at its last fix before the gap,
+    /// since nothing is known about where the track was. `None` bridges any
+    /// gap.
+    pub max_gap: Option<Duration>,
+    /// Shortest absence that ends a visit. Fixes outside a fence for less
+    /// than this, measured from the last fix inside, are treated as GPS
+    /// jitter at the boundary and the visit continues. Zero ends a visit at
+    /// the first fix outside.
+    pub min_absence: Duration,
+}
+
+/// Visit in progress while scanning a track
+struct OpenVisit<T> {
+    enter: T,
+    last_inside: T,
+    left: bool,
+}
+
+impl<T: Timestamp> OpenVisit<T> {
+    fn close(self, fence: &Geofence) -> DwellRecord<T> {
+        DwellRecord {
+            fence_id: fence.id.clone(),
+            enter: self.enter,
+            exit: self.last_inside,
+            duration: elapsed(&self.enter, &self.last_inside),
+        }
+    }
+}
+
+/// Visits of a track to each fence of a set, ending a visit at the first
+/// fix outside the fence; see [`dwell_times_with`].
+///
+/// # Example
+/// ```rust
+/// use grid9::{dwell_times, Coordinate, Geofence, GeofenceSet, Grid9Code, Track};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let mut fences = GeofenceSet::new();
+/// let site = vec![Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.01), Coordinate::new(0.01, 0.01), Coordinate::new(0.01, 0.0)];
+/// fences.insert(Geofence::new("site-3", site))?;
+///
+/// let mut track = Track::new();
+/// for (minute, lat) in [(0, 0.02), (10, 0.005), (40, 0.006), (55, 0.02)] {
+///     track.push(Grid9Code::encode(lat, 0.005)?, UNIX_EPOCH + Duration::from_secs(60 * minute))?;
+/// }
+///
+/// let visits = dwell_times(&track, &fences);
+/// assert_eq!(visits[0].fence_id, "site-3");
+/// assert_eq!(visits[0].duration, Duration::from_secs(30 * 60));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn dwell_times<T: Timestamp>(track: &Track<T>, fences: &GeofenceSet) -> Vec<DwellRecord<T>> {
+    dwell_times_with(track, fences, &DwellOptions::default())
+}
+
+/// Visits of a track to each fence of a set, computed in one pass over the
+/// track.
+///
+/// A visit runs from the first to the last fix inside a fence, so time
+/// before the first and after the last fix is not counted. Fences may
+/// overlap; a fix inside several fences counts towards each.
+///
+/// # Arguments
+/// * `track` - Fixes in time order
+/// * `fences` - Fences to report visits to
+/// * `options` - Gap and boundary-jitter handling
+///
+/// # Returns
+/// One record per visit, ordered by entry time and then fence id
+pub fn dwell_times_with<T: Timestamp>(track: &Track<T>, fences: &GeofenceSet, options: &DwellOptions) -> Vec<DwellRecord<T>> {
+    let fences: Vec<&Geofence> = fences.iter().collect();
+    let mut open: Vec<Option<OpenVisit<T>>> = fences.iter().map(|_| None).collect();
+    let mut records = Vec::new();
+    let mut previous: Option<T> = None;
+
+    for point in track.points() {
+        let time = point.time;
+        if previous.is_some_and(|previous| options.max_gap.is_some_and(|max_gap| elapsed(&previous, &time) > max_gap)) {
+            for (fence, visit) in fences.iter().zip(open.iter_mut()) {
+                records.extend(visit.take().map(|visit| visit.close(fence)));
+            }
+        }
+        previous = Some(time);
+
+        let (lat, lon) = point.code.coordinates();
+        for (fence, visit) in fences.iter().zip(open.iter_mut()) {
+            let absent = |visit: &OpenVisit<T>| elapsed(&visit.last_inside, &time) >= options.min_absence;
+            if fence.contains(lat, lon) {
+                match visit {
+                    Some(current) if current.left && absent(current) => {
+                        records.extend(visit.take().map(|visit| visit.close(fence)));
+                        *visit = Some(OpenVisit { enter: time, last_inside: time, left: false });
+                    }
+                    Some(current) => {
+                        current.last_inside = time;
+                        current.left = false;
+                    }
+                    None => *visit = Some(OpenVisit { enter: time, last_inside: time, left: false }),
+                }
+            } else if let Some(current) = visit {
+                current.left = true;
+                if absent(current) {
+                    records.extend(visit.take().map(|visit| visit.close(fence)));
+                }
+            }
+        }
+    }
+
+    for (fence, visit) in fences.iter().zip(open) {
+        records.extend(visit.map(|visit| visit.close(fence)));
+    }
+    records.sort_by(|a, b| a.enter.cmp(&b.enter).then_with(|| a.fence_id.cmp(&b.fence_id)));
+    records
+}
+
+fn write_ring(bytes: &mut Vec<u8>, ring: &[Coordinate]) {
+    bytes.extend_from_slice(&(ring.len() as u32).to_be_bytes());
+    for point in ring {
+        // Vertices were validated on insert
+        let (lat_index, lon_index) = quantize(point.lat, point.lon);
+        bytes.extend_from_slice(&pack_indices(lat_index, lon_index).to_be_bytes()[2..]);
+    }
+}
+
+fn write_text(bytes: &mut Vec<u8>, text: &str) {
+    // Lengths were checked on insert
+    bytes.extend_from_slice(&(text.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+/// Cursor over binary geofence data
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(Grid9Error::InvalidInput("truncated geofence set".into()));
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().expect("four bytes")))
+    }
+
+    fn ring(&mut self) -> Result<Vec<Coordinate>> {
+        (0..self.u32()?)
+            .map(|_| {
+                let mut payload = [0u8; 8];
+                payload[2..].copy_from_slice(self.take(6)?);
+                let (lat_index, lon_index) = unpack_indices(u64::from_be_bytes(payload));
+                let (lat, lon) = Grid9Code::from_indices(lat_index, lon_index)?.coordinates();
+                Ok(Coordinate::new(lat, lon))
+            })
+            .collect()
+    }
+
+    fn text(&mut self) -> Result<String> {
+        let len = u16::from_be_bytes(self.take(2)?.try_into().expect("two bytes")) as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| Grid9Error::InvalidInput("geofence text is not UTF-8".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> GeofenceSet {
+        let mut set = GeofenceSet::new();
+        let square = vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 1.0),
+            Coordinate::new(1.0, 1.0),
+            Coordinate::new(1.0, 0.0),
+            Coordinate::new(0.0, 0.0),
+        ];
+        let triangle = vec![Coordinate::new(0.5, 0.5), Coordinate::new(0.5, 2.0), Coordinate::new(2.0, 0.5)];
+        set.insert(Geofence::new("square", square).with_label("Unit \"square\" ✓")).unwrap();
+        set.insert(Geofence::new("triangle", triangle)).unwrap();
+        set
+    }
+
+    fn ids(set: &GeofenceSet, lat: f64, lon: f64) -> Vec<&str> {
+        set.containing(Grid9Code::encode(lat, lon).unwrap()).map(|fence| fence.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_containment() {
+        let mut set = sample();
+        assert_eq!(ids(&set, 0.2, 0.2), ["square"]);
+        assert_eq!(ids(&set, 0.8, 0.8), ["square", "triangle"]);
+        assert_eq!(ids(&set, 1.2, 1.2), ["triangle"]);
+        assert!(ids(&set, 1.9, 1.9).is_empty());
+
+        let error = set.insert(Geofence::new("square", vec![Coordinate::new(5.0, 5.0)])).unwrap_err();
+        assert!(matches!(error, Grid9Error::InvalidInput(_)));
+        assert!(set.insert(Geofence::new("", set.get("triangle").unwrap().polygons[0][0].clone())).is_err());
+        assert!(set.insert(Geofence::new("far", vec![Coordinate::new(91.0, 0.0); 3])).is_err());
+        let old = set.insert(Geofence::new("triangle", set.get("square").unwrap().polygons[0][0].clone())).unwrap();
+        assert_eq!(old.unwrap().polygons[0][0].len(), 3);
+        assert_eq!(set.remove("triangle").map(|fence| fence.id), Some("triangle".to_string()));
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_holes_and_multipolygons() {
+        let square = |min: f64, max: f64| {
+            vec![Coordinate::new(min, min), Coordinate::new(min, max), Coordinate::new(max, max), Coordinate::new(max, min)]
+        };
+        let fence = Geofence::new("campus", square(0.0, 1.0)).with_hole(square(0.25, 0.75)).with_polygon(square(2.0, 3.0));
+        assert_eq!(fence.polygons.len(), 2);
+        for (lat, lon, inside) in [(0.1, 0.1, true), (0.5, 0.5, false), (2.5, 2.5, true), (1.5, 1.5, false)] {
+            assert_eq!(fence.contains(lat, lon), inside, "{} {}", lat, lon);
+        }
+
+        let region = fence.region(8).unwrap();
+        for (lat, lon) in [(0.1, 0.1), (0.5, 0.5), (2.5, 2.5), (1.5, 1.5)] {
+            assert_eq!(region.contains_code(Grid9Code::encode(lat, lon).unwrap()), fence.contains(lat, lon));
+        }
+
+        let mut set = GeofenceSet::new();
+        set.insert(fence.clone()).unwrap();
+        let loaded = GeofenceSet::from_bytes(&set.to_bytes()).unwrap();
+        let restored = loaded.get("campus").unwrap();
+        assert_eq!(restored.polygons.iter().map(Vec::len).collect::<Vec<_>>(), [2, 1]);
+        assert!(!restored.contains(0.5, 0.5) && restored.contains(0.1, 0.1));
+
+        assert!(set.insert(Geofence::from_polygons("none", vec![])).is_err());
+        assert!(set.insert(Geofence::from_polygons("empty", vec![vec![]])).is_err());
+        assert!(set.insert(Geofence::new("bad-hole", square(0.0, 1.0)).with_hole(square(0.2, 0.4)[..2].to_vec())).is_err());
+    }
+
+    #[test]
+    fn test_reads_version_1() {
+        let mut bytes = b"G9FS\x01\x00\x00\x00\x01\x00\x01a\x00\x00\x00\x00\x03".to_vec();
+        for (lat, lon) in [(0.0, 0.0), (0.0, 1.0), (1.0, 0.0)] {
+            let (lat_index, lon_index) = quantize(lat, lon);
+            bytes.extend_from_slice(&pack_indices(lat_index, lon_index).to_be_bytes()[2..]);
+        }
+        let set = GeofenceSet::from_bytes(&bytes).unwrap();
+        let fence = set.get("a").unwrap();
+        assert_eq!((fence.polygons.len(), fence.polygons[0].len(), fence.polygons[0][0].len()), (1, 1, 3));
+        assert!(fence.contains(0.2, 0.2));
+        assert_eq!(set.to_bytes()[4], GEOFENCE_SET_VERSION);
+    }
+
+    #[test]
+    fn test_binary_roundtrip() {
+        let set = sample();
+        let bytes = set.to_bytes();
+        assert_eq!(&bytes[..5], b"G9FS\x02");
+        let loaded = GeofenceSet::from_bytes(&bytes).unwrap();
+
+        // Vertices snap to grid points, everything else is kept exactly
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get("square").unwrap().label.as_deref(), Some("Unit \"square\" ✓"));
+        assert_eq!(loaded.get("triangle").unwrap().label, None);
+        for (original, restored) in set.iter().zip(loaded.iter()) {
+            for (a, b) in original.polygons[0][0].iter().zip(&restored.polygons[0][0]) {
+                assert!((a.lat - b.lat).abs() < 1e-4 && (a.lon - b.lon).abs() < 1e-4);
+            }
+        }
+        assert_eq!(loaded.to_bytes(), bytes);
+        assert_eq!(GeofenceSet::from_bytes(&GeofenceSet::new().to_bytes()).unwrap(), GeofenceSet::new());
+
+        let mut other_version = bytes.clone();
+        other_version[4] = 3;
+        assert_eq!(GeofenceSet::from_bytes(&other_version), Err(Grid9Error::UnsupportedVersion(3)));
+        assert!(GeofenceSet::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(GeofenceSet::from_bytes(&[bytes.as_slice(), &[0]].concat()).is_err());
+        assert!(GeofenceSet::from_bytes(b"GJSON").is_err());
+    }
+
+    #[cfg(feature = "geojson")]
+    #[test]
+    fn test_geojson_roundtrip() {
+        let set = sample();
+        let geojson = set.to_geojson();
+        assert!(geojson.starts_with(r#"{"type":"FeatureCollection","features":[{"type":"Feature","id":"square""#));
+        let loaded = GeofenceSet::from_geojson(&geojson).unwrap();
+        assert_eq!(loaded.get("square"), set.get("square"));
+        // Open rings come back closed
+        let triangle = &loaded.get("triangle").unwrap().polygons[0][0];
+        assert_eq!((triangle.len(), triangle.first()), (4, triangle.last()));
+
+        let numbered = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "id": 17, "properties": {"name": "Yard"},
+             "geometry": {"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 0]]]}},
+            {"type": "Feature", "properties": {"id": "gate", "other": 1},
+             "geometry": {"type": "Polygon", "coordinates": [[[0, 0], [2, 0], [2, 2], [0, 0]]]}}
+        ]}"#;
+        let loaded = GeofenceSet::from_geojson(numbered).unwrap();
+        assert_eq!(loaded.get("17").unwrap().label.as_deref(), Some("Yard"));
+        assert!(loaded.get("gate").is_some());
+
+        let parts = r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "id": "parts", "geometry":
+            {"type": "MultiPolygon", "coordinates": [
+                [[[0, 0], [4, 0], [4, 4], [0, 4], [0, 0]], [[1, 1], [2, 1], [2, 2], [1, 2], [1, 1]]],
+                [[[10, 10], [11, 10], [11, 11], [10, 10]]]
+            ]}}]}"#;
+        let loaded = GeofenceSet::from_geojson(parts).unwrap();
+        let fence = loaded.get("parts").unwrap();
+        assert!(fence.contains(0.5, 0.5) && !fence.contains(1.5, 1.5) && fence.contains(10.2, 10.8));
+        assert!(loaded.to_geojson().contains(r#""type":"MultiPolygon""#));
+        assert_eq!(GeofenceSet::from_geojson(&loaded.to_geojson()).unwrap(), loaded);
+
+        let rejected = [
+            r#"{"type": "Polygon", "coordinates": []}"#,
+            r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "properties": {},
+                "geometry": {"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 0]]]}}]}"#,
+            r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "id": "p",
+                "geometry": {"type": "Point", "coordinates": [0, 0]}}]}"#,
+        ];
+        for geojson in rejected {
+            assert!(GeofenceSet::from_geojson(geojson).is_err(), "{}", geojson);
+        }
+        let feature = r#"{"type": "Feature", "id": "a", "geometry": {"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1]]]}}"#;
+        let duplicate = format!(r#"{{"type": "FeatureCollection", "features": [{0}, {0}]}}"#, feature);
+        assert!(GeofenceSet::from_geojson(&duplicate).is_err());
+    }
+
+    #[test]
+    fn test_dwell_times() {
+        use std::time::UNIX_EPOCH;
+
+        let set = sample();
+        let at = |seconds: u64| UNIX_EPOCH + Duration::from_secs(seconds);
+        let mut track = Track::new();
+        let fixes = [(1.9, 0), (0.2, 100), (0.2, 200), (1.5, 210), (0.2, 220), (0.8, 300), (1.9, 400), (0.2, 5000), (0.2, 5100)];
+        for (lat, seconds) in fixes {
+            let lon = if lat == 1.5 { 0.2 } else { lat };
+            track.push(Grid9Code::encode(lat, lon).unwrap(), at(seconds)).unwrap();
+        }
+        let visits = |options: DwellOptions| -> Vec<(String, u64, u64)> {
+            let records = dwell_times_with(&track, &set, &options);
+            for record in &records {
+                assert_eq!(record.duration, record.exit.duration_since(record.enter).unwrap());
+            }
+            records.into_iter().map(|r| (r.fence_id, r.enter.duration_since(UNIX_EPOCH).unwrap().as_secs(), r.duration.as_secs())).collect()
+        };
+        let visit = |id: &str, enter: u64, duration: u64| (id.to_string(), enter, duration);
+
+        // Every fix outside ends a visit
+        assert_eq!(
+            visits(DwellOptions::default()),
+            [visit("square", 100, 100), visit("square", 220, 80), visit("triangle", 300, 0), visit("square", 5000, 100)]
+        );
+        assert_eq!(dwell_times(&track, &set).len(), 4);
+
+        // A 10s excursion over the boundary is jitter
+        let debounced = DwellOptions { min_absence: Duration::from_secs(60), ..DwellOptions::default() };
+        assert_eq!(visits(debounced), [visit("square", 100, 200), visit("triangle", 300, 0), visit("square", 5000, 100)]);
+
+        // A long absence is bridged unless the track has a gap
+        let lenient = DwellOptions { min_absence: Duration::from_secs(7200), ..DwellOptions::default() };
+        assert_eq!(visits(lenient), [visit("square", 100, 5000), visit("triangle", 300, 0)]);
+        let gapped = DwellOptions { max_gap: Some(Duration::from_secs(1000)), ..lenient };
+        assert_eq!(visits(gapped), [visit("square", 100, 200), visit("triangle", 300, 0), visit("square", 5000, 100)]);
+
+        assert!(dwell_times(&Track::<std::time::SystemTime>::new(), &set).is_empty());
+    }
+}
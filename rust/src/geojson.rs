@@ -0,0 +1,323 @@
+//! GeoJSON geometries to cells (feature `geojson`)
+//!
+//! Reads a GeoJSON geometry, `Feature` or single-feature
+//! `FeatureCollection` and converts it in one call: points to their bucket,
+//! LineStrings to the buckets they traverse ([`cells_along`]) and Polygons to
+//! the buckets they cover ([`Grid9Region::from_polygon_with_holes`]). The
+//! `Multi*` types and `GeometryCollection`s convert part by part, and
+//! [`GeoJsonCells::to_geojson`] writes cells back out as GeoJSON.
+
+use serde_json::{json, Value};
+
+use crate::{cells_along, Coordinate, Grid9Bucket, Grid9Error, Grid9Region, Result};
+
+/// Cells of a GeoJSON geometry, from [`geojson_cells`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GeoJsonCells {
+    /// Bucket holding a `Point`
+    Point(Grid9Bucket),
+    /// Buckets traversed by a `LineString`, in order of travel
+    Path(Vec<Grid9Bucket>),
+    /// Buckets covered by a `Polygon`, or by any polygon of a `MultiPolygon`
+    Region(Grid9Region),
+    /// Buckets holding the points of a `MultiPoint`, in input order
+    Points(Vec<Grid9Bucket>),
+    /// Buckets traversed by each line of a `MultiLineString`
+    Paths(Vec<Vec<Grid9Bucket>>),
+    /// Cells of each member of a `GeometryCollection`
+    Collection(Vec<GeoJsonCells>),
+}
+
+impl GeoJsonCells {
+    /// Writes the cells as a GeoJSON geometry.
+    ///
+    /// Points and paths become `Point`, `MultiPoint`, `LineString` and
+    /// `MultiLineString` geometries through the bucket centers, regions a
+    /// `MultiPolygon` of bucket outlines and collections a
+    /// `GeometryCollection`. Reading the output back at the same level
+    /// gives the same points, regions and collections of them.
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::geojson_cells;
+    ///
+    /// let stops = r#"{"type": "MultiPoint", "coordinates": [[2.2945, 48.8584], [2.3499, 48.8530]]}"#;
+    /// let cells = geojson_cells(stops, 10)?;
+    /// assert_eq!(geojson_cells(&cells.to_geojson(), 10)?, cells);
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn to_geojson(&self) -> String {
+        self.geometry().to_string()
+    }
+
+    fn geometry(&self) -> Value {
+        let center = |bucket: &Grid9Bucket| {
+            let center = bucket.center();
+            json!([center.lon, center.lat])
+        };
+        let line = |cells: &Vec<Grid9Bucket>| Value::Array(cells.iter().map(center).collect());
+
+        match self {
+            GeoJsonCells::Point(bucket) => json!({ "type": "Point", "coordinates": center(bucket) }),
+            GeoJsonCells::Path(cells) => json!({ "type": "LineString", "coordinates": line(cells) }),
+            GeoJsonCells::Points(cells) => json!({ "type": "MultiPoint", "coordinates": line(cells) }),
+            GeoJsonCells::Paths(paths) => {
+                json!({ "type": "MultiLineString", "coordinates": paths.iter().map(line).collect::<Vec<_>>() })
+            }
+            GeoJsonCells::Region(region) => {
+                let outlines: Vec<Value> = region
+                    .buckets()
+                    .map(|bucket| {
+                        let b = bucket.bounds();
+                        json!([[
+                            [b.min_lon, b.min_lat],
+                            [b.max_lon, b.min_lat],
+                            [b.max_lon, b.max_lat],
+                            [b.min_lon, b.max_lat],
+                            [b.min_lon, b.min_lat]
+                        ]])
+                    })
+                    .collect();
+                json!({ "type": "MultiPolygon", "coordinates": outlines })
+            }
+            GeoJsonCells::Collection(members) => json!({
+                "type": "GeometryCollection",
+                "geometries": members.iter().map(GeoJsonCells::geometry).collect::<Vec<_>>()
+            }),
+        }
+    }
+}
+
+/// Converts a GeoJSON geometry to the buckets of `level` it touches.
+///
+/// Accepts a geometry object, a `Feature`, or a `FeatureCollection` holding
+/// exactly one feature. Positions are `[longitude, latitude]` as in
+/// RFC 7946; altitudes are ignored.
+///
+/// # Arguments
+/// * `geojson` - GeoJSON text
+/// * `level` - Bucket level (0 = single Grid9 cells)
+///
+/// # Returns
+/// The cells of the geometry, see [`GeoJsonCells`]; polygon coverage leaves
+/// out any holes
+///
+/// # Example
+/// ```rust
+/// use grid9::{geojson_cells, GeoJsonCells};
+///
+/// let route = r#"{"type": "LineString", "coordinates": [[-74.0060, 40.7128], [-73.9855, 40.7580]]}"#;
+/// match geojson_cells(route, 8)? {
+///     GeoJsonCells::Path(cells) => assert!(cells.len() > 1),
+///     other => panic!("unexpected {:?}", other),
+/// }
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn geojson_cells(geojson: &str, level: u8) -> Result<GeoJsonCells> {
+    let value: Value =
+        serde_json::from_str(geojson).map_err(|e| Grid9Error::InvalidInput(format!("malformed GeoJSON: {}", e).into()))?;
+    geometry_cells(geometry_of(&value)?, level)
+}
+
+fn geometry_cells(geometry: &Value, level: u8) -> Result<GeoJsonCells> {
+    if geometry.get("type").and_then(Value::as_str) == Some("GeometryCollection") {
+        let members =
+            geometry.get("geometries").and_then(Value::as_array).ok_or_else(|| invalid("collection without geometries"))?;
+        let members = members.iter().map(|member| geometry_cells(member, level)).collect::<Result<_>>()?;
+        return Ok(GeoJsonCells::Collection(members));
+    }
+
+    let coordinates = geometry.get("coordinates").ok_or_else(|| invalid("geometry without coordinates"))?;
+    let members = |message: &str| coordinates.as_array().ok_or_else(|| invalid(message));
+    let point = |value: &Value| {
+        let point = position(value)?;
+        Grid9Bucket::from_coordinates(point.lat, point.lon, level)
+    };
+    let rings = |value: &Value| -> Result<Vec<Vec<Coordinate>>> {
+        let rings = value.as_array().ok_or_else(|| invalid("polygon coordinates must be an array of rings"))?;
+        rings.iter().map(positions).collect()
+    };
+
+    match geometry.get("type").and_then(Value::as_str) {
+        Some("Point") => Ok(GeoJsonCells::Point(point(coordinates)?)),
+        Some("LineString") => Ok(GeoJsonCells::Path(cells_along(&positions(coordinates)?, level)?)),
+        Some("Polygon") => {
+            Ok(GeoJsonCells::Region(Grid9Region::from_polygon_with_holes(&rings(coordinates)?, level)?))
+        }
+        Some("MultiPoint") => {
+            Ok(GeoJsonCells::Points(members("expected an array of points")?.iter().map(point).collect::<Result<_>>()?))
+        }
+        Some("MultiLineString") => Ok(GeoJsonCells::Paths(
+            members("expected an array of lines")?
+                .iter()
+                .map(|line| cells_along(&positions(line)?, level))
+                .collect::<Result<_>>()?,
+        )),
+        Some("MultiPolygon") => {
+            let polygons = members("expected an array of polygons")?.iter().map(rings).collect::<Result<Vec<_>>>()?;
+            Ok(GeoJsonCells::Region(Grid9Region::from_multipolygon(&polygons, level)?))
+        }
+        Some(other) => Err(Grid9Error::InvalidInput(format!("unsupported GeoJSON geometry type {}", other).into())),
+        None => Err(invalid("geometry without type")),
+    }
+}
+
+/// Buckets of `level` traversed by a GeoJSON `LineString`, in order of travel.
+///
+/// Shorthand for [`geojson_cells`] that fails for other geometry types.
+pub fn geojson_line_cells(geojson: &str, level: u8) -> Result<Vec<Grid9Bucket>> {
+    match geojson_cells(geojson, level)? {
+        GeoJsonCells::Path(cells) => Ok(cells),
+        _ => Err(invalid("expected a LineString")),
+    }
+}
+
+/// Buckets of `level` covered by a GeoJSON `Polygon` or `MultiPolygon`.
+///
+/// Shorthand for [`geojson_cells`] that fails for other geometry types.
+pub fn geojson_region(geojson: &str, level: u8) -> Result<Grid9Region> {
+    match geojson_cells(geojson, level)? {
+        GeoJsonCells::Region(region) => Ok(region),
+        _ => Err(invalid("expected a Polygon or MultiPolygon")),
+    }
+}
+
+/// Unwraps features and single-feature collections down to their geometry.
+fn geometry_of(value: &Value) -> Result<&Value> {
+    match value.get("type").and_then(Value::as_str) {
+        Some("Feature") => match value.get("geometry") {
+            Some(geometry) if !geometry.is_null() => geometry_of(geometry),
+            _ => Err(invalid("feature without geometry")),
+        },
+        Some("FeatureCollection") => match value.get("features").and_then(Value::as_array).map(Vec::as_slice) {
+            Some([feature]) => geometry_of(feature),
+            _ => Err(invalid("feature collections must hold exactly one feature")),
+        },
+        _ => Ok(value),
+    }
+}
+
+pub(crate) fn positions(value: &Value) -> Result<Vec<Coordinate>> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid("expected an array of positions"))?
+        .iter()
+        .map(position)
+        .collect()
+}
+
+fn position(value: &Value) -> Result<Coordinate> {
+    match value.as_array().map(Vec::as_slice) {
+        Some([lon, lat, ..]) => match (lat.as_f64(), lon.as_f64()) {
+            (Some(lat), Some(lon)) => Ok(Coordinate::new(lat, lon)),
+            _ => Err(invalid("position components must be numbers")),
+        },
+        _ => Err(invalid("a position needs a longitude and a latitude")),
+    }
+}
+
+pub(crate) fn invalid(message: &str) -> Grid9Error {
+    Grid9Error::InvalidInput(message.to_string().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geometry_types() {
+        let point = r#"{"type": "Point", "coordinates": [2.3522, 48.8566, 35.0]}"#;
+        assert_eq!(
+            geojson_cells(point, 3).unwrap(),
+            GeoJsonCells::Point(Grid9Bucket::from_coordinates(48.8566, 2.3522, 3).unwrap())
+        );
+
+        let line = [Coordinate::new(48.85, 2.29), Coordinate::new(48.86, 2.35)];
+        let geojson = r#"{"type": "LineString", "coordinates": [[2.29, 48.85], [2.35, 48.86]]}"#;
+        assert_eq!(geojson_line_cells(geojson, 5).unwrap(), cells_along(&line, 5).unwrap());
+
+        let ring = [
+            Coordinate::new(48.85, 2.29),
+            Coordinate::new(48.85, 2.35),
+            Coordinate::new(48.87, 2.35),
+            Coordinate::new(48.85, 2.29),
+        ];
+        let feature = r#"{"type": "Feature", "properties": {"name": "wedge"}, "geometry":
+            {"type": "Polygon", "coordinates": [[[2.29, 48.85], [2.35, 48.85], [2.35, 48.87], [2.29, 48.85]]]}}"#;
+        let region = geojson_region(feature, 5).unwrap();
+        assert_eq!(region, Grid9Region::from_polygon(&ring, 5).unwrap());
+        assert!(!region.is_empty());
+
+        let collection = format!(r#"{{"type": "FeatureCollection", "features": [{}]}}"#, feature);
+        assert_eq!(geojson_region(&collection, 5).unwrap(), region);
+
+        let holed = r#"{"type": "Polygon", "coordinates": [[[0, 0], [4, 0], [4, 4], [0, 4], [0, 0]], [[1, 1], [2, 1], [2, 2], [1, 2], [1, 1]]]}"#;
+        let region = geojson_region(holed, 10).unwrap();
+        assert!(region.contains(&crate::encode(0.5, 0.5, false).unwrap()).unwrap());
+        assert!(!region.contains(&crate::encode(1.5, 1.5, false).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_multi_geometries() {
+        let points = r#"{"type": "MultiPoint", "coordinates": [[2.29, 48.85], [2.35, 48.86], [2.29, 48.85]]}"#;
+        let bucket = |lat, lon| Grid9Bucket::from_coordinates(lat, lon, 6).unwrap();
+        assert_eq!(
+            geojson_cells(points, 6).unwrap(),
+            GeoJsonCells::Points(vec![bucket(48.85, 2.29), bucket(48.86, 2.35), bucket(48.85, 2.29)])
+        );
+
+        let lines = r#"{"type": "MultiLineString", "coordinates": [[[2.29, 48.85], [2.35, 48.86]], [[0, 0], [0.01, 0]]]}"#;
+        let GeoJsonCells::Paths(paths) = geojson_cells(lines, 6).unwrap() else { panic!("expected paths") };
+        assert_eq!(paths[1], cells_along(&[Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 0.01)], 6).unwrap());
+
+        let polygons = r#"{"type": "MultiPolygon", "coordinates": [
+            [[[0, 0], [1, 0], [1, 1], [0, 1], [0, 0]]],
+            [[[5, 5], [6, 5], [6, 6], [5, 6], [5, 5]]]
+        ]}"#;
+        let region = geojson_region(polygons, 10).unwrap();
+        assert!(region.contains(&crate::encode(0.5, 0.5, false).unwrap()).unwrap());
+        assert!(region.contains(&crate::encode(5.5, 5.5, false).unwrap()).unwrap());
+        assert!(!region.contains(&crate::encode(3.0, 3.0, false).unwrap()).unwrap());
+
+        let collection = format!(
+            r#"{{"type": "Feature", "geometry": {{"type": "GeometryCollection", "geometries": [{}, {}, {{"type": "GeometryCollection", "geometries": []}}]}}}}"#,
+            points, polygons
+        );
+        let cells = geojson_cells(&collection, 10).unwrap();
+        let GeoJsonCells::Collection(members) = &cells else { panic!("expected a collection") };
+        assert_eq!(members[1], GeoJsonCells::Region(region));
+        assert_eq!(members[2], GeoJsonCells::Collection(vec![]));
+
+        // Written back out, everything but paths reads back identically
+        assert_eq!(geojson_cells(&cells.to_geojson(), 10).unwrap(), cells);
+        let point = geojson_cells(r#"{"type": "Point", "coordinates": [2.35, 48.86]}"#, 4).unwrap();
+        assert_eq!(geojson_cells(&point.to_geojson(), 4).unwrap(), point);
+        assert!(cells.to_geojson().contains(r#""type":"GeometryCollection""#));
+    }
+
+    #[test]
+    fn test_rejected_inputs() {
+        let rejected = [
+            "not json",
+            r#"{"type": "Point"}"#,
+            r#"{"type": "Point", "coordinates": [2.0]}"#,
+            r#"{"type": "Point", "coordinates": ["2", "48"]}"#,
+            r#"{"type": "Point", "coordinates": [200.0, 48.0]}"#,
+            r#"{"type": "MultiPoint", "coordinates": [[2.0, 48.0], [200.0, 48.0]]}"#,
+            r#"{"type": "MultiPolygon", "coordinates": [[]]}"#,
+            r#"{"type": "GeometryCollection", "geometries": [{"type": "Curve", "coordinates": []}]}"#,
+            r#"{"type": "GeometryCollection"}"#,
+            r#"{"type": "Polygon", "coordinates": []}"#,
+            r#"{"type": "Feature", "geometry": null}"#,
+            r#"{"type": "FeatureCollection", "features": []}"#,
+        ];
+        for geojson in rejected {
+            assert!(geojson_cells(geojson, 0).is_err(), "{}", geojson);
+        }
+
+        let point = r#"{"type": "Point", "coordinates": [2.0, 48.0]}"#;
+        assert!(geojson_region(point, 0).is_err());
+        assert!(geojson_line_cells(point, 0).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! gpsd client yielding timestamped Grid9 fixes
+//!
+//! Connects to a gpsd daemon (`localhost:2947` by default), enables JSON
+//! watch mode and turns every TPV (time-position-velocity) report carrying a
+//! position into a [`GpsdFix`].
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+
+use serde_json::Value;
+
+use crate::{encode, Grid9Error, Result};
+
+/// Default gpsd address
+pub const GPSD_DEFAULT_ADDR: &str = "127.0.0.1:2947";
+
+/// Quality of a GPS fix as reported by gpsd's `mode` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FixMode {
+    /// Latitude and longitude only
+    TwoD,
+    /// Latitude, longitude and altitude
+    ThreeD,
+}
+
+/// A position report from gpsd
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GpsdFix {
+    /// Grid9 code of the position
+    pub code: String,
+    /// Latitude in degrees
+    pub latitude: f64,
+    /// Longitude in degrees
+    pub longitude: f64,
+    /// Altitude in meters (3D fixes only)
+    pub altitude_m: Option<f64>,
+    /// Fix quality
+    pub mode: FixMode,
+    /// UTC timestamp in ISO 8601 form, e.g. `2024-05-01T12:00:00.000Z`
+    pub time: Option<String>,
+    /// Estimated horizontal error in meters
+    pub horizontal_error_m: Option<f64>,
+}
+
+/// Parses one line of gpsd JSON output.
+///
+/// # Arguments
+/// * `line` - A single gpsd JSON report
+/// * `human_readable` - If true, fix codes use XXX-XXX-XXX format
+///
+/// # Returns
+/// `Some` fix for TPV reports with a 2D or 3D fix, `None` for other reports
+/// and TPV reports without a fix
+///
+/// # Example
+/// ```rust
+/// use grid9::{parse_tpv, FixMode};
+///
+/// let line = r#"{"class":"TPV","mode":3,"time":"2024-05-01T12:00:00.000Z","lat":40.7128,"lon":-74.0060,"altHAE":10.0,"eph":4.5}"#;
+/// let fix = parse_tpv(line, false)?.unwrap();
+/// assert_eq!(fix.mode, FixMode::ThreeD);
+/// assert_eq!(fix.code, grid9::encode(40.7128, -74.0060, false)?);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn parse_tpv(line: &str, human_readable: bool) -> Result<Option<GpsdFix>> {
+    let report: Value = serde_json::from_str(line)
+        .map_err(|e| Grid9Error::InvalidInput(format!("malformed gpsd report: {}", e).into()))?;
+
+    if report.get("class").and_then(Value::as_str) != Some("TPV") {
+        return Ok(None);
+    }
+    let mode = match report.get("mode").and_then(Value::as_u64) {
+        Some(2) => FixMode::TwoD,
+        Some(3) => FixMode::ThreeD,
+        _ => return Ok(None),
+    };
+    let (Some(latitude), Some(longitude)) = (
+        report.get("lat").and_then(Value::as_f64),
+        report.get("lon").and_then(Value::as_f64),
+    ) else {
+        return Ok(None);
+    };
+
+    let number = |field: &str| report.get(field).and_then(Value::as_f64);
+    let altitude_m = match mode {
+        // gpsd 3.20+ reports altHAE/altMSL; older versions report alt
+        FixMode::ThreeD => number("altHAE").or_else(|| number("alt")).or_else(|| number("altMSL")),
+        FixMode::TwoD => None,
+    };
+    let horizontal_error_m = number("eph").or_else(|| match (number("epx"), number("epy")) {
+        (Some(epx), Some(epy)) => Some(epx.hypot(epy)),
+        _ => None,
+    });
+
+    Ok(Some(GpsdFix {
+        code: encode(latitude, longitude, human_readable)?,
+        latitude,
+        longitude,
+        altitude_m,
+        mode,
+        time: report.get("time").and_then(Value::as_str).map(str::to_string),
+        horizontal_error_m,
+    }))
+}
+
+/// A stream of fixes read from gpsd
+///
+/// Iterating yields one item per TPV report with a fix; other reports are
+/// skipped. Malformed reports are yielded as `InvalidData` errors and
+/// iteration can continue past them.
+///
+/// # Example
+/// ```rust,no_run
+/// use grid9::GpsdClient;
+///
+/// for fix in GpsdClient::connect(grid9::GPSD_DEFAULT_ADDR)?.human_readable(true) {
+///     let fix = fix?;
+///     println!("{} {} ±{:?}m", fix.time.unwrap_or_default(), fix.code, fix.horizontal_error_m);
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct GpsdClient<R = BufReader<TcpStream>> {
+    reader: R,
+    line: String,
+    human_readable: bool,
+}
+
+impl GpsdClient {
+    /// Connects to gpsd and enables JSON watch mode.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(b"?WATCH={\"enable\":true,\"json\":true};\n")?;
+        Ok(Self::from_reader(BufReader::new(stream)))
+    }
+}
+
+impl<R: BufRead> GpsdClient<R> {
+    /// Reads gpsd JSON reports from any buffered reader (e.g. a recorded log).
+    pub fn from_reader(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            human_readable: false,
+        }
+    }
+
+    /// Produce codes in XXX-XXX-XXX format (default: false)
+    pub fn human_readable(mut self, human_readable: bool) -> Self {
+        self.human_readable = human_readable;
+        self
+    }
+}
+
+impl<R: BufRead> Iterator for GpsdClient<R> {
+    type Item = io::Result<GpsdFix>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+
+            let line = self.line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match parse_tpv(line, self.human_readable) {
+                Ok(Some(fix)) => return Some(Ok(fix)),
+                Ok(None) => continue,
+                Err(e) => return Some(Err(io::Error::new(io::ErrorKind::InvalidData, e))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SESSION: &str = concat!(
+        r#"{"class":"VERSION","release":"3.25","rev":"3.25","proto_major":3,"proto_minor":15}"#, "\n",
+        r#"{"class":"DEVICES","devices":[{"class":"DEVICE","path":"/dev/ttyACM0"}]}"#, "\n",
+        r#"{"class":"TPV","device":"/dev/ttyACM0","mode":1}"#, "\n",
+        r#"{"class":"TPV","device":"/dev/ttyACM0","mode":2,"time":"2024-05-01T12:00:00.000Z","lat":51.5074,"lon":-0.1278,"epx":3.0,"epy":4.0}"#, "\n",
+        r#"{"class":"SKY","satellites":[]}"#, "\n",
+        "\n",
+        r#"{"class":"TPV","device":"/dev/ttyACM0","mode":3,"time":"2024-05-01T12:00:01.000Z","lat":51.5075,"lon":-0.1279,"alt":35.0,"eph":2.5}"#, "\n",
+        "not json\n",
+    );
+
+    #[test]
+    fn test_stream_yields_fixes() {
+        let mut client = GpsdClient::from_reader(SESSION.as_bytes()).human_readable(true);
+
+        let first = client.next().unwrap().unwrap();
+        assert_eq!(first.mode, FixMode::TwoD);
+        assert_eq!(first.code, encode(51.5074, -0.1278, true).unwrap());
+        assert_eq!(first.time.as_deref(), Some("2024-05-01T12:00:00.000Z"));
+        assert_eq!(first.horizontal_error_m, Some(5.0));
+        assert_eq!(first.altitude_m, None);
+
+        let second = client.next().unwrap().unwrap();
+        assert_eq!(second.mode, FixMode::ThreeD);
+        assert_eq!(second.altitude_m, Some(35.0));
+        assert_eq!(second.horizontal_error_m, Some(2.5));
+
+        let error = client.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(client.next().is_none());
+    }
+
+    #[test]
+    fn test_non_fix_reports() {
+        assert_eq!(parse_tpv(r#"{"class":"TPV","mode":0}"#, false), Ok(None));
+        assert_eq!(parse_tpv(r#"{"class":"TPV","mode":2}"#, false), Ok(None));
+        assert_eq!(parse_tpv(r#"{"class":"GST"}"#, false), Ok(None));
+        assert!(matches!(
+            parse_tpv(r#"{"class":"TPV","mode":2,"lat":95.0,"lon":0.0}"#, false),
+            Err(Grid9Error::InvalidLatitude(_))
+        ));
+    }
+}
@@ -0,0 +1,262 @@
+//! Grids with a configurable split of the 45 payload bits
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+
+use crate::uniform_precision_compressor::{parse_packed, LAT_BITS, LON_BITS};
+#[cfg(feature = "alloc")]
+use crate::uniform_precision_compressor::{format_packed, validate_coordinates};
+#[cfg(feature = "std")]
+use crate::uniform_precision_compressor::METERS_PER_DEGREE;
+#[cfg(feature = "std")]
+use crate::{Codec, PrecisionInfo};
+use crate::Result;
+
+/// How coordinates are mapped onto grid indices when encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rounding {
+    /// Round down to the grid point south-west of the coordinate (the behavior of [`crate::encode`])
+    #[default]
+    Truncate,
+    /// Round to the nearest grid point, halving the worst-case decode error
+    Nearest,
+}
+
+/// The standard Grid9 grid: 22 latitude bits and 23 longitude bits
+pub type DefaultGrid = Grid<LAT_BITS, LON_BITS>;
+
+/// A uniform latitude/longitude grid spending `LAT_BITS` of the 45 payload
+/// bits on latitude and `LON_BITS` on longitude
+///
+/// Codes are spelled exactly like standard Grid9 codes, but they address
+/// different cells unless the split is the default 22/23 ([`DefaultGrid`]).
+/// Shifting bits towards longitude narrows cells east-west at the cost of
+/// taller cells, which suits deployments near the equator; shifting them
+/// towards latitude suits high latitudes. Both counts must be between 13 and
+/// 32 and add up to 45, which is checked at compile time.
+///
+/// Grids implement [`Codec`], so they work with the codec-aware batch and
+/// file conversion functions.
+///
+/// # Example
+/// ```rust
+/// use grid9::{DefaultGrid, Grid};
+///
+/// // Shorter cells for high latitudes, where longitude steps are narrow anyway
+/// let polar = Grid::<23, 22>::new();
+/// let code = polar.encode(70.0, 25.0, false)?;
+/// let (lat, lon) = polar.decode(&code)?;
+///
+/// assert_eq!(DefaultGrid::new().encode(70.0, 25.0, false)?, grid9::encode(70.0, 25.0, false)?);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Grid<const LAT_BITS: u32, const LON_BITS: u32>;
+
+impl<const LAT_BITS: u32, const LON_BITS: u32> Grid<LAT_BITS, LON_BITS> {
+    const VALID_SPLIT: () = assert!(
+        LAT_BITS >= 13 && LON_BITS >= 13 && LAT_BITS + LON_BITS == 45,
+        "a Grid must split exactly 45 bits, with 13 to 32 bits per axis"
+    );
+
+    /// Largest latitude index
+    pub const LAT_MAX: u64 = (1 << LAT_BITS) - 1;
+    /// Largest longitude index
+    pub const LON_MAX: u64 = (1 << LON_BITS) - 1;
+
+    /// Creates the grid
+    pub const fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_SPLIT;
+        Self
+    }
+
+    /// Encodes coordinates on this grid.
+    ///
+    /// # Arguments
+    /// * `latitude` - Latitude in degrees (-90 to 90)
+    /// * `longitude` - Longitude in degrees (-180 to 180)
+    /// * `human_readable` - If true, returns XXX-XXX-XXX format with dashes
+    ///
+    /// # Returns
+    /// A 9-character code (or 11 characters with dashes if human_readable is true)
+    #[cfg(feature = "alloc")]
+    pub fn encode(&self, latitude: f64, longitude: f64, human_readable: bool) -> Result<String> {
+        validate_coordinates(latitude, longitude)?;
+
+        let (lat_bits, lon_bits) = Self::quantize_with(latitude, longitude, Rounding::Truncate);
+        Ok(format_packed(Self::pack_indices(lat_bits, lon_bits), human_readable))
+    }
+
+    /// Decodes a code produced by this grid.
+    ///
+    /// # Arguments
+    /// * `encoded` - A 9-character code (dashes are automatically removed)
+    ///
+    /// # Returns
+    /// A tuple of (latitude, longitude) in degrees
+    pub fn decode(&self, encoded: &str) -> Result<(f64, f64)> {
+        let (lat_bits, lon_bits) = Self::unpack_indices(parse_packed(encoded)?);
+
+        Ok((Self::lat_of_index(lat_bits as f64), Self::lon_of_index(lon_bits as f64)))
+    }
+
+    /// Gets precision information for this grid at the given location.
+    ///
+    /// # Arguments
+    /// * `latitude` - Latitude in degrees
+    /// * `longitude` - Longitude in degrees
+    ///
+    /// # Returns
+    /// Precision information including lat/lon errors and total error
+    #[cfg(feature = "std")]
+    pub fn precision(&self, latitude: f64, longitude: f64) -> Result<PrecisionInfo> {
+        validate_coordinates(latitude, longitude)?;
+
+        // Calculate precision based on latitude quantization
+        let lat_precision = 180.0 / (1u64 << LAT_BITS) as f64;
+        let lat_error_m = lat_precision * METERS_PER_DEGREE;
+
+        // Calculate precision based on longitude quantization and latitude
+        let lon_precision = 360.0 / (1u64 << LON_BITS) as f64;
+        let lon_error_m = lon_precision * METERS_PER_DEGREE * latitude.to_radians().cos();
+
+        // Total error is the diagonal of the error rectangle
+        let total_error_m = (lat_error_m * lat_error_m + lon_error_m * lon_error_m).sqrt();
+
+        Ok(PrecisionInfo {
+            lat_error_m,
+            lon_error_m,
+            total_error_m,
+        })
+    }
+
+    /// Quantizes validated coordinates to grid indices using the given rounding.
+    pub(crate) fn quantize_with(latitude: f64, longitude: f64, rounding: Rounding) -> (u32, u32) {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_SPLIT;
+
+        let norm_lat = (latitude + 90.0) / 180.0;
+        let norm_lon = (longitude + 180.0) / 360.0;
+
+        let (lat_scaled, lon_scaled) = match rounding {
+            Rounding::Truncate => (norm_lat * Self::LAT_MAX as f64, norm_lon * Self::LON_MAX as f64),
+            Rounding::Nearest => (
+                round_non_negative(norm_lat * Self::LAT_MAX as f64),
+                round_non_negative(norm_lon * Self::LON_MAX as f64),
+            ),
+        };
+
+        let lat_bits = (lat_scaled as u64).min(Self::LAT_MAX);
+        let lon_bits = (lon_scaled as u64).min(Self::LON_MAX);
+
+        (lat_bits as u32, lon_bits as u32)
+    }
+
+    /// Latitude in degrees of a (possibly fractional) latitude grid index.
+    pub(crate) fn lat_of_index(index: f64) -> f64 {
+        index / Self::LAT_MAX as f64 * 180.0 - 90.0
+    }
+
+    /// Longitude in degrees of a (possibly fractional) longitude grid index.
+    pub(crate) fn lon_of_index(index: f64) -> f64 {
+        index / Self::LON_MAX as f64 * 360.0 - 180.0
+    }
+
+    /// Packs grid indices into the 45-bit payload: `[lat index][lon index]`.
+    pub(crate) fn pack_indices(lat_bits: u32, lon_bits: u32) -> u64 {
+        ((lat_bits as u64 & Self::LAT_MAX) << LON_BITS) | (lon_bits as u64 & Self::LON_MAX)
+    }
+
+    /// Splits a 45-bit payload into its (latitude, longitude) grid indices.
+    pub(crate) fn unpack_indices(packed: u64) -> (u32, u32) {
+        (((packed >> LON_BITS) & Self::LAT_MAX) as u32, (packed & Self::LON_MAX) as u32)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const LAT_BITS: u32, const LON_BITS: u32> Codec for Grid<LAT_BITS, LON_BITS> {
+    fn encode_payload(&self, payload: u64) -> String {
+        format_packed(payload & crate::codec::PAYLOAD_MASK, false)
+    }
+
+    fn decode_payload(&self, encoded: &str) -> Result<u64> {
+        parse_packed(encoded)
+    }
+
+    fn encode(&self, latitude: f64, longitude: f64) -> Result<String> {
+        Grid::encode(self, latitude, longitude, false)
+    }
+
+    fn decode(&self, encoded: &str) -> Result<(f64, f64)> {
+        Grid::decode(self, encoded)
+    }
+}
+
+/// `f64::round` for non-negative values, which needs no `std`
+fn round_non_negative(value: f64) -> f64 {
+    let whole = value as u64 as f64;
+    if value - whole >= 0.5 {
+        whole + 1.0
+    } else {
+        whole
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{batch_decode_with_codec, batch_encode_with_codec, decode, encode, get_actual_precision, Coordinate};
+
+    #[test]
+    fn test_default_grid_matches_free_functions() {
+        let grid = DefaultGrid::new();
+
+        for (lat, lon) in [(40.7128, -74.0060), (-33.8688, 151.2093), (90.0, 180.0), (-90.0, -180.0)] {
+            let code = grid.encode(lat, lon, false).unwrap();
+            assert_eq!(code, encode(lat, lon, false).unwrap());
+            assert_eq!(grid.decode(&code).unwrap(), decode(&code).unwrap());
+            assert_eq!(grid.precision(lat, lon).unwrap(), get_actual_precision(lat, lon).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_round_non_negative() {
+        for value in [0.0, 0.49999999999999994, 0.5, 1.5, 2.4999, 4194302.5, 8388606.7, 8388607.0] {
+            assert_eq!(round_non_negative(value), value.round(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_alternative_splits() {
+        let wide = Grid::<20, 25>::new();
+        let tall = Grid::<25, 20>::new();
+
+        let lon_error = wide.precision(0.0, 0.0).unwrap().lon_error_m;
+        assert!(lon_error < DefaultGrid::new().precision(0.0, 0.0).unwrap().lon_error_m / 3.0);
+        assert!(tall.precision(0.0, 0.0).unwrap().lat_error_m < 1.0);
+
+        for grid in [&wide as &dyn Codec, &tall] {
+            let (lat, lon) = grid.decode(&grid.encode(1.2345, 103.8198).unwrap()).unwrap();
+            assert!((lat - 1.2345).abs() < 1e-3 && (lon - 103.8198).abs() < 1e-3);
+        }
+
+        // Same spelling, different cells
+        let code = wide.encode(1.2345, 103.8198, false).unwrap();
+        assert_ne!(wide.decode(&code).unwrap(), decode(&code).unwrap());
+    }
+
+    #[test]
+    fn test_grid_as_codec() {
+        let grid = Grid::<21, 24>::new();
+        let coordinates = vec![Coordinate::new(0.5, 32.5), Coordinate::new(-1.3, 36.8)];
+
+        let codes = batch_encode_with_codec(&coordinates, &grid).unwrap();
+        let decoded = batch_decode_with_codec(&codes, &grid).unwrap();
+        for (original, roundtrip) in coordinates.iter().zip(&decoded) {
+            assert!((original.lat - roundtrip.lat).abs() < 1e-4);
+            assert!((original.lon - roundtrip.lon).abs() < 1e-4);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Moving between adjacent Grid9 cells
+//!
+//! Neighbors are found by stepping grid indices rather than nudging
+//! coordinates, so they are exact at cell boundaries. Steps east and west
+//! wrap across the antimeridian; there are no cells north of the northmost
+//! row or south of the southmost one.
+
+use std::collections::HashSet;
+
+use crate::{Grid9Code, Grid9Metrics, NoopMetrics, Result, MAX_LAT_INDEX, MAX_LON_INDEX};
+
+/// A compass direction between adjacent cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    /// All directions, clockwise from north
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    /// Latitude and longitude index steps of the direction
+    pub fn offsets(self) -> (i8, i8) {
+        match self {
+            Direction::North => (1, 0),
+            Direction::NorthEast => (1, 1),
+            Direction::East => (0, 1),
+            Direction::SouthEast => (-1, 1),
+            Direction::South => (-1, 0),
+            Direction::SouthWest => (-1, -1),
+            Direction::West => (0, -1),
+            Direction::NorthWest => (1, -1),
+        }
+    }
+
+    /// The opposite direction
+    pub fn opposite(self) -> Direction {
+        Direction::ALL[(self as usize + 4) % 8]
+    }
+}
+
+impl Grid9Code {
+    /// The adjacent cell in `direction`, or `None` past the northmost or
+    /// southmost row of cells.
+    pub fn neighbor(&self, direction: Direction) -> Option<Grid9Code> {
+        let (dlat, dlon) = direction.offsets();
+        let (lat_index, lon_index) = self.indices();
+        let lat = lat_index.checked_add_signed(dlat as i32).filter(|&lat| lat <= MAX_LAT_INDEX)?;
+        let lon = (lon_index as i64 + dlon as i64).rem_euclid(MAX_LON_INDEX as i64 + 1);
+        Grid9Code::from_indices(lat, lon as u32).ok()
+    }
+
+    /// The up to eight surrounding cells, clockwise from north; five in the
+    /// northmost and southmost rows.
+    pub fn neighbors(&self) -> Vec<Grid9Code> {
+        Direction::ALL.iter().filter_map(|&direction| self.neighbor(direction)).collect()
+    }
+
+    /// The cells within `k` grid steps (diagonal steps included), ordered
+    /// by distance in steps: the cell itself first, then each square ring
+    /// clockwise from its north-west corner.
+    ///
+    /// A full disk holds `(2k + 1)²` cells; rows beyond the northmost or
+    /// southmost row are left out. `k` is clamped to [`MAX_RING_STEPS`],
+    /// whose disk already spans every row and wraps all the way around in
+    /// longitude, so larger values would only loop over cells already seen.
+    pub fn k_ring(&self, k: u32) -> Vec<Grid9Code> {
+        self.k_ring_with_metrics(k, &NoopMetrics)
+    }
+
+    /// The cells within `k` grid steps like [`Self::k_ring`], reporting the
+    /// number of rings walked (`k` after clamping) to `metrics`.
+    pub fn k_ring_with_metrics(&self, k: u32, metrics: &dyn Grid9Metrics) -> Vec<Grid9Code> {
+        let (lat_index, lon_index) = self.indices();
+        let lon_cells = MAX_LON_INDEX as i64 + 1;
+        let k = k.min(MAX_RING_STEPS);
+        metrics.record_ring_expansion(k);
+        let k = k as i64;
+        // Only a ring wider than the world can reach a cell twice
+        let mut seen = (2 * k + 1 > lon_cells).then(HashSet::new);
+        let mut cells = Vec::new();
+
+        let mut push = |dlat: i64, dlon: i64| {
+            let lat = lat_index as i64 + dlat;
+            if !(0..=MAX_LAT_INDEX as i64).contains(&lat) {
+                return;
+            }
+            let lon = (lon_index as i64 + dlon).rem_euclid(lon_cells);
+            if seen.as_mut().is_none_or(|seen| seen.insert((lat, lon))) {
+                cells.extend(Grid9Code::from_indices(lat as u32, lon as u32).ok());
+            }
+        };
+        push(0, 0);
+        for d in 1..=k {
+            (-d..=d).for_each(|dlon| push(d, dlon));
+            (-d..d).rev().for_each(|dlat| push(dlat, d));
+            (-d..d).rev().for_each(|dlon| push(-d, dlon));
+            (-d + 1..d).for_each(|dlat| push(dlat, -d));
+        }
+        cells
+    }
+}
+
+/// Largest `k` that adds cells to [`Grid9Code::k_ring`]
+pub const MAX_RING_STEPS: u32 = if MAX_LAT_INDEX > MAX_LON_INDEX.div_ceil(2) { MAX_LAT_INDEX } else { MAX_LON_INDEX.div_ceil(2) };
+
+// A disk of MAX_RING_STEPS reaches every row and wraps around in longitude
+const _: () = assert!(MAX_RING_STEPS >= MAX_LAT_INDEX && 2 * MAX_RING_STEPS >= MAX_LON_INDEX);
+
+/// Returns the adjacent cell of a Grid9 code in a direction.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code (any accepted format)
+/// * `direction` - Direction to step in
+///
+/// # Returns
+/// The compact code of the neighbor, or `None` past the northmost or
+/// southmost row of cells
+///
+/// # Example
+/// ```rust
+/// use grid9::{decode, neighbor, Direction};
+///
+/// let north = neighbor("Q7KH2BBYF", Direction::North)?.unwrap();
+/// let (lat, lon) = decode("Q7KH2BBYF")?;
+/// let (north_lat, north_lon) = decode(&north)?;
+/// assert!(north_lat > lat);
+/// assert_eq!(north_lon, lon);
+/// assert_eq!(neighbor(&north, Direction::South)?.as_deref(), Some("Q7KH2BBYF"));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn neighbor(encoded: &str, direction: Direction) -> Result<Option<String>> {
+    let code: Grid9Code = encoded.parse()?;
+    Ok(code.neighbor(direction).map(|neighbor| neighbor.to_string()))
+}
+
+/// Returns the cells surrounding a Grid9 code.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code (any accepted format)
+///
+/// # Returns
+/// Compact codes of the up to eight neighbors, clockwise from north (N, NE,
+/// E, SE, S, SW, W, NW); cells in the northmost and southmost rows have five
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, neighbors};
+///
+/// let around = neighbors(&encode(-16.5, 179.99999, false)?)?;
+/// assert_eq!(around.len(), 8);
+/// // The eastern neighbors lie across the antimeridian
+/// assert!(around[2].starts_with(&encode(-16.5, -180.0, false)?[..3]));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn neighbors(encoded: &str) -> Result<Vec<String>> {
+    let code: Grid9Code = encoded.parse()?;
+    Ok(code.neighbors().iter().map(Grid9Code::to_string).collect())
+}
+
+/// Returns every cell within `k` grid steps of a Grid9 code, like H3's
+/// `kRing`.
+///
+/// Candidate cells for proximity matching come from index arithmetic alone,
+/// with no distance calculations. Steps are one cell in latitude or
+/// longitude (or both), so a ring is roughly `k` cells, ~3m each, from the
+/// center; cells narrow east-west towards the poles.
+///
+/// # Arguments
+/// * `encoded` - Center cell (any accepted format)
+/// * `k` - Number of steps; 0 returns the center alone, values above
+///   [`MAX_RING_STEPS`] are clamped
+///
+/// # Returns
+/// Compact codes of the `(2k + 1)²` cells around the center (fewer next to
+/// the poles), nearest rings first, see [`Grid9Code::k_ring`]
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, k_ring, neighbors};
+///
+/// let ping = encode(52.5200, 13.4050, false)?;
+/// let candidates = k_ring(&ping, 2)?;
+/// assert_eq!(candidates.len(), 25);
+/// assert_eq!(candidates[0], ping);
+/// assert_eq!(k_ring(&ping, 1)?[1..].len(), neighbors(&ping)?.len());
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn k_ring(encoded: &str, k: u32) -> Result<Vec<String>> {
+    let code: Grid9Code = encoded.parse()?;
+    Ok(code.k_ring(k).iter().map(Grid9Code::to_string).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{encode, Grid9Bucket, Grid9Error};
+
+    #[test]
+    fn test_neighbors_step_one_cell() {
+        let code = Grid9Code::encode(48.8566, 2.3522).unwrap();
+        let (lat_index, lon_index) = code.indices();
+        let around = code.neighbors();
+        assert_eq!(around.len(), 8);
+        for (direction, neighbor) in Direction::ALL.iter().zip(&around) {
+            let (dlat, dlon) = direction.offsets();
+            assert_eq!(neighbor.indices(), ((lat_index as i64 + dlat as i64) as u32, (lon_index as i64 + dlon as i64) as u32));
+            assert_eq!(neighbor.neighbor(direction.opposite()), Some(code));
+        }
+
+        let center = Grid9Bucket::from_code(&code.to_string(), 0).unwrap().center();
+        let width = 360.0 / MAX_LON_INDEX as f64;
+        assert_eq!(neighbor(&code.to_string(), Direction::West).unwrap().unwrap(), encode(center.lat, center.lon - width, false).unwrap());
+        let nyc: Grid9Code = "Q7KH2BBYF".parse().unwrap();
+        let north = Grid9Code::from_indices(nyc.lat_index() + 1, nyc.lon_index()).unwrap();
+        assert_eq!(neighbors("Q7K-H2B-BYF").unwrap()[0], north.to_string());
+    }
+
+    #[test]
+    fn test_grid_edges() {
+        let east_edge = Grid9Code::from_indices(100, MAX_LON_INDEX).unwrap();
+        assert_eq!(east_edge.neighbor(Direction::East), Some(Grid9Code::from_indices(100, 0).unwrap()));
+        assert_eq!(east_edge.neighbor(Direction::East).unwrap().neighbor(Direction::West), Some(east_edge));
+
+        let north_pole = Grid9Code::from_indices(MAX_LAT_INDEX, 5).unwrap();
+        assert_eq!(north_pole.neighbor(Direction::NorthEast), None);
+        assert_eq!(north_pole.neighbors().len(), 5);
+        let south_pole = Grid9Code::from_indices(0, 0).unwrap();
+        assert_eq!(neighbor(&south_pole.to_string(), Direction::South).unwrap(), None);
+        assert!(south_pole.neighbors().contains(&Grid9Code::from_indices(1, MAX_LON_INDEX).unwrap()));
+
+        assert_eq!(neighbors("BAD"), Err(Grid9Error::InvalidLength(3)));
+    }
+
+    #[test]
+    fn test_k_ring() {
+        let center = Grid9Code::encode(-33.8688, 151.2093).unwrap();
+        assert_eq!(center.k_ring(0), [center]);
+        let ring = center.k_ring(1);
+        assert_eq!(ring[0], center);
+        // Ring 1 holds the neighbors, starting at the north-west corner
+        let mut around = center.neighbors();
+        around.rotate_right(1);
+        assert_eq!(ring[1..], around[..]);
+
+        let (lat_index, lon_index) = center.indices();
+        let disk = center.k_ring(3);
+        assert_eq!(disk.len(), 49);
+        assert_eq!(disk.iter().collect::<HashSet<_>>().len(), 49);
+        let steps = |code: &Grid9Code| (code.lat_index().abs_diff(lat_index)).max(code.lon_index().abs_diff(lon_index));
+        assert!(disk.windows(2).all(|pair| steps(&pair[0]) <= steps(&pair[1])));
+        assert_eq!(disk[..25], center.k_ring(2)[..]);
+        assert_eq!(steps(disk.last().unwrap()), 3);
+
+        let pole = Grid9Code::from_indices(MAX_LAT_INDEX - 1, MAX_LON_INDEX).unwrap();
+        let capped = pole.k_ring(2);
+        assert_eq!(capped.len(), 20);
+        assert!(capped.contains(&Grid9Code::from_indices(MAX_LAT_INDEX, 1).unwrap()));
+
+        assert_eq!(k_ring(&center.to_string(), 1).unwrap()[0], center.to_string());
+        assert!(k_ring("Q7K-H2B-BY", 1).is_err());
+    }
+
+    #[test]
+    fn test_k_ring_reports_depth() {
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Depths(Mutex<Vec<u32>>);
+
+        impl Grid9Metrics for Depths {
+            fn record_ring_expansion(&self, depth: u32) {
+                self.0.lock().unwrap().push(depth);
+            }
+        }
+
+        let depths = Depths::default();
+        let center = Grid9Code::encode(-33.8688, 151.2093).unwrap();
+        assert_eq!(center.k_ring_with_metrics(2, &depths), center.k_ring(2));
+        center.k_ring_with_metrics(0, &depths);
+        assert_eq!(*depths.0.lock().unwrap(), [2, 0]);
+    }
+}
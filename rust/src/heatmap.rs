@@ -0,0 +1,230 @@
+//! Raster heatmaps of per-bucket counts (feature `image`)
+//!
+//! Paints per-bucket counts (e.g. the group sizes of
+//! [`crate::group_by_grid9_at`]) onto an equirectangular PNG, so binned data
+//! can be inspected without a mapping stack.
+
+use crate::locality::validate_bbox;
+use crate::{BoundingBox, Grid9Bucket, Grid9Error, Result};
+
+/// Largest accepted image side in pixels
+const MAX_SIDE: u32 = 16_384;
+
+/// Area and pixel size of a heatmap
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeatmapViewport {
+    /// Area shown (must not cross the antimeridian)
+    pub bbox: BoundingBox,
+    /// Image width in pixels
+    pub width: u32,
+    /// Image height in pixels
+    pub height: u32,
+}
+
+impl HeatmapViewport {
+    /// Creates a viewport showing `bbox` in a `width` by `height` image
+    pub fn new(bbox: BoundingBox, width: u32, height: u32) -> Self {
+        Self { bbox, width, height }
+    }
+}
+
+/// Colors for counts from low to high, interpolated linearly
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Palette {
+    stops: Vec<[u8; 4]>,
+}
+
+impl Palette {
+    /// A palette from at least two RGBA color stops, lowest count first
+    pub fn new(stops: Vec<[u8; 4]>) -> Result<Self> {
+        if stops.len() < 2 {
+            return Err(Grid9Error::InvalidInput("a palette needs at least two colors".into()));
+        }
+        Ok(Self { stops })
+    }
+
+    /// Translucent blue through cyan, green and yellow to opaque red
+    pub fn heat() -> Self {
+        Self {
+            stops: vec![[0, 0, 255, 128], [0, 255, 255, 160], [0, 255, 0, 192], [255, 255, 0, 224], [255, 0, 0, 255]],
+        }
+    }
+
+    /// Light grey to black
+    pub fn grayscale() -> Self {
+        Self { stops: vec![[224, 224, 224, 255], [0, 0, 0, 255]] }
+    }
+
+    /// Color at `t` in `[0, 1]`
+    fn color(&self, t: f64) -> [u8; 4] {
+        let position = t.clamp(0.0, 1.0) * (self.stops.len() - 1) as f64;
+        let index = (position.floor() as usize).min(self.stops.len() - 2);
+        let fraction = position - index as f64;
+        let (low, high) = (self.stops[index], self.stops[index + 1]);
+        std::array::from_fn(|i| (low[i] as f64 + (high[i] as f64 - low[i] as f64) * fraction).round() as u8)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::heat()
+    }
+}
+
+/// Renders per-bucket counts as a PNG heatmap.
+///
+/// Every pixel inside a bucket takes the bucket's count; buckets smaller than
+/// a pixel add their counts to the pixel containing their center. Colors
+/// follow `log(1 + count)` relative to the largest pixel value, so sparse
+/// and dense areas stay visible together. Pixels without counts are
+/// transparent.
+///
+/// # Arguments
+/// * `bins` - Buckets (of any level) and their counts
+/// * `viewport` - Area and image size
+/// * `palette` - Colors from lowest to highest count
+///
+/// # Returns
+/// The encoded PNG image
+///
+/// # Example
+/// ```rust
+/// use grid9::{render_heatmap, BoundingBox, Grid9Bucket, HeatmapViewport, Palette};
+///
+/// let bins = vec![
+///     (Grid9Bucket::from_coordinates(40.7128, -74.0060, 8)?, 120),
+///     (Grid9Bucket::from_coordinates(40.7580, -73.9855, 8)?, 15),
+/// ];
+/// let viewport = HeatmapViewport::new(BoundingBox::new(40.70, 40.77, -74.02, -73.97), 256, 256);
+/// let png = render_heatmap(bins, &viewport, &Palette::heat())?;
+/// assert_eq!(&png[1..4], b"PNG");
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn render_heatmap<I>(bins: I, viewport: &HeatmapViewport, palette: &Palette) -> Result<Vec<u8>>
+where
+    I: IntoIterator<Item = (Grid9Bucket, u64)>,
+{
+    let pixels = rasterize(bins, viewport)?;
+    let max = pixels.iter().copied().max().unwrap_or(0);
+
+    let mut rgba = Vec::with_capacity(pixels.len() * 4);
+    for &count in &pixels {
+        let color = match count {
+            0 => [0; 4],
+            _ => palette.color((count as f64).ln_1p() / (max as f64).ln_1p()),
+        };
+        rgba.extend_from_slice(&color);
+    }
+
+    let mut png_bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png_bytes, viewport.width, viewport.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(&rgba).map_err(png_error)?;
+    writer.finish().map_err(png_error)?;
+
+    Ok(png_bytes)
+}
+
+/// Accumulates counts into a row-major pixel grid, north row first.
+fn rasterize<I>(bins: I, viewport: &HeatmapViewport) -> Result<Vec<u64>>
+where
+    I: IntoIterator<Item = (Grid9Bucket, u64)>,
+{
+    let HeatmapViewport { bbox, width, height } = viewport;
+    validate_bbox(bbox)?;
+    if !(1..=MAX_SIDE).contains(width) || !(1..=MAX_SIDE).contains(height) {
+        return Err(Grid9Error::InvalidInput(format!(
+            "heatmap size {}x{} outside 1x1 to {}x{}",
+            width, height, MAX_SIDE, MAX_SIDE
+        ).into()));
+    }
+
+    let (width, height) = (*width as usize, *height as usize);
+    let lat_span = (bbox.max_lat - bbox.min_lat).max(f64::MIN_POSITIVE);
+    let lon_span = (bbox.max_lon - bbox.min_lon).max(f64::MIN_POSITIVE);
+    let column = |lon: f64| (lon - bbox.min_lon) / lon_span * width as f64;
+    let row = |lat: f64| (bbox.max_lat - lat) / lat_span * height as f64;
+
+    let mut pixels = vec![0u64; width * height];
+    for (bucket, count) in bins {
+        let bounds = bucket.bounds();
+        let (x0, x1) = (column(bounds.min_lon), column(bounds.max_lon));
+        let (y0, y1) = (row(bounds.max_lat), row(bounds.min_lat));
+        if x1 <= 0.0 || y1 <= 0.0 || x0 >= width as f64 || y0 >= height as f64 {
+            continue;
+        }
+
+        // Pixels whose centers lie inside the bucket, or the pixel holding
+        // the bucket's center when there are none
+        let span = |start: f64, end: f64, size: usize| {
+            let (first, last) = ((start - 0.5).ceil().max(0.0), ((end - 0.5).ceil()).min(size as f64));
+            if first < last {
+                first as usize..last as usize
+            } else {
+                let middle = (((start + end) / 2.0).floor().max(0.0) as usize).min(size - 1);
+                middle..middle + 1
+            }
+        };
+        for y in span(y0, y1, height) {
+            for x in span(x0, x1, width) {
+                pixels[y * width + x] += count;
+            }
+        }
+    }
+
+    Ok(pixels)
+}
+
+fn png_error(error: png::EncodingError) -> Grid9Error {
+    Grid9Error::InvalidInput(format!("PNG encoding failed: {}", error).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rasterize() {
+        let large = Grid9Bucket::from_coordinates(40.7, -74.0, 12).unwrap();
+        let bounds = large.bounds();
+        let viewport = HeatmapViewport::new(bounds.clone(), 10, 10);
+
+        let quadrant = Grid9Bucket::from_coordinates(bounds.min_lat + 1e-6, bounds.min_lon + 1e-6, 11).unwrap();
+        let small = Grid9Bucket::from_coordinates(bounds.max_lat - 1e-6, bounds.max_lon - 1e-6, 0).unwrap();
+        let outside = Grid9Bucket::from_coordinates(bounds.min_lat - 1.0, bounds.min_lon, 0).unwrap();
+
+        let pixels = rasterize([(large, 3), (quadrant, 5), (small, 2), (small, 2), (outside, 9)], &viewport).unwrap();
+        assert_eq!(pixels[9 * 10], 8); // South-west pixel, inside the quadrant
+        assert_eq!(pixels[5 * 10 + 4], 8);
+        assert_eq!(pixels[4 * 10], 3);
+        assert_eq!(pixels[9], 7); // North-east pixel sums the two small bins
+        assert_eq!(pixels.iter().sum::<u64>(), 3 * 100 + 5 * 25 + 4);
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette = Palette::new(vec![[0, 0, 0, 0], [200, 100, 50, 255]]).unwrap();
+        assert_eq!(palette.color(0.0), [0, 0, 0, 0]);
+        assert_eq!(palette.color(0.5), [100, 50, 25, 128]);
+        assert_eq!(palette.color(2.0), [200, 100, 50, 255]);
+        assert_eq!(Palette::heat().color(1.0), [255, 0, 0, 255]);
+        assert!(Palette::new(vec![[0; 4]]).is_err());
+    }
+
+    #[test]
+    fn test_png_output() {
+        let viewport = HeatmapViewport::new(BoundingBox::new(0.0, 1.0, 0.0, 1.0), 4, 3);
+        let png = render_heatmap(Vec::new(), &viewport, &Palette::default()).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[16..24], &[0, 0, 0, 4, 0, 0, 0, 3]); // IHDR width and height
+
+        let oversized = HeatmapViewport::new(BoundingBox::new(0.0, 1.0, 0.0, 1.0), 0, 3);
+        assert!(render_heatmap(Vec::new(), &oversized, &Palette::default()).is_err());
+        let crossing = HeatmapViewport::new(BoundingBox::new(0.0, 1.0, 179.0, -179.0), 4, 3);
+        assert!(render_heatmap(Vec::new(), &crossing, &Palette::default()).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! Hierarchical coarse cells built by truncating Grid9 grid indices
+
+use std::ops::RangeInclusive;
+
+use crate::locality::morton_key;
+use crate::uniform_precision_compressor::{
+    format_packed, lat_of_index, lon_of_index, pack_indices, parse_packed, quantize, unpack_indices,
+    validate_coordinates, METERS_PER_DEGREE,
+};
+use crate::{BoundingBox, Coordinate, Grid9Error, Result, MAX_LEVEL};
+
+/// A coarse cell obtained by dropping the `level` lowest bits of both grid indices.
+///
+/// Level 0 is a single ~3m Grid9 cell; every level up doubles the cell size in
+/// each direction, so a level `n` bucket covers `4^n` Grid9 cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Grid9Bucket {
+    level: u8,
+    lat_index: u32,
+    lon_index: u32,
+}
+
+impl Grid9Bucket {
+    /// Returns the bucket at `level` containing the given Grid9 code.
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::Grid9Bucket;
+    ///
+    /// let bucket = Grid9Bucket::from_code("Q7KH2BBYF", 5)?;
+    /// assert_eq!(bucket.level(), 5);
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn from_code(encoded: &str, level: u8) -> Result<Self> {
+        let (lat_bits, lon_bits) = unpack_indices(parse_packed(encoded)?);
+        Self::from_indices(lat_bits, lon_bits, level)
+    }
+
+    /// Returns the bucket at `level` containing the given coordinates.
+    pub fn from_coordinates(latitude: f64, longitude: f64, level: u8) -> Result<Self> {
+        validate_coordinates(latitude, longitude)?;
+
+        let (lat_bits, lon_bits) = quantize(latitude, longitude);
+        Self::from_indices(lat_bits, lon_bits, level)
+    }
+
+    /// Returns the bucket at `level` containing the Grid9 cell with the given grid indices.
+    pub(crate) fn from_indices(lat_bits: u32, lon_bits: u32, level: u8) -> Result<Self> {
+        if level > MAX_LEVEL {
+            return Err(Grid9Error::InvalidLevel(level));
+        }
+
+        Ok(Self {
+            level,
+            lat_index: lat_bits >> level,
+            lon_index: lon_bits >> level,
+        })
+    }
+
+    /// Bucket level (0 = full Grid9 precision)
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Returns the enclosing bucket one level up, or `None` at [`MAX_LEVEL`].
+    pub fn parent(&self) -> Option<Self> {
+        if self.level >= MAX_LEVEL {
+            return None;
+        }
+
+        Some(Self {
+            level: self.level + 1,
+            lat_index: self.lat_index >> 1,
+            lon_index: self.lon_index >> 1,
+        })
+    }
+
+    /// Returns true if the given Grid9 code lies inside this bucket.
+    pub fn contains(&self, encoded: &str) -> Result<bool> {
+        Ok(Self::from_code(encoded, self.level)? == *self)
+    }
+
+    /// Geographic extent of the bucket
+    pub fn bounds(&self) -> BoundingBox {
+        let lat_start = (self.lat_index as u64) << self.level;
+        let lon_start = (self.lon_index as u64) << self.level;
+        let span = 1u64 << self.level;
+
+        BoundingBox::new(
+            lat_of_index(lat_start as f64),
+            lat_of_index((lat_start + span) as f64).min(90.0),
+            lon_of_index(lon_start as f64),
+            lon_of_index((lon_start + span) as f64).min(180.0),
+        )
+    }
+
+    /// Geographic center of the bucket
+    pub fn center(&self) -> Coordinate {
+        let bounds = self.bounds();
+        Coordinate::new(
+            (bounds.min_lat + bounds.max_lat) / 2.0,
+            (bounds.min_lon + bounds.max_lon) / 2.0,
+        )
+    }
+
+    /// Grid9 code of the cell at the bucket's south-west corner, a stable
+    /// representative that can be stored wherever regular codes are.
+    pub fn to_code(&self, human_readable: bool) -> String {
+        format_packed(self.key(), human_readable)
+    }
+
+    /// Stable integer key of the bucket: the 45-bit payload of [`Self::to_code`].
+    ///
+    /// Keys are unique among buckets of one level; a bucket shares its key
+    /// with its south-west child, so keys of different levels should not be
+    /// mixed in one map.
+    pub fn key(&self) -> u64 {
+        pack_indices(self.lat_index << self.level, self.lon_index << self.level)
+    }
+
+    /// Range of [`crate::sort_key`] values of the cells inside the bucket.
+    ///
+    /// Buckets are aligned quadtree squares, so their cells are contiguous on
+    /// the Morton curve and a single range scan returns all of them.
+    pub fn sort_key_range(&self) -> RangeInclusive<u64> {
+        let start = morton_key(self.lat_index << self.level, self.lon_index << self.level);
+        start..=start + ((1u64 << (2 * self.level as u32)) - 1)
+    }
+
+    /// Approximate (height, width) of the bucket in meters, with the width
+    /// measured along the bucket's central parallel.
+    pub fn dimensions_m(&self) -> (f64, f64) {
+        let bounds = self.bounds();
+        let center_lat = (bounds.min_lat + bounds.max_lat) / 2.0;
+
+        let height = (bounds.max_lat - bounds.min_lat) * METERS_PER_DEGREE;
+        let width = (bounds.max_lon - bounds.min_lon)
+            * METERS_PER_DEGREE
+            * center_lat.to_radians().cos().abs();
+
+        (height, width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode;
+
+    #[test]
+    fn test_level_zero_is_the_cell() {
+        let code = encode(40.7128, -74.0060, false).unwrap();
+        let bucket = Grid9Bucket::from_code(&code, 0).unwrap();
+
+        assert_eq!(bucket.to_code(false), code);
+        assert!(bucket.contains(&code).unwrap());
+    }
+
+    #[test]
+    fn test_parent_chain_contains_code() {
+        let code = encode(51.5074, -0.1278, false).unwrap();
+        let mut bucket = Grid9Bucket::from_code(&code, 0).unwrap();
+
+        while let Some(parent) = bucket.parent() {
+            assert_eq!(parent, Grid9Bucket::from_code(&code, parent.level()).unwrap());
+            assert!(parent.contains(&code).unwrap());
+            bucket = parent;
+        }
+
+        assert_eq!(bucket.level(), MAX_LEVEL);
+    }
+
+    #[test]
+    fn test_bucket_size_doubles_per_level() {
+        let code = encode(0.0, 0.0, false).unwrap();
+        let (h1, w1) = Grid9Bucket::from_code(&code, 4).unwrap().dimensions_m();
+        let (h2, w2) = Grid9Bucket::from_code(&code, 5).unwrap().dimensions_m();
+
+        assert!((h2 / h1 - 2.0).abs() < 1e-6);
+        assert!((w2 / w1 - 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_sort_key_range_covers_bucket() {
+        let bucket = Grid9Bucket::from_code("Q7KH2BBYF", 3).unwrap();
+        let range = bucket.sort_key_range();
+        assert_eq!(range.end() - range.start() + 1, 64);
+
+        let (lat0, lon0) = (bucket.lat_index << 3, bucket.lon_index << 3);
+        for (dy, dx) in [(0, 0), (7, 7), (3, 5), (8, 0)] {
+            let key = morton_key(lat0 + dy, lon0 + dx);
+            assert_eq!(range.contains(&key), dy < 8 && dx < 8);
+        }
+
+        // The coarsest buckets are the western and eastern hemispheres
+        let west = Grid9Bucket::from_code("Q7KH2BBYF", MAX_LEVEL).unwrap().sort_key_range();
+        assert_eq!(west, 0..=(1u64 << 44) - 1);
+    }
+
+    #[test]
+    fn test_invalid_level() {
+        assert!(matches!(
+            Grid9Bucket::from_code("Q7KH2BBYF", MAX_LEVEL + 1),
+            Err(Grid9Error::InvalidLevel(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_postcard_size() {
+        // Level byte plus two varint indices
+        let bucket = Grid9Bucket::from_coordinates(89.99, 179.99, 0).unwrap();
+        let bytes = postcard::to_allocvec(&bucket).unwrap();
+        assert!(bytes.len() <= 9, "{} bytes", bytes.len());
+        assert_eq!(postcard::from_bytes::<Grid9Bucket>(&bytes).unwrap(), bucket);
+    }
+}
@@ -0,0 +1,95 @@
+//! Self-describing decode results for debugging tools and APIs
+
+use crate::{
+    get_actual_precision, BoundingBox, Coordinate, Grid9Bucket, Grid9Code, PrecisionInfo, Result,
+};
+
+/// Everything known about a single Grid9 cell
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecodedCell {
+    /// Compact canonical code
+    pub code: String,
+    /// Center of the cell
+    pub center: Coordinate,
+    /// Extent of the cell
+    pub bounds: BoundingBox,
+    /// Worst-case error of the cell
+    pub precision: PrecisionInfo,
+    /// Codes of the surrounding cells, clockwise from north (N, NE, E, SE,
+    /// S, SW, W, NW); longitude wraps around the antimeridian and cells
+    /// beyond the poles are omitted
+    pub neighbors: Vec<String>,
+    /// Latitude grid index
+    pub lat_index: u32,
+    /// Longitude grid index
+    pub lon_index: u32,
+}
+
+/// Decodes a Grid9 code into a [`DecodedCell`] describing the whole cell.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code (any accepted form)
+///
+/// # Returns
+/// The cell's canonical code, center, bounds, precision, neighbors and grid indices
+///
+/// # Example
+/// ```rust
+/// use grid9::decode_verbose;
+///
+/// let cell = decode_verbose("Q7K-H2B-BYF")?;
+/// assert_eq!(cell.code, "Q7KH2BBYF");
+/// assert_eq!(cell.neighbors.len(), 8);
+/// assert!(cell.bounds.min_lat <= cell.center.lat && cell.center.lat <= cell.bounds.max_lat);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn decode_verbose(encoded: &str) -> Result<DecodedCell> {
+    let code: Grid9Code = encoded.parse()?;
+    let cell = Grid9Bucket::from_code(&code.to_string(), 0)?;
+    let center = cell.center();
+    let (lat_index, lon_index) = code.indices();
+
+    Ok(DecodedCell {
+        code: code.to_string(),
+        precision: get_actual_precision(center.lat, center.lon)?,
+        bounds: cell.bounds(),
+        center,
+        neighbors: code.neighbors().iter().map(Grid9Code::to_string).collect(),
+        lat_index,
+        lon_index,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, encode, from_indices, to_indices, MAX_LAT_INDEX, MAX_LON_INDEX};
+
+    #[test]
+    fn test_decode_verbose_matches_individual_calls() {
+        let code = encode(40.7128, -74.0060, true).unwrap();
+        let cell = decode_verbose(&code).unwrap();
+
+        assert_eq!(cell.code, code.replace('-', ""));
+        assert_eq!((cell.lat_index, cell.lon_index), to_indices(&code).unwrap());
+        let (lat, lon) = decode(&code).unwrap();
+        assert_eq!((cell.bounds.min_lat, cell.bounds.min_lon), (lat, lon));
+        assert!(cell.center.lat > lat && cell.center.lon > lon);
+
+        let north = from_indices(cell.lat_index + 1, cell.lon_index, false).unwrap();
+        assert_eq!(cell.neighbors[0], north);
+        assert!(decode_verbose("INVALID").is_err());
+    }
+
+    #[test]
+    fn test_neighbors_at_grid_edges() {
+        let corner = Grid9Code::from_indices(0, 0).unwrap();
+        let neighbors = corner.neighbors();
+        assert_eq!(neighbors.len(), 5);
+        assert!(neighbors.contains(&Grid9Code::from_indices(0, MAX_LON_INDEX).unwrap()));
+
+        let pole = Grid9Code::from_indices(MAX_LAT_INDEX, 10).unwrap();
+        assert_eq!(pole.neighbors().len(), 5);
+    }
+}
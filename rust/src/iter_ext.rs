@@ -0,0 +1,166 @@
+//! Iterator adapters for Grid9 pipelines
+//!
+//! [`Grid9IteratorExt`] adds lazy encode, decode and binning steps to any
+//! iterator, so pipelines can chain them without collecting into an
+//! intermediate `Vec` between steps.
+
+use crate::{decode, Coordinate, Grid9Bucket, Grid9Encoder, Result};
+
+/// Grid9 adapters for iterators of coordinates and codes
+///
+/// # Example
+/// ```rust
+/// use grid9::{Grid9Encoder, Grid9IteratorExt};
+///
+/// let rows = [(40.7128, -74.0060), (40.7129, -74.0061), (51.5074, -0.1278)];
+///
+/// // Round-trip through codes without intermediate vectors
+/// let decoded = rows.into_iter().encode_grid9(Grid9Encoder::default()).flatten().decode_grid9();
+/// assert_eq!(decoded.flatten().count(), 3);
+///
+/// // ~100m buckets: the two New York rows share one
+/// let mut buckets = Vec::new();
+/// for binned in rows.into_iter().bin_by_cell(5) {
+///     let (bucket, _) = binned?;
+///     if !buckets.contains(&bucket) {
+///         buckets.push(bucket);
+///     }
+/// }
+/// assert_eq!(buckets.len(), 2);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub trait Grid9IteratorExt: Iterator + Sized {
+    /// Encodes each coordinate with `encoder`, yielding one result per item.
+    fn encode_grid9(self, encoder: Grid9Encoder) -> EncodeGrid9<Self>
+    where
+        Self::Item: Into<Coordinate>,
+    {
+        EncodeGrid9 { iter: self, encoder }
+    }
+
+    /// Decodes each Grid9 string (any accepted format), yielding one result
+    /// per item.
+    fn decode_grid9(self) -> DecodeGrid9<Self>
+    where
+        Self::Item: AsRef<str>,
+    {
+        DecodeGrid9 { iter: self }
+    }
+
+    /// Pairs each coordinate with the [`Grid9Bucket`] of `level` containing
+    /// it, ready for grouping or counting.
+    fn bin_by_cell(self, level: u8) -> BinByCell<Self>
+    where
+        Self::Item: Into<Coordinate>,
+    {
+        BinByCell { iter: self, level }
+    }
+}
+
+impl<I: Iterator> Grid9IteratorExt for I {}
+
+/// Iterator returned by [`Grid9IteratorExt::encode_grid9`]
+#[derive(Debug, Clone)]
+pub struct EncodeGrid9<I> {
+    iter: I,
+    encoder: Grid9Encoder,
+}
+
+impl<I> Iterator for EncodeGrid9<I>
+where
+    I: Iterator,
+    I::Item: Into<Coordinate>,
+{
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let coordinate = self.iter.next()?.into();
+        Some(self.encoder.encode(coordinate.lat, coordinate.lon))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Iterator returned by [`Grid9IteratorExt::decode_grid9`]
+#[derive(Debug, Clone)]
+pub struct DecodeGrid9<I> {
+    iter: I,
+}
+
+impl<I> Iterator for DecodeGrid9<I>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    type Item = Result<Coordinate>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let encoded = self.iter.next()?;
+        Some(decode(encoded.as_ref()).map(|(lat, lon)| Coordinate::new(lat, lon)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Iterator returned by [`Grid9IteratorExt::bin_by_cell`]
+#[derive(Debug, Clone)]
+pub struct BinByCell<I> {
+    iter: I,
+    level: u8,
+}
+
+impl<I> Iterator for BinByCell<I>
+where
+    I: Iterator,
+    I::Item: Into<Coordinate>,
+{
+    type Item = Result<(Grid9Bucket, Coordinate)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let coordinate = self.iter.next()?.into();
+        Some(Grid9Bucket::from_coordinates(coordinate.lat, coordinate.lon, self.level).map(|bucket| (bucket, coordinate)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{batch_decode, batch_encode, Grid9Error, Rounding};
+
+    #[test]
+    fn test_adapters_match_batch_functions() {
+        let coordinates = vec![Coordinate::new(40.7128, -74.0060), Coordinate::new(-33.8688, 151.2093)];
+        let encoded: Vec<String> = coordinates.iter().encode_grid9(Grid9Encoder::default()).collect::<Result<_>>().unwrap();
+        assert_eq!(encoded, batch_encode(&coordinates, false).unwrap());
+
+        let decoded: Vec<Coordinate> = encoded.iter().decode_grid9().collect::<Result<_>>().unwrap();
+        assert_eq!(decoded, batch_decode(&encoded).unwrap());
+
+        let nearest = Grid9Encoder::builder().rounding(Rounding::Nearest).human_readable(true).build();
+        let formatted: Vec<Result<String>> = [(40.7128, -74.0060), (91.0, 0.0)].into_iter().encode_grid9(nearest.clone()).collect();
+        assert_eq!(formatted[0], nearest.encode(40.7128, -74.0060));
+        assert_eq!(formatted[1], Err(Grid9Error::InvalidLatitude(91.0)));
+        assert!(["Q7KH2BBYF", "BAD"].into_iter().decode_grid9().nth(1).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_bin_by_cell() {
+        let binned: Vec<(Grid9Bucket, Coordinate)> =
+            [[48.8566, 2.3522], [48.8567, 2.3523]].into_iter().bin_by_cell(8).collect::<Result<_>>().unwrap();
+        assert_eq!(binned[0].0, binned[1].0);
+        assert_eq!(binned[1].1, Coordinate::new(48.8567, 2.3523));
+        assert_eq!(binned[0].0, Grid9Bucket::from_coordinates(48.8566, 2.3522, 8).unwrap());
+
+        let mut invalid = [(0.0, 0.0)].into_iter().bin_by_cell(99);
+        assert_eq!(invalid.size_hint(), (1, Some(1)));
+        assert_eq!(invalid.next(), Some(Err(Grid9Error::InvalidLevel(99))));
+    }
+}
@@ -0,0 +1,232 @@
+//! Streaming conversion of JSON Lines records
+//!
+//! [`process_jsonl`] reads one JSON object per line, looks up coordinate or
+//! code fields by dotted path (`"position.lat"`, `"points.0.lon"`) and writes
+//! each record back out with the converted field added. Records that cannot
+//! be converted are written unchanged and reported with their line number,
+//! so the output always has one line per input record.
+
+use std::io::{self, BufRead, Write};
+
+use serde_json::{Map, Value};
+
+use crate::{decode, encode, Grid9Error, RecordError, Result};
+
+/// Which fields [`process_jsonl`] reads and writes
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum JsonlMapping {
+    /// Reads coordinates and writes a code
+    Encode {
+        /// Path of the latitude field
+        lat_path: String,
+        /// Path of the longitude field
+        lon_path: String,
+        /// Path the code is written to
+        code_path: String,
+        /// Write codes in XXX-XXX-XXX format
+        human_readable: bool,
+    },
+    /// Reads a code and writes its coordinates
+    Decode {
+        /// Path of the code field
+        code_path: String,
+        /// Path the latitude is written to
+        lat_path: String,
+        /// Path the longitude is written to
+        lon_path: String,
+    },
+}
+
+/// Outcome of a [`process_jsonl`] run
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JsonlSummary {
+    /// Non-blank records read
+    pub records: u64,
+    /// Records that could not be converted, in input order
+    pub errors: Vec<RecordError>,
+}
+
+/// Converts a stream of JSON Lines records.
+///
+/// Paths are dot-separated object keys; numeric segments index into arrays.
+/// Coordinates may be JSON numbers or numeric strings. Missing objects along
+/// an output path are created. Blank lines are skipped.
+///
+/// # Arguments
+/// * `reader` - Source of newline-delimited JSON objects
+/// * `writer` - Destination for the converted records
+/// * `mapping` - Fields to read and write
+///
+/// # Returns
+/// The number of records read and the per-record errors, or an I/O error
+/// from the reader or writer
+///
+/// # Example
+/// ```rust
+/// use grid9::{process_jsonl, JsonlMapping};
+///
+/// let input = br#"{"id":1,"position":{"lat":40.7128,"lon":-74.0060}}
+/// {"id":2,"position":{"lat":"91.0","lon":0}}
+/// "#;
+/// let mapping = JsonlMapping::Encode {
+///     lat_path: "position.lat".to_string(),
+///     lon_path: "position.lon".to_string(),
+///     code_path: "position.grid9".to_string(),
+///     human_readable: false,
+/// };
+///
+/// let mut output = Vec::new();
+/// let summary = process_jsonl(&input[..], &mut output, &mapping)?;
+/// assert_eq!(summary.records, 2);
+/// assert_eq!(summary.errors[0].line, 2);
+///
+/// let first = String::from_utf8(output)?.lines().next().unwrap().to_string();
+/// assert!(first.contains(r#""grid9":"Q7KH2BBYE""#));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn process_jsonl<R: BufRead, W: Write>(mut reader: R, mut writer: W, mapping: &JsonlMapping) -> io::Result<JsonlSummary> {
+    let mut summary = JsonlSummary { records: 0, errors: Vec::new() };
+    let mut buffer = Vec::new();
+    let mut line = 0;
+
+    loop {
+        buffer.clear();
+        if reader.read_until(b'\n', &mut buffer)? == 0 {
+            break;
+        }
+        line += 1;
+
+        let record = buffer.trim_ascii();
+        if record.is_empty() {
+            continue;
+        }
+        summary.records += 1;
+
+        match convert_record(record, mapping) {
+            Ok(value) => serde_json::to_writer(&mut writer, &value)?,
+            Err(error) => {
+                writer.write_all(record)?;
+                summary.errors.push(RecordError { line, error });
+            }
+        }
+        writer.write_all(b"\n")?;
+    }
+
+    writer.flush()?;
+    Ok(summary)
+}
+
+fn convert_record(record: &[u8], mapping: &JsonlMapping) -> Result<Value> {
+    let mut value: Value = serde_json::from_slice(record)
+        .map_err(|e| Grid9Error::InvalidInput(format!("invalid JSON: {}", e).into()))?;
+
+    match mapping {
+        JsonlMapping::Encode { lat_path, lon_path, code_path, human_readable } => {
+            let lat = coordinate_field(&value, lat_path)?;
+            let lon = coordinate_field(&value, lon_path)?;
+            let code = encode(lat, lon, *human_readable)?;
+            set_path(&mut value, code_path, Value::String(code))?;
+        }
+        JsonlMapping::Decode { code_path, lat_path, lon_path } => {
+            let code = lookup(&value, code_path)?
+                .as_str()
+                .ok_or_else(|| Grid9Error::InvalidInput(format!("field {} is not a string", code_path).into()))?;
+            let (lat, lon) = decode(code)?;
+            set_path(&mut value, lat_path, lat.into())?;
+            set_path(&mut value, lon_path, lon.into())?;
+        }
+    }
+
+    Ok(value)
+}
+
+fn lookup<'a>(value: &'a Value, path: &str) -> Result<&'a Value> {
+    path.split('.')
+        .try_fold(value, |current, segment| match current {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })
+        .ok_or_else(|| Grid9Error::InvalidInput(format!("missing field {}", path).into()))
+}
+
+fn coordinate_field(value: &Value, path: &str) -> Result<f64> {
+    let field = lookup(value, path)?;
+    field
+        .as_f64()
+        .or_else(|| field.as_str().and_then(|text| text.trim().parse().ok()))
+        .ok_or_else(|| Grid9Error::InvalidInput(format!("field {} is not a number", path).into()))
+}
+
+fn set_path(value: &mut Value, path: &str, new_value: Value) -> Result<()> {
+    let not_object = || Grid9Error::InvalidInput(format!("cannot write field {}", path).into());
+    let mut segments = path.split('.').peekable();
+    let mut current = value;
+
+    while let Some(segment) = segments.next() {
+        let map = current.as_object_mut().ok_or_else(not_object)?;
+        if segments.peek().is_none() {
+            map.insert(segment.to_string(), new_value);
+            return Ok(());
+        }
+        current = map.entry(segment).or_insert_with(|| Value::Object(Map::new()));
+    }
+
+    Err(not_object())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(input: &str, mapping: &JsonlMapping) -> (Vec<String>, JsonlSummary) {
+        let mut output = Vec::new();
+        let summary = process_jsonl(input.as_bytes(), &mut output, mapping).unwrap();
+        let lines = String::from_utf8(output).unwrap().lines().map(str::to_string).collect();
+        (lines, summary)
+    }
+
+    #[test]
+    fn test_encode_records() {
+        let mapping = JsonlMapping::Encode {
+            lat_path: "geo.0".to_string(),
+            lon_path: "geo.1".to_string(),
+            code_path: "meta.cell.code".to_string(),
+            human_readable: true,
+        };
+        let input = "{\"geo\":[48.8566,2.3522]}\r\n\n{\"geo\":[48.8566]}\nnot json\n{\"geo\":[1,2],\"meta\":5}";
+        let (lines, summary) = run(input, &mapping);
+
+        assert_eq!(summary.records, 4);
+        assert_eq!(lines.len(), 4);
+        let first: Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(first["meta"]["cell"]["code"], encode(48.8566, 2.3522, true).unwrap());
+
+        let failed: Vec<u64> = summary.errors.iter().map(|e| e.line).collect();
+        assert_eq!(failed, vec![3, 4, 5]);
+        assert_eq!(lines[1], "{\"geo\":[48.8566]}");
+        assert_eq!(lines[2], "not json");
+    }
+
+    #[test]
+    fn test_decode_records() {
+        let mapping = JsonlMapping::Decode {
+            code_path: "cell".to_string(),
+            lat_path: "lat".to_string(),
+            lon_path: "lon".to_string(),
+        };
+        let (lines, summary) = run("{\"z\":1,\"cell\":\"Q7K-H2B-BYF\"}\n{\"cell\":42}\n", &mapping);
+
+        // Existing fields keep their order; new fields are appended
+        assert!(lines[0].starts_with("{\"z\":1,\"cell\":\"Q7K-H2B-BYF\",\"lat\":"));
+
+        let decoded: Value = serde_json::from_str(&lines[0]).unwrap();
+        let (lat, lon) = decode("Q7KH2BBYF").unwrap();
+        assert_eq!(decoded["lat"].as_f64(), Some(lat));
+        assert_eq!(decoded["lon"].as_f64(), Some(lon));
+        assert_eq!(summary.errors.len(), 1);
+        assert!(matches!(summary.errors[0].error, Grid9Error::InvalidInput(_)));
+    }
+}
@@ -0,0 +1,240 @@
+//! Sortable keys for ordered key-value stores (RocksDB, LMDB, sled, ...)
+//!
+//! A cell key is the 6-byte big-endian [`crate::sort_key`] of a code
+//! ([`Grid9Code::to_bytes`]), so byte order equals Morton order and
+//! spatially close cells are stored close together. Keys may carry an arbitrary suffix (e.g. a record id) to store
+//! several values per cell; suffixed keys sort directly after their cell key.
+//! Stores plug in through the [`KvScan`] trait, which only needs a forward
+//! range iterator.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{ranges_for_bbox, BoundingBox, Grid9Bucket, Grid9Code, Grid9Error, Result};
+
+/// Length in bytes of a cell key
+pub const KEY_LEN: usize = 6;
+
+/// Returns the sortable key of a code.
+///
+/// # Example
+/// ```rust
+/// use grid9::{decode_key, encode_key, Grid9Code};
+///
+/// let code: Grid9Code = "Q7KH2BBYF".parse()?;
+/// let key = encode_key(&code);
+/// assert_eq!(decode_key(&key)?, code);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn encode_key(code: &Grid9Code) -> [u8; KEY_LEN] {
+    code.to_bytes()
+}
+
+/// Returns the sortable key of a code followed by `suffix`.
+pub fn encode_key_with_suffix(code: &Grid9Code, suffix: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(KEY_LEN + suffix.len());
+    key.extend_from_slice(&encode_key(code));
+    key.extend_from_slice(suffix);
+    key
+}
+
+/// Returns the code stored in the first [`KEY_LEN`] bytes of a key; any
+/// suffix is ignored.
+pub fn decode_key(key: &[u8]) -> Result<Grid9Code> {
+    let cell: [u8; KEY_LEN] = key
+        .get(..KEY_LEN)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Grid9Error::InvalidInput(format!("key shorter than {} bytes", KEY_LEN).into()))?;
+
+    Grid9Code::from_bytes(cell)
+}
+
+/// Half-open key range `[start, end)` covering every key (suffixed or not)
+/// of the cells inside a bucket, usable as an iterator lower and upper bound.
+pub fn bucket_key_range(bucket: &Grid9Bucket) -> ([u8; KEY_LEN], [u8; KEY_LEN]) {
+    let range = bucket.sort_key_range();
+    (sort_key_bytes(*range.start()), sort_key_bytes(*range.end() + 1))
+}
+
+/// Half-open key ranges `[start, end)` covering every cell inside a bounding
+/// box, built from [`ranges_for_bbox`]. Ranges may include cells outside the
+/// box.
+pub fn bbox_key_ranges(bbox: &BoundingBox, max_ranges: usize) -> Result<Vec<([u8; KEY_LEN], [u8; KEY_LEN])>> {
+    Ok(ranges_for_bbox(bbox, max_ranges)?
+        .into_iter()
+        .map(|range| (sort_key_bytes(*range.start()), sort_key_bytes(*range.end() + 1)))
+        .collect())
+}
+
+fn sort_key_bytes(key: u64) -> [u8; KEY_LEN] {
+    let bytes = key.to_be_bytes();
+    let mut cell = [0u8; KEY_LEN];
+    cell.copy_from_slice(&bytes[8 - KEY_LEN..]);
+    cell
+}
+
+/// An ordered key-value store that can iterate a key range
+pub trait KvScan {
+    /// Store error type
+    type Error;
+
+    /// Calls `visit` with every entry whose key lies in `[start, end)`, in
+    /// ascending key order.
+    fn scan(&self, start: &[u8], end: &[u8], visit: &mut dyn FnMut(&[u8], &[u8])) -> std::result::Result<(), Self::Error>;
+}
+
+impl KvScan for BTreeMap<Vec<u8>, Vec<u8>> {
+    type Error = std::convert::Infallible;
+
+    fn scan(&self, start: &[u8], end: &[u8], visit: &mut dyn FnMut(&[u8], &[u8])) -> std::result::Result<(), Self::Error> {
+        for (key, value) in self.range(start.to_vec()..end.to_vec()) {
+            visit(key, value);
+        }
+        Ok(())
+    }
+}
+
+/// Error of a spatial scan: invalid query or a failing store
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanError<E> {
+    /// The query itself was invalid
+    Query(Grid9Error),
+    /// The store failed
+    Store(E),
+}
+
+impl<E: fmt::Display> fmt::Display for ScanError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::Query(error) => write!(f, "Invalid scan query: {}", error),
+            ScanError::Store(error) => write!(f, "Store error: {}", error),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for ScanError<E> {}
+
+/// Visits every entry whose cell lies inside a bounding box.
+///
+/// Issues at most `max_ranges` range scans and drops entries from cells
+/// outside the box, so `visit` sees exact results. Keys that do not start
+/// with a valid cell key are skipped.
+///
+/// # Example
+/// ```rust
+/// use std::collections::BTreeMap;
+/// use grid9::{encode_key, scan_bbox, BoundingBox, Grid9Code};
+///
+/// let mut store = BTreeMap::new();
+/// store.insert(encode_key(&Grid9Code::encode(40.7580, -73.9855)?).to_vec(), b"times square".to_vec());
+/// store.insert(encode_key(&Grid9Code::encode(51.5074, -0.1278)?).to_vec(), b"london".to_vec());
+///
+/// let mut found = Vec::new();
+/// scan_bbox(&store, &BoundingBox::new(40.70, 40.88, -74.02, -73.91), 8, |_, value| {
+///     found.push(value.to_vec());
+/// }).unwrap();
+/// assert_eq!(found, vec![b"times square".to_vec()]);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn scan_bbox<S, F>(store: &S, bbox: &BoundingBox, max_ranges: usize, mut visit: F) -> std::result::Result<(), ScanError<S::Error>>
+where
+    S: KvScan + ?Sized,
+    F: FnMut(Grid9Code, &[u8]),
+{
+    let ranges = bbox_key_ranges(bbox, max_ranges).map_err(ScanError::Query)?;
+    let (lat_range, lon_range) = crate::locality::bbox_index_ranges(bbox).map_err(ScanError::Query)?;
+
+    for (start, end) in ranges {
+        store
+            .scan(&start, &end, &mut |key, value| {
+                if let Ok(code) = decode_key(key) {
+                    if lat_range.contains(&code.lat_index()) && lon_range.contains(&code.lon_index()) {
+                        visit(code, value);
+                    }
+                }
+            })
+            .map_err(ScanError::Store)?;
+    }
+
+    Ok(())
+}
+
+/// Visits every entry whose cell lies inside a bucket, with a single range scan.
+pub fn scan_bucket<S, F>(store: &S, bucket: &Grid9Bucket, mut visit: F) -> std::result::Result<(), S::Error>
+where
+    S: KvScan + ?Sized,
+    F: FnMut(Grid9Code, &[u8]),
+{
+    let (start, end) = bucket_key_range(bucket);
+    store.scan(&start, &end, &mut |key, value| {
+        if let Ok(code) = decode_key(key) {
+            visit(code, value);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_with(points: &[(f64, f64)]) -> BTreeMap<Vec<u8>, Vec<u8>> {
+        points
+            .iter()
+            .enumerate()
+            .map(|(i, &(lat, lon))| {
+                let code = Grid9Code::encode(lat, lon).unwrap();
+                (encode_key_with_suffix(&code, &(i as u32).to_be_bytes()), vec![i as u8])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_key_order_matches_code_order() {
+        let mut codes: Vec<Grid9Code> = [(40.7, -74.0), (-33.9, 151.2), (51.5, -0.1), (0.0, 0.0)]
+            .iter()
+            .map(|&(lat, lon)| Grid9Code::encode(lat, lon).unwrap())
+            .collect();
+        let mut keys: Vec<[u8; KEY_LEN]> = codes.iter().map(encode_key).collect();
+
+        codes.sort();
+        keys.sort();
+        assert_eq!(keys.iter().map(|key| decode_key(key).unwrap()).collect::<Vec<_>>(), codes);
+
+        let suffixed = encode_key_with_suffix(&codes[0], b"id-1");
+        assert_eq!(decode_key(&suffixed).unwrap(), codes[0]);
+        assert!(decode_key(&[0, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_scan_bbox_is_exact() {
+        let points = [(40.7580, -73.9855), (40.7484, -73.9857), (40.6892, -74.0445), (51.5074, -0.1278)];
+        let store = store_with(&points);
+
+        for max_ranges in [1, 4, 64] {
+            let mut found = Vec::new();
+            scan_bbox(&store, &BoundingBox::new(40.70, 40.88, -74.02, -73.91), max_ranges, |_, value| {
+                found.push(value[0]);
+            })
+            .unwrap();
+            found.sort_unstable();
+            assert_eq!(found, vec![0, 1]);
+        }
+
+        let invalid = scan_bbox(&store, &BoundingBox::new(1.0, 0.0, 0.0, 1.0), 4, |_, _| {});
+        assert!(matches!(invalid, Err(ScanError::Query(_))));
+    }
+
+    #[test]
+    fn test_scan_bucket() {
+        let store = store_with(&[(40.7580, -73.9855), (40.7581, -73.9856), (40.0, -75.0)]);
+        let bucket = Grid9Bucket::from_code(&Grid9Code::encode(40.7580, -73.9855).unwrap().to_string(), 8).unwrap();
+
+        let mut found = Vec::new();
+        scan_bucket(&store, &bucket, |code, value| {
+            assert!(bucket.contains(&code.to_string()).unwrap());
+            found.push(value[0]);
+        })
+        .unwrap();
+        assert_eq!(found.len(), 2);
+    }
+}
@@ -0,0 +1,103 @@
+//! Land/ocean classification (feature `landmask`)
+//!
+//! The mask is the union of the embedded country outlines (see
+//! [`crate::country_of`]), so it shares their limits:
+//!
+//! * coastlines are accurate to roughly 20 km;
+//! * islands smaller than about 20 km are missing or drawn as boxes;
+//! * lakes and inland seas crossed by borders (the Great Lakes, Lake
+//!   Victoria) count as land, while the Caspian Sea counts as water;
+//! * Antarctica includes its ice shelves.
+//!
+//! [`is_likely_ocean`] therefore only reports ocean when no land lies within
+//! [`COASTAL_MARGIN_M`], trading missed detections near the coast for no
+//! false alarms on coastal roads and ports.
+
+use crate::uniform_precision_compressor::destination_point;
+use crate::{CountryIndex, Grid9Code, Grid9Error, Result};
+
+/// Default distance to the nearest land below which a point is not reported as ocean
+pub const COASTAL_MARGIN_M: f64 = 25_000.0;
+
+/// Returns whether a Grid9 cell is at sea, at least [`COASTAL_MARGIN_M`]
+/// from the embedded coastline.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code (any accepted form)
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, is_likely_ocean};
+///
+/// assert!(is_likely_ocean(&encode(35.0, -40.0, false)?)?);
+/// assert!(!is_likely_ocean(&encode(48.8566, 2.3522, false)?)?);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn is_likely_ocean(encoded: &str) -> Result<bool> {
+    is_likely_ocean_with_margin(encoded, COASTAL_MARGIN_M)
+}
+
+/// Returns whether a Grid9 cell is at sea, at least `margin_m` meters from
+/// the embedded coastline.
+///
+/// The margin is checked at eight points around the cell, so land features
+/// narrower than the gaps between them can be missed. A margin of zero
+/// classifies the cell alone.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code (any accepted form)
+/// * `margin_m` - Required distance to land in meters
+pub fn is_likely_ocean_with_margin(encoded: &str, margin_m: f64) -> Result<bool> {
+    if !margin_m.is_finite() || margin_m < 0.0 {
+        return Err(Grid9Error::InvalidInput(format!("margin must be a non-negative distance, got {}", margin_m).into()));
+    }
+
+    let code: Grid9Code = encoded.parse()?;
+    let (latitude, longitude) = code.coordinates();
+    let countries = CountryIndex::embedded();
+
+    if countries.lookup(latitude, longitude).is_some() {
+        return Ok(false);
+    }
+    if margin_m == 0.0 {
+        return Ok(true);
+    }
+
+    let near_land = (0..8).any(|i| {
+        let (lat, lon) = destination_point(latitude, longitude, i as f64 * 45.0, margin_m);
+        countries.lookup(lat, lon).is_some()
+    });
+    Ok(!near_land)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode;
+
+    fn ocean(latitude: f64, longitude: f64, margin_m: f64) -> bool {
+        is_likely_ocean_with_margin(&encode(latitude, longitude, false).unwrap(), margin_m).unwrap()
+    }
+
+    #[test]
+    fn test_open_ocean_and_land() {
+        for (lat, lon) in [(0.0, -30.0), (-40.0, 90.0), (20.0, -150.0), (45.0, -140.0), (-50.0, -120.0)] {
+            assert!(ocean(lat, lon, COASTAL_MARGIN_M), "({}, {})", lat, lon);
+        }
+        for (lat, lon) in [(40.7128, -74.0060), (-33.8688, 151.2093), (35.6762, 139.6503), (-85.0, 0.0)] {
+            assert!(!ocean(lat, lon, COASTAL_MARGIN_M), "({}, {})", lat, lon);
+        }
+    }
+
+    #[test]
+    fn test_coastal_margin() {
+        // 10 km off Lisbon: at sea, but within the margin of the coastline
+        assert!(ocean(38.70, -9.60, 0.0));
+        assert!(!ocean(38.70, -9.60, COASTAL_MARGIN_M));
+        assert!(ocean(38.70, -10.50, COASTAL_MARGIN_M));
+
+        let code = encode(38.70, -9.60, false).unwrap();
+        assert!(is_likely_ocean_with_margin(&code, -1.0).is_err());
+        assert!(is_likely_ocean("INVALID").is_err());
+    }
+}
@@ -27,17 +27,267 @@
 //! // Calculate distance between codes
 //! let london_code = encode(51.5074, -0.1278, false)?;
 //! let distance = calculate_distance(&code, &london_code)?; // ~5,570km
+//! # Ok::<(), grid9::Grid9Error>(())
 //! ```
+//!
+//! ## `no_std`
+//!
+//! Everything is available with the default `std` feature. Built with
+//! `default-features = false`, the crate is `#![no_std]` and keeps the core
+//! codec, which needs no allocator: [`Grid9Code`], [`encode_ascii`],
+//! [`encode_to_u64`], [`decode`], [`decode_from_u64`] and
+//! [`is_valid_encoding`]. The `alloc` feature adds the functions returning
+//! `String`s, such as [`encode`] and [`Grid9Code::to_code_string`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
+#[cfg(feature = "std")]
+pub mod bands;
+#[cfg(feature = "bulk")]
+pub mod bulk;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "std")]
+pub mod chunked;
+pub mod code;
+#[cfg(feature = "std")]
+pub mod code_stats;
+#[cfg(feature = "std")]
+pub mod codec;
+#[cfg(feature = "std")]
+pub mod coordinate_format;
+#[cfg(feature = "std")]
 pub mod coordinate_operations;
+#[cfg(feature = "countries")]
+pub mod countries;
+#[cfg(feature = "dem")]
+pub mod dem;
+#[cfg(feature = "std")]
+pub mod distance;
+#[cfg(feature = "std")]
+pub mod elevation;
+#[cfg(feature = "std")]
+pub mod encoder;
+#[cfg(feature = "std")]
+pub mod fix;
+#[cfg(feature = "arbitrary")]
+pub mod fuzzing;
+#[cfg(feature = "gazetteer")]
+pub mod gazetteer;
+#[cfg(feature = "http")]
+pub mod geocoder;
+#[cfg(feature = "std")]
+pub mod geofence;
+#[cfg(feature = "countries")]
+mod geodata;
+#[cfg(feature = "geojson")]
+pub mod geojson;
+#[cfg(feature = "gpsd")]
+pub mod gpsd;
+pub mod grid;
+#[cfg(feature = "std")]
+pub mod grid_navigation;
+#[cfg(feature = "image")]
+pub mod heatmap;
+#[cfg(feature = "std")]
+pub mod hierarchy;
+#[cfg(feature = "std")]
+pub mod inspect;
+#[cfg(feature = "std")]
+pub mod iter_ext;
+#[cfg(feature = "jsonl")]
+pub mod jsonl;
+#[cfg(feature = "kv")]
+pub mod kv;
+#[cfg(feature = "landmask")]
+pub mod landmask;
+#[cfg(feature = "std")]
+pub mod locality;
+#[cfg(feature = "magnetic")]
+pub mod magnetic;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod nearest;
+#[cfg(feature = "osm")]
+pub mod osm;
+#[cfg(feature = "std")]
+pub mod overlay;
+#[cfg(feature = "std")]
+pub mod parsing;
+#[cfg(feature = "std")]
+pub mod path;
+#[cfg(feature = "std")]
+pub mod planning;
+#[cfg(feature = "std")]
+pub mod plausibility;
+pub mod precision;
+#[cfg(feature = "std")]
+pub mod privacy;
+#[cfg(feature = "std")]
+pub mod redis_geo;
+#[cfg(feature = "std")]
+pub mod region;
+#[cfg(feature = "std")]
+pub mod report;
+#[cfg(feature = "std")]
+pub mod scan;
+#[cfg(feature = "std")]
+pub mod segment;
+#[cfg(feature = "std")]
+pub mod short_code;
+#[cfg(feature = "std")]
+pub mod similarity;
+#[cfg(feature = "std")]
+pub mod single_precision;
+#[cfg(feature = "sled")]
+pub mod sled_tree;
+#[cfg(feature = "std")]
+pub mod static_map;
+#[cfg(feature = "svg")]
+pub mod svg;
+#[cfg(feature = "std")]
+pub mod test_vectors;
+#[cfg(feature = "timezones")]
+pub mod timezones;
+#[cfg(feature = "std")]
+pub mod track;
 pub mod uniform_precision_compressor;
+#[cfg(feature = "std")]
+pub mod validation;
+pub mod version;
+#[cfg(feature = "std")]
+pub mod wkb;
+#[cfg(feature = "std")]
 pub mod test_simple;
 
+#[cfg(feature = "std")]
+pub use bands::*;
+#[cfg(feature = "bulk")]
+pub use bulk::*;
+#[cfg(feature = "std")]
+pub use cache::*;
+#[cfg(feature = "cbor")]
+pub use cbor::*;
+#[cfg(feature = "std")]
+pub use chunked::*;
+pub use code::*;
+#[cfg(feature = "std")]
+pub use code_stats::*;
+#[cfg(feature = "std")]
+pub use codec::*;
+#[cfg(feature = "std")]
+pub use coordinate_format::*;
+#[cfg(feature = "std")]
 pub use coordinate_operations::*;
+#[cfg(feature = "countries")]
+pub use countries::*;
+#[cfg(feature = "dem")]
+pub use dem::*;
+#[cfg(feature = "std")]
+pub use distance::*;
+#[cfg(feature = "std")]
+pub use elevation::*;
+#[cfg(feature = "std")]
+pub use encoder::*;
+#[cfg(feature = "std")]
+pub use fix::*;
+#[cfg(feature = "gazetteer")]
+pub use gazetteer::*;
+#[cfg(feature = "http")]
+pub use geocoder::*;
+#[cfg(feature = "std")]
+pub use geofence::*;
+#[cfg(feature = "geojson")]
+pub use geojson::*;
+#[cfg(feature = "gpsd")]
+pub use gpsd::*;
+pub use grid::*;
+#[cfg(feature = "std")]
+pub use grid_navigation::*;
+#[cfg(feature = "image")]
+pub use heatmap::*;
+#[cfg(feature = "std")]
+pub use hierarchy::*;
+#[cfg(feature = "std")]
+pub use inspect::*;
+#[cfg(feature = "std")]
+pub use iter_ext::*;
+#[cfg(feature = "jsonl")]
+pub use jsonl::*;
+#[cfg(feature = "kv")]
+pub use kv::*;
+#[cfg(feature = "landmask")]
+pub use landmask::*;
+#[cfg(feature = "std")]
+pub use locality::*;
+#[cfg(feature = "magnetic")]
+pub use magnetic::*;
+#[cfg(feature = "std")]
+pub use metrics::*;
+#[cfg(feature = "std")]
+pub use nearest::*;
+#[cfg(feature = "osm")]
+pub use osm::*;
+#[cfg(feature = "std")]
+pub use overlay::*;
+#[cfg(feature = "std")]
+pub use parsing::*;
+#[cfg(feature = "std")]
+pub use path::*;
+#[cfg(feature = "std")]
+pub use planning::*;
+#[cfg(feature = "std")]
+pub use plausibility::*;
+pub use precision::*;
+#[cfg(feature = "std")]
+pub use privacy::*;
+#[cfg(feature = "std")]
+pub use redis_geo::*;
+#[cfg(feature = "std")]
+pub use region::*;
+#[cfg(feature = "std")]
+pub use report::*;
+#[cfg(feature = "std")]
+pub use scan::*;
+#[cfg(feature = "std")]
+pub use segment::*;
+#[cfg(feature = "std")]
+pub use short_code::*;
+#[cfg(feature = "std")]
+pub use similarity::*;
+#[cfg(feature = "std")]
+pub use single_precision::*;
+#[cfg(feature = "sled")]
+pub use sled_tree::*;
+#[cfg(feature = "std")]
+pub use static_map::*;
+#[cfg(feature = "svg")]
+pub use svg::*;
+#[cfg(feature = "std")]
+pub use test_vectors::*;
+#[cfg(feature = "timezones")]
+pub use timezones::*;
+#[cfg(feature = "std")]
+pub use track::*;
 pub use uniform_precision_compressor::*;
+#[cfg(feature = "std")]
+pub use validation::*;
+pub use version::*;
+#[cfg(feature = "std")]
+pub use wkb::*;
 
 /// Grid9 error types
+///
+/// New variants may be added in minor releases, so matches need a wildcard arm.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Grid9Error {
     /// Invalid latitude (must be between -90 and 90)
     InvalidLatitude(f64),
@@ -49,10 +299,124 @@ pub enum Grid9Error {
     InvalidCharacter(char),
     /// Empty input
     EmptyInput,
+    /// Invalid radius (must be a finite, non-negative number of meters)
+    InvalidRadius(f64),
+    /// Invalid hierarchy level (must not exceed `MAX_LEVEL`)
+    InvalidLevel(u8),
+    /// Invalid privacy budget (must be a finite, positive number)
+    InvalidEpsilon(f64),
+    /// Dash separator at an unexpected position (0-based character index)
+    InvalidSeparator(usize),
+    /// Malformed input record or text (e.g. a non-numeric coordinate field)
+    InvalidInput(ErrorMessage),
+    /// Check character does not match the code
+    InvalidChecksum,
+    /// Code tagged with a format version this library cannot read
+    UnsupportedVersion(u8),
+    /// Grid index outside the grid (see `MAX_LAT_INDEX` and `MAX_LON_INDEX`)
+    InvalidIndex(u32),
 }
 
-impl std::fmt::Display for Grid9Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// Broad class of a [`Grid9Error`], for mapping errors to API responses and metrics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ErrorCategory {
+    /// An argument or input record is malformed or out of its allowed domain
+    InputValidation,
+    /// Text could not be read as a Grid9 code
+    Parse,
+    /// A coordinate or grid index lies outside the grid
+    Range,
+    /// A failure inside the library rather than in its input
+    Internal,
+}
+
+impl ErrorCategory {
+    /// Stable snake_case name, e.g. `input_validation`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCategory::InputValidation => "input_validation",
+            ErrorCategory::Parse => "parse",
+            ErrorCategory::Range => "range",
+            ErrorCategory::Internal => "internal",
+        }
+    }
+}
+
+impl Grid9Error {
+    /// Stable machine-readable identifier, e.g. `invalid_latitude`.
+    ///
+    /// Identifiers never change once released, unlike the `Display` text.
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::{decode, ErrorCategory};
+    ///
+    /// let error = decode("Q7KH2BBY!").unwrap_err();
+    /// assert_eq!(error.code(), "invalid_character");
+    /// assert_eq!(error.numeric_code(), 4);
+    /// assert_eq!(error.category(), ErrorCategory::Parse);
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            Grid9Error::InvalidLatitude(_) => "invalid_latitude",
+            Grid9Error::InvalidLongitude(_) => "invalid_longitude",
+            Grid9Error::InvalidLength(_) => "invalid_length",
+            Grid9Error::InvalidCharacter(_) => "invalid_character",
+            Grid9Error::EmptyInput => "empty_input",
+            Grid9Error::InvalidRadius(_) => "invalid_radius",
+            Grid9Error::InvalidLevel(_) => "invalid_level",
+            Grid9Error::InvalidEpsilon(_) => "invalid_epsilon",
+            Grid9Error::InvalidSeparator(_) => "invalid_separator",
+            Grid9Error::InvalidInput(_) => "invalid_input",
+            Grid9Error::InvalidChecksum => "invalid_checksum",
+            Grid9Error::UnsupportedVersion(_) => "unsupported_version",
+            Grid9Error::InvalidIndex(_) => "invalid_index",
+        }
+    }
+
+    /// Stable numeric identifier, unique per variant and never reused.
+    pub fn numeric_code(&self) -> u16 {
+        match self {
+            Grid9Error::InvalidLatitude(_) => 1,
+            Grid9Error::InvalidLongitude(_) => 2,
+            Grid9Error::InvalidLength(_) => 3,
+            Grid9Error::InvalidCharacter(_) => 4,
+            Grid9Error::EmptyInput => 5,
+            Grid9Error::InvalidRadius(_) => 6,
+            Grid9Error::InvalidLevel(_) => 7,
+            Grid9Error::InvalidEpsilon(_) => 8,
+            Grid9Error::InvalidSeparator(_) => 9,
+            Grid9Error::InvalidInput(_) => 10,
+            Grid9Error::InvalidChecksum => 11,
+            Grid9Error::UnsupportedVersion(_) => 12,
+            Grid9Error::InvalidIndex(_) => 13,
+        }
+    }
+
+    /// Broad class of the error
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Grid9Error::InvalidLatitude(_) | Grid9Error::InvalidLongitude(_) | Grid9Error::InvalidIndex(_) => {
+                ErrorCategory::Range
+            }
+            Grid9Error::InvalidLength(_)
+            | Grid9Error::InvalidCharacter(_)
+            | Grid9Error::EmptyInput
+            | Grid9Error::InvalidSeparator(_)
+            | Grid9Error::InvalidChecksum
+            | Grid9Error::UnsupportedVersion(_) => ErrorCategory::Parse,
+            Grid9Error::InvalidRadius(_) | Grid9Error::InvalidLevel(_) | Grid9Error::InvalidEpsilon(_) => {
+                ErrorCategory::InputValidation
+            }
+            Grid9Error::InvalidInput(_) => ErrorCategory::InputValidation,
+        }
+    }
+}
+
+impl core::fmt::Display for Grid9Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Grid9Error::InvalidLatitude(lat) => {
                 write!(f, "Invalid latitude: {} (must be between -90 and 90)", lat)
@@ -69,14 +433,100 @@ impl std::fmt::Display for Grid9Error {
             Grid9Error::EmptyInput => {
                 write!(f, "Empty input string")
             }
+            Grid9Error::InvalidRadius(radius) => {
+                write!(f, "Invalid radius: {} (must be a non-negative number of meters)", radius)
+            }
+            Grid9Error::InvalidLevel(level) => {
+                write!(f, "Invalid level: {} (must be at most {})", level, MAX_LEVEL)
+            }
+            Grid9Error::InvalidEpsilon(epsilon) => {
+                write!(f, "Invalid epsilon: {} (must be a positive number)", epsilon)
+            }
+            Grid9Error::InvalidSeparator(position) => {
+                write!(f, "Unexpected separator at position {} (expected XXX-XXX-XXX)", position)
+            }
+            Grid9Error::InvalidInput(reason) => {
+                write!(f, "Invalid input: {}", reason)
+            }
+            Grid9Error::InvalidChecksum => {
+                write!(f, "Check character does not match the code")
+            }
+            Grid9Error::UnsupportedVersion(version) => {
+                write!(f, "Unsupported format version: {} (supported: {})", version, FORMAT_VERSION)
+            }
+            Grid9Error::InvalidIndex(index) => {
+                write!(f, "Invalid grid index: {} (outside the grid)", index)
+            }
+        }
+    }
+}
+
+impl core::error::Error for Grid9Error {}
+
+/// Logs the variant and its value without allocating formatting strings.
+#[cfg(feature = "defmt")]
+impl defmt::Format for Grid9Error {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Grid9Error::InvalidLatitude(lat) => defmt::write!(f, "InvalidLatitude({=f64})", lat),
+            Grid9Error::InvalidLongitude(lon) => defmt::write!(f, "InvalidLongitude({=f64})", lon),
+            Grid9Error::InvalidLength(len) => defmt::write!(f, "InvalidLength({=usize})", len),
+            Grid9Error::InvalidCharacter(ch) => defmt::write!(f, "InvalidCharacter({=char})", ch),
+            Grid9Error::EmptyInput => defmt::write!(f, "EmptyInput"),
+            Grid9Error::InvalidRadius(radius) => defmt::write!(f, "InvalidRadius({=f64})", radius),
+            Grid9Error::InvalidLevel(level) => defmt::write!(f, "InvalidLevel({=u8})", level),
+            Grid9Error::InvalidEpsilon(epsilon) => defmt::write!(f, "InvalidEpsilon({=f64})", epsilon),
+            Grid9Error::InvalidSeparator(position) => defmt::write!(f, "InvalidSeparator({=usize})", position),
+            Grid9Error::InvalidInput(reason) => defmt::write!(f, "InvalidInput({=str})", reason.as_str()),
+            Grid9Error::InvalidChecksum => defmt::write!(f, "InvalidChecksum"),
+            Grid9Error::UnsupportedVersion(version) => defmt::write!(f, "UnsupportedVersion({=u8})", version),
+            Grid9Error::InvalidIndex(index) => defmt::write!(f, "InvalidIndex({=u32})", index),
         }
     }
 }
 
-impl std::error::Error for Grid9Error {}
+/// Description carried by [`Grid9Error::InvalidInput`]
+///
+/// Without the `alloc` feature only static text can be stored; with it any
+/// `String` can. Read it with [`ErrorMessage::as_str`] or `Display`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct ErrorMessage(
+    #[cfg(feature = "alloc")] alloc::borrow::Cow<'static, str>,
+    #[cfg(not(feature = "alloc"))] &'static str,
+);
+
+impl ErrorMessage {
+    /// The message text
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&'static str> for ErrorMessage {
+    fn from(message: &'static str) -> Self {
+        #[cfg(feature = "alloc")]
+        let message = alloc::borrow::Cow::Borrowed(message);
+        ErrorMessage(message)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<alloc::string::String> for ErrorMessage {
+    fn from(message: alloc::string::String) -> Self {
+        ErrorMessage(alloc::borrow::Cow::Owned(message))
+    }
+}
+
+impl core::fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
 /// Result type for Grid9 operations
-pub type Result<T> = std::result::Result<T, Grid9Error>;
+pub type Result<T> = core::result::Result<T, Grid9Error>;
 
 #[cfg(test)]
 mod tests {
@@ -136,6 +586,36 @@ mod tests {
         // Invalid encoded strings
         assert!(matches!(decode(""), Err(Grid9Error::EmptyInput)));
         assert!(matches!(decode("TOOLONG"), Err(Grid9Error::InvalidLength(_))));
-        assert!(matches!(decode("INVALID!"), Err(Grid9Error::InvalidCharacter(_))));
+        assert!(matches!(decode("INVALID!!"), Err(Grid9Error::InvalidCharacter(_))));
+    }
+
+    #[test]
+    fn test_error_codes_and_categories() {
+        let errors = [
+            Grid9Error::InvalidLatitude(91.0),
+            Grid9Error::InvalidLongitude(181.0),
+            Grid9Error::InvalidLength(7),
+            Grid9Error::InvalidCharacter('!'),
+            Grid9Error::EmptyInput,
+            Grid9Error::InvalidRadius(-1.0),
+            Grid9Error::InvalidLevel(9),
+            Grid9Error::InvalidEpsilon(0.0),
+            Grid9Error::InvalidSeparator(1),
+            Grid9Error::InvalidInput("x".into()),
+            Grid9Error::InvalidChecksum,
+            Grid9Error::UnsupportedVersion(7),
+            Grid9Error::InvalidIndex(1 << 23),
+        ];
+
+        let codes: std::collections::HashSet<_> = errors.iter().map(Grid9Error::code).collect();
+        let ids: std::collections::HashSet<_> = errors.iter().map(Grid9Error::numeric_code).collect();
+        assert_eq!((codes.len(), ids.len()), (errors.len(), errors.len()));
+
+        assert_eq!(encode(91.0, 0.0, false).unwrap_err().category(), ErrorCategory::Range);
+        assert_eq!(decode("TOOLONG").unwrap_err().category(), ErrorCategory::Parse);
+        assert_eq!(Grid9Error::InvalidRadius(-1.0).category().as_str(), "input_validation");
+        let message = Grid9Error::InvalidInput("bad record".into());
+        assert_eq!(message.to_string(), "Invalid input: bad record");
+        assert_eq!(message, Grid9Error::InvalidInput(String::from("bad record").into()));
     }
 }
\ No newline at end of file
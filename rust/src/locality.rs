@@ -0,0 +1,594 @@
+//! Locality-preserving (Morton / Z-order) ordering of Grid9 cells
+
+use std::cmp::Ordering;
+use std::ops::RangeInclusive;
+
+use crate::codec::PAYLOAD_MASK;
+use crate::uniform_precision_compressor::{
+    format_packed, lat_of_index, lon_of_index, pack_indices, parse_digits, parse_packed, quantize, unpack_indices,
+    validate_coordinates, LAT_BITS,
+};
+use crate::{BoundingBox, Codec, Grid9Code, Grid9Error, Result};
+
+/// Spreads the bits of `value` so that bit `i` moves to bit `2 * i`.
+pub(crate) fn spread_bits(value: u32) -> u64 {
+    let mut x = value as u64;
+    x = (x | (x << 16)) & 0x0000_FFFF_0000_FFFF;
+    x = (x | (x << 8)) & 0x00FF_00FF_00FF_00FF;
+    x = (x | (x << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    x = (x | (x << 2)) & 0x3333_3333_3333_3333;
+    x = (x | (x << 1)) & 0x5555_5555_5555_5555;
+    x
+}
+
+/// Inverse of [`spread_bits`]: gathers the even bits of `value`.
+pub(crate) fn compact_bits(value: u64) -> u32 {
+    let mut x = value & 0x5555_5555_5555_5555;
+    x = (x | (x >> 1)) & 0x3333_3333_3333_3333;
+    x = (x | (x >> 2)) & 0x0F0F_0F0F_0F0F_0F0F;
+    x = (x | (x >> 4)) & 0x00FF_00FF_00FF_00FF;
+    x = (x | (x >> 8)) & 0x0000_FFFF_0000_FFFF;
+    x = (x | (x >> 16)) & 0x0000_0000_FFFF_FFFF;
+    x as u32
+}
+
+/// Morton key of grid indices: longitude bits on even positions, latitude
+/// bits on odd positions, so the top bit splits the world into east and west
+/// halves and every following pair of bits splits a square cell into four.
+pub(crate) fn morton_key(lat_index: u32, lon_index: u32) -> u64 {
+    spread_bits(lon_index) | (spread_bits(lat_index) << 1)
+}
+
+/// Grid indices of a Morton key
+pub(crate) fn morton_indices(key: u64) -> (u32, u32) {
+    (compact_bits(key >> 1), compact_bits(key))
+}
+
+/// Returns a locality-preserving sort key for a Grid9 code.
+///
+/// Keys follow the Morton (Z-order) curve over the grid: sorting codes by key
+/// keeps codes of the same quadtree cell at every zoom level next to each
+/// other, which improves compression and scan locality of sorted data. Keys
+/// are 45-bit values, usable directly as big-endian database keys.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code
+///
+/// # Returns
+/// The sort key (`< 2^45`)
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, sort_key};
+///
+/// let mut codes = vec![
+///     encode(51.5074, -0.1278, false)?, // London
+///     encode(40.7128, -74.0060, false)?, // New York
+///     encode(48.8566, 2.3522, false)?,  // Paris
+/// ];
+/// codes.sort_by_key(|code| sort_key(code).unwrap());
+/// assert_eq!(codes[0], encode(40.7128, -74.0060, false)?);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn sort_key(encoded: &str) -> Result<u64> {
+    let (lat_index, lon_index) = unpack_indices(parse_packed(encoded)?);
+    Ok(morton_key(lat_index, lon_index))
+}
+
+/// Checks that a bounding box has valid corners and does not cross the antimeridian
+pub(crate) fn validate_bbox(bbox: &BoundingBox) -> Result<()> {
+    validate_coordinates(bbox.min_lat, bbox.min_lon)?;
+    validate_coordinates(bbox.max_lat, bbox.max_lon)?;
+    if bbox.min_lat > bbox.max_lat || bbox.min_lon > bbox.max_lon {
+        return Err(Grid9Error::InvalidInput(
+            "bounding box minimum exceeds its maximum".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Inclusive (latitude, longitude) grid index ranges of the cells covering a bounding box
+pub(crate) fn bbox_index_ranges(bbox: &BoundingBox) -> Result<(RangeInclusive<u32>, RangeInclusive<u32>)> {
+    validate_bbox(bbox)?;
+
+    let (lat_lo, lon_lo) = quantize(bbox.min_lat, bbox.min_lon);
+    let (lat_hi, lon_hi) = quantize(bbox.max_lat, bbox.max_lon);
+    Ok((lat_lo..=lat_hi, lon_lo..=lon_hi))
+}
+
+/// Returns sort key ranges covering every cell inside a bounding box.
+///
+/// The ranges are over the [`sort_key`] space, sorted and non-overlapping,
+/// so they map directly onto range scans of ordered stores keyed by sort
+/// key (RocksDB, BigTable, DynamoDB sort keys). The quadtree decomposition is
+/// refined until about `4 * max_ranges` ranges, then the smallest gaps
+/// between ranges are merged until at most `max_ranges` remain. Ranges may
+/// therefore include cells outside the box; filter scanned rows by position
+/// if exact results are needed.
+///
+/// # Arguments
+/// * `bbox` - Area to cover (must not cross the antimeridian)
+/// * `max_ranges` - Maximum number of ranges to return (at least 1)
+///
+/// # Returns
+/// Inclusive sort key ranges in ascending order
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, ranges_for_bbox, sort_key, BoundingBox};
+///
+/// let manhattan = BoundingBox::new(40.70, 40.88, -74.02, -73.91);
+/// let ranges = ranges_for_bbox(&manhattan, 8)?;
+/// assert!(ranges.len() <= 8);
+///
+/// let key = sort_key(&encode(40.7580, -73.9855, false)?)?; // Times Square
+/// assert!(ranges.iter().any(|range| range.contains(&key)));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn ranges_for_bbox(bbox: &BoundingBox, max_ranges: usize) -> Result<Vec<RangeInclusive<u64>>> {
+    if max_ranges == 0 {
+        return Err(Grid9Error::InvalidInput("max_ranges must be at least 1".into()));
+    }
+    let (lat_range, lon_range) = bbox_index_ranges(bbox)?;
+    let budget = max_ranges.saturating_mul(4).max(16);
+
+    // Returns None if the square node at (lat0, lon0) misses the box, else
+    // whether it lies completely inside
+    let classify = |lat0: u32, lon0: u32, level: u32| {
+        let lat_end = lat0 + ((1 << level) - 1);
+        let lon_end = lon0 + ((1 << level) - 1);
+        if lat_end < *lat_range.start()
+            || lat0 > *lat_range.end()
+            || lon_end < *lon_range.start()
+            || lon0 > *lon_range.end()
+        {
+            return None;
+        }
+        Some(
+            lat0 >= *lat_range.start()
+                && lat_end <= *lat_range.end()
+                && lon0 >= *lon_range.start()
+                && lon_end <= *lon_range.end(),
+        )
+    };
+    let key_range = |lat0: u32, lon0: u32, level: u32| {
+        let start = morton_key(lat0, lon0);
+        start..=start + ((1u64 << (2 * level)) - 1)
+    };
+
+    // The two quadtree roots are the western and eastern hemispheres
+    let mut level = LAT_BITS;
+    let mut ranges = Vec::new();
+    let mut frontier = Vec::new();
+    for (lat0, lon0) in [(0, 0), (0, 1 << level)] {
+        match classify(lat0, lon0, level) {
+            Some(true) => ranges.push(key_range(lat0, lon0, level)),
+            Some(false) => frontier.push((lat0, lon0)),
+            None => {}
+        }
+    }
+
+    while level > 0 && !frontier.is_empty() && ranges.len() + frontier.len() * 4 <= budget {
+        level -= 1;
+        let half = 1 << level;
+        let mut next = Vec::with_capacity(frontier.len() * 4);
+        for (lat0, lon0) in frontier {
+            for (lat, lon) in [(lat0, lon0), (lat0, lon0 + half), (lat0 + half, lon0), (lat0 + half, lon0 + half)] {
+                match classify(lat, lon, level) {
+                    Some(true) => ranges.push(key_range(lat, lon, level)),
+                    Some(false) => next.push((lat, lon)),
+                    None => {}
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    // Partially covered nodes left at the refinement limit are scanned whole
+    ranges.extend(frontier.into_iter().map(|(lat0, lon0)| key_range(lat0, lon0, level)));
+    ranges.sort_unstable_by_key(|range| *range.start());
+
+    Ok(merge_ranges(ranges, max_ranges))
+}
+
+/// Joins touching ranges, then closes the smallest gaps until at most
+/// `max_ranges` ranges remain. `ranges` must be sorted and non-overlapping.
+fn merge_ranges(ranges: Vec<RangeInclusive<u64>>, max_ranges: usize) -> Vec<RangeInclusive<u64>> {
+    let mut merged: Vec<RangeInclusive<u64>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if *last.end() + 1 == *range.start() => *last = *last.start()..=*range.end(),
+            _ => merged.push(range),
+        }
+    }
+    if merged.len() <= max_ranges {
+        return merged;
+    }
+
+    // Close the smallest gaps; gap i lies between range i and range i + 1
+    let mut gaps: Vec<usize> = (0..merged.len() - 1).collect();
+    gaps.sort_unstable_by_key(|&i| (*merged[i + 1].start() - *merged[i].end(), i));
+    let mut closed = vec![false; merged.len() - 1];
+    for &i in &gaps[..merged.len() - max_ranges] {
+        closed[i] = true;
+    }
+
+    let mut result = Vec::with_capacity(max_ranges);
+    let mut start = *merged[0].start();
+    for (i, range) in merged.iter().enumerate() {
+        if i == closed.len() || !closed[i] {
+            result.push(start..=*range.end());
+            if let Some(next) = merged.get(i + 1) {
+                start = *next.start();
+            }
+        }
+    }
+    result
+}
+
+/// Grid index bounds of a bounding box, precomputed for [`bbox_contains_code_fast`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BboxIndices {
+    pub min_lat_index: u32,
+    pub max_lat_index: u32,
+    pub min_lon_index: u32,
+    pub max_lon_index: u32,
+}
+
+impl BboxIndices {
+    /// Index bounds of the cells covering a bounding box (which must not
+    /// cross the antimeridian)
+    pub fn from_bbox(bbox: &BoundingBox) -> Result<Self> {
+        let (lat_range, lon_range) = bbox_index_ranges(bbox)?;
+        Ok(Self {
+            min_lat_index: *lat_range.start(),
+            max_lat_index: *lat_range.end(),
+            min_lon_index: *lon_range.start(),
+            max_lon_index: *lon_range.end(),
+        })
+    }
+}
+
+/// Tests whether a code lies in a bounding box using integer comparisons only.
+///
+/// Equivalent to checking that the code's cell overlaps the box, so every
+/// point inside the box encodes to a matching code, as do points in the
+/// parts of edge cells just outside it. Meant for filtering large numbers
+/// of codes against a few boxes without decoding them to degrees.
+///
+/// # Arguments
+/// * `bbox` - Bounds from [`BboxIndices::from_bbox`]
+/// * `code` - Code to test
+///
+/// # Example
+/// ```rust
+/// use grid9::{bbox_contains_code_fast, BboxIndices, BoundingBox, Grid9Code};
+///
+/// let manhattan = BboxIndices::from_bbox(&BoundingBox::new(40.70, 40.88, -74.02, -73.91))?;
+/// assert!(bbox_contains_code_fast(&manhattan, Grid9Code::encode(40.7580, -73.9855)?));
+/// assert!(!bbox_contains_code_fast(&manhattan, Grid9Code::encode(40.6892, -74.0445)?)); // Liberty Island
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[inline]
+pub fn bbox_contains_code_fast(bbox: &BboxIndices, code: Grid9Code) -> bool {
+    let (lat_index, lon_index) = code.indices();
+    (bbox.min_lat_index..=bbox.max_lat_index).contains(&lat_index)
+        && (bbox.min_lon_index..=bbox.max_lon_index).contains(&lon_index)
+}
+
+impl Grid9Code {
+    /// Locality-preserving sort key (see [`sort_key`])
+    pub fn sort_key(&self) -> u64 {
+        let (lat_index, lon_index) = self.indices();
+        morton_key(lat_index, lon_index)
+    }
+
+    /// Returns the code with the given sort key.
+    pub fn from_sort_key(key: u64) -> Result<Self> {
+        if key > PAYLOAD_MASK {
+            return Err(Grid9Error::InvalidInput(format!("sort key {} exceeds 45 bits", key).into()));
+        }
+
+        let (lat_index, lon_index) = morton_indices(key);
+        Ok(Self::from_packed(pack_indices(lat_index, lon_index)))
+    }
+
+    /// The sort key as 6 big-endian bytes, so comparing the bytes orders
+    /// codes like [`Ord`]; usable as a key in ordered stores such as LMDB
+    /// or RocksDB.
+    ///
+    /// These are Morton-interleaved indices, not the payload of
+    /// [`Grid9Code::to_payload_bytes`].
+    pub fn to_bytes(&self) -> [u8; 6] {
+        let mut bytes = [0u8; 6];
+        bytes.copy_from_slice(&self.sort_key().to_be_bytes()[2..]);
+        bytes
+    }
+
+    /// Returns the code of 6 bytes from [`Grid9Code::to_bytes`]; fails with
+    /// `InvalidInput` if the value exceeds 45 bits.
+    pub fn from_bytes(bytes: [u8; 6]) -> Result<Self> {
+        let mut padded = [0u8; 8];
+        padded[2..].copy_from_slice(&bytes);
+        Self::from_sort_key(u64::from_be_bytes(padded))
+    }
+}
+
+impl Ord for Grid9Code {
+    /// Orders codes along the Morton curve, see [`sort_key`]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl PartialOrd for Grid9Code {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Codes spelling the [`sort_key`] instead of the standard payload
+///
+/// Latitude and longitude bits are interleaved (Morton / Z-order), so the
+/// first `k` characters of a code name a quadtree cell containing it: nearby
+/// places share long prefixes and codes sort along the curve as plain
+/// strings, which turns area queries into prefix or range scans of a
+/// key-value store. Codes look like standard Grid9 codes and address the
+/// same cells, but spell them differently; never mix the two in one column.
+///
+/// # Example
+/// ```rust
+/// use grid9::{Codec, MortonCodec};
+///
+/// let times_square = MortonCodec.encode(40.7580, -73.9855)?;
+/// let bryant_park = MortonCodec.encode(40.7536, -73.9832)?;
+/// assert_eq!(times_square[..5], bryant_park[..5]);
+///
+/// // Everything under a prefix lies in one cell
+/// let cell = MortonCodec::prefix_bounds(&times_square[..5])?;
+/// assert!(cell.contains(40.7580, -73.9855) && cell.contains(40.7536, -73.9832));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MortonCodec;
+
+impl MortonCodec {
+    /// Area covered by the codes starting with `prefix`.
+    ///
+    /// # Arguments
+    /// * `prefix` - The first 1 to 9 characters of a code (dashes are
+    ///   ignored, as in [`crate::decode`])
+    ///
+    /// # Returns
+    /// The bounding box of the prefix's cell: one of 32 slices of the world
+    /// for a single character, a single cell for a full code
+    pub fn prefix_bounds(prefix: &str) -> Result<BoundingBox> {
+        let (value, len) = parse_digits(prefix, |len| len <= 9)?;
+
+        let free_bits = 5 * (9 - len as u32);
+        let first = value << free_bits;
+        let last = first | ((1 << free_bits) - 1);
+        let (min_lat, min_lon) = morton_indices(first);
+        let (max_lat, max_lon) = morton_indices(last);
+        Ok(BoundingBox::new(
+            lat_of_index(min_lat as f64),
+            lat_of_index(max_lat as f64 + 1.0).min(90.0),
+            lon_of_index(min_lon as f64),
+            lon_of_index(max_lon as f64 + 1.0).min(180.0),
+        ))
+    }
+}
+
+impl Codec for MortonCodec {
+    fn encode_payload(&self, payload: u64) -> String {
+        let (lat_index, lon_index) = unpack_indices(payload & PAYLOAD_MASK);
+        format_packed(morton_key(lat_index, lon_index), false)
+    }
+
+    fn decode_payload(&self, encoded: &str) -> Result<u64> {
+        let (lat_index, lon_index) = morton_indices(parse_packed(encoded)?);
+        Ok(pack_indices(lat_index, lon_index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uniform_precision_compressor::lat_of_index;
+    use crate::{encode, format_for_humans, MAX_LAT_INDEX, MAX_LON_INDEX};
+
+    #[test]
+    fn test_bit_spreading_roundtrip() {
+        for value in [0, 1, 0x2A_AAAA, 0x55_5555, 0x7F_FFFF, 0x12_3456] {
+            assert_eq!(compact_bits(spread_bits(value)), value);
+        }
+        assert_eq!(morton_key(MAX_LAT_INDEX, MAX_LON_INDEX), PAYLOAD_MASK);
+        assert_eq!(morton_indices(morton_key(1234, 56789)), (1234, 56789));
+    }
+
+    #[test]
+    fn test_byte_order_matches_code_order() {
+        let mut codes: Vec<Grid9Code> = (0..200)
+            .map(|i| Grid9Code::encode((i as f64 * 37.1) % 180.0 - 90.0, (i as f64 * 71.3) % 360.0 - 180.0).unwrap())
+            .collect();
+        let mut by_bytes = codes.clone();
+        by_bytes.sort_by_key(Grid9Code::to_bytes);
+        codes.sort();
+        assert_eq!(by_bytes, codes);
+        for code in &codes {
+            assert_eq!(Grid9Code::from_bytes(code.to_bytes()).unwrap(), *code);
+        }
+
+        // Payload bytes order codes like their strings instead
+        let mut payloads: Vec<[u8; 6]> = codes.iter().map(Grid9Code::to_payload_bytes).collect();
+        payloads.sort();
+        let mut strings: Vec<String> = codes.iter().map(Grid9Code::to_string).collect();
+        strings.sort();
+        assert_eq!(payloads.iter().map(|b| Grid9Code::from_payload_bytes(*b).unwrap().to_string()).collect::<Vec<_>>(), strings);
+
+        let corner = Grid9Code::from_indices(MAX_LAT_INDEX, MAX_LON_INDEX).unwrap();
+        assert_eq!(corner.to_bytes(), [0x1F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(corner.to_payload_bytes(), corner.to_bytes());
+        let nyc: Grid9Code = "Q7KH2BBYF".parse().unwrap();
+        assert_eq!(nyc.to_payload_bytes(), nyc.as_u64().to_be_bytes()[2..]);
+        assert_ne!(nyc.to_payload_bytes(), nyc.to_bytes());
+        assert!(matches!(Grid9Code::from_bytes([0x20, 0, 0, 0, 0, 0]), Err(Grid9Error::InvalidInput(_))));
+        assert!(matches!(Grid9Code::from_payload_bytes([0x20, 0, 0, 0, 0, 0]), Err(Grid9Error::InvalidIndex(_))));
+    }
+
+    #[test]
+    fn test_morton_codec() {
+        let places = [
+            (40.7580, -73.9855),
+            (40.7536, -73.9832),
+            (-33.8688, 151.2093),
+            (51.5074, -0.1278),
+            (90.0, 180.0),
+            (-90.0, -180.0),
+        ];
+        let mut morton: Vec<String> = Vec::new();
+        for &(lat, lon) in &places {
+            let code = MortonCodec.encode(lat, lon).unwrap();
+            let standard = Grid9Code::encode(lat, lon).unwrap();
+            assert_eq!(MortonCodec.decode(&code).unwrap(), standard.coordinates());
+            assert_eq!(code, format_packed(standard.sort_key(), false));
+            assert_eq!(MortonCodec.decode_payload(&format_for_humans(&code)).unwrap(), standard.as_u64());
+            morton.push(code);
+        }
+
+        // String order is curve order
+        let mut by_string = morton.clone();
+        by_string.sort();
+        let mut by_code: Vec<Grid9Code> = places.iter().map(|&(lat, lon)| Grid9Code::encode(lat, lon).unwrap()).collect();
+        by_code.sort();
+        assert_eq!(by_string, by_code.iter().map(|code| MortonCodec.encode_payload(code.as_u64())).collect::<Vec<_>>());
+
+        // Neighbours share a prefix whose cell holds both
+        let shared = morton[0].chars().zip(morton[1].chars()).take_while(|(a, b)| a == b).count();
+        assert!(shared >= 5);
+        let cell = MortonCodec::prefix_bounds(&morton[0][..shared]).unwrap();
+        assert!(cell.contains(places[0].0, places[0].1) && cell.contains(places[1].0, places[1].1));
+        assert!(cell.max_lat - cell.min_lat < 0.05 && cell.max_lon - cell.min_lon < 0.05);
+
+        let full = Grid9Code::encode(places[0].0, places[0].1).unwrap();
+        assert_eq!(MortonCodec::prefix_bounds(&morton[0]).unwrap(), full.bounds());
+        // The first character fixes two latitude and three longitude bits
+        let (lat_edge, lon_edge) = ((MAX_LAT_INDEX + 1) as f64 / 4.0, (MAX_LON_INDEX + 1) as f64 / 8.0);
+        let first_slice = BoundingBox::new(-90.0, lat_of_index(lat_edge), -180.0, lon_of_index(lon_edge));
+        assert_eq!(MortonCodec::prefix_bounds("0").unwrap(), first_slice);
+        assert_eq!(MortonCodec::prefix_bounds("Q7KH2BBYF0"), Err(Grid9Error::InvalidLength(10)));
+        assert_eq!(MortonCodec::prefix_bounds(""), Err(Grid9Error::EmptyInput));
+    }
+
+    #[test]
+    fn test_ord_matches_sort_key() {
+        let codes: Vec<Grid9Code> = [(40.7, -74.0), (40.7001, -74.0001), (-33.9, 151.2), (51.5, -0.1)]
+            .iter()
+            .map(|&(lat, lon)| Grid9Code::encode(lat, lon).unwrap())
+            .collect();
+
+        let mut sorted = codes.clone();
+        sorted.sort();
+        for pair in sorted.windows(2) {
+            assert!(pair[0].sort_key() <= pair[1].sort_key());
+        }
+        for code in &codes {
+            assert_eq!(sort_key(&code.to_string()).unwrap(), code.sort_key());
+            assert_eq!(Grid9Code::from_sort_key(code.sort_key()).unwrap(), *code);
+        }
+        assert!(Grid9Code::from_sort_key(1 << 45).is_err());
+    }
+
+    #[test]
+    fn test_ranges_cover_every_cell_in_bbox() {
+        let bbox = BoundingBox::new(40.70, 40.71, -74.02, -74.00);
+        let (lat_range, lon_range) = bbox_index_ranges(&bbox).unwrap();
+
+        for max_ranges in [1, 4, 32] {
+            let ranges = ranges_for_bbox(&bbox, max_ranges).unwrap();
+            assert!(!ranges.is_empty() && ranges.len() <= max_ranges);
+            for pair in ranges.windows(2) {
+                assert!(pair[0].end() < pair[1].start());
+            }
+
+            for lat in lat_range.clone().step_by(37).chain([*lat_range.end()]) {
+                for lon in lon_range.clone().step_by(41).chain([*lon_range.end()]) {
+                    let key = morton_key(lat, lon);
+                    assert!(ranges.iter().any(|range| range.contains(&key)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_more_ranges_scan_fewer_keys() {
+        let bbox = BoundingBox::new(-10.0, 25.0, 10.0, 50.0);
+        let scanned = |ranges: &[RangeInclusive<u64>]| ranges.iter().map(|r| r.end() - r.start() + 1).sum::<u64>();
+
+        let coarse = ranges_for_bbox(&bbox, 1).unwrap();
+        let fine = ranges_for_bbox(&bbox, 64).unwrap();
+        assert!(scanned(&fine) < scanned(&coarse));
+
+        // The exact cell count is a lower bound
+        let (lat_range, lon_range) = bbox_index_ranges(&bbox).unwrap();
+        let cells = (lat_range.count() as u64) * (lon_range.count() as u64);
+        assert!(scanned(&fine) >= cells);
+    }
+
+    #[test]
+    fn test_whole_world_and_invalid_input() {
+        let world = BoundingBox::new(-90.0, 90.0, -180.0, 180.0);
+        assert_eq!(ranges_for_bbox(&world, 4).unwrap(), vec![0..=PAYLOAD_MASK]);
+
+        assert!(ranges_for_bbox(&world, 0).is_err());
+        assert!(ranges_for_bbox(&BoundingBox::new(10.0, 0.0, 0.0, 1.0), 4).is_err());
+        assert!(ranges_for_bbox(&BoundingBox::new(0.0, 1.0, 0.0, 181.0), 4).is_err());
+    }
+
+    #[test]
+    fn test_sorting_groups_nearby_points() {
+        // A 4x4 block of cells occupies 16 consecutive keys when aligned
+        let (lat0, lon0) = (1024u32, 2048u32);
+        let mut keys: Vec<u64> = (0..4)
+            .flat_map(|dy| (0..4).map(move |dx| morton_key(lat0 + dy, lon0 + dx)))
+            .collect();
+        keys.sort_unstable();
+        assert_eq!(keys[15] - keys[0], 15);
+
+        let near = sort_key(&encode(40.7128, -74.0060, false).unwrap()).unwrap();
+        let nearer = sort_key(&encode(40.7129, -74.0061, false).unwrap()).unwrap();
+        let far = sort_key(&encode(-33.8688, 151.2093, false).unwrap()).unwrap();
+        assert!(near.abs_diff(nearer) < near.abs_diff(far));
+    }
+
+    #[test]
+    fn test_bbox_contains_code_fast() {
+        let bbox = BoundingBox::new(-10.0, 10.0, 100.0, 120.0);
+        let indices = BboxIndices::from_bbox(&bbox).unwrap();
+
+        for lat in (-20..=20).map(|i| i as f64 * 0.7) {
+            for lon in (0..=40).map(|i| 90.0 + i as f64 * 0.9) {
+                let code = Grid9Code::encode(lat, lon).unwrap();
+                let inside = (bbox.min_lat..=bbox.max_lat).contains(&lat) && (bbox.min_lon..=bbox.max_lon).contains(&lon);
+                assert_eq!(bbox_contains_code_fast(&indices, code), inside, "{} {}", lat, lon);
+            }
+        }
+
+        // Whole edge cells count, even where they extend past the box
+        let north_edge = lat_of_index(indices.max_lat_index as f64 + 0.999);
+        assert!(north_edge > bbox.max_lat);
+        assert!(bbox_contains_code_fast(&indices, Grid9Code::encode(north_edge, 110.0).unwrap()));
+        assert!(!bbox_contains_code_fast(&indices, Grid9Code::encode(lat_of_index(indices.max_lat_index as f64 + 1.0), 110.0).unwrap()));
+        assert!(BboxIndices::from_bbox(&BoundingBox::new(0.0, 1.0, 10.0, -10.0)).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_bbox_indices_postcard_size() {
+        let indices = BboxIndices::from_bbox(&BoundingBox::new(-89.9, 89.9, -179.9, 179.9)).unwrap();
+        let bytes = postcard::to_allocvec(&indices).unwrap();
+        assert!(bytes.len() <= 16, "{} bytes", bytes.len());
+        assert_eq!(postcard::from_bytes::<BboxIndices>(&bytes).unwrap(), indices);
+    }
+}
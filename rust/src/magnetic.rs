@@ -0,0 +1,325 @@
+//! Magnetic declination from the World Magnetic Model (feature `magnetic`)
+//!
+//! The embedded coefficients are WMM2025 (`data/WMM2025.COF`), valid from
+//! 2025.0 to 2030.0 with a declination error of about 0.5° away from the
+//! magnetic poles. Dates outside the model's five-year window are rejected
+//! rather than extrapolated; load the successor model's `.COF` file with
+//! [`MagneticModel::from_cof`] once it is published.
+
+use std::sync::OnceLock;
+
+use crate::uniform_precision_compressor::initial_bearing;
+use crate::{decode, Grid9Code, Grid9Error, Result, Timestamp};
+
+const EMBEDDED_MODEL: &str = include_str!("../data/WMM2025.COF");
+
+/// WGS84 semi-major axis in kilometers
+const WGS84_A_KM: f64 = 6378.137;
+/// WGS84 flattening
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+/// Geomagnetic reference radius in kilometers
+const REFERENCE_RADIUS_KM: f64 = 6371.2;
+/// Years a model is valid for after its epoch
+const VALIDITY_YEARS: f64 = 5.0;
+
+const SECONDS_PER_DAY: i128 = 86_400;
+const NANOS_PER_DAY: i128 = SECONDS_PER_DAY * 1_000_000_000;
+
+/// Spherical harmonic main field model in the NOAA `.COF` format
+#[derive(Debug, Clone)]
+pub struct MagneticModel {
+    epoch: f64,
+    degree: usize,
+    /// Schmidt semi-normalized coefficients, indexed `[n][m]`
+    g: Vec<Vec<f64>>,
+    h: Vec<Vec<f64>>,
+    g_rate: Vec<Vec<f64>>,
+    h_rate: Vec<Vec<f64>>,
+}
+
+impl MagneticModel {
+    /// The embedded WMM2025 model, parsed on first use.
+    pub fn wmm() -> &'static MagneticModel {
+        static MODEL: OnceLock<MagneticModel> = OnceLock::new();
+        MODEL.get_or_init(|| MagneticModel::from_cof(EMBEDDED_MODEL).expect("embedded magnetic model is valid"))
+    }
+
+    /// Parses a model from the text of a `.COF` coefficient file: an
+    /// `epoch name date` header, then `n m g h g_rate h_rate` lines up to a
+    /// line of `9`s.
+    pub fn from_cof(text: &str) -> Result<Self> {
+        let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let invalid = |number: usize, message: &str| Grid9Error::InvalidInput(format!("line {}: {}", number + 1, message).into());
+
+        let (number, header) = lines.next().ok_or(Grid9Error::EmptyInput)?;
+        let epoch: f64 = header
+            .split_whitespace()
+            .next()
+            .and_then(|epoch| epoch.parse().ok())
+            .ok_or_else(|| invalid(number, "expected the model epoch"))?;
+
+        let mut terms = Vec::new();
+        for (number, line) in lines {
+            if line.trim_start().starts_with("9999") {
+                break;
+            }
+            let fields = line
+                .split_whitespace()
+                .map(str::parse::<f64>)
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(|_| invalid(number, "invalid coefficient"))?;
+            let &[n, m, g, h, g_rate, h_rate] = &fields[..] else {
+                return Err(invalid(number, "expected n, m, g, h and their yearly rates"));
+            };
+            if n < 1.0 || m < 0.0 || m > n || n.fract() != 0.0 || m.fract() != 0.0 {
+                return Err(invalid(number, "invalid degree or order"));
+            }
+            terms.push((n as usize, m as usize, [g, h, g_rate, h_rate]));
+        }
+
+        let degree = terms.iter().map(|&(n, _, _)| n).max().ok_or(Grid9Error::EmptyInput)?;
+        let zeros = vec![vec![0.0; degree + 1]; degree + 1];
+        let mut model =
+            Self { epoch, degree, g: zeros.clone(), h: zeros.clone(), g_rate: zeros.clone(), h_rate: zeros };
+        for (n, m, [g, h, g_rate, h_rate]) in terms {
+            model.g[n][m] = g;
+            model.h[n][m] = h;
+            model.g_rate[n][m] = g_rate;
+            model.h_rate[n][m] = h_rate;
+        }
+        Ok(model)
+    }
+
+    /// Decimal year the coefficients refer to, e.g. `2025.0`
+    pub fn epoch(&self) -> f64 {
+        self.epoch
+    }
+
+    /// Magnetic declination in degrees (positive east of true north) at sea
+    /// level.
+    ///
+    /// # Arguments
+    /// * `latitude` - Geodetic latitude in degrees
+    /// * `longitude` - Longitude in degrees
+    /// * `decimal_year` - Date as a decimal year, e.g. `2026.5`
+    pub fn declination(&self, latitude: f64, longitude: f64, decimal_year: f64) -> Result<f64> {
+        if !(self.epoch..=self.epoch + VALIDITY_YEARS).contains(&decimal_year) {
+            return Err(Grid9Error::InvalidInput(format!(
+                "date {:.2} outside the magnetic model's validity ({} to {})",
+                decimal_year,
+                self.epoch,
+                self.epoch + VALIDITY_YEARS
+            ).into()));
+        }
+        let (north, east, _) = self.field(latitude, longitude, decimal_year - self.epoch);
+        Ok(east.atan2(north).to_degrees())
+    }
+
+    /// North, east and down field components in nT at sea level, `years`
+    /// after the epoch.
+    fn field(&self, latitude: f64, longitude: f64, years: f64) -> (f64, f64, f64) {
+        // Geodetic to geocentric spherical coordinates
+        let phi = latitude.to_radians();
+        let e2 = WGS84_F * (2.0 - WGS84_F);
+        let prime_vertical = WGS84_A_KM / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+        let p = prime_vertical * phi.cos();
+        let z = prime_vertical * (1.0 - e2) * phi.sin();
+        let r = p.hypot(z);
+        let phi_c = (z / r).asin();
+        let lambda = longitude.to_radians();
+
+        // Schmidt semi-normalized associated Legendre functions of sin(phi_c)
+        // and their derivatives with respect to colatitude
+        let (sin_theta, cos_theta) = (phi_c.cos(), phi_c.sin());
+        let size = self.degree + 1;
+        let mut p_nm = vec![vec![0.0; size]; size];
+        let mut dp_nm = vec![vec![0.0; size]; size];
+        p_nm[0][0] = 1.0;
+        for n in 1..size {
+            for m in 0..=n {
+                if n == m {
+                    let scale = if n == 1 { 1.0 } else { ((2 * n - 1) as f64 / (2 * n) as f64).sqrt() };
+                    p_nm[n][n] = scale * sin_theta * p_nm[n - 1][n - 1];
+                    dp_nm[n][n] = scale * (sin_theta * dp_nm[n - 1][n - 1] + cos_theta * p_nm[n - 1][n - 1]);
+                } else {
+                    let root = ((n * n - m * m) as f64).sqrt();
+                    let a = (2 * n - 1) as f64 / root;
+                    let b = if n >= m + 2 { (((n - 1) * (n - 1) - m * m) as f64).sqrt() / root } else { 0.0 };
+                    let (p2, dp2) = if n >= m + 2 { (p_nm[n - 2][m], dp_nm[n - 2][m]) } else { (0.0, 0.0) };
+                    p_nm[n][m] = a * cos_theta * p_nm[n - 1][m] - b * p2;
+                    dp_nm[n][m] = a * (cos_theta * dp_nm[n - 1][m] - sin_theta * p_nm[n - 1][m]) - b * dp2;
+                }
+            }
+        }
+
+        let (mut b_r, mut b_theta, mut b_lambda) = (0.0, 0.0, 0.0);
+        for n in 1..size {
+            let radial = (REFERENCE_RADIUS_KM / r).powi(n as i32 + 2);
+            for m in 0..=n {
+                let g = self.g[n][m] + years * self.g_rate[n][m];
+                let h = self.h[n][m] + years * self.h_rate[n][m];
+                let (sin_m, cos_m) = (m as f64 * lambda).sin_cos();
+                let cos_term = g * cos_m + h * sin_m;
+
+                b_r += (n + 1) as f64 * radial * cos_term * p_nm[n][m];
+                b_theta -= radial * cos_term * dp_nm[n][m];
+                b_lambda += radial * m as f64 * (g * sin_m - h * cos_m) * p_nm[n][m];
+            }
+        }
+        // Longitude component is undefined at the geographic poles
+        b_lambda /= sin_theta.max(1e-10);
+
+        // Rotate from geocentric back to geodetic north/down
+        let psi = phi_c - phi;
+        let (north_c, down_c) = (-b_theta, -b_r);
+        let north = north_c * psi.cos() - down_c * psi.sin();
+        let down = north_c * psi.sin() + down_c * psi.cos();
+        (north, b_lambda, down)
+    }
+}
+
+/// Converts a timestamp to a decimal year (`2026.5` is mid-2026).
+fn decimal_year<T: Timestamp>(time: &T) -> f64 {
+    let nanos = time.unix_nanos();
+    let days = nanos.div_euclid(NANOS_PER_DAY) as i64;
+    let year = civil_year(days);
+    let start = days_from_civil_year(year);
+    let length = days_from_civil_year(year + 1) - start;
+
+    let elapsed_nanos = nanos - start as i128 * NANOS_PER_DAY;
+    year as f64 + elapsed_nanos as f64 / (length as i128 * NANOS_PER_DAY) as f64
+}
+
+/// Days from 1970-01-01 to January 1st of `year` (proleptic Gregorian).
+fn days_from_civil_year(year: i64) -> i64 {
+    let y = year - 1;
+    365 * (y - 1969) + (y.div_euclid(4) - 492) - (y.div_euclid(100) - 19) + (y.div_euclid(400) - 4)
+}
+
+/// Gregorian year containing the given day since 1970-01-01.
+fn civil_year(days: i64) -> i64 {
+    let mut year = 1970 + days.div_euclid(366);
+    while days_from_civil_year(year + 1) <= days {
+        year += 1;
+    }
+    year
+}
+
+/// Returns the magnetic declination at a Grid9 cell, using the embedded
+/// World Magnetic Model.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code (any accepted form)
+/// * `date` - Date of the observation
+///
+/// # Returns
+/// Declination in degrees, positive when magnetic north is east of true north;
+/// an error for dates outside the model's validity (2025 to 2030)
+///
+/// # Example
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use grid9::{declination, encode};
+///
+/// // 2026-01-01
+/// let date = UNIX_EPOCH + Duration::from_secs(1_767_225_600);
+/// let boulder = encode(40.015, -105.2705, false)?;
+/// let degrees = declination(&boulder, &date)?;
+/// assert!((degrees - 7.5).abs() < 1.0);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn declination<T: Timestamp>(encoded: &str, date: &T) -> Result<f64> {
+    let code: Grid9Code = encoded.parse()?;
+    let (latitude, longitude) = code.coordinates();
+    MagneticModel::wmm().declination(latitude, longitude, decimal_year(date))
+}
+
+/// Calculates the initial bearing from one Grid9 code to another relative
+/// to magnetic north, as read on a compass at the starting point.
+///
+/// # Arguments
+/// * `encoded1` - Starting Grid9 code
+/// * `encoded2` - Destination Grid9 code
+/// * `date` - Date of travel
+///
+/// # Returns
+/// Magnetic bearing in degrees clockwise from magnetic north, in `[0, 360)`
+pub fn calculate_magnetic_bearing<T: Timestamp>(encoded1: &str, encoded2: &str, date: &T) -> Result<f64> {
+    let (lat1, lon1) = decode(encoded1)?;
+    let (lat2, lon2) = decode(encoded2)?;
+    let declination = MagneticModel::wmm().declination(lat1, lon1, decimal_year(date))?;
+
+    Ok((initial_bearing(lat1, lon1, lat2, lon2) - declination).rem_euclid(360.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    /// 2026-01-01T00:00:00Z
+    const JAN_2026: u64 = 1_767_225_600;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn test_declinations() {
+        let places = [
+            ("Boulder", 40.015, -105.2705, 7.5),
+            ("London", 51.5074, -0.1278, 1.3),
+            ("New York", 40.7128, -74.0060, -12.7),
+            ("Sydney", -33.8688, 151.2093, 12.9),
+            ("Tokyo", 35.6762, 139.6503, -7.7),
+            ("Cape Town", -33.9249, 18.4241, -26.4),
+            ("Reykjavik", 64.1466, -21.9426, -11.5),
+            ("Rio de Janeiro", -22.9068, -43.1729, -23.5),
+        ];
+
+        for (name, lat, lon, expected) in places {
+            let degrees = declination(&encode(lat, lon, false).unwrap(), &at(JAN_2026)).unwrap();
+            assert!((degrees - expected).abs() < 1.0, "{}: {:.2}", name, degrees);
+        }
+    }
+
+    #[test]
+    fn test_validity_window() {
+        let code = encode(40.7128, -74.0060, false).unwrap();
+        assert!(declination(&code, &at(1_700_000_000)).is_err());
+        assert!(declination(&code, &at(1_900_000_000)).is_err());
+        assert!(declination("INVALID", &at(JAN_2026)).is_err());
+    }
+
+    #[test]
+    fn test_magnetic_bearing() {
+        let nyc = encode(40.7128, -74.0060, false).unwrap();
+        let north = encode(41.7128, -74.0060, false).unwrap();
+        let bearing = calculate_magnetic_bearing(&nyc, &north, &at(JAN_2026)).unwrap();
+        let declination = declination(&nyc, &at(JAN_2026)).unwrap();
+        assert!((bearing - (-declination).rem_euclid(360.0)).abs() < 0.05);
+        assert!(bearing > 10.0 && bearing < 15.0);
+    }
+
+    #[test]
+    fn test_decimal_year() {
+        assert_eq!(decimal_year(&at(JAN_2026)), 2026.0);
+        assert_eq!(decimal_year(&UNIX_EPOCH), 1970.0);
+        // 2024-07-02T00:00:00Z, day 183 of a leap year
+        assert!((decimal_year(&at(1_719_878_400)) - (2024.0 + 183.0 / 366.0)).abs() < 1e-9);
+        assert_eq!(days_from_civil_year(2000), 10_957);
+    }
+
+    #[test]
+    fn test_parse_cof() {
+        let model = MagneticModel::from_cof("2025.0 TEST\n1 0 -30000 0 0 0\n9999\n").unwrap();
+        assert_eq!(model.epoch(), 2025.0);
+        // A pure axial dipole points at true north everywhere
+        assert!(model.declination(45.0, 10.0, 2026.0).unwrap().abs() < 1e-9);
+
+        assert!(MagneticModel::from_cof("").is_err());
+        assert!(MagneticModel::from_cof("2025.0 TEST\n1 2 0 0 0 0\n").is_err());
+        assert!(MagneticModel::from_cof("2025.0 TEST\n1 0 x 0 0 0\n").is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! Backend-agnostic metrics hooks
+
+use std::time::Duration;
+
+/// Batch operation kinds reported to [`Grid9Metrics`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BatchOperation {
+    /// Coordinates encoded to Grid9 codes
+    Encode,
+    /// Grid9 codes decoded to coordinates
+    Decode,
+    /// Grid9 codes validated
+    Validate,
+    /// Points matched with their nearest facility by [`crate::assign_nearest_with_metrics`]
+    Assign,
+    /// Entries returned by one spatial index query
+    Query,
+    /// Lines of one chunk processed by [`crate::process_chunks`],
+    /// [`crate::process_slice_chunks`] or a bulk file conversion
+    Chunk,
+}
+
+/// Receiver for operational metrics emitted by the batch, cache and index subsystems.
+///
+/// Every method has an empty default implementation, so implementors only
+/// override what they export. Implementations are called inline on the hot
+/// path and should be cheap (e.g. atomic counter updates or forwarding to a
+/// Prometheus/StatsD client).
+///
+/// # Example
+/// ```rust
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::time::Duration;
+/// use grid9::{batch_encode_with_metrics, BatchOperation, Coordinate, Grid9Metrics};
+///
+/// #[derive(Default)]
+/// struct Counter(AtomicUsize);
+///
+/// impl Grid9Metrics for Counter {
+///     fn record_batch(&self, _op: BatchOperation, items: usize, _errors: usize, _elapsed: Duration) {
+///         self.0.fetch_add(items, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let counter = Counter::default();
+/// batch_encode_with_metrics(&[Coordinate::new(40.7128, -74.0060)], false, &counter)?;
+/// assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub trait Grid9Metrics: Send + Sync {
+    /// Called once per batch with the number of items processed, how many of
+    /// them failed and the wall-clock time taken.
+    fn record_batch(&self, operation: BatchOperation, items: usize, errors: usize, elapsed: Duration) {
+        let _ = (operation, items, errors, elapsed);
+    }
+
+    /// Called for every cache lookup with whether it was served from the cache.
+    fn record_cache_lookup(&self, hit: bool) {
+        let _ = hit;
+    }
+
+    /// Called when a ring search finishes, with the number of rings it
+    /// walked around the center cell.
+    fn record_ring_expansion(&self, depth: u32) {
+        let _ = depth;
+    }
+}
+
+/// A [`Grid9Metrics`] implementation that discards everything
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl Grid9Metrics for NoopMetrics {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noop_metrics_accepts_everything() {
+        let metrics: &dyn Grid9Metrics = &NoopMetrics;
+        metrics.record_batch(BatchOperation::Encode, 10, 0, Duration::from_millis(1));
+        metrics.record_cache_lookup(true);
+        metrics.record_ring_expansion(3);
+    }
+}
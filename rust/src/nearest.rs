@@ -0,0 +1,231 @@
+//! Nearest-facility assignment
+//!
+//! [`assign_nearest`] pairs each point with its closest facility within a
+//! search radius, such as each order with its depot. Facilities are
+//! bucketed into latitude bands one radius tall and sorted by longitude
+//! within each band, so a point only measures the distance to facilities in
+//! the three bands around it and the longitude window the radius spans at
+//! its latitude, instead of to every facility.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::uniform_precision_compressor::{haversine_distance, validate_coordinates, EARTH_RADIUS_M};
+use crate::{BatchOperation, Coordinate, Grid9Error, Grid9Metrics, NoopMetrics, Result};
+
+/// Assigns each point to the nearest facility within `max_distance_m`.
+///
+/// Distances are great-circle distances on a spherical Earth, as in
+/// [`crate::calculate_distance`]. Ties go to the facility listed first.
+///
+/// # Arguments
+/// * `points` - Points to assign, e.g. orders
+/// * `facilities` - Candidate facilities, e.g. depots
+/// * `max_distance_m` - Search radius in meters; `f64::INFINITY` assigns
+///   every point
+///
+/// # Returns
+/// For each point, the index of its nearest facility and the distance in
+/// meters, or `None` if no facility is within the radius. Fails with
+/// `InvalidInput` for a radius that is not positive and with
+/// `InvalidLatitude`/`InvalidLongitude` for coordinates out of range.
+///
+/// # Example
+/// ```rust
+/// use grid9::{assign_nearest, Coordinate};
+///
+/// let depots = [Coordinate::new(40.7128, -74.0060), Coordinate::new(40.6413, -73.7781)];
+/// let orders = [Coordinate::new(40.7306, -73.9866), Coordinate::new(40.6500, -73.7900), Coordinate::new(42.6526, -73.7562)];
+///
+/// let assigned = assign_nearest(&orders, &depots, 20_000.0)?;
+/// assert_eq!(assigned[0].map(|(depot, _)| depot), Some(0));
+/// assert_eq!(assigned[1].map(|(depot, _)| depot), Some(1));
+/// assert_eq!(assigned[2], None); // Albany is beyond 20km
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn assign_nearest(points: &[Coordinate], facilities: &[Coordinate], max_distance_m: f64) -> Result<Vec<Option<(usize, f64)>>> {
+    assign_nearest_with_metrics(points, facilities, max_distance_m, &NoopMetrics)
+}
+
+/// Assigns points like [`assign_nearest`], reporting the number of points,
+/// failures and elapsed time to `metrics`.
+///
+/// Inputs are validated before any point is assigned, so a failed call
+/// reports every point as failed.
+///
+/// # Arguments
+/// * `points` - Points to assign, e.g. orders
+/// * `facilities` - Candidate facilities, e.g. depots
+/// * `max_distance_m` - Search radius in meters
+/// * `metrics` - Receiver for the batch metrics
+///
+/// # Returns
+/// For each point, the index of its nearest facility and the distance in
+/// meters, or `None` if no facility is within the radius
+pub fn assign_nearest_with_metrics(
+    points: &[Coordinate],
+    facilities: &[Coordinate],
+    max_distance_m: f64,
+    metrics: &dyn Grid9Metrics,
+) -> Result<Vec<Option<(usize, f64)>>> {
+    let start = Instant::now();
+    let result = assign(points, facilities, max_distance_m);
+    let errors = if result.is_err() { points.len() } else { 0 };
+    metrics.record_batch(BatchOperation::Assign, points.len(), errors, start.elapsed());
+    result
+}
+
+fn assign(points: &[Coordinate], facilities: &[Coordinate], max_distance_m: f64) -> Result<Vec<Option<(usize, f64)>>> {
+    if max_distance_m.is_nan() || max_distance_m <= 0.0 {
+        return Err(Grid9Error::InvalidInput(format!("invalid search radius {}", max_distance_m).into()));
+    }
+    for point in points.iter().chain(facilities) {
+        validate_coordinates(point.lat, point.lon)?;
+    }
+
+    // Angular radius; beyond half a circumference every facility is in range
+    let radius = (max_distance_m / EARTH_RADIUS_M).min(std::f64::consts::PI);
+    let band_height = radius.to_degrees();
+    let band = |lat: f64| ((lat + 90.0) / band_height).floor() as i64;
+
+    let mut bands: HashMap<i64, Vec<(f64, usize)>> = HashMap::new();
+    for (index, facility) in facilities.iter().enumerate() {
+        bands.entry(band(facility.lat)).or_default().push((facility.lon, index));
+    }
+    for members in bands.values_mut() {
+        members.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    }
+
+    Ok(points
+        .iter()
+        .map(|point| {
+            let half_width = lon_half_width(point.lat, radius);
+            let windows = lon_windows(point.lon, half_width);
+            let mut nearest: Option<(usize, f64)> = None;
+            for row in band(point.lat) - 1..=band(point.lat) + 1 {
+                let Some(members) = bands.get(&row) else { continue };
+                for &(min, max) in &windows {
+                    let start = members.partition_point(|&(lon, _)| lon < min);
+                    for &(_, index) in members[start..].iter().take_while(|&&(lon, _)| lon <= max) {
+                        let facility = &facilities[index];
+                        let distance = haversine_distance(point.lat, point.lon, facility.lat, facility.lon);
+                        let closer = match nearest {
+                            Some((best, best_distance)) => distance < best_distance || (distance == best_distance && index < best),
+                            None => true,
+                        };
+                        if distance <= max_distance_m && closer {
+                            nearest = Some((index, distance));
+                        }
+                    }
+                }
+            }
+            nearest
+        })
+        .collect())
+}
+
+/// Largest longitude difference, in degrees, of a point within angular
+/// distance `radius` of latitude `lat`; 180 if the radius reaches a pole.
+fn lon_half_width(lat: f64, radius: f64) -> f64 {
+    let lat = lat.to_radians();
+    if radius >= std::f64::consts::FRAC_PI_2 - lat.abs() {
+        return 180.0;
+    }
+    // Slightly widened so rounding never drops a facility on the edge
+    ((radius.sin() / lat.cos()).min(1.0).asin().to_degrees() * (1.0 + 1e-9) + 1e-9).min(180.0)
+}
+
+/// Longitude ranges within `half_width` of `lon`, split at the antimeridian
+fn lon_windows(lon: f64, half_width: f64) -> Vec<(f64, f64)> {
+    if half_width >= 180.0 {
+        return vec![(-180.0, 180.0)];
+    }
+    let (min, max) = (lon - half_width, lon + half_width);
+    let mut windows = vec![(min.max(-180.0), max.min(180.0))];
+    if min < -180.0 {
+        windows.push((min + 360.0, 180.0));
+    }
+    if max > 180.0 {
+        windows.push((-180.0, max - 360.0));
+    }
+    windows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference answer by comparing every point with every facility
+    fn brute_force(points: &[Coordinate], facilities: &[Coordinate], max_distance_m: f64) -> Vec<Option<(usize, f64)>> {
+        points
+            .iter()
+            .map(|p| {
+                let mut nearest: Option<(usize, f64)> = None;
+                for (index, f) in facilities.iter().enumerate() {
+                    let distance = haversine_distance(p.lat, p.lon, f.lat, f.lon);
+                    if distance <= max_distance_m && nearest.is_none_or(|(_, best)| distance < best) {
+                        nearest = Some((index, distance));
+                    }
+                }
+                nearest
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        // Deterministic scatter over the globe, including the poles and the antimeridian
+        let scatter = |n: usize, seed: f64| -> Vec<Coordinate> {
+            (0..n)
+                .map(|i| {
+                    let t = (i as f64 + seed) * 0.618_033_988_75;
+                    Coordinate::new((t * 7.3).sin() * 90.0, ((t * 13.7).fract() * 360.0 - 180.0).clamp(-180.0, 180.0))
+                })
+                .collect()
+        };
+        let mut facilities = scatter(300, 0.5);
+        facilities.extend([Coordinate::new(89.9, 0.0), Coordinate::new(-16.5, 179.99)]);
+        let mut points = scatter(500, 0.25);
+        points.extend([Coordinate::new(89.95, 120.0), Coordinate::new(-16.5, -179.99), Coordinate::new(90.0, 0.0)]);
+
+        for max_distance_m in [5_000.0, 250_000.0, 2_000_000.0, f64::INFINITY] {
+            assert_eq!(assign_nearest(&points, &facilities, max_distance_m).unwrap(), brute_force(&points, &facilities, max_distance_m));
+        }
+        let assigned = assign_nearest(&points, &facilities, 50_000.0).unwrap();
+        assert_eq!(assigned[501].unwrap().0, 301); // Across the antimeridian
+        assert!(assigned[500].unwrap().1 < 20_000.0); // Across the pole
+    }
+
+    #[test]
+    fn test_ties_and_invalid_input() {
+        let depot = Coordinate::new(10.0, 10.0);
+        let assigned = assign_nearest(&[Coordinate::new(10.0, 10.0)], &[depot.clone(), depot], 1.0).unwrap();
+        assert_eq!(assigned, [Some((0, 0.0))]);
+        assert_eq!(assign_nearest(&[Coordinate::new(0.0, 0.0)], &[], 1000.0).unwrap(), [None]);
+
+        assert!(matches!(assign_nearest(&[], &[], 0.0), Err(Grid9Error::InvalidInput(_))));
+        assert!(assign_nearest(&[], &[], f64::NAN).is_err());
+        assert_eq!(assign_nearest(&[], &[Coordinate::new(0.0, 181.0)], 1.0), Err(Grid9Error::InvalidLongitude(181.0)));
+    }
+
+    #[test]
+    fn test_reports_batches_to_metrics() {
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<(BatchOperation, usize, usize)>>);
+
+        impl Grid9Metrics for Recorder {
+            fn record_batch(&self, operation: BatchOperation, items: usize, errors: usize, _: std::time::Duration) {
+                self.0.lock().unwrap().push((operation, items, errors));
+            }
+        }
+
+        let recorder = Recorder::default();
+        let points = [Coordinate::new(10.0, 10.0), Coordinate::new(50.0, 50.0)];
+        let assigned = assign_nearest_with_metrics(&points, &[Coordinate::new(10.0, 10.0)], 1000.0, &recorder).unwrap();
+        assert_eq!(assigned, [Some((0, 0.0)), None]);
+        assert!(assign_nearest_with_metrics(&points, &[], -1.0, &recorder).is_err());
+        assert_eq!(*recorder.0.lock().unwrap(), [(BatchOperation::Assign, 2, 0), (BatchOperation::Assign, 2, 2)]);
+    }
+}
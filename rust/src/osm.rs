@@ -0,0 +1,595 @@
+//! OpenStreetMap PBF extracts by region (feature `osm`)
+//!
+//! Streams an `.osm.pbf` file and keeps the nodes inside a [`Grid9Region`]
+//! and the ways referencing them. Nodes are first checked against the
+//! region's bounding box, so most of a large extract is rejected without a
+//! set lookup. Raw and zlib-compressed blobs are supported; relations,
+//! metadata and the file header are skipped.
+
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, Read};
+
+use flate2::read::ZlibDecoder;
+
+use crate::{BoundingBox, Coordinate, Grid9Code, Grid9Region};
+
+/// Largest `BlobHeader` accepted (the PBF format limits it to 64 KiB)
+const MAX_HEADER_SIZE: usize = 64 * 1024;
+/// Largest blob accepted, compressed or not (the PBF format limit is 32 MiB)
+const MAX_BLOB_SIZE: usize = 32 * 1024 * 1024;
+
+/// A node or way kept by [`OsmRegionFilter`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OsmElement {
+    /// A node inside the region
+    Node {
+        id: i64,
+        coordinate: Coordinate,
+        tags: Vec<(String, String)>,
+    },
+    /// A way with at least one node inside the region
+    Way {
+        id: i64,
+        node_ids: Vec<i64>,
+        tags: Vec<(String, String)>,
+    },
+}
+
+/// Iterator over the nodes and ways of an OSM PBF file touching a region
+///
+/// Ways are matched against the nodes seen before them, so the file must
+/// list nodes before ways, as planet dumps and Geofabrik extracts do
+/// (`Sort.Type_then_ID`). Only the ids of nodes inside the region are kept
+/// in memory. Ways are returned with all their node ids, including nodes
+/// outside the region, which are not returned themselves.
+///
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+/// use std::io::BufReader;
+/// use grid9::{BoundingBox, Grid9Region, OsmElement, OsmRegionFilter};
+///
+/// let depot = Grid9Region::from_bbox(&BoundingBox::new(52.50, 52.54, 13.36, 13.42), 8)?;
+/// let file = BufReader::new(File::open("berlin-latest.osm.pbf")?);
+/// for element in OsmRegionFilter::new(file, &depot) {
+///     if let OsmElement::Way { id, tags, .. } = element? {
+///         println!("way {} {:?}", id, tags);
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct OsmRegionFilter<'a, R> {
+    reader: R,
+    region: &'a Grid9Region,
+    bounds: Option<BoundingBox>,
+    nodes_inside: HashSet<i64>,
+    pending: VecDeque<OsmElement>,
+    finished: bool,
+}
+
+impl<'a, R: Read> OsmRegionFilter<'a, R> {
+    /// Filters the PBF data read from `reader` by `region`
+    pub fn new(reader: R, region: &'a Grid9Region) -> Self {
+        Self {
+            reader,
+            region,
+            bounds: region.bounds(),
+            nodes_inside: HashSet::new(),
+            pending: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    /// Reads and filters the next blob, returning false at the end of input.
+    fn read_blob(&mut self) -> io::Result<bool> {
+        let mut length = [0; 4];
+        if !read_exact_or_eof(&mut self.reader, &mut length)? {
+            return Ok(false);
+        }
+        let header = read_bytes(&mut self.reader, u32::from_be_bytes(length) as usize, MAX_HEADER_SIZE)?;
+
+        let (mut is_data, mut data_size) = (false, None);
+        for field in Message(&header) {
+            match field? {
+                (1, Field::Bytes(kind)) => is_data = kind == b"OSMData",
+                (3, Field::Varint(size)) => data_size = Some(size as usize),
+                _ => {}
+            }
+        }
+        let data_size = data_size.ok_or_else(|| invalid_data("blob header without data size"))?;
+        let blob = read_bytes(&mut self.reader, data_size, MAX_BLOB_SIZE)?;
+
+        // OSMHeader and unknown blob types carry no elements
+        if is_data {
+            self.filter_block(&blob_contents(&blob)?)?;
+        }
+        Ok(true)
+    }
+
+    fn filter_block(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut block = Block { strings: Vec::new(), granularity: 100, lat_offset: 0, lon_offset: 0 };
+        let mut groups = Vec::new();
+        for field in Message(data) {
+            match field? {
+                (1, Field::Bytes(table)) => {
+                    for entry in Message(table) {
+                        if let (1, Field::Bytes(string)) = entry? {
+                            block.strings.push(String::from_utf8_lossy(string).into_owned());
+                        }
+                    }
+                }
+                (2, Field::Bytes(group)) => groups.push(group),
+                (17, Field::Varint(granularity)) => block.granularity = granularity as i64,
+                (19, Field::Varint(offset)) => block.lat_offset = offset as i64,
+                (20, Field::Varint(offset)) => block.lon_offset = offset as i64,
+                _ => {}
+            }
+        }
+
+        for group in groups {
+            for field in Message(group) {
+                match field? {
+                    (1, Field::Bytes(node)) => self.filter_node(&block, node)?,
+                    (2, Field::Bytes(dense)) => self.filter_dense_nodes(&block, dense)?,
+                    (3, Field::Bytes(way)) => self.filter_way(&block, way)?,
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn filter_node(&mut self, block: &Block, node: &[u8]) -> io::Result<()> {
+        let (mut id, mut lat, mut lon) = (0, 0, 0);
+        let (mut keys, mut values) = (Vec::new(), Vec::new());
+        for field in Message(node) {
+            match field? {
+                (1, Field::Varint(value)) => id = zigzag(value),
+                (2, field) => keys.extend(packed(field)?),
+                (3, field) => values.extend(packed(field)?),
+                (8, Field::Varint(value)) => lat = zigzag(value),
+                (9, Field::Varint(value)) => lon = zigzag(value),
+                _ => {}
+            }
+        }
+
+        let coordinate = block.coordinate(lat, lon);
+        if self.is_inside(&coordinate) {
+            let tags = block.tags(keys.into_iter().zip(values))?;
+            self.keep_node(id, coordinate, tags);
+        }
+        Ok(())
+    }
+
+    fn filter_dense_nodes(&mut self, block: &Block, dense: &[u8]) -> io::Result<()> {
+        let (mut ids, mut lats, mut lons, mut keys_values) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        for field in Message(dense) {
+            match field? {
+                (1, field) => ids.extend(packed(field)?),
+                (8, field) => lats.extend(packed(field)?),
+                (9, field) => lons.extend(packed(field)?),
+                (10, field) => keys_values.extend(packed(field)?),
+                _ => {}
+            }
+        }
+        if lats.len() != ids.len() || lons.len() != ids.len() {
+            return Err(invalid_data("dense nodes with mismatched id and coordinate counts"));
+        }
+
+        // Coordinates and ids are delta coded; tags are key/value string
+        // indices with a 0 after each node's tags (absent if no node has tags)
+        let (mut id, mut lat, mut lon) = (0i64, 0i64, 0i64);
+        let mut tags = keys_values.into_iter();
+        for i in 0..ids.len() {
+            id = id.wrapping_add(zigzag(ids[i]));
+            lat = lat.wrapping_add(zigzag(lats[i]));
+            lon = lon.wrapping_add(zigzag(lons[i]));
+
+            let mut pairs = Vec::new();
+            while let Some(key) = tags.next().filter(|&key| key != 0) {
+                let value = tags.next().ok_or_else(|| invalid_data("dense node tag without value"))?;
+                pairs.push((key, value));
+            }
+
+            let coordinate = block.coordinate(lat, lon);
+            if self.is_inside(&coordinate) {
+                let tags = block.tags(pairs)?;
+                self.keep_node(id, coordinate, tags);
+            }
+        }
+        Ok(())
+    }
+
+    fn filter_way(&mut self, block: &Block, way: &[u8]) -> io::Result<()> {
+        let mut id = 0;
+        let (mut keys, mut values, mut refs) = (Vec::new(), Vec::new(), Vec::new());
+        for field in Message(way) {
+            match field? {
+                (1, Field::Varint(value)) => id = value as i64,
+                (2, field) => keys.extend(packed(field)?),
+                (3, field) => values.extend(packed(field)?),
+                (8, field) => refs.extend(packed(field)?),
+                _ => {}
+            }
+        }
+
+        let mut node_id = 0i64;
+        let node_ids: Vec<i64> = refs
+            .into_iter()
+            .map(|delta| {
+                node_id = node_id.wrapping_add(zigzag(delta));
+                node_id
+            })
+            .collect();
+        if node_ids.iter().any(|node_id| self.nodes_inside.contains(node_id)) {
+            let tags = block.tags(keys.into_iter().zip(values))?;
+            self.pending.push_back(OsmElement::Way { id, node_ids, tags });
+        }
+        Ok(())
+    }
+
+    fn is_inside(&self, coordinate: &Coordinate) -> bool {
+        let Some(bounds) = &self.bounds else {
+            return false;
+        };
+        let in_bounds = (bounds.min_lat..=bounds.max_lat).contains(&coordinate.lat)
+            && (bounds.min_lon..=bounds.max_lon).contains(&coordinate.lon);
+        in_bounds && Grid9Code::encode(coordinate.lat, coordinate.lon).is_ok_and(|code| self.region.contains_code(code))
+    }
+
+    fn keep_node(&mut self, id: i64, coordinate: Coordinate, tags: Vec<(String, String)>) {
+        self.nodes_inside.insert(id);
+        self.pending.push_back(OsmElement::Node { id, coordinate, tags });
+    }
+}
+
+impl<R: Read> Iterator for OsmRegionFilter<'_, R> {
+    type Item = io::Result<OsmElement>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(element) = self.pending.pop_front() {
+                return Some(Ok(element));
+            }
+            if self.finished {
+                return None;
+            }
+            match self.read_blob() {
+                Ok(true) => {}
+                Ok(false) => self.finished = true,
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+/// String table and coordinate scaling of a `PrimitiveBlock`
+struct Block {
+    strings: Vec<String>,
+    granularity: i64,
+    lat_offset: i64,
+    lon_offset: i64,
+}
+
+impl Block {
+    fn coordinate(&self, lat: i64, lon: i64) -> Coordinate {
+        Coordinate::new(
+            1e-9 * (self.lat_offset + self.granularity * lat) as f64,
+            1e-9 * (self.lon_offset + self.granularity * lon) as f64,
+        )
+    }
+
+    fn tags(&self, pairs: impl IntoIterator<Item = (u64, u64)>) -> io::Result<Vec<(String, String)>> {
+        let string = |index: u64| {
+            self.strings.get(index as usize).cloned().ok_or_else(|| invalid_data("string table index out of range"))
+        };
+        pairs.into_iter().map(|(key, value)| Ok((string(key)?, string(value)?))).collect()
+    }
+}
+
+/// Uncompressed contents of a `Blob`
+fn blob_contents(blob: &[u8]) -> io::Result<Vec<u8>> {
+    let mut raw_size = 0;
+    for field in Message(blob) {
+        match field? {
+            (1, Field::Bytes(raw)) => return Ok(raw.to_vec()),
+            (2, Field::Varint(size)) => raw_size = size as usize,
+            (3, Field::Bytes(compressed)) => {
+                if raw_size > MAX_BLOB_SIZE {
+                    return Err(invalid_data("blob larger than 32 MiB"));
+                }
+                let mut data = Vec::with_capacity(raw_size);
+                ZlibDecoder::new(compressed).take(MAX_BLOB_SIZE as u64 + 1).read_to_end(&mut data)?;
+                if data.len() > MAX_BLOB_SIZE {
+                    return Err(invalid_data("blob larger than 32 MiB"));
+                }
+                return Ok(data);
+            }
+            (4..=7, _) => return Err(invalid_data("unsupported blob compression (only raw and zlib are read)")),
+            _ => {}
+        }
+    }
+    Err(invalid_data("blob without data"))
+}
+
+/// A protobuf field value; fixed-width values are not used by the format
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Iterator over the (field number, value) pairs of a protobuf message
+struct Message<'a>(&'a [u8]);
+
+impl<'a> Iterator for Message<'a> {
+    type Item = io::Result<(u32, Field<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let field = (|| {
+            let key = varint(&mut self.0)?;
+            let value = match key & 7 {
+                0 => Field::Varint(varint(&mut self.0)?),
+                1 => {
+                    self.0 = self.0.get(8..).ok_or_else(|| invalid_data("truncated protobuf field"))?;
+                    Field::Fixed
+                }
+                2 => {
+                    let length = varint(&mut self.0)? as usize;
+                    if length > self.0.len() {
+                        return Err(invalid_data("truncated protobuf field"));
+                    }
+                    let (bytes, rest) = self.0.split_at(length);
+                    self.0 = rest;
+                    Field::Bytes(bytes)
+                }
+                5 => {
+                    self.0 = self.0.get(4..).ok_or_else(|| invalid_data("truncated protobuf field"))?;
+                    Field::Fixed
+                }
+                _ => return Err(invalid_data("unsupported protobuf wire type")),
+            };
+            Ok(((key >> 3) as u32, value))
+        })();
+        if field.is_err() {
+            self.0 = &[];
+        }
+        Some(field)
+    }
+}
+
+fn varint(bytes: &mut &[u8]) -> io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first().ok_or_else(|| invalid_data("truncated protobuf varint"))?;
+        *bytes = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid_data("protobuf varint longer than 64 bits"))
+}
+
+/// Values of a repeated integer field, packed or not
+fn packed(field: Field) -> io::Result<Vec<u64>> {
+    match field {
+        Field::Varint(value) => Ok(vec![value]),
+        Field::Bytes(mut bytes) => {
+            let mut values = Vec::new();
+            while !bytes.is_empty() {
+                values.push(varint(&mut bytes)?);
+            }
+            Ok(values)
+        }
+        Field::Fixed => Err(invalid_data("unexpected fixed-width protobuf field")),
+    }
+}
+
+fn zigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Fills `buf`, returning false if the input ended before its first byte.
+fn read_exact_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated PBF file")),
+            Ok(n) => filled += n,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(true)
+}
+
+fn read_bytes<R: Read>(reader: &mut R, length: usize, max: usize) -> io::Result<Vec<u8>> {
+    if length > max {
+        return Err(invalid_data(&format!("PBF block of {} bytes exceeds the {} byte limit", length, max)));
+    }
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn put_int(out: &mut Vec<u8>, field: u32, value: u64) {
+        put_varint(out, (field as u64) << 3);
+        put_varint(out, value);
+    }
+
+    fn put_bytes(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+        put_varint(out, ((field as u64) << 3) | 2);
+        put_varint(out, bytes.len() as u64);
+        out.extend_from_slice(bytes);
+    }
+
+    fn put_packed(out: &mut Vec<u8>, field: u32, values: &[u64]) {
+        let mut bytes = Vec::new();
+        for &value in values {
+            put_varint(&mut bytes, value);
+        }
+        put_bytes(out, field, &bytes);
+    }
+
+    fn zig(value: i64) -> u64 {
+        ((value << 1) ^ (value >> 63)) as u64
+    }
+
+    /// Zigzag-coded deltas of `values`
+    fn deltas(values: &[i64]) -> Vec<u64> {
+        let mut previous = 0;
+        values
+            .iter()
+            .map(|&value| {
+                let delta = value - previous;
+                previous = value;
+                zig(delta)
+            })
+            .collect()
+    }
+
+    fn blob(out: &mut Vec<u8>, kind: &str, data: &[u8], compress: bool) {
+        let mut blob = Vec::new();
+        if compress {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data).unwrap();
+            put_int(&mut blob, 2, data.len() as u64);
+            put_bytes(&mut blob, 3, &encoder.finish().unwrap());
+        } else {
+            put_bytes(&mut blob, 1, data);
+        }
+
+        let mut header = Vec::new();
+        put_bytes(&mut header, 1, kind.as_bytes());
+        put_int(&mut header, 3, blob.len() as u64);
+        out.extend_from_slice(&(header.len() as u32).to_be_bytes());
+        out.extend_from_slice(&header);
+        out.extend_from_slice(&blob);
+    }
+
+    /// A file with dense nodes 1 (inside), 2 (outside) and plain node 3
+    /// (inside), then ways 10 (nodes 2, 3), 11 (node 2 only) and a relation.
+    fn sample_file(compress: bool) -> Vec<u8> {
+        let mut strings = Vec::new();
+        for s in ["", "amenity", "fuel", "highway", "service"] {
+            put_bytes(&mut strings, 1, s.as_bytes());
+        }
+
+        // Granularity 100 with offsets: units of 1e-7 degrees
+        let mut dense = Vec::new();
+        put_packed(&mut dense, 1, &deltas(&[1, 2]));
+        put_packed(&mut dense, 8, &deltas(&[525_200_000, 100_000_000]));
+        put_packed(&mut dense, 9, &deltas(&[133_900_000, 100_000_000]));
+        put_packed(&mut dense, 10, &[1, 2, 0, 0]);
+
+        let mut node = Vec::new();
+        put_int(&mut node, 1, zig(3));
+        put_int(&mut node, 8, zig(525_210_000));
+        put_int(&mut node, 9, zig(133_910_000));
+
+        let mut nodes_group = Vec::new();
+        put_bytes(&mut nodes_group, 2, &dense);
+        put_bytes(&mut nodes_group, 1, &node);
+
+        let mut ways_group = Vec::new();
+        for (id, refs, tags) in [(10, vec![2, 3], vec![3u64, 4u64]), (11, vec![2], vec![])] {
+            let mut way = Vec::new();
+            put_int(&mut way, 1, id);
+            if !tags.is_empty() {
+                put_packed(&mut way, 2, &tags[..1]);
+                put_packed(&mut way, 3, &tags[1..]);
+            }
+            put_packed(&mut way, 8, &deltas(&refs));
+            put_bytes(&mut ways_group, 3, &way);
+        }
+        put_bytes(&mut ways_group, 4, &[8, 1]); // A relation, skipped
+
+        let mut block = Vec::new();
+        put_bytes(&mut block, 1, &strings);
+        put_bytes(&mut block, 2, &nodes_group);
+        put_bytes(&mut block, 2, &ways_group);
+
+        let mut file = Vec::new();
+        blob(&mut file, "OSMHeader", &[], compress);
+        blob(&mut file, "OSMData", &block, compress);
+        file
+    }
+
+    fn depot() -> Grid9Region {
+        Grid9Region::from_bbox(&BoundingBox::new(52.50, 52.54, 13.36, 13.42), 8).unwrap()
+    }
+
+    #[test]
+    fn test_filter() {
+        let region = depot();
+        for compress in [false, true] {
+            let elements: Vec<OsmElement> =
+                OsmRegionFilter::new(sample_file(compress).as_slice(), &region).collect::<io::Result<_>>().unwrap();
+
+            assert_eq!(elements.len(), 3, "{:?}", elements);
+            match &elements[0] {
+                OsmElement::Node { id, coordinate, tags } => {
+                    assert_eq!(*id, 1);
+                    assert!((coordinate.lat - 52.52).abs() < 1e-9 && (coordinate.lon - 13.39).abs() < 1e-9);
+                    assert_eq!(tags, &vec![("amenity".to_string(), "fuel".to_string())]);
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+            assert!(matches!(&elements[1], OsmElement::Node { id: 3, tags, .. } if tags.is_empty()));
+            assert_eq!(
+                elements[2],
+                OsmElement::Way {
+                    id: 10,
+                    node_ids: vec![2, 3],
+                    tags: vec![("highway".to_string(), "service".to_string())]
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_malformed_files() {
+        let region = depot();
+        assert_eq!(OsmRegionFilter::new(&[][..], &region).count(), 0);
+
+        let file = sample_file(true);
+        let truncated: Vec<_> = OsmRegionFilter::new(&file[..file.len() - 3], &region).collect();
+        assert!(truncated.last().unwrap().is_err());
+
+        let mut oversized = Vec::new();
+        oversized.extend_from_slice(&(MAX_HEADER_SIZE as u32 + 1).to_be_bytes());
+        assert!(OsmRegionFilter::new(oversized.as_slice(), &region).next().unwrap().is_err());
+
+        assert_eq!(zigzag(zig(-5)), -5);
+        assert_eq!(zigzag(zig(i64::MAX)), i64::MAX);
+    }
+}
@@ -0,0 +1,124 @@
+//! Reference grids for web map viewports
+//!
+//! Picks the bucket level whose cells stay readable at a Web Mercator zoom
+//! level and lists the buckets covering a viewport with their outlines, so
+//! map frontends can draw a Grid9 grid without client-side math.
+
+use crate::locality::bbox_index_ranges;
+use crate::{BoundingBox, Coordinate, Grid9Bucket, Grid9Error, Result, MAX_LEVEL};
+
+/// Smallest on-screen bucket width in pixels the overlay aims for
+const MIN_BUCKET_PIXELS: f64 = 32.0;
+/// Width of a Web Mercator tile in pixels
+pub(crate) const TILE_PIXELS: f64 = 256.0;
+/// Deepest zoom level accepted (beyond any tile server)
+const MAX_ZOOM: f64 = 30.0;
+
+/// Bucket level drawn by [`grid_overlay`] at a Web Mercator zoom level.
+///
+/// Chooses the finest level whose buckets are at least 32 pixels wide, so
+/// single Grid9 cells (level 0) appear from zoom 20.
+///
+/// # Arguments
+/// * `zoom` - Map zoom level (0 = whole world in one 256px tile; fractional zooms allowed)
+pub fn grid_overlay_level(zoom: f64) -> Result<u8> {
+    if !(0.0..=MAX_ZOOM).contains(&zoom) {
+        return Err(Grid9Error::InvalidInput(format!("invalid zoom level {}", zoom).into()));
+    }
+
+    // A level-n bucket spans 2^(n - 23) of the world's width, which is
+    // 256 * 2^zoom pixels wide
+    let min_level = 23.0 + (MIN_BUCKET_PIXELS / TILE_PIXELS).log2() - zoom;
+    Ok(min_level.ceil().clamp(0.0, MAX_LEVEL as f64) as u8)
+}
+
+/// Lists the buckets to draw over a map viewport, with their outlines.
+///
+/// # Arguments
+/// * `viewport` - Visible area (must not cross the antimeridian; split it in two if it does)
+/// * `zoom` - Map zoom level, see [`grid_overlay_level`]
+/// * `max_buckets` - Upper limit on the number of buckets returned
+///
+/// # Returns
+/// Each bucket touching the viewport with its outline as a closed ring of five
+/// corners (south-west, south-east, north-east, north-west, south-west), or
+/// an error if more than `max_buckets` buckets would be needed
+///
+/// # Example
+/// ```rust
+/// use grid9::{grid_overlay, BoundingBox};
+///
+/// let viewport = BoundingBox::new(40.7550, 40.7610, -73.9900, -73.9810); // Times Square
+/// let grid = grid_overlay(&viewport, 17.0, 1_000)?;
+/// assert!(!grid.is_empty() && grid.iter().all(|(bucket, _)| bucket.level() == 3));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn grid_overlay(viewport: &BoundingBox, zoom: f64, max_buckets: usize) -> Result<Vec<(Grid9Bucket, Vec<Coordinate>)>> {
+    let level = grid_overlay_level(zoom)?;
+    let (lat_range, lon_range) = bbox_index_ranges(viewport)?;
+    let lat_buckets = (lat_range.start() >> level)..=(lat_range.end() >> level);
+    let lon_buckets = (lon_range.start() >> level)..=(lon_range.end() >> level);
+
+    let count = lat_buckets.clone().count() as u64 * lon_buckets.clone().count() as u64;
+    if count > max_buckets as u64 {
+        return Err(Grid9Error::InvalidInput(format!(
+            "viewport needs {} buckets at level {}, more than {}",
+            count, level, max_buckets
+        ).into()));
+    }
+
+    let mut overlay = Vec::with_capacity(count as usize);
+    for lat_bucket in lat_buckets {
+        for lon_bucket in lon_buckets.clone() {
+            let bucket = Grid9Bucket::from_indices(lat_bucket << level, lon_bucket << level, level)?;
+            let bounds = bucket.bounds();
+            let ring = vec![
+                Coordinate::new(bounds.min_lat, bounds.min_lon),
+                Coordinate::new(bounds.min_lat, bounds.max_lon),
+                Coordinate::new(bounds.max_lat, bounds.max_lon),
+                Coordinate::new(bounds.max_lat, bounds.min_lon),
+                Coordinate::new(bounds.min_lat, bounds.min_lon),
+            ];
+            overlay.push((bucket, ring));
+        }
+    }
+
+    Ok(overlay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell_count_in;
+
+    #[test]
+    fn test_overlay_levels() {
+        assert_eq!(grid_overlay_level(20.0).unwrap(), 0);
+        assert_eq!(grid_overlay_level(22.0).unwrap(), 0);
+        assert_eq!(grid_overlay_level(19.5).unwrap(), 1);
+        assert_eq!(grid_overlay_level(12.0).unwrap(), 8);
+        assert_eq!(grid_overlay_level(0.0).unwrap(), 20);
+
+        for zoom in [-1.0, 31.0, f64::NAN] {
+            assert!(grid_overlay_level(zoom).is_err());
+        }
+    }
+
+    #[test]
+    fn test_grid_overlay() {
+        let viewport = BoundingBox::new(51.500, 51.510, -0.130, -0.115);
+        let grid = grid_overlay(&viewport, 15.0, 10_000).unwrap();
+        assert_eq!(grid.len() as u64, cell_count_in(&viewport, 5).unwrap());
+
+        for (bucket, ring) in &grid {
+            assert_eq!(bucket.level(), 5);
+            assert_eq!(ring.len(), 5);
+            assert_eq!(ring[0], ring[4]);
+            let bounds = bucket.bounds();
+            assert_eq!((ring[2].lat, ring[2].lon), (bounds.max_lat, bounds.max_lon));
+        }
+
+        assert!(grid_overlay(&viewport, 20.0, 100).is_err());
+        assert!(grid_overlay(&BoundingBox::new(0.0, 1.0, 179.0, -179.0), 10.0, 100).is_err());
+    }
+}
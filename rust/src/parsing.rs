@@ -0,0 +1,235 @@
+//! Parsing of free-form latitude/longitude text
+//!
+//! Accepts the formats found in spreadsheets and CSV exports: decimal points
+//! or commas, `,` `;` or whitespace between the values, and N/S/E/W
+//! hemisphere letters before or after each value.
+
+use crate::uniform_precision_compressor::validate_coordinates;
+use crate::{Grid9Error, Result};
+
+/// Decimal separator of the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecimalSeparator {
+    /// Detect from the input: commas are decimal separators when the input
+    /// has no `.` and every comma sits between two digits (and there is a
+    /// second separator, so "40,74" still reads as two values)
+    #[default]
+    Auto,
+    /// `40.7128, -74.0060`
+    Point,
+    /// `40,7128; -74,0060`
+    Comma,
+}
+
+/// Options for [`parse_lat_lon`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseOptions {
+    /// Decimal separator of the input
+    pub decimal_separator: DecimalSeparator,
+}
+
+impl ParseOptions {
+    /// Options with the given decimal separator
+    pub fn with_decimal_separator(decimal_separator: DecimalSeparator) -> Self {
+        Self { decimal_separator }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Number(f64),
+    Hemisphere(char),
+}
+
+/// Parses a latitude/longitude pair from text.
+///
+/// Values are read as latitude then longitude unless hemisphere letters
+/// say otherwise (`74.0060 W 40.7128 N`). `S` and `W` negate the value;
+/// a degree sign after a value is ignored.
+///
+/// # Arguments
+/// * `input` - Text such as `40.7128, -74.0060`, `40,7128; -74,0060` or `40.7128N 74.0060W`
+/// * `options` - Parsing options
+///
+/// # Returns
+/// A tuple of (latitude, longitude) in degrees
+///
+/// # Example
+/// ```rust
+/// use grid9::{parse_lat_lon, ParseOptions};
+///
+/// let options = ParseOptions::default();
+/// assert_eq!(parse_lat_lon("40,7128; -74,0060", options)?, (40.7128, -74.0060));
+/// assert_eq!(parse_lat_lon("40.7128° N\t74.0060° W", options)?, (40.7128, -74.0060));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn parse_lat_lon(input: &str, options: ParseOptions) -> Result<(f64, f64)> {
+    if input.trim().is_empty() {
+        return Err(Grid9Error::EmptyInput);
+    }
+
+    let decimal_comma = match options.decimal_separator {
+        DecimalSeparator::Point => false,
+        DecimalSeparator::Comma => true,
+        DecimalSeparator::Auto => detect_decimal_comma(input),
+    };
+
+    let mut values: Vec<(f64, Option<char>)> = Vec::with_capacity(2);
+    let mut prefix: Option<char> = None;
+    let mut after_number = false;
+
+    for token in tokenize(input, decimal_comma)? {
+        match token {
+            Token::Number(value) => {
+                values.push((value, prefix.take()));
+                after_number = true;
+            }
+            Token::Hemisphere(hemisphere) => {
+                match values.last_mut() {
+                    Some((_, suffix @ None)) if after_number && prefix.is_none() => *suffix = Some(hemisphere),
+                    _ if prefix.is_none() => prefix = Some(hemisphere),
+                    _ => return Err(Grid9Error::InvalidCharacter(hemisphere)),
+                }
+                after_number = false;
+            }
+        }
+    }
+
+    if let Some(hemisphere) = prefix {
+        return Err(Grid9Error::InvalidCharacter(hemisphere));
+    }
+    let [first, second] = values[..] else {
+        return Err(Grid9Error::InvalidInput(format!(
+            "expected a latitude and a longitude, found {} values",
+            values.len()
+        ).into()));
+    };
+
+    let (latitude, longitude) = match (first.1, second.1) {
+        (Some('E' | 'W'), _) | (_, Some('N' | 'S')) => (second, first),
+        _ => (first, second),
+    };
+    let latitude = apply_hemisphere(latitude, 'N', 'S')?;
+    let longitude = apply_hemisphere(longitude, 'E', 'W')?;
+
+    validate_coordinates(latitude, longitude)?;
+    Ok((latitude, longitude))
+}
+
+/// Auto-detects decimal commas (see [`DecimalSeparator::Auto`]).
+fn detect_decimal_comma(input: &str) -> bool {
+    if input.contains('.') || !input.contains(',') {
+        return false;
+    }
+
+    let bytes = input.as_bytes();
+    let between_digits = bytes.iter().enumerate().filter(|&(_, &b)| b == b',').all(|(i, _)| {
+        i > 0 && bytes[i - 1].is_ascii_digit() && bytes.get(i + 1).is_some_and(u8::is_ascii_digit)
+    });
+    let has_other_separator = input.chars().any(|c| c == ';' || c.is_whitespace());
+
+    between_digits && has_other_separator
+}
+
+fn tokenize(input: &str, decimal_comma: bool) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut number = String::new();
+
+    let flush = |number: &mut String, tokens: &mut Vec<Token>| -> Result<()> {
+        if !number.is_empty() {
+            let value = number
+                .parse::<f64>()
+                .map_err(|_| Grid9Error::InvalidInput(format!("invalid number '{}'", number).into()))?;
+            tokens.push(Token::Number(value));
+            number.clear();
+        }
+        Ok(())
+    };
+
+    for ch in input.chars() {
+        match ch {
+            '0'..='9' | '.' => number.push(ch),
+            ',' if decimal_comma => number.push('.'),
+            '+' | '-' => {
+                flush(&mut number, &mut tokens)?;
+                number.push(ch);
+            }
+            'N' | 'S' | 'E' | 'W' | 'n' | 's' | 'e' | 'w' => {
+                flush(&mut number, &mut tokens)?;
+                tokens.push(Token::Hemisphere(ch.to_ascii_uppercase()));
+            }
+            ',' | ';' | '°' => flush(&mut number, &mut tokens)?,
+            c if c.is_whitespace() => flush(&mut number, &mut tokens)?,
+            c => return Err(Grid9Error::InvalidCharacter(c)),
+        }
+    }
+    flush(&mut number, &mut tokens)?;
+
+    Ok(tokens)
+}
+
+fn apply_hemisphere((value, hemisphere): (f64, Option<char>), positive: char, negative: char) -> Result<f64> {
+    match hemisphere {
+        None => Ok(value),
+        Some(h) if value < 0.0 => Err(Grid9Error::InvalidInput(format!(
+            "negative value {} with hemisphere {}",
+            value, h
+        ).into())),
+        Some(h) if h == positive => Ok(value),
+        Some(h) if h == negative => Ok(-value),
+        Some(h) => Err(Grid9Error::InvalidCharacter(h)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<(f64, f64)> {
+        parse_lat_lon(input, ParseOptions::default())
+    }
+
+    #[test]
+    fn test_separators() {
+        let nyc = Ok((40.7128, -74.0060));
+        assert_eq!(parse("40.7128, -74.0060"), nyc);
+        assert_eq!(parse("40.7128,-74.0060"), nyc);
+        assert_eq!(parse("  40.7128 \t -74.0060\n"), nyc);
+        assert_eq!(parse("40,7128; -74,0060"), nyc);
+        assert_eq!(parse("40,7128\u{a0}-74,0060"), nyc);
+        assert_eq!(parse("40,74"), Ok((40.0, 74.0)));
+
+        let comma = ParseOptions::with_decimal_separator(DecimalSeparator::Comma);
+        assert_eq!(parse_lat_lon("40,7128 ; -74,0060", comma), nyc);
+        let point = ParseOptions::with_decimal_separator(DecimalSeparator::Point);
+        assert_eq!(parse_lat_lon("40,7128 -74,0060", point), Err(Grid9Error::InvalidInput(
+            "expected a latitude and a longitude, found 4 values".into()
+        )));
+    }
+
+    #[test]
+    fn test_hemispheres() {
+        let nyc = Ok((40.7128, -74.0060));
+        assert_eq!(parse("40.7128N 74.0060W"), nyc);
+        assert_eq!(parse("N 40.7128, W 74.0060"), nyc);
+        assert_eq!(parse("74.0060° W, 40.7128° N"), nyc);
+        assert_eq!(parse("40,7128 n; 74,0060 w"), nyc);
+        assert_eq!(parse("33.8688 S 151.2093 E"), Ok((-33.8688, 151.2093)));
+
+        assert!(parse("-40.7 S 74.0 W").is_err());
+        assert!(parse("40.7 N S 74.0").is_err());
+        assert_eq!(parse("40.7 N 74.0"), Ok((40.7, 74.0)));
+        assert_eq!(parse("40.7, 74.0 W N"), Err(Grid9Error::InvalidCharacter('N')));
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        assert_eq!(parse("   "), Err(Grid9Error::EmptyInput));
+        assert_eq!(parse("40.7 x -74.0"), Err(Grid9Error::InvalidCharacter('x')));
+        assert_eq!(parse("95.0, 10.0"), Err(Grid9Error::InvalidLatitude(95.0)));
+        assert!(parse("40.7.1, 10").is_err());
+        assert!(parse("40.7").is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! Cells traversed by paths
+//!
+//! Turns a polyline into the ordered list of buckets it passes through, e.g.
+//! to match a planned route against buckets of recorded positions.
+
+use crate::region::scaled_position;
+use crate::uniform_precision_compressor::validate_coordinates;
+use crate::{Coordinate, Grid9Bucket, Grid9Error, Result, MAX_LAT_INDEX, MAX_LEVEL, MAX_LON_INDEX};
+
+/// Lists the buckets a polyline passes through, in order of travel.
+///
+/// Every bucket touched by a segment is included, so consecutive buckets
+/// always share an edge; a bucket is repeated only when the line leaves and
+/// later re-enters it. Segments are straight lines in latitude and longitude
+/// and do not wrap across the antimeridian.
+///
+/// # Arguments
+/// * `points` - Vertices of the polyline
+/// * `level` - Bucket level (0 = single Grid9 cells)
+///
+/// # Returns
+/// The traversed buckets, empty for an empty polyline
+///
+/// # Example
+/// ```rust
+/// use grid9::{cells_along, Coordinate};
+///
+/// let route = [Coordinate::new(40.7128, -74.0060), Coordinate::new(40.7580, -73.9855)];
+/// let cells = cells_along(&route, 8)?;
+/// assert_eq!(cells.first().unwrap().to_code(false), grid9::Grid9Bucket::from_coordinates(40.7128, -74.0060, 8)?.to_code(false));
+/// assert!(cells.windows(2).all(|pair| pair[0] != pair[1]));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn cells_along(points: &[Coordinate], level: u8) -> Result<Vec<Grid9Bucket>> {
+    if level > MAX_LEVEL {
+        return Err(Grid9Error::InvalidLevel(level));
+    }
+    for point in points {
+        validate_coordinates(point.lat, point.lon)?;
+    }
+
+    let mut cells: Vec<Grid9Bucket> = Vec::new();
+    let mut push = |column: i64, row: i64| -> Result<()> {
+        let bucket = Grid9Bucket::from_indices((row as u32) << level, (column as u32) << level, level)?;
+        if cells.last() != Some(&bucket) {
+            cells.push(bucket);
+        }
+        Ok(())
+    };
+
+    if let [only] = points {
+        let (x, y) = scaled_position(only.lat, only.lon, level);
+        let (column, row) = bucket_of(x, y, level);
+        push(column, row)?;
+    }
+    for segment in points.windows(2) {
+        let (x0, y0) = scaled_position(segment[0].lat, segment[0].lon, level);
+        let (x1, y1) = scaled_position(segment[1].lat, segment[1].lon, level);
+        let (mut column, mut row) = bucket_of(x0, y0, level);
+        let (end_column, end_row) = bucket_of(x1, y1, level);
+
+        // Grid traversal (Amanatides & Woo): step into whichever neighbouring
+        // column or row the segment reaches first
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let step_column = if end_column > column { 1 } else { -1 };
+        let step_row = if end_row > row { 1 } else { -1 };
+        let crossing = |position: f64, cell: i64, step: i64, delta: f64| {
+            if delta == 0.0 {
+                f64::INFINITY
+            } else if step > 0 {
+                ((cell + 1) as f64 - position) / delta
+            } else {
+                (position - cell as f64) / -delta
+            }
+        };
+        let mut next_column = crossing(x0, column, step_column, dx);
+        let mut next_row = crossing(y0, row, step_row, dy);
+        let column_step = if dx == 0.0 { f64::INFINITY } else { 1.0 / dx.abs() };
+        let row_step = if dy == 0.0 { f64::INFINITY } else { 1.0 / dy.abs() };
+
+        push(column, row)?;
+        while (column, row) != (end_column, end_row) {
+            if row == end_row || (column != end_column && next_column < next_row) {
+                column += step_column;
+                next_column += column_step;
+            } else {
+                row += step_row;
+                next_row += row_step;
+            }
+            push(column, row)?;
+        }
+    }
+
+    Ok(cells)
+}
+
+/// Bucket (column, row) holding a scaled position, clamped to the grid.
+fn bucket_of(x: f64, y: f64, level: u8) -> (i64, i64) {
+    (
+        (x.floor() as i64).clamp(0, (MAX_LON_INDEX >> level) as i64),
+        (y.floor() as i64).clamp(0, (MAX_LAT_INDEX >> level) as i64),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_connected(cells: &[Grid9Bucket]) -> bool {
+        cells.windows(2).all(|pair| {
+            let (a, b) = (pair[0].bounds(), pair[1].bounds());
+            let shares_lat_edge = a.max_lat == b.min_lat || b.max_lat == a.min_lat;
+            let shares_lon_edge = a.max_lon == b.min_lon || b.max_lon == a.min_lon;
+            (shares_lat_edge && a.min_lon == b.min_lon) || (shares_lon_edge && a.min_lat == b.min_lat)
+        })
+    }
+
+    #[test]
+    fn test_diagonal_line() {
+        let line = [Coordinate::new(10.0, 10.0), Coordinate::new(10.01, 10.02)];
+        let cells = cells_along(&line, 4).unwrap();
+
+        assert_eq!(cells[0], Grid9Bucket::from_coordinates(10.0, 10.0, 4).unwrap());
+        assert_eq!(*cells.last().unwrap(), Grid9Bucket::from_coordinates(10.01, 10.02, 4).unwrap());
+        assert!(is_connected(&cells));
+        // Every cell is touched by the segment
+        for cell in &cells {
+            let bounds = cell.bounds();
+            let t_lat = ((bounds.min_lat - 10.0) / 0.01, (bounds.max_lat - 10.0) / 0.01);
+            let t_lon = ((bounds.min_lon - 10.0) / 0.02, (bounds.max_lon - 10.0) / 0.02);
+            assert!(t_lat.0.max(t_lon.0) <= t_lat.1.min(t_lon.1) + 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_polyline_and_degenerate_inputs() {
+        let route = [
+            Coordinate::new(51.50, -0.12),
+            Coordinate::new(51.52, -0.12),
+            Coordinate::new(51.52, -0.08),
+            Coordinate::new(51.50, -0.12),
+        ];
+        let cells = cells_along(&route, 6).unwrap();
+        assert!(is_connected(&cells));
+        assert_eq!(cells.first(), cells.last());
+
+        assert!(cells_along(&[], 0).unwrap().is_empty());
+        let point = [Coordinate::new(1.0, 2.0)];
+        assert_eq!(cells_along(&point, 0).unwrap(), vec![Grid9Bucket::from_coordinates(1.0, 2.0, 0).unwrap()]);
+        assert_eq!(cells_along(&[point[0].clone(), point[0].clone()], 0).unwrap().len(), 1);
+
+        // Grid edges stay inside the grid
+        let edge = [Coordinate::new(90.0, 180.0), Coordinate::new(89.9999, 179.9999)];
+        assert!(is_connected(&cells_along(&edge, 0).unwrap()));
+
+        assert!(cells_along(&[Coordinate::new(0.0, 181.0)], 0).is_err());
+        assert_eq!(cells_along(&point, MAX_LEVEL + 1), Err(Grid9Error::InvalidLevel(MAX_LEVEL + 1)));
+    }
+}
@@ -0,0 +1,145 @@
+//! Capacity planning for coverage sets and indexes
+//!
+//! Estimates how many cells an area spans at a bucket level and how much
+//! memory storing them takes, so a resolution can be chosen before any
+//! cells are generated.
+
+use std::mem::size_of;
+
+use crate::locality::bbox_index_ranges;
+use crate::{BoundingBox, Grid9Code, Grid9Error, Result, MAX_LEVEL};
+
+/// Bookkeeping bytes the allocator adds to each heap allocation (typical of
+/// glibc malloc on 64-bit targets)
+const ALLOCATION_OVERHEAD: u64 = 8;
+/// Control bytes hashbrown appends to every table for group probing
+const HASH_GROUP_WIDTH: u64 = 16;
+
+/// How a set of cells is held in memory, for [`estimate_memory`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Representation {
+    /// `Vec<String>` of compact codes, one heap allocation per code
+    Strings,
+    /// `Vec<String>` of XXX-XXX-XXX codes
+    HumanReadableStrings,
+    /// `Vec<Grid9Code>`, or `Vec<u64>` of payloads or sort keys
+    Codes,
+    /// `HashSet<Grid9Code>` at its default load factor
+    CodeSet,
+}
+
+/// Counts the cells of a bucket level overlapping a bounding box.
+///
+/// Cells on the edges of the box count in full. For other regions, the
+/// count over their bounding box is an upper bound.
+///
+/// # Arguments
+/// * `bbox` - Area to cover (must not cross the antimeridian)
+/// * `level` - Bucket level (0 = full Grid9 precision, see [`crate::Grid9Bucket`])
+///
+/// # Returns
+/// Number of level-`level` buckets touching the box
+///
+/// # Example
+/// ```rust
+/// use grid9::{cell_count_in, estimate_memory, BoundingBox, Representation};
+///
+/// let manhattan = BoundingBox::new(40.70, 40.88, -74.02, -73.91);
+/// let cells = cell_count_in(&manhattan, 0)?;
+/// assert!(cells > 10_000_000);
+///
+/// let coarse = cell_count_in(&manhattan, 6)?;
+/// let bytes = estimate_memory(coarse, Representation::CodeSet);
+/// assert!(bytes < 1 << 20);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn cell_count_in(bbox: &BoundingBox, level: u8) -> Result<u64> {
+    if level > MAX_LEVEL {
+        return Err(Grid9Error::InvalidLevel(level));
+    }
+    let (lat_range, lon_range) = bbox_index_ranges(bbox)?;
+
+    let rows = ((lat_range.end() >> level) - (lat_range.start() >> level)) as u64 + 1;
+    let columns = ((lon_range.end() >> level) - (lon_range.start() >> level)) as u64 + 1;
+    Ok(rows * columns)
+}
+
+/// Estimates the memory needed to hold `cell_count` cells, in bytes.
+///
+/// Includes the container's own allocation and, for strings, one heap
+/// allocation per code with typical allocator overhead. Actual figures vary
+/// with the allocator and, for vectors grown by pushing, spare capacity of
+/// up to 2x.
+///
+/// # Arguments
+/// * `cell_count` - Number of cells (e.g. from [`cell_count_in`])
+/// * `representation` - How the cells are stored
+pub fn estimate_memory(cell_count: u64, representation: Representation) -> u64 {
+    let code_size = size_of::<Grid9Code>() as u64;
+    let string_cost = |length: u64| size_of::<String>() as u64 + length.next_multiple_of(8) + ALLOCATION_OVERHEAD;
+
+    match representation {
+        Representation::Strings => cell_count.saturating_mul(string_cost(9)),
+        Representation::HumanReadableStrings => cell_count.saturating_mul(string_cost(11)),
+        Representation::Codes => cell_count.saturating_mul(code_size),
+        Representation::CodeSet => {
+            if cell_count == 0 {
+                return 0;
+            }
+            // hashbrown keeps tables at most 7/8 full with power-of-two bucket counts
+            let buckets = match cell_count {
+                0..=3 => 4,
+                4..=7 => 8,
+                n => n.saturating_mul(8).div_ceil(7).checked_next_power_of_two().unwrap_or(u64::MAX),
+            };
+            buckets.saturating_mul(code_size + 1).saturating_add(HASH_GROUP_WIDTH)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, encode, to_indices};
+
+    #[test]
+    fn test_cell_counts() {
+        let code = encode(40.7128, -74.0060, false).unwrap();
+        let (lat, lon) = decode(&code).unwrap();
+        let point = BoundingBox::new(lat, lat, lon, lon);
+        assert_eq!(cell_count_in(&point, 0).unwrap(), 1);
+        assert_eq!(cell_count_in(&point, 10).unwrap(), 1);
+
+        let area = BoundingBox::new(40.70, 40.88, -74.02, -73.91);
+        let fine = cell_count_in(&area, 0).unwrap();
+        let (lat_lo, lon_lo) = to_indices(&encode(40.70, -74.02, false).unwrap()).unwrap();
+        let (lat_hi, lon_hi) = to_indices(&encode(40.88, -73.91, false).unwrap()).unwrap();
+        assert_eq!(fine, (lat_hi - lat_lo + 1) as u64 * (lon_hi - lon_lo + 1) as u64);
+
+        // Each level up divides the count by about four
+        let ratio = fine as f64 / cell_count_in(&area, 1).unwrap() as f64;
+        assert!((ratio - 4.0).abs() < 0.01);
+        assert_eq!(cell_count_in(&BoundingBox::new(-90.0, 90.0, -180.0, 180.0), MAX_LEVEL).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_invalid_areas() {
+        let area = BoundingBox::new(40.70, 40.88, -74.02, -73.91);
+        assert_eq!(cell_count_in(&area, MAX_LEVEL + 1), Err(Grid9Error::InvalidLevel(MAX_LEVEL + 1)));
+        assert!(cell_count_in(&BoundingBox::new(41.0, 40.0, 0.0, 1.0), 0).is_err());
+        assert!(cell_count_in(&BoundingBox::new(40.0, 91.0, 0.0, 1.0), 0).is_err());
+    }
+
+    #[test]
+    fn test_memory_estimates() {
+        assert_eq!(estimate_memory(1_000, Representation::Codes), 8_000);
+        assert_eq!(estimate_memory(1_000, Representation::Strings), 48_000);
+        assert_eq!(estimate_memory(0, Representation::CodeSet), 0);
+
+        // 1,000 codes need 1,143 slots, rounded up to 2,048
+        assert_eq!(estimate_memory(1_000, Representation::CodeSet), 2_048 * 9 + 16);
+        assert!(estimate_memory(3, Representation::CodeSet) < estimate_memory(4, Representation::CodeSet));
+        assert_eq!(estimate_memory(u64::MAX, Representation::Strings), u64::MAX);
+    }
+}
@@ -0,0 +1,129 @@
+//! Heuristics for coordinates that are valid but probably wrong
+//!
+//! Swapped latitude/longitude pairs and placeholder values encode to valid
+//! codes, so they pass [`crate::encode`] silently. These checks flag them
+//! before they enter a dataset.
+
+use crate::BoundingBox;
+
+/// Coordinates this close to (0, 0) are treated as "null island"
+const NULL_ISLAND_TOLERANCE_DEG: f64 = 1e-6;
+
+/// Reason a coordinate pair looks suspicious
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlausibilityWarning {
+    /// Latitude or longitude is NaN or infinite
+    NotFinite,
+    /// The pair is out of range as given but valid with latitude and longitude
+    /// swapped, or only the swapped pair lies inside the expected region
+    LikelySwapped,
+    /// The point is (0, 0), a common placeholder for missing data
+    NullIsland,
+    /// Latitude and longitude are equal, a common sign of a copied column
+    RepeatedValues,
+    /// The point lies outside the expected region (and swapping does not help)
+    OutsideExpectedRegion,
+}
+
+/// Checks a coordinate pair for signs of bad upstream data.
+///
+/// # Arguments
+/// * `latitude` - Latitude in degrees
+/// * `longitude` - Longitude in degrees
+///
+/// # Returns
+/// All warnings that apply, empty if the pair looks plausible
+///
+/// # Example
+/// ```rust
+/// use grid9::{check_plausibility, PlausibilityWarning};
+///
+/// assert!(check_plausibility(40.7128, -74.0060).is_empty());
+/// assert_eq!(check_plausibility(151.2093, -33.8688), vec![PlausibilityWarning::LikelySwapped]);
+/// assert_eq!(check_plausibility(0.0, 0.0), vec![PlausibilityWarning::NullIsland]);
+/// ```
+pub fn check_plausibility(latitude: f64, longitude: f64) -> Vec<PlausibilityWarning> {
+    let mut warnings = Vec::new();
+
+    if !latitude.is_finite() || !longitude.is_finite() {
+        warnings.push(PlausibilityWarning::NotFinite);
+        return warnings;
+    }
+
+    if latitude.abs() > 90.0 && longitude.abs() <= 90.0 && latitude.abs() <= 180.0 {
+        warnings.push(PlausibilityWarning::LikelySwapped);
+    }
+    if latitude.abs() < NULL_ISLAND_TOLERANCE_DEG && longitude.abs() < NULL_ISLAND_TOLERANCE_DEG {
+        warnings.push(PlausibilityWarning::NullIsland);
+    } else if latitude == longitude {
+        warnings.push(PlausibilityWarning::RepeatedValues);
+    }
+
+    warnings
+}
+
+/// Checks a coordinate pair against the region it is expected to lie in,
+/// e.g. the bounding box of the country a record claims to come from.
+///
+/// Adds [`PlausibilityWarning::LikelySwapped`] when only the swapped pair
+/// lies inside `expected`, and [`PlausibilityWarning::OutsideExpectedRegion`]
+/// when neither does, to the warnings of [`check_plausibility`].
+///
+/// # Example
+/// ```rust
+/// use grid9::{check_plausibility_in, BoundingBox, PlausibilityWarning};
+///
+/// let spain = BoundingBox::new(35.9, 43.8, -9.4, 3.4);
+/// assert!(check_plausibility_in(40.4168, -3.7038, &spain).is_empty());
+/// assert_eq!(check_plausibility_in(-3.7038, 40.4168, &spain), vec![PlausibilityWarning::LikelySwapped]);
+/// ```
+pub fn check_plausibility_in(latitude: f64, longitude: f64, expected: &BoundingBox) -> Vec<PlausibilityWarning> {
+    let mut warnings = check_plausibility(latitude, longitude);
+    if warnings.contains(&PlausibilityWarning::NotFinite) || contains(expected, latitude, longitude) {
+        return warnings;
+    }
+
+    let warning = if contains(expected, longitude, latitude) {
+        PlausibilityWarning::LikelySwapped
+    } else {
+        PlausibilityWarning::OutsideExpectedRegion
+    };
+    if !warnings.contains(&warning) {
+        warnings.push(warning);
+    }
+
+    warnings
+}
+
+fn contains(bbox: &BoundingBox, latitude: f64, longitude: f64) -> bool {
+    (bbox.min_lat..=bbox.max_lat).contains(&latitude) && (bbox.min_lon..=bbox.max_lon).contains(&longitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_global_checks() {
+        use PlausibilityWarning::*;
+
+        assert!(check_plausibility(51.5074, -0.1278).is_empty());
+        assert_eq!(check_plausibility(120.0, 45.0), vec![LikelySwapped]);
+        assert!(check_plausibility(120.0, 145.0).is_empty());
+        assert_eq!(check_plausibility(0.0, 0.0000001), vec![NullIsland]);
+        assert_eq!(check_plausibility(12.5, 12.5), vec![RepeatedValues]);
+        assert_eq!(check_plausibility(f64::NAN, 0.0), vec![NotFinite]);
+    }
+
+    #[test]
+    fn test_expected_region() {
+        use PlausibilityWarning::*;
+
+        let new_york = BoundingBox::new(40.4, 45.1, -79.8, -71.8);
+        assert!(check_plausibility_in(40.7128, -74.0060, &new_york).is_empty());
+        assert_eq!(check_plausibility_in(-74.0060, 40.7128, &new_york), vec![LikelySwapped]);
+        assert_eq!(check_plausibility_in(51.5074, -0.1278, &new_york), vec![OutsideExpectedRegion]);
+        assert_eq!(check_plausibility_in(0.0, 0.0, &new_york), vec![NullIsland, OutsideExpectedRegion]);
+    }
+}
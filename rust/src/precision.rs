@@ -0,0 +1,193 @@
+//! Codes of 6, 9 and 12 characters
+//!
+//! Each length is a grid of its own, splitting its `5 × length` bits between
+//! latitude (the smaller half) and longitude like the standard 22/23 split,
+//! so 9-character codes are exactly the codes of [`crate::encode`]. A
+//! shorter code is not a prefix of a longer one for the same place; decoding
+//! tells the lengths apart.
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+
+use crate::uniform_precision_compressor::parse_digits;
+#[cfg(feature = "alloc")]
+use crate::uniform_precision_compressor::{validate_coordinates, BASE32_ALPHABET};
+use crate::{Grid9Error, Result};
+
+/// Length of a variable-precision code
+///
+/// Cell sizes are given at the equator; cells narrow east-west towards the
+/// poles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Precision {
+    /// 6 characters (15 + 15 bits), cells ~610m × 1.2km: districts, coarse asset tracking
+    Chars6,
+    /// 9 characters (22 + 23 bits), cells ~4.8m × 4.8m: the standard code
+    Chars9,
+    /// 12 characters (30 + 30 bits), cells ~1.9cm × 3.7cm: survey points
+    Chars12,
+}
+
+impl Precision {
+    /// All precisions, coarsest first
+    pub const ALL: [Precision; 3] = [Precision::Chars6, Precision::Chars9, Precision::Chars12];
+
+    /// Number of code characters
+    pub fn chars(self) -> usize {
+        match self {
+            Precision::Chars6 => 6,
+            Precision::Chars9 => 9,
+            Precision::Chars12 => 12,
+        }
+    }
+
+    /// Precision of a code with `chars` characters (dashes not counted)
+    pub fn from_chars(chars: usize) -> Option<Self> {
+        Precision::ALL.into_iter().find(|precision| precision.chars() == chars)
+    }
+
+    /// Latitude and longitude bits of the grid
+    pub fn bits(self) -> (u32, u32) {
+        let bits = 5 * self.chars() as u32;
+        (bits / 2, bits - bits / 2)
+    }
+
+    /// Height and width of a cell in degrees
+    pub fn cell_size_deg(self) -> (f64, f64) {
+        let (lat_max, lon_max) = self.max_indices();
+        (180.0 / lat_max as f64, 360.0 / lon_max as f64)
+    }
+
+    fn max_indices(self) -> (u64, u64) {
+        let (lat_bits, lon_bits) = self.bits();
+        ((1 << lat_bits) - 1, (1 << lon_bits) - 1)
+    }
+}
+
+/// Encodes coordinates to a code of the given length.
+///
+/// Positions are truncated to the grid point south-west of them, as in
+/// [`crate::encode`].
+///
+/// # Arguments
+/// * `latitude` - Latitude in degrees (-90 to 90)
+/// * `longitude` - Longitude in degrees (-180 to 180)
+/// * `precision` - Code length
+/// * `human_readable` - If true, separates groups of three characters with
+///   dashes (XXX-XXX, XXX-XXX-XXX, XXX-XXX-XXX-XXX)
+///
+/// # Returns
+/// A code of `precision.chars()` characters (plus dashes)
+///
+/// # Example
+/// ```rust
+/// use grid9::{decode_with_precision, encode, encode_with_precision, Precision};
+///
+/// let district = encode_with_precision(40.7128, -74.0060, Precision::Chars6, false)?;
+/// let survey = encode_with_precision(40.7128, -74.0060, Precision::Chars12, true)?;
+/// assert_eq!((district.len(), survey.len()), (6, 15));
+/// assert_eq!(encode_with_precision(40.7128, -74.0060, Precision::Chars9, false)?, encode(40.7128, -74.0060, false)?);
+///
+/// let (lat, lon, precision) = decode_with_precision(&survey)?;
+/// assert_eq!(precision, Precision::Chars12);
+/// assert!((lat - 40.7128).abs() < 1e-6 && (lon + 74.0060).abs() < 1e-6);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[cfg(feature = "alloc")]
+pub fn encode_with_precision(latitude: f64, longitude: f64, precision: Precision, human_readable: bool) -> Result<String> {
+    validate_coordinates(latitude, longitude)?;
+
+    let (lat_max, lon_max) = precision.max_indices();
+    let lat_index = (((latitude + 90.0) / 180.0 * lat_max as f64) as u64).min(lat_max);
+    let lon_index = (((longitude + 180.0) / 360.0 * lon_max as f64) as u64).min(lon_max);
+    let value = (lat_index << precision.bits().1) | lon_index;
+
+    let chars = precision.chars();
+    let mut code = String::with_capacity(chars + chars / 3);
+    for position in 0..chars {
+        if human_readable && position > 0 && position % 3 == 0 {
+            code.push('-');
+        }
+        let digit = (value >> (5 * (chars - 1 - position))) & 0x1F;
+        code.push(BASE32_ALPHABET[digit as usize] as char);
+    }
+    Ok(code)
+}
+
+/// Decodes a 6, 9 or 12 character code, inferring the precision from its
+/// length.
+///
+/// Codes are read with the lenient rules of [`crate::decode`]: dashes are
+/// accepted anywhere.
+///
+/// # Returns
+/// The (latitude, longitude) of the cell's south-west corner in degrees and
+/// the precision, or `InvalidLength` for codes of any other length
+pub fn decode_with_precision(encoded: &str) -> Result<(f64, f64, Precision)> {
+    let (value, len) = parse_digits(encoded, |len| Precision::from_chars(len).is_some())?;
+    let precision = Precision::from_chars(len).ok_or(Grid9Error::InvalidLength(len))?;
+
+    let (lat_max, lon_max) = precision.max_indices();
+    let lon_bits = precision.bits().1;
+    let (lat_index, lon_index) = (value >> lon_bits, value & lon_max);
+    Ok((
+        lat_index as f64 / lat_max as f64 * 180.0 - 90.0,
+        lon_index as f64 / lon_max as f64 * 360.0 - 180.0,
+        precision,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, encode};
+
+    #[test]
+    fn test_nine_characters_match_standard_codes() {
+        assert_eq!(Precision::Chars9.bits(), (22, 23));
+        for (lat, lon) in [(40.7128, -74.0060), (-33.8688, 151.2093), (90.0, 180.0), (-90.0, -180.0), (0.0, 0.0)] {
+            let code = encode_with_precision(lat, lon, Precision::Chars9, false).unwrap();
+            assert_eq!(code, encode(lat, lon, false).unwrap());
+            assert_eq!(encode_with_precision(lat, lon, Precision::Chars9, true).unwrap(), encode(lat, lon, true).unwrap());
+            let (decoded_lat, decoded_lon) = decode(&code).unwrap();
+            assert_eq!(decode_with_precision(&code).unwrap(), (decoded_lat, decoded_lon, Precision::Chars9));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_within_one_cell() {
+        for precision in Precision::ALL {
+            let (height, width) = precision.cell_size_deg();
+            for i in 0..=60 {
+                let (lat, lon) = (-90.0 + i as f64 * 2.999_97, -180.0 + i as f64 * 5.999_93);
+                let code = encode_with_precision(lat, lon, precision, false).unwrap();
+                assert_eq!(code.len(), precision.chars());
+
+                let (decoded_lat, decoded_lon, decoded) = decode_with_precision(&code).unwrap();
+                assert_eq!(decoded, precision);
+                assert!(lat - decoded_lat >= -1e-9 && lat - decoded_lat < height + 1e-9, "{:?} {}", precision, lat);
+                assert!(lon - decoded_lon >= -1e-9 && lon - decoded_lon < width + 1e-9, "{:?} {}", precision, lon);
+            }
+        }
+        assert!(Precision::Chars12.cell_size_deg().0 * 111_320.0 < 0.02);
+        assert!(Precision::Chars6.cell_size_deg().0 * 111_320.0 > 600.0);
+    }
+
+    #[test]
+    fn test_formats_and_invalid_codes() {
+        let survey = encode_with_precision(51.5074, -0.1278, Precision::Chars12, true).unwrap();
+        assert_eq!(survey.matches('-').count(), 3);
+        assert_eq!(decode_with_precision(&survey), decode_with_precision(&survey.replace('-', "")));
+        let district = encode_with_precision(51.5074, -0.1278, Precision::Chars6, true).unwrap();
+        assert_eq!(district.len(), 7);
+        assert_eq!(decode_with_precision(&district).unwrap().2, Precision::Chars6);
+
+        assert_eq!(decode_with_precision("Q7KH2BB"), Err(Grid9Error::InvalidLength(7)));
+        assert_eq!(decode_with_precision(""), Err(Grid9Error::EmptyInput));
+        assert_eq!(decode_with_precision("Q7KH2U"), Err(Grid9Error::InvalidCharacter('U')));
+        assert!(encode_with_precision(0.0, 181.0, Precision::Chars6, false).is_err());
+        assert_eq!(Precision::from_chars(10), None);
+    }
+}
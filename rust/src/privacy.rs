@@ -0,0 +1,177 @@
+//! Privacy-preserving precision reduction for Grid9 codes
+
+use crate::{Grid9Bucket, Grid9Error, Result, MAX_LEVEL};
+#[cfg(feature = "rand")]
+use crate::uniform_precision_compressor::{destination_point, format_packed, pack_indices, quantize};
+
+/// Reduces a Grid9 code to the finest bucket whose extent covers at least
+/// `min_radius_m` in every direction from its center.
+///
+/// A bucket qualifies when both its height and its width (measured along the
+/// bucket's central parallel) are at least `2 * min_radius_m`. The output only
+/// depends on the input code, so repeated blurring of the same code always
+/// yields the same bucket. Near the poles, where cells narrow towards zero
+/// width, the coarsest bucket is returned if no finer one qualifies.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code to blur
+/// * `min_radius_m` - Required anonymity radius in meters
+///
+/// # Returns
+/// The coarse bucket containing the original code
+///
+/// # Example
+/// ```rust
+/// use grid9::blur;
+///
+/// let bucket = blur("Q7KH2BBYF", 500.0)?;
+/// let (height, width) = bucket.dimensions_m();
+/// assert!(height >= 1000.0 && width >= 1000.0);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn blur(encoded: &str, min_radius_m: f64) -> Result<Grid9Bucket> {
+    if !min_radius_m.is_finite() || min_radius_m < 0.0 {
+        return Err(Grid9Error::InvalidRadius(min_radius_m));
+    }
+
+    let min_extent = 2.0 * min_radius_m;
+    for level in 0..MAX_LEVEL {
+        let bucket = Grid9Bucket::from_code(encoded, level)?;
+        let (height, width) = bucket.dimensions_m();
+        if height >= min_extent && width >= min_extent {
+            return Ok(bucket);
+        }
+    }
+
+    Grid9Bucket::from_code(encoded, MAX_LEVEL)
+}
+
+/// Perturbs a Grid9 code with planar Laplace noise for geo-indistinguishability.
+///
+/// The noise is applied to the decoded cell position, never to the original
+/// coordinates, and the perturbed point is re-encoded. Any two codes `d` meters
+/// apart therefore produce output distributions within a factor of
+/// `exp(epsilon * d)` of each other; re-encoding is post-processing and does not
+/// weaken the guarantee. The displacement follows a great circle, so results
+/// stay valid across the antimeridian and near the poles.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code to perturb
+/// * `epsilon` - Privacy budget per meter (e.g. `ln(4) / 200.0` for ε = ln 4 within 200m)
+/// * `rng` - Random number generator
+///
+/// # Returns
+/// A 9-character Grid9 code for the perturbed position
+///
+/// # Example
+/// ```rust
+/// use grid9::{jitter, is_valid_encoding};
+///
+/// let mut rng = rand::thread_rng();
+/// let noisy = jitter("Q7KH2BBYF", 4f64.ln() / 200.0, &mut rng)?;
+/// assert!(is_valid_encoding(&noisy));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[cfg(feature = "rand")]
+pub fn jitter<R: rand::Rng + ?Sized>(encoded: &str, epsilon: f64, rng: &mut R) -> Result<String> {
+    if !epsilon.is_finite() || epsilon <= 0.0 {
+        return Err(Grid9Error::InvalidEpsilon(epsilon));
+    }
+
+    let (lat, lon) = crate::decode(encoded)?;
+
+    // The planar Laplace radius follows a Gamma(2, 1/epsilon) distribution,
+    // i.e. the sum of two exponential variates. `1 - gen()` lies in (0, 1].
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = 1.0 - rng.gen::<f64>();
+    let radius_m = -(u1.ln() + u2.ln()) / epsilon;
+    let bearing_deg = rng.gen::<f64>() * 360.0;
+
+    let (noisy_lat, noisy_lon) = destination_point(lat, lon, bearing_deg, radius_m);
+    let (lat_bits, lon_bits) = quantize(noisy_lat, noisy_lon);
+
+    Ok(format_packed(pack_indices(lat_bits, lon_bits), false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode;
+
+    #[test]
+    fn test_blur_guarantees_radius() {
+        let code = encode(40.7128, -74.0060, false).unwrap();
+
+        for radius in [10.0, 250.0, 5_000.0] {
+            let bucket = blur(&code, radius).unwrap();
+            let (height, width) = bucket.dimensions_m();
+
+            assert!(height >= 2.0 * radius && width >= 2.0 * radius);
+            assert!(bucket.contains(&code).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_blur_is_minimal_and_deterministic() {
+        let code = encode(51.5074, -0.1278, false).unwrap();
+        let bucket = blur(&code, 100.0).unwrap();
+
+        assert_eq!(bucket, blur(&code, 100.0).unwrap());
+
+        let finer = Grid9Bucket::from_code(&code, bucket.level() - 1).unwrap();
+        let (height, width) = finer.dimensions_m();
+        assert!(height < 200.0 || width < 200.0);
+    }
+
+    #[test]
+    fn test_blur_zero_radius_keeps_cell() {
+        let bucket = blur("Q7KH2BBYF", 0.0).unwrap();
+        assert_eq!(bucket.level(), 0);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_jitter_mean_radius() {
+        use rand::SeedableRng;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let epsilon = 0.01; // mean displacement of 2 / epsilon = 200m
+        let samples = 2_000;
+
+        let mut total = 0.0;
+        for _ in 0..samples {
+            let noisy = jitter("Q7KH2BBYF", epsilon, &mut rng).unwrap();
+            total += crate::calculate_distance("Q7KH2BBYF", &noisy).unwrap();
+        }
+
+        let mean = total / samples as f64;
+        assert!((mean - 200.0).abs() < 20.0, "mean displacement {}", mean);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_jitter_near_poles_and_antimeridian() {
+        use rand::SeedableRng;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        for (lat, lon) in [(89.9999, 0.0), (-89.9999, 45.0), (0.0, 179.9999), (0.0, -180.0)] {
+            let code = encode(lat, lon, false).unwrap();
+            for _ in 0..100 {
+                assert!(crate::is_valid_encoding(&jitter(&code, 0.001, &mut rng).unwrap()));
+            }
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_jitter_invalid_epsilon() {
+        let mut rng = rand::thread_rng();
+        assert!(matches!(jitter("Q7KH2BBYF", 0.0, &mut rng), Err(Grid9Error::InvalidEpsilon(_))));
+    }
+
+    #[test]
+    fn test_blur_invalid_radius() {
+        assert!(matches!(blur("Q7KH2BBYF", -1.0), Err(Grid9Error::InvalidRadius(_))));
+        assert!(matches!(blur("Q7KH2BBYF", f64::NAN), Err(Grid9Error::InvalidRadius(_))));
+    }
+}
@@ -0,0 +1,136 @@
+//! Conversion between Grid9 codes and Redis GEO scores
+
+use crate::locality::{compact_bits, spread_bits};
+use crate::uniform_precision_compressor::{
+    format_packed, lat_of_index, lon_of_index, pack_indices, parse_packed, quantize, unpack_indices,
+};
+use crate::{Grid9Error, Result};
+
+/// Latitude limits of Redis GEO sets (the Web Mercator range)
+const REDIS_LAT_MIN: f64 = -85.051_128_78;
+const REDIS_LAT_MAX: f64 = 85.051_128_78;
+const REDIS_LON_MIN: f64 = -180.0;
+const REDIS_LON_MAX: f64 = 180.0;
+/// Bits per axis of a Redis GEO score
+const REDIS_STEP: u32 = 26;
+
+/// Returns the Redis GEO score (the 52-bit interleaved geohash Redis stores
+/// in GEO sorted sets) of a Grid9 cell.
+///
+/// Redis scores are finer than Grid9 cells, so the score of the cell center
+/// is used and [`from_redis_geo_score`] maps it back to the same code. Scores
+/// can be written with `ZADD key <score> <member>` and queried with
+/// `GEOSEARCH`.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code
+///
+/// # Returns
+/// The 52-bit score, or `InvalidLatitude` for cells outside Redis's
+/// ±85.05112878° latitude range
+///
+/// # Example
+/// ```rust
+/// use grid9::{from_redis_geo_score, to_redis_geo_score};
+///
+/// let score = to_redis_geo_score("Q7KH2BBYF")?;
+/// assert_eq!(from_redis_geo_score(score)?, "Q7KH2BBYF");
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn to_redis_geo_score(encoded: &str) -> Result<u64> {
+    let (lat_index, lon_index) = unpack_indices(parse_packed(encoded)?);
+    let latitude = lat_of_index(lat_index as f64 + 0.5).min(90.0);
+    let longitude = lon_of_index(lon_index as f64 + 0.5).min(REDIS_LON_MAX);
+
+    if !(REDIS_LAT_MIN..=REDIS_LAT_MAX).contains(&latitude) {
+        return Err(Grid9Error::InvalidLatitude(latitude));
+    }
+
+    let cells = (1u64 << REDIS_STEP) as f64;
+    let max_index = (1u32 << REDIS_STEP) - 1;
+    let lat_cell = ((latitude - REDIS_LAT_MIN) / (REDIS_LAT_MAX - REDIS_LAT_MIN) * cells) as u32;
+    let lon_cell = ((longitude - REDIS_LON_MIN) / (REDIS_LON_MAX - REDIS_LON_MIN) * cells) as u32;
+
+    // Redis interleaves latitude into the even bits and longitude into the odd bits
+    Ok(spread_bits(lat_cell.min(max_index)) | (spread_bits(lon_cell.min(max_index)) << 1))
+}
+
+/// Returns the Grid9 code of the cell containing the center of a Redis GEO score.
+///
+/// Round-trips every code accepted by [`to_redis_geo_score`], except the
+/// zero-width column at exactly 180° longitude, which maps to its western
+/// neighbor.
+///
+/// # Arguments
+/// * `score` - Score of a member of a Redis GEO set (`ZSCORE key member`)
+///
+/// # Returns
+/// The compact Grid9 code
+pub fn from_redis_geo_score(score: u64) -> Result<String> {
+    if score >> (2 * REDIS_STEP) != 0 {
+        return Err(Grid9Error::InvalidInput(format!(
+            "Redis GEO score {} exceeds {} bits",
+            score,
+            2 * REDIS_STEP
+        ).into()));
+    }
+
+    let cells = (1u64 << REDIS_STEP) as f64;
+    let lat_cell = compact_bits(score);
+    let lon_cell = compact_bits(score >> 1);
+    let latitude = REDIS_LAT_MIN + (lat_cell as f64 + 0.5) / cells * (REDIS_LAT_MAX - REDIS_LAT_MIN);
+    let longitude = REDIS_LON_MIN + (lon_cell as f64 + 0.5) / cells * (REDIS_LON_MAX - REDIS_LON_MIN);
+
+    let (lat_index, lon_index) = quantize(latitude, longitude);
+    Ok(format_packed(pack_indices(lat_index, lon_index), false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, encode, from_indices, to_indices, MAX_LON_INDEX};
+
+    #[test]
+    fn test_roundtrip() {
+        for (lat, lon) in [
+            (40.7128, -74.0060),
+            (-33.8688, 151.2093),
+            (85.05, 179.9999),
+            (-85.05, -180.0),
+            (0.0, 0.0),
+        ] {
+            let code = encode(lat, lon, false).unwrap();
+            let score = to_redis_geo_score(&code).unwrap();
+            assert!(score < 1 << 52);
+            assert_eq!(from_redis_geo_score(score).unwrap(), code);
+        }
+
+        let (lat_index, _) = to_indices("Q7KH2BBYF").unwrap();
+        let edge = from_indices(lat_index, MAX_LON_INDEX, false).unwrap();
+        let west = from_indices(lat_index, MAX_LON_INDEX - 1, false).unwrap();
+        assert_eq!(from_redis_geo_score(to_redis_geo_score(&edge).unwrap()).unwrap(), west);
+    }
+
+    #[test]
+    fn test_matches_redis_scores() {
+        // GEOADD Sicily 13.361389 38.115556 "Palermo"; ZSCORE Sicily Palermo
+        let palermo = encode(38.115556, 13.361389, false).unwrap();
+        let expected = 3_479_099_956_230_698u64;
+        let score = to_redis_geo_score(&palermo).unwrap();
+
+        // The Grid9 cell center lies within a couple of Redis cells of Palermo
+        let (lat, lon) = (compact_bits(score) as i64, compact_bits(score >> 1) as i64);
+        let (exp_lat, exp_lon) = (compact_bits(expected) as i64, compact_bits(expected >> 1) as i64);
+        assert!((lat - exp_lat).abs() <= 16 && (lon - exp_lon).abs() <= 16);
+
+        let (dlat, dlon) = decode(&from_redis_geo_score(expected).unwrap()).unwrap();
+        assert!((dlat - 38.115556).abs() < 1e-4 && (dlon - 13.361389).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let pole = encode(89.0, 0.0, false).unwrap();
+        assert!(matches!(to_redis_geo_score(&pole), Err(Grid9Error::InvalidLatitude(_))));
+        assert!(from_redis_geo_score(1 << 52).is_err());
+    }
+}
@@ -0,0 +1,769 @@
+//! Coverage sets of buckets
+//!
+//! A [`Grid9Region`] is the set of buckets of one level covering an area,
+//! such as the inside of a polygon.
+
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+
+use crate::locality::bbox_index_ranges;
+use crate::uniform_precision_compressor::{
+    from_vector, haversine_distance, pack_indices, unit_vector, unpack_indices, validate_coordinates, EARTH_RADIUS_M,
+};
+use crate::{BboxIndices, BoundingBox, Coordinate, Grid9Bucket, Grid9Code, Grid9Error, Result, MAX_LAT_INDEX, MAX_LEVEL, MAX_LON_INDEX};
+
+/// A set of buckets of one level
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, Coordinate, Grid9Region};
+///
+/// // Central Park, roughly
+/// let park = [
+///     Coordinate::new(40.7681, -73.9819),
+///     Coordinate::new(40.7968, -73.9490),
+///     Coordinate::new(40.8006, -73.9580),
+///     Coordinate::new(40.7644, -73.9730),
+/// ];
+/// let region = Grid9Region::from_polygon(&park, 6)?;
+/// assert!(region.contains(&encode(40.7812, -73.9665, false)?)?);
+/// assert!(!region.contains(&encode(40.7580, -73.9855, false)?)?); // Times Square
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+pub struct Grid9Region {
+    level: u8,
+    /// [`Grid9Bucket::key`]s of the member buckets
+    keys: BTreeSet<u64>,
+}
+
+impl Grid9Region {
+    /// An empty region of buckets at `level`
+    pub fn new(level: u8) -> Result<Self> {
+        if level > MAX_LEVEL {
+            return Err(Grid9Error::InvalidLevel(level));
+        }
+        Ok(Self { level, keys: BTreeSet::new() })
+    }
+
+    /// The buckets of `level` overlapping a bounding box.
+    ///
+    /// See [`crate::cell_count_in`] for the size of the result.
+    pub fn from_bbox(bbox: &BoundingBox, level: u8) -> Result<Self> {
+        let mut region = Self::new(level)?;
+        let (lat_range, lon_range) = bbox_index_ranges(bbox)?;
+
+        for lat_bucket in (lat_range.start() >> level)..=(lat_range.end() >> level) {
+            for lon_bucket in (lon_range.start() >> level)..=(lon_range.end() >> level) {
+                region.insert(Grid9Bucket::from_indices(lat_bucket << level, lon_bucket << level, level)?)?;
+            }
+        }
+        Ok(region)
+    }
+
+    /// The buckets of `level` whose centers lie inside a polygon.
+    ///
+    /// The ring may be open or closed, and edges are straight lines in
+    /// latitude and longitude (the ring must not cross the antimeridian);
+    /// run long edges through [`densify`] first to follow great circles.
+    /// Polygons smaller than a bucket may cover no bucket center; use a
+    /// finer level for them. The work done grows with the number of buckets
+    /// in the polygon's bounding box, see [`crate::cell_count_in`].
+    ///
+    /// # Arguments
+    /// * `ring` - Outer boundary, at least three vertices
+    /// * `level` - Bucket level (0 = single Grid9 cells)
+    pub fn from_polygon(ring: &[Coordinate], level: u8) -> Result<Self> {
+        Self::from_polygon_with_holes(&[ring], level)
+    }
+
+    /// The buckets of `level` whose centers lie inside a polygon with holes.
+    ///
+    /// Rings follow [`Grid9Region::from_polygon`] and combine by the
+    /// even-odd rule, so a bucket inside a hole is left out; the order and
+    /// winding of the rings do not matter.
+    ///
+    /// # Arguments
+    /// * `rings` - Outer boundary followed by any holes, as in GeoJSON and WKB
+    /// * `level` - Bucket level (0 = single Grid9 cells)
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::{Coordinate, Grid9Code, Grid9Region};
+    ///
+    /// let square = |min: f64, max: f64| {
+    ///     vec![Coordinate::new(min, min), Coordinate::new(min, max), Coordinate::new(max, max), Coordinate::new(max, min)]
+    /// };
+    /// let campus = Grid9Region::from_polygon_with_holes(&[square(0.0, 0.03), square(0.01, 0.02)], 4)?;
+    /// assert!(campus.contains_code(Grid9Code::encode(0.005, 0.005)?));
+    /// assert!(!campus.contains_code(Grid9Code::encode(0.015, 0.015)?)); // Courtyard
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn from_polygon_with_holes<R: AsRef<[Coordinate]>>(rings: &[R], level: u8) -> Result<Self> {
+        let mut region = Self::new(level)?;
+        if rings.is_empty() {
+            return Err(Grid9Error::InvalidInput("a polygon needs an outer ring".into()));
+        }
+        let rings = rings.iter().map(|ring| scaled_ring(ring.as_ref(), level)).collect::<Result<Vec<_>>>()?;
+
+        let max_row = (MAX_LAT_INDEX >> level) as i64;
+        let max_column = (MAX_LON_INDEX >> level) as i64;
+        let (min_y, max_y) = rings.iter().flatten().fold((f64::MAX, f64::MIN), |(lo, hi), &(_, y)| (lo.min(y), hi.max(y)));
+
+        let mut crossings = Vec::new();
+        for row in (min_y.floor() as i64).max(0)..=(max_y.floor() as i64).min(max_row) {
+            // Even-odd scanline through the centers of the row's buckets
+            let y = row as f64 + 0.5;
+            crossings.clear();
+            for points in &rings {
+                let mut previous = points[points.len() - 1];
+                for &current in points {
+                    let ((x1, y1), (x2, y2)) = (previous, current);
+                    if (y1 > y) != (y2 > y) {
+                        crossings.push(x1 + (y - y1) / (y2 - y1) * (x2 - x1));
+                    }
+                    previous = current;
+                }
+            }
+            crossings.sort_by(f64::total_cmp);
+
+            for span in crossings.chunks_exact(2) {
+                let first = ((span[0] - 0.5).ceil() as i64).max(0);
+                let last = ((span[1] - 0.5).ceil() as i64 - 1).min(max_column);
+                for column in first..=last {
+                    let bucket = Grid9Bucket::from_indices((row as u32) << level, (column as u32) << level, level)?;
+                    region.insert(bucket)?;
+                }
+            }
+        }
+
+        Ok(region)
+    }
+
+    /// The buckets of `level` whose centers lie inside any polygon of a
+    /// multipolygon.
+    ///
+    /// Each polygon is covered as by [`Grid9Region::from_polygon_with_holes`]
+    /// and the results are merged, so overlapping polygons add up instead of
+    /// cancelling out.
+    ///
+    /// # Arguments
+    /// * `polygons` - Polygons, each an outer boundary followed by any holes
+    /// * `level` - Bucket level (0 = single Grid9 cells)
+    pub fn from_multipolygon<P, R>(polygons: &[P], level: u8) -> Result<Self>
+    where
+        P: AsRef<[R]>,
+        R: AsRef<[Coordinate]>,
+    {
+        let mut region = Self::new(level)?;
+        for polygon in polygons {
+            region.keys.append(&mut Self::from_polygon_with_holes(polygon.as_ref(), level)?.keys);
+        }
+        Ok(region)
+    }
+
+    /// Bucket level of the region
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Number of buckets
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// True if the region has no buckets
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Smallest box holding every bucket, or `None` for an empty region
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        let mut bounds = self.buckets().map(|bucket| bucket.bounds());
+        let first = bounds.next()?;
+        Some(bounds.fold(first, |acc, b| {
+            BoundingBox::new(acc.min_lat.min(b.min_lat), acc.max_lat.max(b.max_lat), acc.min_lon.min(b.min_lon), acc.max_lon.max(b.max_lon))
+        }))
+    }
+
+    /// Adds a bucket, returning whether it was new.
+    ///
+    /// Fails with [`Grid9Error::InvalidLevel`] if the bucket's level differs
+    /// from the region's.
+    pub fn insert(&mut self, bucket: Grid9Bucket) -> Result<bool> {
+        if bucket.level() != self.level {
+            return Err(Grid9Error::InvalidLevel(bucket.level()));
+        }
+        Ok(self.keys.insert(bucket.key()))
+    }
+
+    /// True if the region covers the cell of a code
+    pub fn contains_code(&self, code: Grid9Code) -> bool {
+        let (lat_index, lon_index) = code.indices();
+        Grid9Bucket::from_indices(lat_index, lon_index, self.level).is_ok_and(|bucket| self.keys.contains(&bucket.key()))
+    }
+
+    /// True if the region covers the cell of a Grid9 string
+    pub fn contains(&self, encoded: &str) -> Result<bool> {
+        Ok(self.contains_code(encoded.parse()?))
+    }
+
+    /// True if any bucket of the region overlaps a bounding box.
+    ///
+    /// Works on packed indices only, as a cheap pre-filter before exact
+    /// geometry checks: with bucket keys ordered row by row, each row of the
+    /// box costs one ordered-set lookup.
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::{BboxIndices, BoundingBox, Grid9Region};
+    ///
+    /// let depot = Grid9Region::from_bbox(&BoundingBox::new(52.50, 52.54, 13.36, 13.42), 8)?;
+    /// let nearby = BboxIndices::from_bbox(&BoundingBox::new(52.53, 52.60, 13.40, 13.50))?;
+    /// let elsewhere = BboxIndices::from_bbox(&BoundingBox::new(48.10, 48.20, 11.50, 11.60))?;
+    /// assert!(depot.intersects_bbox(&nearby));
+    /// assert!(!depot.intersects_bbox(&elsewhere));
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn intersects_bbox(&self, bbox: &BboxIndices) -> bool {
+        self.buckets_in_bbox(bbox).next().is_some()
+    }
+
+    /// Buckets of the region overlapping a bounding box, ordered by
+    /// [`Grid9Bucket::key`]; see [`Grid9Region::intersects_bbox`].
+    pub fn buckets_in_bbox<'a>(&'a self, bbox: &BboxIndices) -> impl Iterator<Item = Grid9Bucket> + 'a {
+        let level = self.level;
+        let align = |index: u32| (index >> level) << level;
+        let (lat_first, lat_last) = (align(bbox.min_lat_index), align(bbox.max_lat_index));
+        let (lon_first, lon_last) = (align(bbox.min_lon_index), align(bbox.max_lon_index));
+        let last = pack_indices(lat_last, lon_last);
+
+        let mut next = (lat_first <= lat_last && lon_first <= lon_last).then(|| pack_indices(lat_first, lon_first));
+        std::iter::from_fn(move || {
+            while let Some(from) = next.filter(|&from| from <= last) {
+                let &key = self.keys.range(from..=last).next()?;
+                let (lat_index, lon_index) = unpack_indices(key);
+                if lon_index < lon_first {
+                    next = Some(pack_indices(lat_index, lon_first));
+                } else if lon_index > lon_last {
+                    next = lat_index.checked_add(1 << level).map(|lat| pack_indices(lat, lon_first));
+                } else {
+                    next = key.checked_add(1);
+                    return Grid9Bucket::from_indices(lat_index, lon_index, level).ok();
+                }
+            }
+            None
+        })
+    }
+
+    /// Member buckets, ordered by [`Grid9Bucket::key`]
+    pub fn buckets(&self) -> impl Iterator<Item = Grid9Bucket> + '_ {
+        self.keys.iter().filter_map(|&key| {
+            let (lat_index, lon_index) = unpack_indices(key);
+            Grid9Bucket::from_indices(lat_index, lon_index, self.level).ok()
+        })
+    }
+}
+
+/// Collects codes into a level 0 region, one bucket per distinct cell.
+///
+/// To bin codes at a coarser level, [`Extend`] an empty region of that
+/// level instead.
+///
+/// # Example
+/// ```rust
+/// use grid9::{Grid9Code, Grid9Region};
+///
+/// let fixes = [(48.8566, 2.3522), (48.8566, 2.3522), (48.8570, 2.3530)];
+/// let codes = fixes.iter().map(|&(lat, lon)| Grid9Code::encode(lat, lon)).collect::<Result<Vec<_>, _>>()?;
+///
+/// let visited: Grid9Region = codes.iter().copied().collect();
+/// assert_eq!(visited.len(), 2);
+///
+/// // Coarse buckets absorb nearby codes as they are added
+/// let mut district = Grid9Region::new(8)?;
+/// district.extend(&codes);
+/// assert_eq!(district.len(), 1);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+impl FromIterator<Grid9Code> for Grid9Region {
+    fn from_iter<I: IntoIterator<Item = Grid9Code>>(codes: I) -> Self {
+        let mut region = Self::default();
+        region.extend(codes);
+        region
+    }
+}
+
+/// Adds the region's bucket containing each code; codes falling in a bucket
+/// already present leave the region unchanged.
+impl Extend<Grid9Code> for Grid9Region {
+    fn extend<I: IntoIterator<Item = Grid9Code>>(&mut self, codes: I) {
+        let level = self.level;
+        self.keys.extend(codes.into_iter().map(|code| {
+            let (lat_index, lon_index) = code.indices();
+            pack_indices((lat_index >> level) << level, (lon_index >> level) << level)
+        }));
+    }
+}
+
+impl<'a> Extend<&'a Grid9Code> for Grid9Region {
+    fn extend<I: IntoIterator<Item = &'a Grid9Code>>(&mut self, codes: I) {
+        self.extend(codes.into_iter().copied());
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl Grid9Region {
+    /// Serializes the region for zero-copy loading with [`Grid9Region::archived`]
+    pub fn to_archive(&self) -> rkyv::util::AlignedVec {
+        rkyv::to_bytes::<rkyv::rancor::Error>(self).expect("serializing to memory cannot fail")
+    }
+
+    /// Validates archived region bytes, such as a memory-mapped file, and
+    /// returns a view that answers queries without deserializing.
+    ///
+    /// `bytes` must be 16-byte aligned, as [`Grid9Region::to_archive`] output
+    /// and memory maps are.
+    ///
+    /// # Returns
+    /// The archived region, `InvalidInput` for malformed bytes or
+    /// `InvalidLevel` for an out-of-range level
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::{BoundingBox, Grid9Code, Grid9Region};
+    ///
+    /// let region = Grid9Region::from_bbox(&BoundingBox::new(48.80, 48.90, 2.25, 2.42), 10)?;
+    /// let bytes = region.to_archive();
+    ///
+    /// let archived = Grid9Region::archived(&bytes)?;
+    /// assert_eq!(archived.len(), region.len());
+    /// assert!(archived.contains_code(Grid9Code::encode(48.8566, 2.3522)?));
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn archived(bytes: &[u8]) -> Result<&ArchivedGrid9Region> {
+        let archived = rkyv::access::<ArchivedGrid9Region, rkyv::rancor::Error>(bytes)
+            .map_err(|e| Grid9Error::InvalidInput(format!("invalid archived region: {}", e).into()))?;
+        if archived.level > MAX_LEVEL {
+            return Err(Grid9Error::InvalidLevel(archived.level));
+        }
+        Ok(archived)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl ArchivedGrid9Region {
+    /// Bucket level of the region
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Number of buckets
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// True if the region has no buckets
+    pub fn is_empty(&self) -> bool {
+        self.keys.len() == 0
+    }
+
+    /// True if the region covers the cell of a code
+    pub fn contains_code(&self, code: Grid9Code) -> bool {
+        let (lat_index, lon_index) = code.indices();
+        Grid9Bucket::from_indices(lat_index, lon_index, self.level)
+            .is_ok_and(|bucket| self.keys.contains_key(&rkyv::rend::u64_le::from_native(bucket.key())))
+    }
+}
+
+/// Inserts vertices along the great circles between a ring's vertices, so
+/// that no edge is longer than `max_segment_m`.
+///
+/// Coverage and point-in-polygon code treats edges as straight lines in
+/// latitude and longitude, which cut corners against the geodesic edge
+/// between distant vertices (a fence from 50°N 60°W to 50°N 60°E arcs up to
+/// 67°N). Densified rings keep those algorithms within the given tolerance.
+///
+/// # Arguments
+/// * `polygon` - Ring vertices, open or closed; the closing edge is densified too
+/// * `max_segment_m` - Longest edge allowed in meters (finite and positive)
+///
+/// # Returns
+/// The ring with the original vertices and the inserted ones, closed if the
+/// input was
+///
+/// # Example
+/// ```rust
+/// use grid9::{densify, Coordinate};
+///
+/// let fence = [Coordinate::new(50.0, -60.0), Coordinate::new(50.0, 60.0), Coordinate::new(40.0, 0.0)];
+/// let dense = densify(&fence, 100_000.0)?;
+/// assert!(dense.len() > 80);
+/// assert!(dense.iter().any(|point| point.lat > 67.0));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn densify(polygon: &[Coordinate], max_segment_m: f64) -> Result<Vec<Coordinate>> {
+    if !max_segment_m.is_finite() || max_segment_m <= 0.0 {
+        return Err(Grid9Error::InvalidInput(format!("invalid segment length {}", max_segment_m).into()));
+    }
+    for point in polygon {
+        validate_coordinates(point.lat, point.lon)?;
+    }
+
+    let closed = polygon.len() > 1 && polygon.first() == polygon.last();
+    let mut dense = Vec::with_capacity(polygon.len());
+    for (i, start) in polygon.iter().enumerate() {
+        dense.push(start.clone());
+        let end = match polygon.get(i + 1) {
+            Some(end) => end,
+            None if !closed && polygon.len() > 2 => &polygon[0],
+            None => break,
+        };
+
+        let length = haversine_distance(start.lat, start.lon, end.lat, end.lon);
+        let segments = (length / max_segment_m).ceil();
+        if segments <= 1.0 {
+            continue;
+        }
+        let angle = length / EARTH_RADIUS_M;
+        if angle.sin() < 1e-12 {
+            return Err(Grid9Error::InvalidInput("edge between antipodal points has no unique great circle".into()));
+        }
+
+        // Spherical linear interpolation between the edge's unit vectors
+        let (a, b) = (unit_vector(start.lat, start.lon), unit_vector(end.lat, end.lon));
+        for k in 1..segments as u64 {
+            let t = k as f64 / segments;
+            let (wa, wb) = (((1.0 - t) * angle).sin(), (t * angle).sin());
+            let (lat, lon) = from_vector(std::array::from_fn(|axis| wa * a[axis] + wb * b[axis]));
+            dense.push(Coordinate::new(lat, lon));
+        }
+    }
+
+    Ok(dense)
+}
+
+/// Validates a polygon ring and converts it to (x, y) positions in units of
+/// buckets of `level`.
+fn scaled_ring(ring: &[Coordinate], level: u8) -> Result<Vec<(f64, f64)>> {
+    let mut points = Vec::with_capacity(ring.len());
+    for point in ring {
+        validate_coordinates(point.lat, point.lon)?;
+        points.push(scaled_position(point.lat, point.lon, level));
+    }
+    if points.first() == points.last() {
+        points.pop();
+    }
+    if points.len() < 3 {
+        return Err(Grid9Error::InvalidInput("a polygon ring needs at least three vertices".into()));
+    }
+    Ok(points)
+}
+
+/// Position of validated coordinates in units of buckets of `level`, such
+/// that flooring yields the bucket's index (as in [`crate::encode`]).
+pub(crate) fn scaled_position(latitude: f64, longitude: f64, level: u8) -> (f64, f64) {
+    let bucket = (1u32 << level) as f64;
+    (
+        (longitude + 180.0) / 360.0 * MAX_LON_INDEX as f64 / bucket,
+        (latitude + 90.0) / 180.0 * MAX_LAT_INDEX as f64 / bucket,
+    )
+}
+
+/// Splits a region into territories, assigning each bucket to the seed
+/// nearest its center by great-circle distance (a Voronoi partition).
+///
+/// Territories are contiguous apart from buckets cut off by gaps in the
+/// region itself, since each is the intersection of the region with a
+/// convex cell on the sphere. Every bucket is compared with every seed, so
+/// the cost is the number of buckets times the number of seeds.
+///
+/// # Arguments
+/// * `region` - Buckets to split, e.g. a city's coverage
+/// * `seeds` - Seed ids, such as depot names, with their positions
+///
+/// # Returns
+/// A region of the same level per seed id, empty for seeds nearest to no
+/// bucket; ties go to the seed listed first. Fails with `EmptyInput` for no
+/// seeds and `InvalidInput` for duplicate ids.
+///
+/// # Example
+/// ```rust
+/// use grid9::{partition_region, BoundingBox, Coordinate, Grid9Code, Grid9Region};
+///
+/// let city = Grid9Region::from_bbox(&BoundingBox::new(52.45, 52.55, 13.30, 13.50), 8)?;
+/// let depots = [("west", Coordinate::new(52.50, 13.33)), ("east", Coordinate::new(52.50, 13.47))];
+///
+/// let territories = partition_region(&city, &depots)?;
+/// assert_eq!(territories["west"].len() + territories["east"].len(), city.len());
+/// assert!(territories["east"].contains_code(Grid9Code::encode(52.52, 13.45)?));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn partition_region<K: Clone + Eq + Hash>(region: &Grid9Region, seeds: &[(K, Coordinate)]) -> Result<HashMap<K, Grid9Region>> {
+    if seeds.is_empty() {
+        return Err(Grid9Error::EmptyInput);
+    }
+    let mut territories = HashMap::with_capacity(seeds.len());
+    for (id, position) in seeds {
+        validate_coordinates(position.lat, position.lon)?;
+        if territories.insert(id.clone(), Grid9Region::new(region.level)?).is_some() {
+            return Err(Grid9Error::InvalidInput("duplicate seed id".into()));
+        }
+    }
+
+    // The nearest seed on the sphere is the one whose unit vector has the
+    // largest dot product with the bucket center's
+    let vectors: Vec<[f64; 3]> = seeds.iter().map(|(_, position)| unit_vector(position.lat, position.lon)).collect();
+    for bucket in region.buckets() {
+        let center = bucket.center();
+        let [x, y, z] = unit_vector(center.lat, center.lon);
+        let mut nearest = 0;
+        let mut best = f64::NEG_INFINITY;
+        for (index, [sx, sy, sz]) in vectors.iter().enumerate() {
+            let dot = x * sx + y * sy + z * sz;
+            if dot > best {
+                (nearest, best) = (index, dot);
+            }
+        }
+        let territory = territories.get_mut(&seeds[nearest].0).expect("every seed has a territory");
+        territory.keys.insert(bucket.key());
+    }
+    Ok(territories)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cell_count_in, encode};
+
+    fn square(min: f64, max: f64) -> Vec<Coordinate> {
+        vec![
+            Coordinate::new(min, min),
+            Coordinate::new(min, max),
+            Coordinate::new(max, max),
+            Coordinate::new(max, min),
+            Coordinate::new(min, min),
+        ]
+    }
+
+    #[test]
+    fn test_polygon_matches_bbox() {
+        let level = 8;
+        let polygon = Grid9Region::from_polygon(&square(10.0, 12.0), level).unwrap();
+        let bbox = Grid9Region::from_bbox(&BoundingBox::new(10.0, 12.0, 10.0, 12.0), level).unwrap();
+
+        // The box also holds the partial buckets along its edges
+        assert!(polygon.buckets().all(|bucket| bbox.contains_code(bucket.to_code(false).parse().unwrap())));
+        assert_eq!(bbox.len() as u64, cell_count_in(&BoundingBox::new(10.0, 12.0, 10.0, 12.0), level).unwrap());
+        let ratio = polygon.len() as f64 / bbox.len() as f64;
+        assert!(ratio > 0.95 && ratio <= 1.0, "{}", ratio);
+    }
+
+    #[test]
+    fn test_triangle() {
+        let triangle = [Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 1.0), Coordinate::new(1.0, 0.0)];
+        let region = Grid9Region::from_polygon(&triangle, 6).unwrap();
+
+        assert!(region.contains(&encode(0.2, 0.2, false).unwrap()).unwrap());
+        assert!(!region.contains(&encode(0.7, 0.7, false).unwrap()).unwrap());
+        // About half the unit square's buckets
+        let full = Grid9Region::from_polygon(&square(0.0, 1.0), 6).unwrap();
+        let ratio = region.len() as f64 / full.len() as f64;
+        assert!((ratio - 0.5).abs() < 0.01, "{}", ratio);
+    }
+
+    #[test]
+    fn test_holes_and_multipolygons() {
+        let outer = square(0.0, 1.0);
+        let hole = square(0.25, 0.75);
+        let ring = Grid9Region::from_polygon(&outer, 8).unwrap();
+        let holed = Grid9Region::from_polygon_with_holes(&[outer.clone(), hole.clone()], 8).unwrap();
+        let inner = Grid9Region::from_polygon(&hole, 8).unwrap();
+
+        assert_eq!(holed.len() + inner.len(), ring.len());
+        assert!(holed.contains(&encode(0.1, 0.1, false).unwrap()).unwrap());
+        assert!(!holed.contains(&encode(0.5, 0.5, false).unwrap()).unwrap());
+        assert_eq!(Grid9Region::from_polygon_with_holes(&[hole.clone(), outer.clone()], 8).unwrap(), holed);
+
+        // An island inside the hole, and a polygon overlapping the first one
+        let island = square(0.4, 0.6);
+        let overlapping = square(0.9, 1.5);
+        let parts = Grid9Region::from_multipolygon(&[vec![outer.clone(), hole], vec![island], vec![overlapping.clone()]], 8).unwrap();
+        assert!(parts.contains(&encode(0.5, 0.5, false).unwrap()).unwrap());
+        assert!(!parts.contains(&encode(0.3, 0.3, false).unwrap()).unwrap());
+        assert!(parts.contains(&encode(0.95, 0.95, false).unwrap()).unwrap());
+        assert!(parts.contains(&encode(1.2, 1.2, false).unwrap()).unwrap());
+
+        let empty: [Vec<Coordinate>; 0] = [];
+        assert!(Grid9Region::from_polygon_with_holes(&empty, 8).is_err());
+        assert!(Grid9Region::from_multipolygon(&[vec![outer, overlapping[..2].to_vec()]], 8).is_err());
+        assert!(Grid9Region::from_multipolygon::<Vec<Vec<Coordinate>>, _>(&[], 8).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_insert_and_order() {
+        let mut region = Grid9Region::new(4).unwrap();
+        let a = Grid9Bucket::from_coordinates(10.0, 10.0, 4).unwrap();
+        let b = Grid9Bucket::from_coordinates(-10.0, -10.0, 4).unwrap();
+        assert!(region.insert(a).unwrap());
+        assert!(region.insert(b).unwrap());
+        assert!(!region.insert(a).unwrap());
+        let bounds = region.bounds().unwrap();
+        assert_eq!((bounds.min_lat, bounds.max_lat), (b.bounds().min_lat, a.bounds().max_lat));
+        assert_eq!((bounds.min_lon, bounds.max_lon), (b.bounds().min_lon, a.bounds().max_lon));
+        assert_eq!(Grid9Region::new(4).unwrap().bounds(), None);
+        assert_eq!(region.buckets().collect::<Vec<_>>(), vec![b, a]);
+
+        let other_level = Grid9Bucket::from_coordinates(10.0, 10.0, 5).unwrap();
+        assert_eq!(region.insert(other_level), Err(Grid9Error::InvalidLevel(5)));
+        assert_eq!(Grid9Region::new(MAX_LEVEL + 1), Err(Grid9Error::InvalidLevel(MAX_LEVEL + 1)));
+    }
+
+    #[test]
+    fn test_collect_and_extend() {
+        let codes: Vec<Grid9Code> = [(10.0, 10.0), (10.0, 10.0), (10.0001, 10.0001), (-10.0, -10.0)]
+            .iter()
+            .map(|&(lat, lon)| Grid9Code::encode(lat, lon).unwrap())
+            .collect();
+        let cells: Grid9Region = codes.iter().copied().collect();
+        assert_eq!(cells.level(), 0);
+        assert_eq!(cells.len(), 3);
+        assert!(codes.iter().all(|&code| cells.contains_code(code)));
+
+        let mut coarse = Grid9Region::new(4).unwrap();
+        coarse.extend(&codes);
+        assert_eq!(coarse.len(), 2);
+        assert!(coarse.contains_code(Grid9Code::encode(10.00001, 10.00001).unwrap()));
+        assert_eq!(
+            coarse.buckets().collect::<Vec<_>>(),
+            vec![Grid9Bucket::from_coordinates(-10.0, -10.0, 4).unwrap(), Grid9Bucket::from_coordinates(10.0, 10.0, 4).unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_bbox_intersection() {
+        let ring = [Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 2.0), Coordinate::new(2.0, 0.0)];
+        let region = Grid9Region::from_polygon(&ring, 9).unwrap();
+
+        let boxes = [
+            BoundingBox::new(0.2, 0.4, 0.2, 0.4),
+            BoundingBox::new(1.5, 1.9, 1.5, 1.9), // Beyond the hypotenuse
+            BoundingBox::new(-1.0, 3.0, 1.0, 1.1),
+            BoundingBox::new(5.0, 6.0, 5.0, 6.0),
+            BoundingBox::new(-1.0, 3.0, -1.0, 3.0),
+        ];
+        for bbox in &boxes {
+            let indices = BboxIndices::from_bbox(bbox).unwrap();
+            let found: Vec<Grid9Bucket> = region.buckets_in_bbox(&indices).collect();
+            let expected: Vec<Grid9Bucket> = region
+                .buckets()
+                .filter(|bucket| {
+                    let b = bucket.bounds();
+                    b.min_lat <= bbox.max_lat && b.max_lat > bbox.min_lat && b.min_lon <= bbox.max_lon && b.max_lon > bbox.min_lon
+                })
+                .collect();
+            assert_eq!(found, expected, "{:?}", bbox);
+            assert_eq!(region.intersects_bbox(&indices), !expected.is_empty());
+        }
+        assert!(!Grid9Region::new(0).unwrap().intersects_bbox(&BboxIndices::from_bbox(&boxes[4]).unwrap()));
+    }
+
+    #[test]
+    fn test_partition_region() {
+        let region = Grid9Region::from_polygon(&square(10.0, 11.0), 9).unwrap();
+        let seeds = [(1, Coordinate::new(10.2, 10.2)), (2, Coordinate::new(10.8, 10.3)), (3, Coordinate::new(10.5, 10.9)), (4, Coordinate::new(-40.0, 0.0))];
+        let territories = partition_region(&region, &seeds).unwrap();
+
+        assert_eq!(territories.len(), 4);
+        assert!(territories[&4].is_empty());
+        assert_eq!(territories.values().map(Grid9Region::len).sum::<usize>(), region.len());
+        for (id, territory) in &territories {
+            assert_eq!(territory.level(), 9);
+            for bucket in territory.buckets() {
+                assert!(region.contains_code(bucket.to_code(false).parse().unwrap()));
+                let center = bucket.center();
+                let distance = |position: &Coordinate| haversine_distance(center.lat, center.lon, position.lat, position.lon);
+                let own = distance(&seeds[id - 1].1);
+                assert!(seeds.iter().all(|(_, position)| own <= distance(position) + 1e-6), "{}", id);
+            }
+        }
+
+        assert_eq!(partition_region::<u8>(&region, &[]), Err(Grid9Error::EmptyInput));
+        let duplicate = [("a", Coordinate::new(0.0, 0.0)), ("a", Coordinate::new(1.0, 1.0))];
+        assert!(matches!(partition_region(&region, &duplicate), Err(Grid9Error::InvalidInput(_))));
+        assert!(partition_region(&region, &[("a", Coordinate::new(91.0, 0.0))]).is_err());
+    }
+
+    #[test]
+    fn test_densify() {
+        let closed = [
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 10.0),
+            Coordinate::new(10.0, 0.0),
+            Coordinate::new(0.0, 0.0),
+        ];
+        let dense = densify(&closed, 50_000.0).unwrap();
+        assert_eq!(dense.first(), dense.last());
+        for pair in dense.windows(2) {
+            assert!(haversine_distance(pair[0].lat, pair[0].lon, pair[1].lat, pair[1].lon) <= 50_000.0 + 1e-6);
+        }
+        // Original vertices are kept, and the equator is a great circle
+        assert!(closed.iter().all(|vertex| dense.contains(vertex)));
+        assert!(dense.iter().filter(|point| point.lon > 0.0 && point.lon < 10.0 && point.lat.abs() < 1e-9).count() > 10);
+
+        // Open rings get their closing edge densified but stay open
+        let open = densify(&closed[..3], 50_000.0).unwrap();
+        assert_eq!(open.len(), dense.len() - 1);
+
+        // Short edges are left alone
+        assert_eq!(densify(&closed, 5_000_000.0).unwrap(), closed.to_vec());
+        assert!(densify(&closed, 0.0).is_err());
+        assert!(densify(&[Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 180.0)], 1_000.0).is_err());
+    }
+
+    #[test]
+    fn test_invalid_polygons() {
+        let line = [Coordinate::new(0.0, 0.0), Coordinate::new(1.0, 1.0), Coordinate::new(0.0, 0.0)];
+        assert!(Grid9Region::from_polygon(&line, 0).is_err());
+        let outside = [Coordinate::new(91.0, 0.0), Coordinate::new(0.0, 1.0), Coordinate::new(1.0, 0.0)];
+        assert!(Grid9Region::from_polygon(&outside, 0).is_err());
+
+        // Smaller than a bucket: no bucket center inside
+        let tiny = [Coordinate::new(0.1, 0.1), Coordinate::new(0.1, 0.1001), Coordinate::new(0.1001, 0.1)];
+        assert!(Grid9Region::from_polygon(&tiny, 10).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_postcard_size() {
+        let region = Grid9Region::from_bbox(&BoundingBox::new(48.80, 48.90, 2.25, 2.42), 10).unwrap();
+        let bytes = postcard::to_allocvec(&region).unwrap();
+
+        // Level, length prefix and at most 7 varint bytes per 45-bit key
+        assert!(bytes.len() <= 1 + 3 + 7 * region.len(), "{} bytes for {} buckets", bytes.len(), region.len());
+        assert_eq!(postcard::from_bytes::<Grid9Region>(&bytes).unwrap(), region);
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn test_archive() {
+        let region = Grid9Region::from_polygon(&square(10.0, 12.0), 8).unwrap();
+        let bytes = region.to_archive();
+        let archived = Grid9Region::archived(&bytes).unwrap();
+
+        assert_eq!((archived.level(), archived.len()), (8, region.len()));
+        for (lat, lon) in [(11.0, 11.0), (10.01, 11.99), (9.9, 11.0), (-11.0, -11.0)] {
+            let code = Grid9Code::encode(lat, lon).unwrap();
+            assert_eq!(archived.contains_code(code), region.contains_code(code), "{} {}", lat, lon);
+        }
+        assert!(Grid9Region::archived(&Grid9Region::new(3).unwrap().to_archive()).unwrap().is_empty());
+
+        let mut corrupt = bytes.clone();
+        let len = corrupt.len();
+        corrupt[len - 4..].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(Grid9Region::archived(&corrupt), Err(Grid9Error::InvalidInput(_))));
+        assert!(Grid9Region::archived(&bytes[..8]).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+//! Fixed-size binary position reports for constrained uplinks
+//!
+//! A [`PositionReport`] packs a Grid9 code, a time offset, battery level and
+//! application flags into [`POSITION_REPORT_LEN`] bytes, small enough for the
+//! 11-byte payload of the slowest LoRaWAN data rate. All fields are
+//! big-endian:
+//!
+//! | Bytes | Bits | Field |
+//! |-------|------|-------|
+//! | 0-5   | 47-45 | Layout version ([`POSITION_REPORT_VERSION`]) |
+//! |       | 44-0  | Grid9 payload (`[22-bit lat index][23-bit lon index]`) |
+//! | 6-8   | 23-0  | Seconds since a device-agreed epoch (up to [`MAX_TIME_DELTA_S`], ~194 days) |
+//! | 9     | 7-0   | Battery level in percent (0-100), `0xFF` if unknown |
+//! | 10    | 7-0   | Application-defined flags |
+
+use crate::{Grid9Code, Grid9Error, Result};
+
+/// Length in bytes of an encoded position report
+pub const POSITION_REPORT_LEN: usize = 11;
+
+/// Layout version written into the top three bits of a report
+pub const POSITION_REPORT_VERSION: u8 = 1;
+
+/// Largest time offset a report can carry, in seconds
+pub const MAX_TIME_DELTA_S: u32 = (1 << 24) - 1;
+
+/// Battery byte of a report without a battery reading
+const BATTERY_UNKNOWN: u8 = 0xFF;
+
+/// Bits of the Grid9 payload
+const PAYLOAD_MASK: u64 = (1 << 45) - 1;
+
+/// A device position report
+///
+/// # Example
+/// ```rust
+/// use grid9::{Grid9Code, PositionReport, POSITION_REPORT_LEN};
+///
+/// let report = PositionReport {
+///     code: Grid9Code::encode(40.7128, -74.0060)?,
+///     time_delta_s: 3_600,
+///     battery_percent: Some(87),
+///     flags: 0b0000_0001,
+/// };
+/// let bytes: [u8; POSITION_REPORT_LEN] = report.to_bytes()?;
+/// assert_eq!(PositionReport::from_bytes(&bytes)?, report);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PositionReport {
+    /// Reported position
+    pub code: Grid9Code,
+    /// Seconds since the epoch agreed between device and backend (e.g. the
+    /// session start), at most [`MAX_TIME_DELTA_S`]
+    pub time_delta_s: u32,
+    /// Battery level in percent (0-100)
+    pub battery_percent: Option<u8>,
+    /// Application-defined flags (motion, alarm, ...)
+    pub flags: u8,
+}
+
+impl PositionReport {
+    /// Encodes the report in the fixed binary layout.
+    ///
+    /// # Returns
+    /// The report bytes, or `InvalidInput` if the time offset or battery
+    /// level is out of range
+    pub fn to_bytes(&self) -> Result<[u8; POSITION_REPORT_LEN]> {
+        if self.time_delta_s > MAX_TIME_DELTA_S {
+            return Err(Grid9Error::InvalidInput(format!(
+                "time delta {}s exceeds {}s",
+                self.time_delta_s, MAX_TIME_DELTA_S
+            ).into()));
+        }
+        let battery = match self.battery_percent {
+            Some(percent) if percent > 100 => {
+                return Err(Grid9Error::InvalidInput(format!("battery level {}% exceeds 100%", percent).into()));
+            }
+            Some(percent) => percent,
+            None => BATTERY_UNKNOWN,
+        };
+
+        let (lat_index, lon_index) = self.code.indices();
+        let payload = ((lat_index as u64) << 23) | lon_index as u64;
+        let head = ((POSITION_REPORT_VERSION as u64) << 45) | payload;
+
+        let mut bytes = [0u8; POSITION_REPORT_LEN];
+        bytes[..6].copy_from_slice(&head.to_be_bytes()[2..]);
+        bytes[6..9].copy_from_slice(&self.time_delta_s.to_be_bytes()[1..]);
+        bytes[9] = battery;
+        bytes[10] = self.flags;
+        Ok(bytes)
+    }
+
+    /// Decodes a report from its binary layout.
+    ///
+    /// # Returns
+    /// The report, `UnsupportedVersion` for another layout version, or
+    /// `InvalidInput` for a wrong length or battery byte
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let bytes: &[u8; POSITION_REPORT_LEN] = bytes.try_into().map_err(|_| {
+            Grid9Error::InvalidInput(format!("position report must be {} bytes, got {}", POSITION_REPORT_LEN, bytes.len()).into())
+        })?;
+
+        let mut head = [0u8; 8];
+        head[2..].copy_from_slice(&bytes[..6]);
+        let head = u64::from_be_bytes(head);
+        let version = (head >> 45) as u8;
+        if version != POSITION_REPORT_VERSION {
+            return Err(Grid9Error::UnsupportedVersion(version));
+        }
+
+        let payload = head & PAYLOAD_MASK;
+        let code = Grid9Code::from_indices((payload >> 23) as u32, (payload & ((1 << 23) - 1)) as u32)?;
+        let time_delta_s = u32::from_be_bytes([0, bytes[6], bytes[7], bytes[8]]);
+        let battery_percent = match bytes[9] {
+            BATTERY_UNKNOWN => None,
+            percent if percent <= 100 => Some(percent),
+            other => return Err(Grid9Error::InvalidInput(format!("invalid battery byte {:#04x}", other).into())),
+        };
+
+        Ok(Self { code, time_delta_s, battery_percent, flags: bytes[10] })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(lat: f64, lon: f64) -> PositionReport {
+        PositionReport {
+            code: Grid9Code::encode(lat, lon).unwrap(),
+            time_delta_s: MAX_TIME_DELTA_S,
+            battery_percent: None,
+            flags: 0xA5,
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let bytes = report(90.0, 180.0).to_bytes().unwrap();
+        // Version 1 in the top three bits, then an all-ones payload
+        assert_eq!(bytes, [0x3F, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xA5]);
+
+        let bytes = report(-90.0, -180.0).to_bytes().unwrap();
+        assert_eq!(&bytes[..6], &[0x20, 0, 0, 0, 0, 0]);
+
+        for (lat, lon) in [(-90.0, -180.0), (51.5074, -0.1278), (90.0, 180.0)] {
+            let original = report(lat, lon);
+            assert_eq!(PositionReport::from_bytes(&original.to_bytes().unwrap()).unwrap(), original);
+        }
+    }
+
+    #[test]
+    fn test_rejects_out_of_range() {
+        let mut invalid = report(0.0, 0.0);
+        invalid.time_delta_s = MAX_TIME_DELTA_S + 1;
+        assert!(invalid.to_bytes().is_err());
+        invalid.time_delta_s = 0;
+        invalid.battery_percent = Some(101);
+        assert!(invalid.to_bytes().is_err());
+
+        let mut bytes = report(0.0, 0.0).to_bytes().unwrap();
+        assert!(PositionReport::from_bytes(&bytes[..10]).is_err());
+        bytes[9] = 150;
+        assert!(PositionReport::from_bytes(&bytes).is_err());
+        bytes[0] &= 0x1F;
+        assert_eq!(PositionReport::from_bytes(&bytes), Err(Grid9Error::UnsupportedVersion(0)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_postcard_size() {
+        let original = PositionReport { battery_percent: Some(100), ..report(51.5074, -0.1278) };
+        let bytes = postcard::to_allocvec(&original).unwrap();
+
+        // Never much larger than the fixed wire layout
+        assert!(bytes.len() <= POSITION_REPORT_LEN + 3, "{} bytes", bytes.len());
+        assert_eq!(postcard::from_bytes::<PositionReport>(&bytes).unwrap(), original);
+    }
+}
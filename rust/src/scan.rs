@@ -0,0 +1,248 @@
+//! Fast paths for validating large volumes of codes
+//!
+//! Ingestion services see codes almost exclusively in their canonical
+//! `XXXXXXXXX` / `XXX-XXX-XXX` shapes. These are checked eight bytes at a
+//! time with branch-free byte-range tests on a `u64` (SIMD within a
+//! register, so no `unsafe` or platform intrinsics are needed); anything
+//! else falls back to the regular lenient parser, so results always agree
+//! with [`is_valid_encoding`].
+
+use crate::is_valid_encoding;
+#[cfg(feature = "bulk")]
+use crate::uniform_precision_compressor::parse_packed;
+use crate::uniform_precision_compressor::{DIGIT_VALUES, INVALID_DIGIT};
+#[cfg(feature = "bulk")]
+use crate::{chunked::trim_line, Grid9Code, Grid9Error, RecordError};
+
+/// `0x01` repeated in every byte of a `u64`
+const ONES: u64 = 0x0101_0101_0101_0101;
+/// `0x80` repeated in every byte of a `u64`
+const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+
+/// Bytes that are code characters: digits and uppercase letters other than
+/// `I`, `L`, `O` and `U`
+const CODE_BYTES: [bool; 256] = {
+    let mut table = [false; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        table[byte] = DIGIT_VALUES[byte] != INVALID_DIGIT;
+        byte += 1;
+    }
+    table
+};
+
+/// Validates many codes, with a fast path for canonical shapes.
+///
+/// # Arguments
+/// * `codes` - Candidate Grid9 codes
+///
+/// # Returns
+/// One flag per input, equal to [`is_valid_encoding`] of that input
+///
+/// # Example
+/// ```rust
+/// use grid9::validate_many;
+///
+/// let incoming = ["Q7KH2BBYF", "Q7K-H2B-BYF", "Q-7KH2BB-YF", "Q7KH2BBY", "Q7KH2BBYU"];
+/// assert_eq!(validate_many(&incoming), vec![true, true, true, false, false]);
+/// ```
+pub fn validate_many<S: AsRef<str>>(codes: &[S]) -> Vec<bool> {
+    codes
+        .iter()
+        .map(|code| {
+            let code = code.as_ref();
+            is_canonical_shape(code.as_bytes()) || is_valid_encoding(code)
+        })
+        .collect()
+}
+
+/// Iterates over the codes of a newline-delimited buffer, such as a
+/// memory-mapped file.
+///
+/// Line breaks are located with `memchr` and canonical codes are decoded
+/// straight from the buffer, so no `String` is allocated per line; other
+/// shapes fall back to the lenient parser. Blank lines are skipped and
+/// `\r\n` line endings are accepted.
+///
+/// # Arguments
+/// * `buffer` - Newline-delimited Grid9 codes
+///
+/// # Returns
+/// An iterator yielding each decoded code, or the 1-based line number and
+/// error of a line that is not a valid code
+///
+/// # Example
+/// ```rust
+/// use grid9::iter_codes_in;
+///
+/// let buffer = b"Q7KH2BBYF\r\nQ7K-H2B-BYF\n\nnot a code\n";
+/// let results: Vec<_> = iter_codes_in(buffer).collect();
+/// assert_eq!(results.len(), 3);
+/// assert_eq!(results[0].as_ref().unwrap(), results[1].as_ref().unwrap());
+/// assert_eq!(results[2].as_ref().unwrap_err().line, 4);
+/// ```
+#[cfg(feature = "bulk")]
+pub fn iter_codes_in(buffer: &[u8]) -> CodesIn<'_> {
+    CodesIn { buffer, line: 0 }
+}
+
+/// Iterator returned by [`iter_codes_in`]
+#[cfg(feature = "bulk")]
+#[derive(Debug, Clone)]
+pub struct CodesIn<'a> {
+    buffer: &'a [u8],
+    line: u64,
+}
+
+#[cfg(feature = "bulk")]
+impl Iterator for CodesIn<'_> {
+    type Item = std::result::Result<Grid9Code, RecordError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.buffer.is_empty() {
+            let end = memchr::memchr(b'\n', self.buffer).map_or(self.buffer.len(), |i| i + 1);
+            let (line, rest) = self.buffer.split_at(end);
+            self.buffer = rest;
+            self.line += 1;
+
+            let line = trim_line(line);
+            if line.is_empty() {
+                continue;
+            }
+            return Some(decode_line(line).map_err(|error| RecordError { line: self.line, error }));
+        }
+        None
+    }
+}
+
+/// Decodes one line, without allocating for canonical shapes.
+#[cfg(feature = "bulk")]
+fn decode_line(line: &[u8]) -> crate::Result<Grid9Code> {
+    if is_canonical_shape(line) {
+        let packed = line
+            .iter()
+            .filter(|&&b| b != b'-')
+            .fold(0u64, |packed, &b| (packed << 5) | DIGIT_VALUES[b as usize] as u64);
+        return Ok(Grid9Code::from_packed(packed));
+    }
+
+    let text = std::str::from_utf8(line)
+        .map_err(|_| Grid9Error::InvalidInput("record is not valid UTF-8".into()))?;
+    parse_packed(text).map(Grid9Code::from_packed)
+}
+
+/// True for nine code characters, optionally split by dashes as
+/// `XXX-XXX-XXX`. False only means the input needs the full parser.
+pub(crate) fn is_canonical_shape(bytes: &[u8]) -> bool {
+    let chars: [u8; 9] = match bytes.len() {
+        9 => bytes.try_into().expect("length checked"),
+        11 if bytes[3] == b'-' && bytes[7] == b'-' => [
+            bytes[0], bytes[1], bytes[2], bytes[4], bytes[5], bytes[6], bytes[8], bytes[9], bytes[10],
+        ],
+        _ => return false,
+    };
+
+    let word = u64::from_le_bytes(chars[..8].try_into().expect("eight bytes"));
+    all_code_bytes(word) && CODE_BYTES[chars[8] as usize]
+}
+
+/// True if all eight bytes of `word` are code characters.
+fn all_code_bytes(word: u64) -> bool {
+    if word & HIGH_BITS != 0 {
+        return false;
+    }
+
+    // With every byte below 0x80, adding up to 0x80 per byte never carries
+    // into the next byte, so each byte's high bit holds its own comparison
+    let at_least = |low: u8| word + ONES * (0x80 - low as u64);
+    let above = |high: u8| word + ONES * (0x7f - high as u64);
+    let in_range = |low: u8, high: u8| at_least(low) & !above(high) & HIGH_BITS;
+
+    let classes = in_range(b'0', b'9') | in_range(b'A', b'Z');
+    let excluded = in_range(b'I', b'I') | in_range(b'L', b'L') | in_range(b'O', b'O') | in_range(b'U', b'U');
+    classes & !excluded == HIGH_BITS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode;
+
+    #[test]
+    fn test_word_classes_match_table() {
+        for byte in 0..=255u8 {
+            for position in 0..8 {
+                let mut chars = *b"00000000";
+                chars[position] = byte;
+                let word = u64::from_le_bytes(chars);
+                assert_eq!(all_code_bytes(word), CODE_BYTES[byte as usize], "{:#x} at {}", byte, position);
+            }
+        }
+    }
+
+    #[test]
+    fn test_agrees_with_parser() {
+        let code = encode(48.8566, 2.3522, false).unwrap();
+        let human = encode(48.8566, 2.3522, true).unwrap();
+        let mut inputs = vec![
+            code.clone(),
+            human.clone(),
+            code.to_lowercase(),
+            format!(" {} ", code),
+            format!("U1{}", code),
+            format!("{}-", code),
+            String::new(),
+            "ABC-DEF-GH".to_string(),
+            "ABCD-EF-GHJ".to_string(),
+            "ＡＢＣＤＥＦＧＨＪ".to_string(),
+        ];
+        for position in 0..human.len() {
+            for replacement in ["U", "u", "!", "-", "é", "O", "l", " "] {
+                let mut variant = human.clone();
+                variant.replace_range(position..position + 1, replacement);
+                inputs.push(variant);
+            }
+        }
+
+        let fast = validate_many(&inputs);
+        for (input, valid) in inputs.iter().zip(fast) {
+            assert_eq!(valid, is_valid_encoding(input), "{:?}", input);
+        }
+    }
+
+    #[cfg(feature = "bulk")]
+    #[test]
+    fn test_iter_codes_in_matches_parser() {
+        let points = [(48.8566, 2.3522), (-33.8688, 151.2093), (40.7128, -74.0060), (89.9, -179.9)];
+        let mut buffer = String::new();
+        let mut expected = Vec::new();
+        for (i, &(lat, lon)) in points.iter().enumerate() {
+            let code = encode(lat, lon, i % 2 == 1).unwrap();
+            let line = if i == 2 { format!("{} ", code) } else { code.clone() };
+            buffer.push_str(&line);
+            buffer.push_str(if i == 3 { "\r\n" } else { "\n" });
+            expected.push(code.parse::<Grid9Code>().unwrap());
+        }
+        buffer.push_str("  U1Q7KH2BBYF \n");
+        expected.push("Q7KH2BBYF".parse().unwrap());
+
+        let decoded: Vec<Grid9Code> = iter_codes_in(buffer.as_bytes()).map(|r| r.unwrap()).collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[cfg(feature = "bulk")]
+    #[test]
+    fn test_iter_codes_in_reports_lines() {
+        let buffer = b"Q7KH2BBYF\n\nQ7KH2BBYU\n\xff\xfe\n   \nQ7KH2BBYF";
+        let results: Vec<_> = iter_codes_in(buffer).collect();
+
+        assert_eq!(results.len(), 5);
+        assert!(results[0].is_ok());
+        assert_eq!(results[1].as_ref().unwrap_err().line, 3);
+        assert!(matches!(results[2].as_ref().unwrap_err().error, Grid9Error::InvalidInput(_)));
+        assert_eq!(results[2].as_ref().unwrap_err().line, 4);
+        assert_eq!(results[3].as_ref().unwrap_err().line, 5);
+        assert!(results[4].is_ok());
+        assert_eq!(iter_codes_in(b"").count(), 0);
+    }
+}
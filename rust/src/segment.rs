@@ -0,0 +1,463 @@
+//! Distances from points to great-circle segments
+//!
+//! Building blocks for corridor checks and route-adherence monitoring: how
+//! far a cell lies off the great circle through a segment, and from the
+//! segment itself. All functions take Grid9 codes and work on a sphere of
+//! the mean Earth radius, like [`crate::calculate_distance`].
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::uniform_precision_compressor::{
+    from_vector, haversine_distance, unit_vector, unpack_indices, validate_coordinates, EARTH_RADIUS_M,
+};
+use crate::{
+    cells_along, decode, Coordinate, Grid9Bucket, Grid9Code, Grid9Error, Grid9Region, Result, MAX_LAT_INDEX, MAX_LON_INDEX,
+};
+
+/// Route segments grouped under one bounding circle by [`RouteIndex`]
+const SEGMENTS_PER_RUN: usize = 32;
+
+/// Signed distance in meters from a cell to the great circle through a segment.
+///
+/// Positive values lie to the right of the direction of travel from
+/// `segment_start` to `segment_end`, negative values to the left. The
+/// great circle continues past the segment's ends; see
+/// [`point_segment_distance`] for the distance to the segment itself.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code of the point
+/// * `segment_start` - Grid9 code where the segment starts
+/// * `segment_end` - Grid9 code where the segment ends (a different cell)
+///
+/// # Example
+/// ```rust
+/// use grid9::{cross_track_distance, encode};
+///
+/// // Northbound along 0° longitude; a point 0.1° east lies ~11 km to the right
+/// let (start, end) = (encode(0.0, 0.0, false)?, encode(1.0, 0.0, false)?);
+/// let offset = cross_track_distance(&encode(0.5, 0.1, false)?, &start, &end)?;
+/// assert!((offset - 11_120.0).abs() < 10.0);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn cross_track_distance(encoded: &str, segment_start: &str, segment_end: &str) -> Result<f64> {
+    let [point, start, end] = decode_all([encoded, segment_start, segment_end])?;
+    let normal = segment_normal(start, end)?;
+    Ok(cross_track_angle(point, normal) * EARTH_RADIUS_M)
+}
+
+/// Signed distance in meters from a segment's start to the point of its
+/// great circle nearest a cell.
+///
+/// Negative before `segment_start`, larger than the segment length beyond
+/// `segment_end`, which makes the fraction of a leg completed a single
+/// division.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code of the point
+/// * `segment_start` - Grid9 code where the segment starts
+/// * `segment_end` - Grid9 code where the segment ends (a different cell)
+///
+/// # Example
+/// ```rust
+/// use grid9::{along_track_distance, calculate_distance, encode};
+///
+/// let (depot, customer) = (encode(51.5074, -0.1278, false)?, encode(52.2053, 0.1218, false)?);
+/// let vehicle = encode(51.85, 0.02, false)?;
+/// let done = along_track_distance(&vehicle, &depot, &customer)? / calculate_distance(&depot, &customer)?;
+/// assert!((0.45..0.55).contains(&done));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn along_track_distance(encoded: &str, segment_start: &str, segment_end: &str) -> Result<f64> {
+    let [point, start, end] = decode_all([encoded, segment_start, segment_end])?;
+    let normal = segment_normal(start, end)?;
+    Ok(along_track_angle(point, start, normal) * EARTH_RADIUS_M)
+}
+
+/// Distance in meters from a cell to the nearest point of a segment.
+///
+/// Equals the absolute cross-track distance when the cell projects onto the
+/// segment, and the distance to the nearer end otherwise. A segment whose
+/// ends are the same cell is treated as that point.
+///
+/// # Example
+/// ```rust
+/// use grid9::{calculate_distance, encode, point_segment_distance};
+///
+/// let (start, end) = (encode(0.0, 0.0, false)?, encode(1.0, 0.0, false)?);
+/// let beyond = encode(2.0, 0.0, false)?;
+/// assert_eq!(point_segment_distance(&beyond, &start, &end)?, calculate_distance(&beyond, &end)?);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn point_segment_distance(encoded: &str, segment_start: &str, segment_end: &str) -> Result<f64> {
+    let [point, start, end] = decode_all([encoded, segment_start, segment_end])?;
+    Ok(closest_on_segment(point, start, end).1)
+}
+
+/// Closest point of a segment to a point, and the distance to it in meters.
+pub(crate) fn closest_on_segment(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> ((f64, f64), f64) {
+    let to_start = haversine_distance(point.0, point.1, start.0, start.1);
+    let Ok(normal) = segment_normal(start, end) else {
+        return (start, to_start);
+    };
+
+    let along = along_track_angle(point, start, normal);
+    let length = haversine_distance(start.0, start.1, end.0, end.1) / EARTH_RADIUS_M;
+    if along > 0.0 && along < length {
+        // Rotate `start` along the great circle by the along-track angle
+        let (a, forward) = (unit_vector(start.0, start.1), cross(unit_vector(start.0, start.1), normal));
+        let projected = from_vector(std::array::from_fn(|i| along.cos() * a[i] + along.sin() * forward[i]));
+        (projected, cross_track_angle(point, normal).abs() * EARTH_RADIUS_M)
+    } else {
+        let to_end = haversine_distance(point.0, point.1, end.0, end.1);
+        if to_start <= to_end {
+            (start, to_start)
+        } else {
+            (end, to_end)
+        }
+    }
+}
+
+/// Closest point of a route to a cell.
+///
+/// Builds a [`RouteIndex`] for a single query; keep one around to look up
+/// many cells against the same route.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code of the point
+/// * `route` - Route vertices, at least one
+///
+/// # Returns
+/// The cell of the closest point on the route, the distance to it in
+/// meters, and the index `i` of the segment `route[i]`–`route[i + 1]` it
+/// lies on (0 for a single-point route)
+///
+/// # Example
+/// ```rust
+/// use grid9::{closest_point_on_route, encode, Coordinate};
+///
+/// let route = [Coordinate::new(0.0, 0.0), Coordinate::new(0.0, 1.0), Coordinate::new(1.0, 1.0)];
+/// let (closest, distance_m, segment) = closest_point_on_route(&encode(0.5, 1.1, false)?, &route)?;
+/// assert_eq!(segment, 1);
+/// assert!((distance_m - 11_120.0).abs() < 20.0);
+/// assert_eq!(closest, grid9::Grid9Code::encode(0.5, 1.0)?);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn closest_point_on_route(encoded: &str, route: &[Coordinate]) -> Result<(Grid9Code, f64, usize)> {
+    RouteIndex::new(route)?.closest_point(encoded)
+}
+
+/// A route prepared for repeated closest-point queries
+///
+/// Consecutive segments are grouped into runs with a bounding circle each;
+/// a query visits runs nearest first and stops once no remaining run can
+/// hold a closer point, so most segments of a long route are never
+/// examined.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RouteIndex {
+    points: Vec<(f64, f64)>,
+    runs: Vec<Run>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Run {
+    /// Index of the run's first segment
+    first_segment: usize,
+    center: (f64, f64),
+    radius_m: f64,
+}
+
+impl RouteIndex {
+    /// Indexes a route of at least one vertex
+    pub fn new(route: &[Coordinate]) -> Result<Self> {
+        if route.is_empty() {
+            return Err(Grid9Error::EmptyInput);
+        }
+        for point in route {
+            validate_coordinates(point.lat, point.lon)?;
+        }
+
+        let points: Vec<(f64, f64)> = route.iter().map(|point| (point.lat, point.lon)).collect();
+        let segment_count = points.len().saturating_sub(1).max(1);
+        let runs = (0..segment_count)
+            .step_by(SEGMENTS_PER_RUN)
+            .map(|first_segment| {
+                let vertices = &points[first_segment..(first_segment + SEGMENTS_PER_RUN + 1).min(points.len())];
+                let center = vertices[0];
+                let radius_m = vertices
+                    .iter()
+                    .map(|vertex| haversine_distance(center.0, center.1, vertex.0, vertex.1))
+                    .fold(0.0, f64::max);
+                // A circle holds the great-circle arcs between its points only
+                // while it is smaller than a hemisphere
+                let radius_m = if radius_m < EARTH_RADIUS_M { radius_m } else { f64::INFINITY };
+                Run { first_segment, center, radius_m }
+            })
+            .collect();
+
+        Ok(Self { points, runs })
+    }
+
+    /// Closest point of the route to a cell, see [`closest_point_on_route`]
+    pub fn closest_point(&self, encoded: &str) -> Result<(Grid9Code, f64, usize)> {
+        let ((lat, lon), distance, segment) = self.nearest(decode(encoded)?);
+        Ok((Grid9Code::encode(lat, lon)?, distance, segment))
+    }
+
+    /// Closest route point to (lat, lon), its distance and segment index
+    fn nearest(&self, point: (f64, f64)) -> ((f64, f64), f64, usize) {
+        let mut candidates: Vec<(f64, &Run)> = self
+            .runs
+            .iter()
+            .map(|run| {
+                let to_center = haversine_distance(point.0, point.1, run.center.0, run.center.1);
+                ((to_center - run.radius_m).max(0.0), run)
+            })
+            .collect();
+        candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut best = (self.points[0], f64::INFINITY, 0);
+        for (lower_bound, run) in candidates {
+            if lower_bound >= best.1 {
+                break;
+            }
+            let last_segment = (run.first_segment + SEGMENTS_PER_RUN).min(self.points.len().saturating_sub(1));
+            for segment in run.first_segment..last_segment.max(run.first_segment + 1) {
+                let start = self.points[segment];
+                let end = self.points.get(segment + 1).copied().unwrap_or(start);
+                let (closest, distance) = closest_on_segment(point, start, end);
+                if distance < best.1 {
+                    best = (closest, distance, segment);
+                }
+            }
+        }
+
+        best
+    }
+}
+
+/// Cells within `width_m` of a route, see [`corridor_cells_at`]
+pub fn corridor_cells(route: &[Coordinate], width_m: f64) -> Result<Grid9Region> {
+    corridor_cells_at(route, width_m, 0)
+}
+
+/// Buckets of `level` within `width_m` of a route, e.g. everything within
+/// 25 m of a pipeline.
+///
+/// Errs on the side of inclusion: a bucket is kept if any part of it may lie
+/// within the buffer, so buckets up to half a bucket diagonal farther out can
+/// appear. The work done grows with the number of buckets returned, which is
+/// roughly route length × 2 × `width_m` / bucket area.
+///
+/// # Arguments
+/// * `route` - Route vertices, at least one
+/// * `width_m` - Buffer distance on each side of the route in meters
+/// * `level` - Bucket level (0 = single Grid9 cells)
+///
+/// # Example
+/// ```rust
+/// use grid9::{corridor_cells_at, encode, Coordinate};
+///
+/// let pipeline = [Coordinate::new(52.0, 5.0), Coordinate::new(52.0, 5.01)];
+/// let corridor = corridor_cells_at(&pipeline, 25.0, 2)?;
+/// assert!(corridor.contains(&encode(52.0002, 5.005, false)?)?); // ~22 m north
+/// assert!(!corridor.contains(&encode(52.0006, 5.005, false)?)?); // ~67 m north
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn corridor_cells_at(route: &[Coordinate], width_m: f64, level: u8) -> Result<Grid9Region> {
+    if !width_m.is_finite() || width_m < 0.0 {
+        return Err(Grid9Error::InvalidRadius(width_m));
+    }
+    let index = RouteIndex::new(route)?;
+    let mut region = Grid9Region::new(level)?;
+
+    // Flood fill outwards from the buckets the route passes through
+    let mut queue: VecDeque<Grid9Bucket> = cells_along(route, level)?.into();
+    let mut visited: HashSet<u64> = queue.iter().map(Grid9Bucket::key).collect();
+    let step = 1u32 << level;
+    while let Some(bucket) = queue.pop_front() {
+        region.insert(bucket)?;
+
+        let (lat_index, lon_index) = unpack_indices(bucket.key());
+        let neighbours = [
+            (lat_index.checked_add(step).filter(|&lat| lat <= MAX_LAT_INDEX), Some(lon_index)),
+            (lat_index.checked_sub(step), Some(lon_index)),
+            (Some(lat_index), lon_index.checked_add(step).filter(|&lon| lon <= MAX_LON_INDEX)),
+            (Some(lat_index), lon_index.checked_sub(step)),
+        ];
+        for (lat_index, lon_index) in neighbours {
+            let (Some(lat_index), Some(lon_index)) = (lat_index, lon_index) else {
+                continue;
+            };
+            let neighbour = Grid9Bucket::from_indices(lat_index, lon_index, level)?;
+            if !visited.insert(neighbour.key()) {
+                continue;
+            }
+
+            let center = neighbour.center();
+            let (height, width) = neighbour.dimensions_m();
+            let (_, distance, _) = index.nearest((center.lat, center.lon));
+            if distance <= width_m + height.hypot(width) / 2.0 {
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    Ok(region)
+}
+
+/// Decodes codes to (lat, lon) pairs
+pub(crate) fn decode_all<const N: usize>(encoded: [&str; N]) -> Result<[(f64, f64); N]> {
+    let mut points = [(0.0, 0.0); N];
+    for (point, code) in points.iter_mut().zip(encoded) {
+        *point = decode(code)?;
+    }
+    Ok(points)
+}
+
+/// Unit normal of the great circle from `start` to `end`, pointing so that
+/// points right of the direction of travel have positive components.
+pub(crate) fn segment_normal(start: (f64, f64), end: (f64, f64)) -> Result<[f64; 3]> {
+    let (a, b) = (unit_vector(start.0, start.1), unit_vector(end.0, end.1));
+    let normal = cross(b, a);
+    let length = dot(normal, normal).sqrt();
+    if length < 1e-12 {
+        return Err(Grid9Error::InvalidInput(
+            "segment ends coincide or are antipodal, so its great circle is undefined".into(),
+        ));
+    }
+    Ok(normal.map(|component| component / length))
+}
+
+/// Signed angle in radians between a point and a great circle
+pub(crate) fn cross_track_angle(point: (f64, f64), normal: [f64; 3]) -> f64 {
+    dot(unit_vector(point.0, point.1), normal).clamp(-1.0, 1.0).asin()
+}
+
+/// Signed angle in radians from `start` to the projection of a point onto
+/// the great circle, positive in the direction of travel.
+pub(crate) fn along_track_angle(point: (f64, f64), start: (f64, f64), normal: [f64; 3]) -> f64 {
+    let (p, a) = (unit_vector(point.0, point.1), unit_vector(start.0, start.1));
+    // The direction of travel at `start` is start × normal
+    let forward = cross(a, normal);
+    dot(p, forward).atan2(dot(p, a))
+}
+
+fn cross(u: [f64; 3], v: [f64; 3]) -> [f64; 3] {
+    [u[1] * v[2] - u[2] * v[1], u[2] * v[0] - u[0] * v[2], u[0] * v[1] - u[1] * v[0]]
+}
+
+fn dot(u: [f64; 3], v: [f64; 3]) -> f64 {
+    u[0] * v[0] + u[1] * v[1] + u[2] * v[2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calculate_distance, encode, BoundingBox};
+
+    fn code(lat: f64, lon: f64) -> String {
+        encode(lat, lon, false).unwrap()
+    }
+
+    #[test]
+    fn test_cross_track_sign_and_magnitude() {
+        // Eastbound along the equator: north is left
+        let (start, end) = (code(0.0, 0.0), code(0.0, 10.0));
+        let north = cross_track_distance(&code(1.0, 5.0), &start, &end).unwrap();
+        let south = cross_track_distance(&code(-1.0, 5.0), &start, &end).unwrap();
+        assert!((north + 111_195.0).abs() < 20.0, "{}", north);
+        assert!((south - 111_195.0).abs() < 20.0, "{}", south);
+
+        // Reversing the segment flips the sign
+        let reversed = cross_track_distance(&code(1.0, 5.0), &end, &start).unwrap();
+        assert!((reversed + north).abs() < 1e-6);
+
+        // Points on the great circle beyond the segment are on it
+        assert!(cross_track_distance(&code(0.0, 40.0), &start, &end).unwrap().abs() < 1.0);
+        assert!(cross_track_distance(&start, &start, &start).is_err());
+    }
+
+    #[test]
+    fn test_point_segment_distance() {
+        // A diagonal segment across Europe
+        let (start, end) = (code(48.8566, 2.3522), code(52.5200, 13.4050));
+        let inside = code(50.1109, 8.6821); // Frankfurt, beside the segment
+        let distance = point_segment_distance(&inside, &start, &end).unwrap();
+        assert!((distance - cross_track_distance(&inside, &start, &end).unwrap().abs()).abs() < 1e-6);
+        assert!(distance < calculate_distance(&inside, &start).unwrap());
+
+        let before = code(48.0, -2.0);
+        assert_eq!(point_segment_distance(&before, &start, &end).unwrap(), calculate_distance(&before, &start).unwrap());
+        assert_eq!(point_segment_distance(&before, &start, &start).unwrap(), calculate_distance(&before, &start).unwrap());
+        assert!(point_segment_distance("invalid", &start, &end).is_err());
+    }
+
+    #[test]
+    fn test_closest_point_on_route() {
+        // A long zigzag route: the index must agree with a linear scan
+        let route: Vec<Coordinate> =
+            (0..500).map(|i| Coordinate::new(45.0 + (i % 2) as f64 * 0.01, 5.0 + i as f64 * 0.01)).collect();
+        let index = RouteIndex::new(&route).unwrap();
+        for (lat, lon) in [(45.003, 6.234), (44.0, 3.0), (46.0, 12.0), (45.5, 7.5)] {
+            let query = code(lat, lon);
+            let (closest, distance, segment) = index.closest_point(&query).unwrap();
+
+            let point = decode(&query).unwrap();
+            let linear = (0..route.len() - 1)
+                .map(|i| closest_on_segment(point, (route[i].lat, route[i].lon), (route[i + 1].lat, route[i + 1].lon)).1)
+                .fold(f64::INFINITY, f64::min);
+            assert!((distance - linear).abs() < 1e-6, "{} vs {}", distance, linear);
+            let (start, end) = (&route[segment], &route[segment + 1]);
+            let ((lat, lon), _) = closest_on_segment(point, (start.lat, start.lon), (end.lat, end.lon));
+            assert_eq!(closest, Grid9Code::encode(lat, lon).unwrap());
+        }
+
+        let single = [Coordinate::new(1.0, 1.0)];
+        let (closest, _, segment) = closest_point_on_route(&code(2.0, 2.0), &single).unwrap();
+        assert_eq!((closest, segment), (Grid9Code::encode(1.0, 1.0).unwrap(), 0));
+        assert_eq!(closest_point_on_route(&code(2.0, 2.0), &[]), Err(Grid9Error::EmptyInput));
+    }
+
+    #[test]
+    fn test_corridor_cells() {
+        let route = [Coordinate::new(40.0, -3.0), Coordinate::new(40.0, -2.999), Coordinate::new(40.0005, -2.998)];
+        let corridor = corridor_cells(&route, 10.0).unwrap();
+        let index = RouteIndex::new(&route).unwrap();
+
+        // Every kept cell may touch the buffer, and every cell whose center is
+        // inside the buffer is kept
+        for bucket in corridor.buckets() {
+            let center = bucket.center();
+            let (_, distance, _) = index.nearest((center.lat, center.lon));
+            assert!(distance <= 10.0 + 3.0, "{}", distance);
+        }
+        let near = Grid9Region::from_bbox(&BoundingBox::new(39.9997, 40.0008, -3.0003, -2.9977), 0).unwrap();
+        for bucket in near.buckets() {
+            let center = bucket.center();
+            if index.nearest((center.lat, center.lon)).1 <= 10.0 {
+                assert!(corridor.contains_code(bucket.to_code(false).parse().unwrap()));
+            }
+        }
+
+        // Zero width still keeps every traversed bucket
+        let thin = corridor_cells_at(&route, 0.0, 4).unwrap();
+        assert!(cells_along(&route, 4).unwrap().iter().all(|bucket| thin.contains_code(bucket.to_code(false).parse().unwrap())));
+        assert_eq!(corridor_cells(&route, -1.0), Err(Grid9Error::InvalidRadius(-1.0)));
+        assert_eq!(corridor_cells(&[], 1.0), Err(Grid9Error::EmptyInput));
+    }
+
+    #[test]
+    fn test_along_track_distance() {
+        let (start, end) = (code(0.0, 0.0), code(0.0, 10.0));
+        let length = calculate_distance(&start, &end).unwrap();
+
+        let middle = along_track_distance(&code(2.0, 5.0), &start, &end).unwrap();
+        assert!((middle / length - 0.5).abs() < 1e-3, "{}", middle / length);
+        assert!(along_track_distance(&code(0.0, -3.0), &start, &end).unwrap() < 0.0);
+        assert!(along_track_distance(&code(0.0, 12.0), &start, &end).unwrap() > length);
+        assert!((along_track_distance(&end, &start, &end).unwrap() - length).abs() < 1e-6);
+        assert!(along_track_distance(&end, &start, &start).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+//! Short codes relative to a reference location
+//!
+//! Like short Plus Codes, a short Grid9 code leaves out what the reader
+//! already knows. It keeps only the low bits of both grid indices, spelled
+//! in 4 to 8 characters; [`expand`] restores the high bits from the
+//! reference location by picking the matching cell nearest to it.
+//!
+//! | Characters | Lat / lon bits | Recoverable within (lat × lon at the equator) |
+//! |------------|----------------|-----------------------------------------------|
+//! | 4 | 10 / 10 | ±1.2 km × ±1.2 km |
+//! | 5 | 12 / 13 | ±4.9 km × ±9.8 km |
+//! | 6 | 15 / 15 | ±39 km × ±39 km |
+//! | 7 | 17 / 18 | ±156 km × ±313 km |
+//! | 8 | 20 / 20 | ±1250 km × ±1250 km |
+//!
+//! East-west ranges shrink with the cosine of the latitude. [`shorten`] only
+//! drops characters while the code lies within these ranges of the
+//! reference, which leaves an equal margin for expanding with a slightly
+//! different reference (another point in the same city, say).
+
+use crate::uniform_precision_compressor::{
+    canonicalize, digit_value, format_packed, pack_indices, parse_packed, quantize, unpack_indices, validate_coordinates,
+    BASE32_ALPHABET, LAT_BITS, LON_BITS,
+};
+use crate::{Coordinate, Grid9Error, Result};
+
+/// Fewest characters of a short code
+pub const MIN_SHORT_CODE_LEN: usize = 4;
+
+/// Characters of a full code
+const CODE_LEN: usize = 9;
+
+/// Shortens a code for display next to a known location.
+///
+/// # Arguments
+/// * `code` - Grid9 code (any accepted format)
+/// * `reference` - Location the reader knows, such as the city center
+///
+/// # Returns
+/// The shortest short code that [`expand`] restores from any reference near
+/// `reference`, or the full compact code if the code is too far away
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, expand, shorten, Coordinate};
+///
+/// let city_center = Coordinate::new(48.8566, 2.3522);
+/// let code = encode(48.8584, 2.2945, false)?; // Eiffel Tower
+///
+/// let short = shorten(&code, &city_center)?;
+/// assert_eq!(short.len(), 5);
+/// // Any nearby reference works, e.g. the courier's current position
+/// assert_eq!(expand(&short, &Coordinate::new(48.87, 2.33))?, code);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn shorten(code: &str, reference: &Coordinate) -> Result<String> {
+    let packed = parse_packed(code)?;
+    let (lat_index, lon_index) = unpack_indices(packed);
+    let (ref_lat, ref_lon) = reference_indices(reference)?;
+
+    for len in MIN_SHORT_CODE_LEN..CODE_LEN {
+        let (lat_bits, lon_bits) = short_bits(len);
+        let lat_offset = lat_index.abs_diff(ref_lat);
+        let lon_offset = lon_index.wrapping_sub(ref_lon) & low_mask(LON_BITS);
+        let lon_offset = lon_offset.min((1 << LON_BITS) - lon_offset);
+
+        if lat_offset < 1 << (lat_bits - 2) && lon_offset < 1 << (lon_bits - 2) {
+            let short = ((lat_index & low_mask(lat_bits)) as u64) << lon_bits | (lon_index & low_mask(lon_bits)) as u64;
+            return Ok(spell(short, len));
+        }
+    }
+
+    Ok(format_packed(packed, false))
+}
+
+/// Restores a full code from a short code and a reference location.
+///
+/// Full codes are accepted too and returned in compact form, so stored
+/// values can be expanded without checking their length first.
+///
+/// # Arguments
+/// * `short_code` - Short code from [`shorten`] (dashes are tolerated)
+/// * `reference` - Location near the one the code was shortened against
+///
+/// # Returns
+/// The full compact code, or an error for invalid characters or a length
+/// other than 4-9 characters
+pub fn expand(short_code: &str, reference: &Coordinate) -> Result<String> {
+    let clean = canonicalize(short_code);
+    let len = clean.chars().count();
+    if len == CODE_LEN {
+        return Ok(format_packed(parse_packed(&clean)?, false));
+    }
+    if len == 0 {
+        return Err(Grid9Error::EmptyInput);
+    }
+    if !(MIN_SHORT_CODE_LEN..CODE_LEN).contains(&len) {
+        return Err(Grid9Error::InvalidLength(len));
+    }
+
+    let short = clean.chars().try_fold(0u64, |short, ch| {
+        let digit = digit_value(ch).ok_or(Grid9Error::InvalidCharacter(ch))?;
+        Ok::<_, Grid9Error>((short << 5) | digit as u64)
+    })?;
+    let (lat_bits, lon_bits) = short_bits(len);
+    let (ref_lat, ref_lon) = reference_indices(reference)?;
+
+    let lat_index = nearest(ref_lat, (short >> lon_bits) as u32, lat_bits);
+    let lat_index = if lat_index < 0 {
+        lat_index + (1 << lat_bits)
+    } else if lat_index > low_mask(LAT_BITS) as i64 {
+        lat_index - (1 << lat_bits)
+    } else {
+        lat_index
+    };
+    let lon_index = nearest(ref_lon, short as u32 & low_mask(lon_bits), lon_bits) & low_mask(LON_BITS) as i64;
+
+    Ok(format_packed(pack_indices(lat_index as u32, lon_index as u32), false))
+}
+
+/// Latitude and longitude bits kept by a short code of `len` characters
+fn short_bits(len: usize) -> (u32, u32) {
+    let bits = 5 * len as u32;
+    (bits / 2, bits - bits / 2)
+}
+
+fn low_mask(bits: u32) -> u32 {
+    (1 << bits) - 1
+}
+
+fn reference_indices(reference: &Coordinate) -> Result<(u32, u32)> {
+    validate_coordinates(reference.lat, reference.lon)?;
+    Ok(quantize(reference.lat, reference.lon))
+}
+
+/// Index ending in the `bits` low bits `low` that is nearest to `reference`,
+/// in `reference - 2^(bits-1) .. reference + 2^(bits-1)`
+fn nearest(reference: u32, low: u32, bits: u32) -> i64 {
+    let window = 1i64 << bits;
+    let start = reference as i64 - window / 2;
+    start + (low as i64 - start).rem_euclid(window)
+}
+
+/// Spells the low `5 * len` bits of `value` in `len` characters
+fn spell(value: u64, len: usize) -> String {
+    (0..len)
+        .rev()
+        .map(|i| BASE32_ALPHABET[((value >> (5 * i)) & 0x1F) as usize] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, encode, remove_formatting};
+
+    #[test]
+    fn test_roundtrip_and_length() {
+        let reference = Coordinate::new(40.7128, -74.0060);
+        let cases = [
+            (40.7130, -74.0050, 4),
+            (40.7306, -73.9866, 5), // East Village
+            (40.6413, -73.7781, 6), // JFK
+            (39.9526, -75.1652, 7), // Philadelphia
+            (51.5074, -0.1278, 9),  // London
+        ];
+        for (lat, lon, len) in cases {
+            let code = encode(lat, lon, false).unwrap();
+            let short = shorten(&code, &reference).unwrap();
+            assert_eq!(short.len(), len, "{} {}", lat, lon);
+            assert_eq!(expand(&short, &reference).unwrap(), code);
+        }
+        let london = encode(51.5074, -0.1278, true).unwrap();
+        assert_eq!(expand(&london, &reference).unwrap(), remove_formatting(&london));
+    }
+
+    #[test]
+    fn test_reference_margin() {
+        let reference = Coordinate::new(-33.8688, 151.2093);
+        for i in 0..200 {
+            let (dlat, dlon) = ((i % 20) as f64 * 0.004 - 0.04, (i / 20) as f64 * 0.008 - 0.04);
+            let code = encode(-33.8688 + dlat, 151.2093 + dlon, false).unwrap();
+            let short = shorten(&code, &reference).unwrap();
+            // The shortening reference itself may lie on the far side of the code
+            let (code_lat, code_lon) = decode(&code).unwrap();
+            let mirrored = Coordinate::new(2.0 * reference.lat - code_lat, 2.0 * reference.lon - code_lon);
+            assert_eq!(expand(&short, &Coordinate::new(code_lat, code_lon)).unwrap(), code);
+            assert_eq!(expand(&short, &mirrored).unwrap(), code, "{}", short);
+        }
+    }
+
+    #[test]
+    fn test_antimeridian_and_poles() {
+        let code = encode(-16.5, -179.999, false).unwrap();
+        let short = shorten(&code, &Coordinate::new(-16.5, 179.999)).unwrap();
+        assert_eq!(short.len(), 4);
+        assert_eq!(expand(&short, &Coordinate::new(-16.5, 179.99)).unwrap(), code);
+
+        for lat in [90.0, -90.0] {
+            let code = encode(lat, 10.0, false).unwrap();
+            let reference = Coordinate::new(lat - lat.signum() * 0.01, 10.0);
+            let short = shorten(&code, &reference).unwrap();
+            assert!(short.len() < CODE_LEN);
+            assert_eq!(expand(&short, &Coordinate::new(lat, 10.0)).unwrap(), code);
+            assert_eq!(expand(&short, &reference).unwrap(), code);
+        }
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let reference = Coordinate::new(0.0, 0.0);
+        assert_eq!(expand("ABC", &reference), Err(Grid9Error::InvalidLength(3)));
+        assert_eq!(expand("ABCDEFGHJK", &reference), Err(Grid9Error::InvalidLength(10)));
+        assert_eq!(expand("", &reference), Err(Grid9Error::EmptyInput));
+        assert_eq!(expand("AB-UD", &reference), Err(Grid9Error::InvalidCharacter('U')));
+        // Lengths count characters, not bytes
+        assert_eq!(expand("Q7KH2BBY\u{00DC}", &reference), Err(Grid9Error::InvalidCharacter('\u{00DC}')));
+        assert_eq!(expand("AB\u{00DC}", &reference), Err(Grid9Error::InvalidLength(3)));
+        assert_eq!(expand("ab10", &reference), Err(Grid9Error::InvalidCharacter('a')));
+        assert_eq!(expand("AB-10", &reference), expand("AB10", &reference));
+        assert!(shorten("Q7KH2BBYF", &Coordinate::new(91.0, 0.0)).is_err());
+    }
+}
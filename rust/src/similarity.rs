@@ -0,0 +1,207 @@
+//! Similar and near-duplicate track search
+//!
+//! [`find_similar_tracks`] finds the tracks of a corpus within a discrete
+//! Fréchet distance of a query track, and [`dedup_tracks`] collapses
+//! near-duplicate traces. Exact comparisons are quadratic in the track
+//! lengths, so both first compare cell signatures: the buckets a track
+//! passes through, at a level whose buckets are at least as large as the
+//! threshold. Two tracks within the threshold of each other only visit
+//! buckets next to buckets the other visits, so tracks failing that test
+//! are skipped without losing matches.
+
+use std::collections::HashSet;
+
+use crate::uniform_precision_compressor::{EARTH_RADIUS_M, LAT_BITS, LON_BITS};
+use crate::{frechet_distance, Grid9Code, Grid9Error, Result, Timestamp, Track, MAX_LEVEL};
+
+/// Buckets a track passes through, as (latitude, longitude) bucket numbers
+struct Signature {
+    buckets: HashSet<(u32, u32)>,
+}
+
+impl Signature {
+    fn new(codes: &[Grid9Code], level: u8) -> Self {
+        let buckets = codes
+            .iter()
+            .map(|code| {
+                let (lat_index, lon_index) = code.indices();
+                (lat_index >> level, lon_index >> level)
+            })
+            .collect();
+        Self { buckets }
+    }
+
+    /// True if every bucket of `self` is in or next to a bucket of `other`
+    fn near(&self, other: &Signature, level: u8) -> bool {
+        let lon_buckets = 1u32 << (LON_BITS - level as u32);
+        self.buckets.iter().all(|&(lat, lon)| {
+            (lat.saturating_sub(1)..=lat + 1).any(|lat| {
+                [lon_buckets - 1, 0, 1].iter().any(|&step| other.buckets.contains(&(lat, (lon + step) % lon_buckets)))
+            })
+        })
+    }
+}
+
+/// Finds the tracks of a corpus within a discrete Fréchet distance of a
+/// query track.
+///
+/// # Arguments
+/// * `query` - Track to match, e.g. a new upload
+/// * `corpus` - Tracks to search, e.g. the archive
+/// * `threshold_m` - Largest Fréchet distance in meters (see
+///   [`crate::frechet_distance`]) counted as similar
+///
+/// # Returns
+/// Indices into `corpus` of the non-empty similar tracks with their
+/// distances, nearest first. Fails with `InvalidInput` for a negative or
+/// NaN threshold and `EmptyInput` for an empty query.
+///
+/// # Example
+/// ```rust
+/// use grid9::{find_similar_tracks, Grid9Code, Track};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let trace = |lon: f64| -> Result<Track<_>, grid9::Grid9Error> {
+///     let mut track = Track::new();
+///     for i in 0..20 {
+///         track.push(Grid9Code::encode(48.85 + i as f64 * 0.001, lon)?, UNIX_EPOCH + Duration::from_secs(10 * i))?;
+///     }
+///     Ok(track)
+/// };
+/// let archive = [trace(2.3500)?, trace(2.3502)?, trace(2.3600)?];
+///
+/// let similar = find_similar_tracks(&trace(2.3501)?, &archive, 25.0)?;
+/// assert_eq!(similar.iter().map(|&(index, _)| index).collect::<Vec<_>>(), [0, 1]);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn find_similar_tracks<T: Timestamp, U: Timestamp>(query: &Track<T>, corpus: &[Track<U>], threshold_m: f64) -> Result<Vec<(usize, f64)>> {
+    if threshold_m.is_nan() || threshold_m < 0.0 {
+        return Err(Grid9Error::InvalidInput(format!("invalid similarity threshold {}", threshold_m).into()));
+    }
+    let query: Vec<Grid9Code> = query.codes().collect();
+    if query.is_empty() {
+        return Err(Grid9Error::EmptyInput);
+    }
+    let level = signature_level(&query, threshold_m);
+    let query_signature = level.map(|level| Signature::new(&query, level));
+
+    let mut similar = Vec::new();
+    for (index, track) in corpus.iter().enumerate() {
+        let codes: Vec<Grid9Code> = track.codes().collect();
+        if codes.is_empty() {
+            continue;
+        }
+        if let (Some(level), Some(query_signature)) = (level, &query_signature) {
+            let signature = Signature::new(&codes, level);
+            if !signature.near(query_signature, level) || !query_signature.near(&signature, level) {
+                continue;
+            }
+        }
+        let distance = frechet_distance(&query, &codes)?;
+        if distance <= threshold_m {
+            similar.push((index, distance));
+        }
+    }
+    similar.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+    Ok(similar)
+}
+
+/// Collapses near-duplicate tracks.
+///
+/// Tracks are taken in order; a track within `threshold_m` (discrete
+/// Fréchet distance) of a track already kept is dropped as its duplicate.
+///
+/// # Returns
+/// Indices of the tracks to keep, in order. Empty tracks are dropped.
+pub fn dedup_tracks<T: Timestamp>(tracks: &[Track<T>], threshold_m: f64) -> Result<Vec<usize>> {
+    let mut kept: Vec<usize> = Vec::new();
+    let mut kept_tracks: Vec<Track<T>> = Vec::new();
+    for (index, track) in tracks.iter().enumerate() {
+        if track.is_empty() {
+            continue;
+        }
+        if find_similar_tracks(track, &kept_tracks, threshold_m)?.is_empty() {
+            kept.push(index);
+            kept_tracks.push(track.clone());
+        }
+    }
+    Ok(kept)
+}
+
+/// Finest level whose buckets are at least `threshold_m` tall and wide
+/// everywhere the track or tracks within the threshold of it can be, or
+/// `None` if no level is (near the poles, or for huge thresholds)
+fn signature_level(codes: &[Grid9Code], threshold_m: f64) -> Option<u8> {
+    let radius = threshold_m / EARTH_RADIUS_M;
+    let max_lat = (codes.iter().map(|code| code.coordinates().0.abs()).fold(0.0, f64::max).to_radians() + radius).min(std::f64::consts::FRAC_PI_2);
+    // Largest latitude and longitude differences within the threshold
+    let max_dlat = radius.to_degrees();
+    let max_dlon = 2.0 * ((radius / 2.0).sin() / max_lat.cos()).min(1.0).asin().to_degrees();
+    let (lat_cell, lon_cell) = (180.0 / (1u64 << LAT_BITS) as f64, 360.0 / (1u64 << LON_BITS) as f64);
+    (0..MAX_LEVEL).find(|&level| {
+        let scale = (1u64 << level) as f64;
+        lat_cell * scale >= max_dlat && lon_cell * scale >= max_dlon
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn trace(points: &[(f64, f64)]) -> Track<SystemTime> {
+        let mut track = Track::new();
+        for (i, &(lat, lon)) in points.iter().enumerate() {
+            track.push(Grid9Code::encode(lat, lon).unwrap(), UNIX_EPOCH + Duration::from_secs(i as u64)).unwrap();
+        }
+        track
+    }
+
+    fn line(lat: f64, lon: f64, n: usize) -> Track<SystemTime> {
+        trace(&(0..n).map(|i| (lat + i as f64 * 0.0005, lon)).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_matches_exhaustive_search() {
+        let query = line(45.0, 7.0, 30);
+        let mut corpus: Vec<Track<SystemTime>> = (0..40).map(|i| line(45.0 + (i % 5) as f64 * 0.0002, 7.0 + i as f64 * 0.00007, 30)).collect();
+        corpus.push(Track::new());
+        corpus.push(trace(&[(45.0, 7.0), (45.0145, 7.0)])); // Same ends, sparse
+        let reversed: Vec<(f64, f64)> = (0..30).rev().map(|i| (45.0 + i as f64 * 0.0005, 7.0)).collect();
+        corpus.push(trace(&reversed));
+
+        for threshold_m in [0.0, 5.0, 30.0, 100.0, 500.0, 10_000.0] {
+            let mut expected: Vec<(usize, f64)> = corpus
+                .iter()
+                .enumerate()
+                .filter(|(_, track)| !track.is_empty())
+                .map(|(i, track)| (i, query.frechet_distance(track).unwrap()))
+                .filter(|&(_, distance)| distance <= threshold_m)
+                .collect();
+            expected.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+            assert_eq!(find_similar_tracks(&query, &corpus, threshold_m).unwrap(), expected, "{}", threshold_m);
+        }
+        assert_eq!(find_similar_tracks(&query, &corpus, 0.0).unwrap(), [(0, 0.0)]);
+    }
+
+    #[test]
+    fn test_antimeridian_and_poles() {
+        let east = trace(&[(-16.5, 179.9999), (-16.501, 179.9999)]);
+        let west = [trace(&[(-16.5, -179.9999), (-16.501, -179.9999)])];
+        assert_eq!(find_similar_tracks(&east, &west, 50.0).unwrap().len(), 1);
+
+        let polar = trace(&[(89.9999, 0.0), (89.9999, 90.0)]);
+        assert_eq!(signature_level(&polar.codes().collect::<Vec<_>>(), 50.0), None);
+        assert_eq!(find_similar_tracks(&polar, &[trace(&[(89.9999, 45.0)])], 50.0).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_dedup_and_invalid_input() {
+        let tracks = [line(10.0, 10.0, 10), line(10.0, 10.00005, 10), Track::new(), line(10.0, 10.01, 10), line(10.0, 10.0, 10)];
+        assert_eq!(dedup_tracks(&tracks, 20.0).unwrap(), [0, 3]);
+        assert_eq!(dedup_tracks(&tracks, 0.0).unwrap(), [0, 1, 3]);
+
+        assert!(matches!(find_similar_tracks(&tracks[0], &tracks, -1.0), Err(Grid9Error::InvalidInput(_))));
+        assert_eq!(find_similar_tracks(&tracks[2], &tracks, 1.0), Err(Grid9Error::EmptyInput));
+    }
+}
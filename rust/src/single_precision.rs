@@ -0,0 +1,126 @@
+//! `f32` variants of encode and decode
+//!
+//! For coordinates that already live as `f32` (GPU buffers, sensor packets,
+//! compact columnar stores), so callers do not widen whole arrays just to
+//! call the `f64` API. An `f32` has a 24-bit mantissa: near ±180° longitude
+//! its spacing is about 1.7 m and near ±90° latitude about 0.85 m, against
+//! Grid9 cells of about 4.8 m. Two consequences:
+//!
+//! * an `f32` coordinate is already rounded by up to half that spacing, so a
+//!   point within a meter of a cell edge may encode into the neighbouring
+//!   cell compared with its `f64` source value;
+//! * decoded coordinates carry up to the same extra error. [`decode_f32`]
+//!   rounds into the cell, so `encode_f32(decode_f32(code))` returns `code`.
+
+use crate::{decode, encode, Result};
+
+/// Encodes `f32` coordinates to a Grid9 string.
+///
+/// Same as [`encode`] on the widened values; see the module documentation
+/// for the precision implications.
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, encode_f32};
+///
+/// assert_eq!(encode_f32(40.7128, -74.0060, false)?, encode(40.7128, -74.0060, false)?);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn encode_f32(latitude: f32, longitude: f32, human_readable: bool) -> Result<String> {
+    encode(latitude as f64, longitude as f64, human_readable)
+}
+
+/// Decodes a Grid9 string to `f32` latitude and longitude.
+///
+/// The cell corner returned by [`decode`] is rounded to the nearest `f32`
+/// inside the cell, so re-encoding yields the same code.
+///
+/// # Example
+/// ```rust
+/// use grid9::{decode_f32, encode_f32};
+///
+/// let code = encode_f32(-33.8688, 151.2093, false)?;
+/// let (lat, lon) = decode_f32(&code)?;
+/// assert_eq!(encode_f32(lat, lon, false)?, code);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn decode_f32(encoded: &str) -> Result<(f32, f32)> {
+    let (latitude, longitude) = decode(encoded)?;
+    Ok((round_up_f32(latitude), round_up_f32(longitude)))
+}
+
+/// Encodes `[latitude, longitude]` pairs, failing on the first invalid pair.
+///
+/// # Arguments
+/// * `coordinates` - Interleaved `f32` pairs, e.g. a vertex buffer
+/// * `human_readable` - If true, returns XXX-XXX-XXX format with dashes
+pub fn batch_encode_f32(coordinates: &[[f32; 2]], human_readable: bool) -> Result<Vec<String>> {
+    coordinates.iter().map(|&[lat, lon]| encode_f32(lat, lon, human_readable)).collect()
+}
+
+/// Decodes Grid9 strings to `[latitude, longitude]` pairs, failing on the
+/// first invalid code.
+pub fn batch_decode_f32<S: AsRef<str>>(encoded: &[S]) -> Result<Vec<[f32; 2]>> {
+    encoded
+        .iter()
+        .map(|code| decode_f32(code.as_ref()).map(|(lat, lon)| [lat, lon]))
+        .collect()
+}
+
+/// Nearest `f32` not below `value` (within one `f32` step of it).
+fn round_up_f32(value: f64) -> f32 {
+    let rounded = value as f32;
+    if (rounded as f64) >= value {
+        return rounded;
+    }
+    // Step one unit in the last place towards +infinity
+    match rounded.to_bits() {
+        bits if rounded > 0.0 => f32::from_bits(bits + 1),
+        _ if rounded == 0.0 => f32::from_bits(1),
+        bits => f32::from_bits(bits - 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Grid9Error;
+
+    #[test]
+    fn test_round_trip() {
+        let points = [(40.7128, -74.0060), (-33.8688, 151.2093), (89.99999, 179.99999), (-90.0, -180.0), (0.0, 0.0)];
+        for (lat, lon) in points {
+            let code = encode_f32(lat, lon, false).unwrap();
+            let (decoded_lat, decoded_lon) = decode_f32(&code).unwrap();
+            assert_eq!(encode_f32(decoded_lat, decoded_lon, false).unwrap(), code);
+
+            let (exact_lat, exact_lon) = decode(&code).unwrap();
+            assert!((decoded_lat as f64 - exact_lat).abs() < 1e-5);
+            assert!((decoded_lon as f64 - exact_lon).abs() < 2e-5);
+        }
+    }
+
+    #[test]
+    fn test_round_up() {
+        for value in [-179.99999999, -0.000000001, 0.000000001, 1.0 / 3.0, 151.20930000001] {
+            let rounded = round_up_f32(value);
+            assert!(rounded as f64 >= value, "{}", value);
+            assert!((rounded as f64 - value).abs() < 2e-5);
+        }
+        assert_eq!(round_up_f32(0.5), 0.5);
+    }
+
+    #[test]
+    fn test_batches() {
+        let pairs = [[40.7128f32, -74.0060], [51.5074, -0.1278]];
+        let codes = batch_encode_f32(&pairs, true).unwrap();
+        assert_eq!(codes[0], encode_f32(40.7128, -74.0060, true).unwrap());
+
+        let decoded = batch_decode_f32(&codes).unwrap();
+        assert_eq!(batch_encode_f32(&decoded, true).unwrap(), codes);
+
+        assert_eq!(batch_encode_f32(&[[91.0, 0.0]], false), Err(Grid9Error::InvalidLatitude(91.0)));
+        assert!(batch_decode_f32(&["INVALID"]).is_err());
+        assert!(encode_f32(f32::NAN, 0.0, false).is_err());
+    }
+}
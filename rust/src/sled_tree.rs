@@ -0,0 +1,386 @@
+//! Durable spatial lookup on top of sled
+//!
+//! [`Grid9Tree`] stores values in a `sled::Tree` under [`crate::kv`] cell
+//! keys followed by a caller-chosen record id, so entries are laid out along
+//! the Morton curve and spatial queries become a handful of range scans.
+//! Query validation errors are returned up front; store errors surface per
+//! item from the returned iterators.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+use sled::IVec;
+
+use crate::uniform_precision_compressor::{haversine_distance, validate_coordinates, METERS_PER_DEGREE};
+use crate::{
+    bbox_contains_code_fast, bbox_key_ranges, decode_key, encode_key_with_suffix, BatchOperation, BboxIndices,
+    BoundingBox, Grid9Code, Grid9Error, Grid9Metrics, KvScan, Result, KEY_LEN,
+};
+
+/// Range scans issued per bounding box query
+const MAX_RANGES: usize = 16;
+
+/// A sled tree keyed by Grid9 cell
+#[derive(Clone)]
+pub struct Grid9Tree {
+    tree: sled::Tree,
+    metrics: Option<Arc<dyn Grid9Metrics>>,
+}
+
+impl fmt::Debug for Grid9Tree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Grid9Tree").field("tree", &self.tree).finish_non_exhaustive()
+    }
+}
+
+/// An entry returned by [`Grid9Tree`] queries
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeEntry {
+    /// Cell the entry is stored under
+    pub code: Grid9Code,
+    /// Full store key: cell key followed by the record id
+    pub key: IVec,
+    /// Stored value
+    pub value: IVec,
+}
+
+impl TreeEntry {
+    /// Record id the entry was inserted with
+    pub fn id(&self) -> &[u8] {
+        &self.key[KEY_LEN..]
+    }
+}
+
+impl Grid9Tree {
+    /// Wraps an existing sled tree
+    pub fn new(tree: sled::Tree) -> Self {
+        Self { tree, metrics: None }
+    }
+
+    /// Wraps an existing sled tree, reporting every query to `metrics` as a
+    /// [`BatchOperation::Query`] batch of the entries and store errors it
+    /// yielded, once its iterator is dropped.
+    pub fn with_metrics(tree: sled::Tree, metrics: Arc<dyn Grid9Metrics>) -> Self {
+        Self { tree, metrics: Some(metrics) }
+    }
+
+    /// Opens (or creates) the tree `name` of a sled database
+    pub fn open(db: &sled::Db, name: &str) -> sled::Result<Self> {
+        db.open_tree(name).map(Self::new)
+    }
+
+    /// The underlying sled tree
+    pub fn tree(&self) -> &sled::Tree {
+        &self.tree
+    }
+
+    /// Number of stored entries
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// True if the tree holds no entries
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Stores `value` under a cell and record id, returning the previous value.
+    ///
+    /// # Arguments
+    /// * `code` - Cell to store the value under
+    /// * `id` - Record id distinguishing entries of the same cell
+    /// * `value` - Value to store
+    pub fn insert(&self, code: &Grid9Code, id: &[u8], value: impl Into<IVec>) -> sled::Result<Option<IVec>> {
+        self.tree.insert(encode_key_with_suffix(code, id), value)
+    }
+
+    /// Returns the value stored under a cell and record id
+    pub fn get(&self, code: &Grid9Code, id: &[u8]) -> sled::Result<Option<IVec>> {
+        self.tree.get(encode_key_with_suffix(code, id))
+    }
+
+    /// Removes the value stored under a cell and record id, returning it
+    pub fn remove(&self, code: &Grid9Code, id: &[u8]) -> sled::Result<Option<IVec>> {
+        self.tree.remove(encode_key_with_suffix(code, id))
+    }
+
+    /// Iterates over the entries whose cell lies inside a bounding box.
+    ///
+    /// Entries come in key order; cells scanned by the covering ranges but
+    /// outside the box are dropped, so results are exact.
+    ///
+    /// # Arguments
+    /// * `bbox` - Query box (must not cross the antimeridian)
+    ///
+    /// # Returns
+    /// An iterator over the matching entries, or an error for an invalid box
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::{BoundingBox, Grid9Code, Grid9Tree};
+    ///
+    /// let db = sled::Config::new().temporary(true).open().unwrap();
+    /// let places = Grid9Tree::open(&db, "places").unwrap();
+    /// places.insert(&Grid9Code::encode(40.7580, -73.9855)?, b"1", "times square").unwrap();
+    /// places.insert(&Grid9Code::encode(51.5074, -0.1278)?, b"2", "london").unwrap();
+    ///
+    /// let manhattan = BoundingBox::new(40.70, 40.88, -74.02, -73.91);
+    /// let found: Vec<_> = places.in_bbox(&manhattan)?.map(|entry| entry.unwrap()).collect();
+    /// assert_eq!(found.len(), 1);
+    /// assert_eq!(found[0].id(), b"1");
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn in_bbox(&self, bbox: &BoundingBox) -> Result<impl Iterator<Item = sled::Result<TreeEntry>>> {
+        let start = Instant::now();
+        Ok(self.metered(self.bbox_entries(bbox)?, start))
+    }
+
+    /// Entries inside a bounding box, without reporting metrics
+    fn bbox_entries(&self, bbox: &BoundingBox) -> Result<impl Iterator<Item = sled::Result<TreeEntry>>> {
+        let indices = BboxIndices::from_bbox(bbox)?;
+        let ranges = bbox_key_ranges(bbox, MAX_RANGES)?;
+        let tree = self.tree.clone();
+
+        Ok(ranges
+            .into_iter()
+            .flat_map(move |(start, end)| tree.range(start..end))
+            .filter_map(move |item| match item {
+                Ok((key, value)) => {
+                    let code = decode_key(&key).ok()?;
+                    bbox_contains_code_fast(&indices, code).then_some(Ok(TreeEntry { code, key, value }))
+                }
+                Err(error) => Some(Err(error)),
+            }))
+    }
+
+    /// Iterates over the entries within a radius of a point, with their
+    /// distance in meters.
+    ///
+    /// Distances are measured to the cell of each entry. Entries come in key
+    /// order, not by distance; circles crossing the antimeridian or
+    /// reaching a pole are handled.
+    ///
+    /// # Arguments
+    /// * `lat` - Latitude of the center
+    /// * `lon` - Longitude of the center
+    /// * `radius_m` - Search radius in meters
+    ///
+    /// # Returns
+    /// An iterator over `(entry, distance_m)` pairs, or an error for invalid
+    /// coordinates or a negative radius
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::{Grid9Code, Grid9Tree};
+    ///
+    /// let db = sled::Config::new().temporary(true).open().unwrap();
+    /// let places = Grid9Tree::open(&db, "places").unwrap();
+    /// places.insert(&Grid9Code::encode(40.7580, -73.9855)?, b"1", "times square").unwrap();
+    /// places.insert(&Grid9Code::encode(40.7484, -73.9857)?, b"2", "empire state").unwrap();
+    ///
+    /// let near: Vec<_> = places.nearby(40.7580, -73.9855, 500.0)?.map(|item| item.unwrap()).collect();
+    /// assert_eq!(near.len(), 1);
+    /// assert_eq!(near[0].0.id(), b"1");
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn nearby(&self, lat: f64, lon: f64, radius_m: f64) -> Result<impl Iterator<Item = sled::Result<(TreeEntry, f64)>>> {
+        let start = Instant::now();
+        validate_coordinates(lat, lon)?;
+        if !(radius_m >= 0.0 && radius_m.is_finite()) {
+            return Err(Grid9Error::InvalidRadius(radius_m));
+        }
+
+        let mut scans = Vec::new();
+        for bbox in circle_bboxes(lat, lon, radius_m) {
+            scans.push(self.bbox_entries(&bbox)?);
+        }
+
+        let entries = scans.into_iter().flatten().filter_map(move |item| match item {
+            Ok(entry) => {
+                let (entry_lat, entry_lon) = entry.code.coordinates();
+                let distance = haversine_distance(lat, lon, entry_lat, entry_lon);
+                (distance <= radius_m).then_some(Ok((entry, distance)))
+            }
+            Err(error) => Some(Err(error)),
+        });
+        Ok(self.metered(entries, start))
+    }
+
+    fn metered<I>(&self, inner: I, start: Instant) -> Metered<I> {
+        Metered { inner, metrics: self.metrics.clone(), start, items: 0, errors: 0 }
+    }
+}
+
+/// Query iterator that reports what it yielded to the tree's metrics when dropped
+struct Metered<I> {
+    inner: I,
+    metrics: Option<Arc<dyn Grid9Metrics>>,
+    start: Instant,
+    items: usize,
+    errors: usize,
+}
+
+impl<I, T> Iterator for Metered<I>
+where
+    I: Iterator<Item = sled::Result<T>>,
+{
+    type Item = sled::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        self.items += 1;
+        self.errors += usize::from(item.is_err());
+        Some(item)
+    }
+}
+
+impl<I> Drop for Metered<I> {
+    fn drop(&mut self) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_batch(BatchOperation::Query, self.items, self.errors, self.start.elapsed());
+        }
+    }
+}
+
+impl KvScan for sled::Tree {
+    type Error = sled::Error;
+
+    fn scan(&self, start: &[u8], end: &[u8], visit: &mut dyn FnMut(&[u8], &[u8])) -> std::result::Result<(), Self::Error> {
+        for item in self.range(start..end) {
+            let (key, value) = item?;
+            visit(&key, &value);
+        }
+        Ok(())
+    }
+}
+
+/// Bounding boxes covering a circle, split at the antimeridian
+fn circle_bboxes(lat: f64, lon: f64, radius_m: f64) -> Vec<BoundingBox> {
+    let d_lat = radius_m / METERS_PER_DEGREE;
+    let min_lat = (lat - d_lat).max(-90.0);
+    let max_lat = (lat + d_lat).min(90.0);
+
+    // Circles reaching a pole cover every longitude near it
+    let cos_lat = lat.abs().max(min_lat.abs()).max(max_lat.abs()).to_radians().cos();
+    if max_lat >= 90.0 || min_lat <= -90.0 {
+        return vec![BoundingBox::new(min_lat, max_lat, -180.0, 180.0)];
+    }
+
+    let d_lon = d_lat / cos_lat;
+    if d_lon >= 180.0 {
+        vec![BoundingBox::new(min_lat, max_lat, -180.0, 180.0)]
+    } else if lon - d_lon < -180.0 {
+        vec![
+            BoundingBox::new(min_lat, max_lat, -180.0, lon + d_lon),
+            BoundingBox::new(min_lat, max_lat, lon - d_lon + 360.0, 180.0),
+        ]
+    } else if lon + d_lon > 180.0 {
+        vec![
+            BoundingBox::new(min_lat, max_lat, lon - d_lon, 180.0),
+            BoundingBox::new(min_lat, max_lat, -180.0, lon + d_lon - 360.0),
+        ]
+    } else {
+        vec![BoundingBox::new(min_lat, max_lat, lon - d_lon, lon + d_lon)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan_bbox;
+
+    fn temporary_tree() -> Grid9Tree {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        Grid9Tree::open(&db, "test").unwrap()
+    }
+
+    #[test]
+    fn test_insert_get_remove() {
+        let tree = temporary_tree();
+        let code = Grid9Code::encode(48.8566, 2.3522).unwrap();
+
+        assert_eq!(tree.insert(&code, b"a", "first").unwrap(), None);
+        tree.insert(&code, b"b", "second").unwrap();
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.get(&code, b"a").unwrap().unwrap(), "first");
+        assert_eq!(tree.remove(&code, b"a").unwrap().unwrap(), "first");
+        assert_eq!(tree.get(&code, b"a").unwrap(), None);
+        assert_eq!(tree.len(), 1);
+    }
+
+    #[test]
+    fn test_in_bbox_is_exact() {
+        let tree = temporary_tree();
+        let points = [(40.7580, -73.9855), (40.7484, -73.9857), (40.6892, -74.0445), (51.5074, -0.1278)];
+        for (i, &(lat, lon)) in points.iter().enumerate() {
+            tree.insert(&Grid9Code::encode(lat, lon).unwrap(), &[i as u8], vec![i as u8]).unwrap();
+        }
+
+        let bbox = BoundingBox::new(40.70, 40.88, -74.02, -73.91);
+        let mut found: Vec<u8> = tree.in_bbox(&bbox).unwrap().map(|entry| entry.unwrap().id()[0]).collect();
+        found.sort_unstable();
+        assert_eq!(found, vec![0, 1]);
+
+        let mut scanned = Vec::new();
+        scan_bbox(tree.tree(), &bbox, 4, |_, value| scanned.push(value[0])).unwrap();
+        scanned.sort_unstable();
+        assert_eq!(scanned, found);
+
+        assert!(tree.in_bbox(&BoundingBox::new(1.0, 0.0, 0.0, 1.0)).is_err());
+    }
+
+    #[test]
+    fn test_nearby_across_antimeridian_and_pole() {
+        let tree = temporary_tree();
+        let points = [(0.0, 179.999), (0.0, -179.999), (0.0, 179.0), (89.999, 0.0), (89.999, 180.0)];
+        for (i, &(lat, lon)) in points.iter().enumerate() {
+            tree.insert(&Grid9Code::encode(lat, lon).unwrap(), &[i as u8], vec![i as u8]).unwrap();
+        }
+
+        let ids = |lat: f64, lon: f64, radius_m: f64| {
+            let mut ids: Vec<u8> = tree.nearby(lat, lon, radius_m).unwrap().map(|item| item.unwrap().0.id()[0]).collect();
+            ids.sort_unstable();
+            ids
+        };
+        assert_eq!(ids(0.0, 180.0, 1_000.0), vec![0, 1]);
+        assert_eq!(ids(0.0, -179.9995, 200.0), vec![0, 1]);
+        assert_eq!(ids(89.9995, 90.0, 1_000.0), vec![3, 4]);
+
+        assert_eq!(tree.nearby(0.0, 0.0, -1.0).err(), Some(Grid9Error::InvalidRadius(-1.0)));
+        assert!(tree.nearby(91.0, 0.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_reports_queries_to_metrics() {
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<(BatchOperation, usize, usize)>>);
+
+        impl Grid9Metrics for Recorder {
+            fn record_batch(&self, operation: BatchOperation, items: usize, errors: usize, _: std::time::Duration) {
+                self.0.lock().unwrap().push((operation, items, errors));
+            }
+        }
+
+        let recorder = Arc::new(Recorder::default());
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let tree = Grid9Tree::with_metrics(db.open_tree("test").unwrap(), recorder.clone());
+        for (i, lon) in [0.0, 0.001, 1.0].into_iter().enumerate() {
+            tree.insert(&Grid9Code::encode(0.0, lon).unwrap(), &[i as u8], vec![i as u8]).unwrap();
+        }
+
+        assert_eq!(tree.in_bbox(&BoundingBox::new(-0.1, 0.1, -0.1, 0.1)).unwrap().count(), 2);
+        assert_eq!(tree.nearby(0.0, 1.0, 100.0).unwrap().count(), 1);
+        // Abandoned queries report what they yielded so far
+        drop(tree.in_bbox(&BoundingBox::new(-1.0, 1.0, -1.0, 2.0)).unwrap().next());
+        assert!(format!("{:?}", tree).starts_with("Grid9Tree"));
+
+        assert_eq!(*recorder.0.lock().unwrap(), [
+            (BatchOperation::Query, 2, 0),
+            (BatchOperation::Query, 1, 0),
+            (BatchOperation::Query, 1, 0),
+        ]);
+    }
+}
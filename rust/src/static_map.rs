@@ -0,0 +1,268 @@
+//! Static map image URLs for location previews
+//!
+//! [`static_map_url`] builds a URL for a static map service showing markers
+//! at decoded positions, and optionally the path through them, for
+//! embedding in emails, tickets and chat messages where no map widget is
+//! available.
+
+use crate::overlay::TILE_PIXELS;
+use crate::{Grid9Code, Grid9Error, Result};
+
+/// Largest image side accepted by the supported services, in pixels
+pub const MAX_STATIC_MAP_SIZE: u32 = 2048;
+
+/// Zoom level used when all positions coincide
+const SINGLE_POINT_ZOOM: u8 = 16;
+
+/// Deepest zoom level the services render
+const MAX_MAP_ZOOM: u8 = 18;
+
+/// Web Mercator latitude limit
+const MAX_MERCATOR_LAT: f64 = 85.051_128_78;
+
+/// A static map service
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StaticMapProvider {
+    /// The OpenStreetMap static map service (`staticmap.openstreetmap.de`);
+    /// draws markers only
+    Osm,
+    /// MapTiler Static Maps API
+    MapTiler {
+        /// API key
+        key: String,
+    },
+}
+
+/// Appearance of a static map
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapStyle {
+    /// Base map: a MapTiler map id such as `streets-v2`, or an OSM map type
+    /// such as `mapnik`
+    pub map: String,
+    /// Marker and path color, as a name (`red`) or hex code (`#ff0000`)
+    pub color: String,
+    /// Draw a line through the positions in order (a track) and mark only
+    /// its start and end
+    pub path: bool,
+    /// Fixed zoom level; `None` fits all positions in the image
+    pub zoom: Option<u8>,
+}
+
+impl Default for MapStyle {
+    fn default() -> Self {
+        Self { map: "streets-v2".to_string(), color: "red".to_string(), path: false, zoom: None }
+    }
+}
+
+/// Builds a static map URL showing the positions of codes.
+///
+/// # Arguments
+/// * `codes` - Positions to show, e.g. a slice of codes or [`crate::Track::codes`]
+/// * `provider` - Static map service
+/// * `size` - Image width and height in pixels (1 to [`MAX_STATIC_MAP_SIZE`])
+/// * `style` - Base map, colors, path drawing and zoom
+///
+/// # Returns
+/// The image URL, or `InvalidInput` for no positions, an invalid size or
+/// zoom, or a path requested from a provider that cannot draw one
+///
+/// # Example
+/// ```rust
+/// use grid9::{static_map_url, Grid9Code, MapStyle, StaticMapProvider};
+///
+/// let depot = Grid9Code::encode(40.7128, -74.0060)?;
+/// let customer = Grid9Code::encode(40.7306, -73.9866)?;
+/// let url = static_map_url([depot, customer], &StaticMapProvider::Osm, (600, 400), &MapStyle::default())?;
+/// assert!(url.starts_with("https://staticmap.openstreetmap.de/staticmap.php?center="));
+/// assert!(url.contains("&size=600x400&"));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn static_map_url(
+    codes: impl IntoIterator<Item = Grid9Code>,
+    provider: &StaticMapProvider,
+    size: (u32, u32),
+    style: &MapStyle,
+) -> Result<String> {
+    let (width, height) = size;
+    if !(1..=MAX_STATIC_MAP_SIZE).contains(&width) || !(1..=MAX_STATIC_MAP_SIZE).contains(&height) {
+        return Err(Grid9Error::InvalidInput(format!("invalid map size {}x{}", width, height).into()));
+    }
+    if style.zoom.is_some_and(|zoom| zoom > MAX_MAP_ZOOM) {
+        return Err(Grid9Error::InvalidInput(format!("zoom must not exceed {}", MAX_MAP_ZOOM).into()));
+    }
+    let positions: Vec<(f64, f64)> = codes.into_iter().map(|code| code.coordinates()).collect();
+    if positions.is_empty() {
+        return Err(Grid9Error::InvalidInput("a static map needs at least one position".into()));
+    }
+
+    let markers: Vec<(f64, f64)> = if style.path {
+        let mut ends = vec![positions[0]];
+        ends.extend(positions.last().filter(|_| positions.len() > 1));
+        ends
+    } else {
+        positions.clone()
+    };
+    let color = encode_component(&style.color);
+
+    match provider {
+        StaticMapProvider::Osm => {
+            if style.path {
+                return Err(Grid9Error::InvalidInput("the OSM static map service cannot draw paths".into()));
+            }
+            let ((lat, lon), zoom) = view(&positions, size, style.zoom);
+            let markers: Vec<String> =
+                markers.iter().map(|(lat, lon)| format!("{:.6},{:.6},{}-pushpin", lat, lon, color)).collect();
+            Ok(format!(
+                "https://staticmap.openstreetmap.de/staticmap.php?center={:.6},{:.6}&zoom={}&size={}x{}&maptype={}&markers={}",
+                lat,
+                lon,
+                zoom,
+                width,
+                height,
+                encode_component(&style.map),
+                markers.join("%7C")
+            ))
+        }
+        StaticMapProvider::MapTiler { key } => {
+            let viewport = match style.zoom {
+                Some(zoom) => {
+                    let ((lat, lon), _) = view(&positions, size, None);
+                    format!("{:.6},{:.6},{}", lon, lat, zoom)
+                }
+                None => "auto".to_string(),
+            };
+            let mut url = format!(
+                "https://api.maptiler.com/maps/{}/static/{}/{}x{}.png?key={}",
+                encode_component(&style.map),
+                viewport,
+                width,
+                height,
+                encode_component(key)
+            );
+            let markers: Vec<String> = markers.iter().map(|(lat, lon)| format!("{:.6},{:.6},{}", lon, lat, color)).collect();
+            url.push_str("&markers=");
+            url.push_str(&markers.join("%7C"));
+            if style.path {
+                url.push_str(&format!("&path=stroke:{}%7Cwidth:3", color));
+                for (lat, lon) in &positions {
+                    url.push_str(&format!("%7C{:.6},{:.6}", lon, lat));
+                }
+            }
+            Ok(url)
+        }
+    }
+}
+
+/// Center and zoom showing every position: the center of their bounding box
+/// and the deepest zoom at which the box fits in the image, or `zoom` if set
+fn view(positions: &[(f64, f64)], (width, height): (u32, u32), zoom: Option<u8>) -> ((f64, f64), u8) {
+    let (mut min_x, mut max_x, mut min_y, mut max_y) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+    for &(lat, lon) in positions {
+        let (x, y) = mercator(lat, lon);
+        (min_x, max_x, min_y, max_y) = (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y));
+    }
+    let center = (inverse_mercator_lat((min_y + max_y) / 2.0), (min_x + max_x) / 2.0 * 360.0 - 180.0);
+
+    let zoom = zoom.unwrap_or_else(|| {
+        // Fractions of the world's width and height at zoom 0
+        let (span_x, span_y) = (max_x - min_x, max_y - min_y);
+        if span_x == 0.0 && span_y == 0.0 {
+            return SINGLE_POINT_ZOOM;
+        }
+        let fits = |zoom: u8| {
+            let world = TILE_PIXELS * (1u64 << zoom) as f64;
+            span_x * world <= width as f64 && span_y * world <= height as f64
+        };
+        (0..=MAX_MAP_ZOOM).rev().find(|&zoom| fits(zoom)).unwrap_or(0)
+    });
+    (center, zoom)
+}
+
+/// Web Mercator position as fractions of the world (0..1 from west and north)
+fn mercator(lat: f64, lon: f64) -> (f64, f64) {
+    let lat = lat.clamp(-MAX_MERCATOR_LAT, MAX_MERCATOR_LAT).to_radians();
+    let y = 0.5 - (std::f64::consts::FRAC_PI_4 + lat / 2.0).tan().ln() / (2.0 * std::f64::consts::PI);
+    ((lon + 180.0) / 360.0, y)
+}
+
+fn inverse_mercator_lat(y: f64) -> f64 {
+    (2.0 * ((0.5 - y) * 2.0 * std::f64::consts::PI).exp().atan() - std::f64::consts::FRAC_PI_2).to_degrees()
+}
+
+/// Percent-encodes everything but unreserved URL characters
+fn encode_component(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Track;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn code(lat: f64, lon: f64) -> Grid9Code {
+        Grid9Code::encode(lat, lon).unwrap()
+    }
+
+    #[test]
+    fn test_osm_markers() {
+        let style = MapStyle { map: "mapnik".to_string(), ..MapStyle::default() };
+        let url = static_map_url([code(48.8566, 2.3522)], &StaticMapProvider::Osm, (300, 200), &style).unwrap();
+        let (lat, lon) = code(48.8566, 2.3522).coordinates();
+        assert_eq!(
+            url,
+            format!(
+                "https://staticmap.openstreetmap.de/staticmap.php?center={0:.6},{1:.6}&zoom=16&size=300x200&maptype=mapnik&markers={0:.6},{1:.6},red-pushpin",
+                lat, lon
+            )
+        );
+
+        // A wider spread needs a lower zoom
+        let spread = [code(48.80, 2.25), code(48.90, 2.42)];
+        let url = static_map_url(spread, &StaticMapProvider::Osm, (600, 400), &style).unwrap();
+        assert!(url.contains("&zoom=11&"), "{}", url);
+        assert_eq!(url.matches("pushpin").count(), 2);
+
+        let path = MapStyle { path: true, ..style };
+        assert!(static_map_url(spread, &StaticMapProvider::Osm, (600, 400), &path).is_err());
+    }
+
+    #[test]
+    fn test_maptiler_track() {
+        let mut track = Track::new();
+        for (i, (lat, lon)) in [(40.7128, -74.0060), (40.7200, -74.0000), (40.7306, -73.9866)].into_iter().enumerate() {
+            track.push(code(lat, lon), UNIX_EPOCH + Duration::from_secs(60 * i as u64)).unwrap();
+        }
+        let provider = StaticMapProvider::MapTiler { key: "k&y".to_string() };
+        let style = MapStyle { color: "#0080ff".to_string(), path: true, ..MapStyle::default() };
+        let url = static_map_url(track.codes(), &provider, (640, 480), &style).unwrap();
+
+        assert!(url.starts_with("https://api.maptiler.com/maps/streets-v2/static/auto/640x480.png?key=k%26y&markers="));
+        // Start and end markers, and a three-vertex path
+        assert_eq!(url.matches("%230080ff").count(), 3);
+        assert!(url.contains("&path=stroke:%230080ff%7Cwidth:3%7C-74.006"), "{}", url);
+        assert_eq!(url.split("&path=").nth(1).unwrap().matches(",40.7").count(), 3);
+
+        let zoomed = MapStyle { zoom: Some(14), ..style };
+        let url = static_map_url(track.codes(), &provider, (640, 480), &zoomed).unwrap();
+        assert!(url.contains("/static/-73.996"), "{}", url);
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let style = MapStyle::default();
+        let osm = StaticMapProvider::Osm;
+        assert!(static_map_url([], &osm, (600, 400), &style).is_err());
+        assert!(static_map_url([code(0.0, 0.0)], &osm, (0, 400), &style).is_err());
+        assert!(static_map_url([code(0.0, 0.0)], &osm, (600, MAX_STATIC_MAP_SIZE + 1), &style).is_err());
+        let too_deep = MapStyle { zoom: Some(MAX_MAP_ZOOM + 1), ..style };
+        assert!(static_map_url([code(0.0, 0.0)], &osm, (600, 400), &too_deep).is_err());
+    }
+}
@@ -0,0 +1,316 @@
+//! Quick-look SVG rendering (feature `svg`)
+//!
+//! Draws codes, cells, tracks and bounding boxes into a standalone SVG
+//! document for reports and debugging dashboards. The view is fitted to the
+//! items; there is no base map.
+
+use std::f64::consts::FRAC_PI_4;
+use std::fmt::Write;
+
+use crate::{BoundingBox, Coordinate, Grid9Bucket, Grid9Code};
+
+/// Latitude limit of the Web Mercator projection
+const MERCATOR_MAX_LAT: f64 = 85.051_128_78;
+/// Smallest view extent in projected degrees, so single points get a view
+const MIN_EXTENT: f64 = 1e-4;
+
+/// Map projection used by [`render_svg`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SvgProjection {
+    /// Longitude and latitude plotted linearly (plate carrée)
+    #[default]
+    Equirectangular,
+    /// Web Mercator, as used by web maps (latitudes clamped to ±85.05°)
+    Mercator,
+}
+
+/// Something to draw with [`render_svg`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SvgItem {
+    /// A point marker at the center of a code's cell
+    Code(Grid9Code),
+    /// The filled outline of a bucket (level 0 for a single cell)
+    Cell(Grid9Bucket),
+    /// A line through positions in order
+    Track(Vec<Coordinate>),
+    /// The outline of a bounding box
+    BoundingBox(BoundingBox),
+}
+
+/// Size, projection and colors for [`render_svg`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SvgStyle {
+    /// Document width in pixels
+    pub width: u32,
+    /// Document height in pixels
+    pub height: u32,
+    /// Margin around the drawn items in pixels
+    pub padding: f64,
+    /// Map projection
+    pub projection: SvgProjection,
+    /// Color of outlines, tracks and markers (any SVG color)
+    pub stroke: String,
+    /// Fill color of cells
+    pub fill: String,
+    /// Opacity of cell fills, from 0 to 1
+    pub fill_opacity: f64,
+    /// Line width in pixels
+    pub stroke_width: f64,
+    /// Radius of code markers in pixels
+    pub point_radius: f64,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 600,
+            padding: 16.0,
+            projection: SvgProjection::Equirectangular,
+            stroke: "#1f5fbf".to_string(),
+            fill: "#1f5fbf".to_string(),
+            fill_opacity: 0.3,
+            stroke_width: 1.5,
+            point_radius: 3.0,
+        }
+    }
+}
+
+impl SvgStyle {
+    /// Default colors with the given document size
+    pub fn with_size(width: u32, height: u32) -> Self {
+        Self { width, height, ..Self::default() }
+    }
+
+    /// Overrides the projection
+    pub fn projection(mut self, projection: SvgProjection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Overrides the stroke and fill colors
+    pub fn colors(mut self, stroke: &str, fill: &str) -> Self {
+        self.stroke = stroke.to_string();
+        self.fill = fill.to_string();
+        self
+    }
+}
+
+/// Renders items into an SVG document fitted to their extent.
+///
+/// Items are drawn in order, so later items appear on top. Positions with
+/// non-finite coordinates are skipped.
+///
+/// # Arguments
+/// * `items` - Codes, cells, tracks and boxes to draw
+/// * `style` - Document size, projection and colors
+///
+/// # Returns
+/// A standalone SVG document
+///
+/// # Example
+/// ```rust
+/// use grid9::{render_svg, Coordinate, Grid9Bucket, Grid9Code, SvgItem, SvgStyle};
+///
+/// let code = Grid9Code::encode(40.7128, -74.0060)?;
+/// let svg = render_svg(
+///     &[
+///         SvgItem::Cell(Grid9Bucket::from_code(&code.to_string(), 6)?),
+///         SvgItem::Code(code),
+///         SvgItem::Track(vec![Coordinate::new(40.7120, -74.0070), Coordinate::new(40.7135, -74.0050)]),
+///     ],
+///     &SvgStyle::default(),
+/// );
+/// assert!(svg.starts_with("<svg") && svg.contains("<polyline"));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn render_svg(items: &[SvgItem], style: &SvgStyle) -> String {
+    let shapes: Vec<Vec<(f64, f64)>> = items.iter().map(|item| project_item(item, style.projection)).collect();
+    let view = View::fit(shapes.iter().flatten(), style);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = style.width,
+        h = style.height
+    );
+    let stroke = escape_attribute(&style.stroke);
+    let fill = escape_attribute(&style.fill);
+
+    for (item, shape) in items.iter().zip(&shapes) {
+        if shape.is_empty() {
+            continue;
+        }
+        let points = shape.iter().map(|&point| view.pixel(point)).fold(String::new(), |mut out, (x, y)| {
+            let _ = write!(out, "{}{:.2},{:.2}", if out.is_empty() { "" } else { " " }, x, y);
+            out
+        });
+        let _ = match item {
+            SvgItem::Code(_) => {
+                let (x, y) = view.pixel(shape[0]);
+                writeln!(svg, r#"  <circle cx="{:.2}" cy="{:.2}" r="{}" fill="{}"/>"#, x, y, style.point_radius, stroke)
+            }
+            SvgItem::Cell(_) => writeln!(
+                svg,
+                r#"  <polygon points="{}" fill="{}" fill-opacity="{}" stroke="{}" stroke-width="{}"/>"#,
+                points, fill, style.fill_opacity, stroke, style.stroke_width
+            ),
+            SvgItem::Track(_) => writeln!(
+                svg,
+                r#"  <polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linejoin="round"/>"#,
+                points, stroke, style.stroke_width
+            ),
+            SvgItem::BoundingBox(_) => writeln!(
+                svg,
+                r#"  <polygon points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-dasharray="4 3"/>"#,
+                points, stroke, style.stroke_width
+            ),
+        };
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Projected vertices of an item (one vertex for point markers)
+fn project_item(item: &SvgItem, projection: SvgProjection) -> Vec<(f64, f64)> {
+    let outline = |bounds: &BoundingBox| {
+        vec![
+            (bounds.min_lat, bounds.min_lon),
+            (bounds.min_lat, bounds.max_lon),
+            (bounds.max_lat, bounds.max_lon),
+            (bounds.max_lat, bounds.min_lon),
+        ]
+    };
+    let positions = match item {
+        SvgItem::Code(code) => {
+            let (lat_index, lon_index) = code.indices();
+            match Grid9Bucket::from_indices(lat_index, lon_index, 0).map(|cell| cell.bounds()) {
+                Ok(b) => vec![((b.min_lat + b.max_lat) / 2.0, (b.min_lon + b.max_lon) / 2.0)],
+                Err(_) => Vec::new(),
+            }
+        }
+        SvgItem::Cell(bucket) => outline(&bucket.bounds()),
+        SvgItem::Track(points) => points.iter().map(|point| (point.lat, point.lon)).collect(),
+        SvgItem::BoundingBox(bbox) => outline(bbox),
+    };
+
+    positions
+        .into_iter()
+        .filter(|(lat, lon)| lat.is_finite() && lon.is_finite())
+        .map(|(lat, lon)| project(lat, lon, projection))
+        .collect()
+}
+
+/// Projects to (x, y) in degree-like units, y growing northwards.
+fn project(lat: f64, lon: f64, projection: SvgProjection) -> (f64, f64) {
+    match projection {
+        SvgProjection::Equirectangular => (lon, lat),
+        SvgProjection::Mercator => {
+            let lat = lat.clamp(-MERCATOR_MAX_LAT, MERCATOR_MAX_LAT).to_radians();
+            (lon, (FRAC_PI_4 + lat / 2.0).tan().ln().to_degrees())
+        }
+    }
+}
+
+/// Maps projected coordinates to pixels with a uniform scale
+struct View {
+    min_x: f64,
+    max_y: f64,
+    scale: f64,
+    offset_x: f64,
+    offset_y: f64,
+}
+
+impl View {
+    fn fit<'a>(points: impl Iterator<Item = &'a (f64, f64)>, style: &SvgStyle) -> Self {
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+        for &(x, y) in points {
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+            min_y = min_y.min(y);
+            max_y = max_y.max(y);
+        }
+        if min_x > max_x {
+            (min_x, max_x, min_y, max_y) = (-180.0, 180.0, -90.0, 90.0);
+        }
+        let (extent_x, extent_y) = ((max_x - min_x).max(MIN_EXTENT), (max_y - min_y).max(MIN_EXTENT));
+
+        let inner_w = (style.width as f64 - 2.0 * style.padding).max(1.0);
+        let inner_h = (style.height as f64 - 2.0 * style.padding).max(1.0);
+        let scale = (inner_w / extent_x).min(inner_h / extent_y);
+
+        // Center the drawing within the document
+        Self {
+            min_x: (min_x + max_x - extent_x) / 2.0,
+            max_y: (min_y + max_y + extent_y) / 2.0,
+            scale,
+            offset_x: style.padding + (inner_w - extent_x * scale) / 2.0,
+            offset_y: style.padding + (inner_h - extent_y * scale) / 2.0,
+        }
+    }
+
+    fn pixel(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (self.offset_x + (x - self.min_x) * self.scale, self.offset_y + (self.max_y - y) * self.scale)
+    }
+}
+
+fn escape_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers_after(svg: &str, attribute: &str) -> Vec<f64> {
+        let start = svg.find(&format!("{}=\"", attribute)).unwrap() + attribute.len() + 2;
+        let end = start + svg[start..].find('"').unwrap();
+        svg[start..end].split([' ', ',']).map(|n| n.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_fits_items_to_document() {
+        let bbox = BoundingBox::new(10.0, 20.0, 30.0, 50.0);
+        let svg = render_svg(&[SvgItem::BoundingBox(bbox)], &SvgStyle::with_size(420, 420));
+
+        // 20° wide by 10° high, fitted to the 388px inside the padding and centered vertically
+        let points = numbers_after(&svg, "points");
+        assert_eq!(points, vec![16.0, 307.0, 404.0, 307.0, 404.0, 113.0, 16.0, 113.0]);
+        assert!(svg.contains(r#"width="420" height="420""#));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_items_and_styles() {
+        let code = Grid9Code::encode(51.5074, -0.1278).unwrap();
+        let items = [
+            SvgItem::Cell(Grid9Bucket::from_code(&code.to_string(), 4).unwrap()),
+            SvgItem::Code(code),
+            SvgItem::Track(vec![Coordinate::new(51.5, -0.13), Coordinate::new(f64::NAN, 0.0), Coordinate::new(51.51, -0.12)]),
+        ];
+        let svg = render_svg(&items, &SvgStyle::default().colors("red", "a\"b"));
+
+        assert_eq!(svg.matches("<polygon").count(), 1);
+        assert_eq!(svg.matches("<circle").count(), 1);
+        assert_eq!(numbers_after(&svg, "<polyline points").len(), 4);
+        assert!(svg.contains(r#"fill="a&quot;b""#) && svg.contains(r#"stroke="red""#));
+
+        // A lone point still gets a view, and no items give an empty document
+        assert!(render_svg(&[SvgItem::Code(code)], &SvgStyle::default()).contains("<circle"));
+        assert_eq!(render_svg(&[], &SvgStyle::default()).lines().count(), 2);
+    }
+
+    #[test]
+    fn test_mercator_projection() {
+        let (x, y) = project(0.0, 10.0, SvgProjection::Mercator);
+        assert!(x == 10.0 && y.abs() < 1e-9);
+        let (_, y) = project(60.0, 0.0, SvgProjection::Mercator);
+        assert!((y - 75.456).abs() < 1e-3);
+        assert_eq!(project(90.0, 0.0, SvgProjection::Mercator), project(MERCATOR_MAX_LAT, 0.0, SvgProjection::Mercator));
+    }
+}
@@ -0,0 +1,333 @@
+//! Canonical test vectors for checking other Grid9 implementations
+//!
+//! [`TestVectors::generate`] runs a fixed set of inputs (cities, poles,
+//! antimeridian and exact cell boundaries) through the Rust core and records
+//! the results; [`TestVectors::to_json`] writes them as a self-describing
+//! JSON document that the C#, Python and JavaScript ports can load to verify
+//! bit-exact parity. Floats are written with Rust's shortest round-trip
+//! representation, so parsing them back yields the identical `f64`.
+
+use std::fmt::Write;
+
+use crate::uniform_precision_compressor::{lat_of_index, lon_of_index, BASE32_ALPHABET, LAT_BITS, LON_BITS};
+use crate::{calculate_distance, decode, encode, Grid9Bucket, Grid9Code};
+
+/// Version of the test-vector document layout
+pub const TEST_VECTOR_FORMAT_VERSION: u32 = 1;
+
+/// Named locations encoded by every implementation
+const LOCATIONS: [(f64, f64); 10] = [
+    (40.7128, -74.0060),
+    (51.5074, -0.1278),
+    (35.6762, 139.6503),
+    (-33.8688, 151.2093),
+    (-22.9068, -43.1729),
+    (64.1466, -21.9426),
+    (-77.8463, 166.6683),
+    (1.3521, 103.8198),
+    (-54.8019, -68.3030),
+    (19.4326, -99.1332),
+];
+
+/// Extreme and degenerate coordinates
+const EDGE_CASES: [(f64, f64); 12] = [
+    (0.0, 0.0),
+    (-0.0, -0.0),
+    (90.0, 180.0),
+    (-90.0, -180.0),
+    (90.0, -180.0),
+    (-90.0, 180.0),
+    (0.0, 180.0),
+    (0.0, -180.0),
+    (89.9999999, 179.9999999),
+    (-89.9999999, -179.9999999),
+    (1e-9, -1e-9),
+    (45.0, 90.0),
+];
+
+/// Bucket levels whose bounds are recorded for every vector code
+const CELL_LEVELS: [u8; 3] = [0, 4, 10];
+
+/// Text inputs exercising lenient parsing
+const PARSE_INPUTS: [&str; 14] = [
+    "Q7KH2BBYF",
+    "Q7K-H2B-BYF",
+    "  Q7KH2BBYF\t",
+    "U1Q7KH2BBYF",
+    "ＱＧＫ-Ｈ２Ｂ-ＢＹＦ",
+    "Q7K\u{2011}H2B\u{2011}BYF",
+    "q7kh2bbyf",
+    "O0IL23456",
+    "Q7KH2BBYU",
+    "Q7KH2BBY",
+    "Q7KH2BBYFF",
+    "Q7K H2B BYF",
+    "Q7KH2BBY!",
+    "",
+];
+
+/// An encoding result
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncodeVector {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Compact code
+    pub code: String,
+    /// Code in XXX-XXX-XXX format
+    pub human_readable: String,
+    pub lat_index: u32,
+    pub lon_index: u32,
+    /// Coordinates the code decodes to
+    pub decoded_latitude: f64,
+    pub decoded_longitude: f64,
+}
+
+/// Bounds of the bucket containing a code
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CellVector {
+    pub code: String,
+    pub level: u8,
+    pub min_lat: f64,
+    pub max_lat: f64,
+    pub min_lon: f64,
+    pub max_lon: f64,
+}
+
+/// Great-circle distance between two decoded codes
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DistanceVector {
+    pub from: String,
+    pub to: String,
+    pub meters: f64,
+}
+
+/// Result of leniently parsing a text input
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseVector {
+    pub input: String,
+    /// Canonical code, or `None` if the input must be rejected
+    pub code: Option<String>,
+}
+
+/// Complete set of test vectors
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TestVectors {
+    pub format_version: u32,
+    pub alphabet: String,
+    pub lat_bits: u32,
+    pub lon_bits: u32,
+    pub encode: Vec<EncodeVector>,
+    pub cells: Vec<CellVector>,
+    pub distances: Vec<DistanceVector>,
+    pub parsing: Vec<ParseVector>,
+}
+
+impl TestVectors {
+    /// Computes the canonical test vectors with this implementation.
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::TestVectors;
+    ///
+    /// let vectors = TestVectors::generate();
+    /// assert!(vectors.encode.iter().any(|v| v.latitude == 90.0 && v.longitude == 180.0));
+    /// std::fs::write(std::env::temp_dir().join("grid9-vectors.json"), vectors.to_json()).unwrap();
+    /// ```
+    pub fn generate() -> Self {
+        let mut points: Vec<(f64, f64)> = LOCATIONS.iter().chain(EDGE_CASES.iter()).copied().collect();
+
+        // Exact cell boundaries, where floor-based quantization must agree
+        for index in [1u32, 1 << 10, (1 << (LAT_BITS - 1)) + 1, (1 << LAT_BITS) - 2] {
+            points.push((lat_of_index(index as f64), 0.0));
+        }
+        for index in [1u32, 1 << 10, (1 << (LON_BITS - 1)) + 1, (1 << LON_BITS) - 2] {
+            points.push((0.0, lon_of_index(index as f64)));
+        }
+
+        let encode: Vec<EncodeVector> = points.iter().map(|&(lat, lon)| encode_vector(lat, lon)).collect();
+
+        let cells = encode
+            .iter()
+            .flat_map(|vector| {
+                CELL_LEVELS.iter().map(move |&level| {
+                    let bounds = Grid9Bucket::from_code(&vector.code, level).expect("generated code").bounds();
+                    CellVector {
+                        code: vector.code.clone(),
+                        level,
+                        min_lat: bounds.min_lat,
+                        max_lat: bounds.max_lat,
+                        min_lon: bounds.min_lon,
+                        max_lon: bounds.max_lon,
+                    }
+                })
+            })
+            .collect();
+
+        // Consecutive pairs, including short hops between boundary cells
+        let distances = encode
+            .windows(2)
+            .map(|pair| DistanceVector {
+                from: pair[0].code.clone(),
+                to: pair[1].code.clone(),
+                meters: calculate_distance(&pair[0].code, &pair[1].code).expect("generated codes"),
+            })
+            .collect();
+
+        let parsing = PARSE_INPUTS
+            .iter()
+            .map(|&input| ParseVector {
+                input: input.to_string(),
+                code: input.parse::<Grid9Code>().ok().map(|code| code.to_string()),
+            })
+            .collect();
+
+        Self {
+            format_version: TEST_VECTOR_FORMAT_VERSION,
+            alphabet: String::from_utf8_lossy(BASE32_ALPHABET).into_owned(),
+            lat_bits: LAT_BITS,
+            lon_bits: LON_BITS,
+            encode,
+            cells,
+            distances,
+            parsing,
+        }
+    }
+
+    /// Writes the vectors as a JSON document, one vector per line.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        out.push_str("{\n");
+        let _ = writeln!(out, "  \"format_version\": {},", self.format_version);
+        let _ = writeln!(out, "  \"alphabet\": {},", json_string(&self.alphabet));
+        let _ = writeln!(out, "  \"lat_bits\": {},", self.lat_bits);
+        let _ = writeln!(out, "  \"lon_bits\": {},", self.lon_bits);
+
+        write_array(&mut out, "encode", &self.encode, |v| {
+            format!(
+                "{{\"latitude\": {:?}, \"longitude\": {:?}, \"code\": {}, \"human_readable\": {}, \"lat_index\": {}, \"lon_index\": {}, \"decoded_latitude\": {:?}, \"decoded_longitude\": {:?}}}",
+                v.latitude,
+                v.longitude,
+                json_string(&v.code),
+                json_string(&v.human_readable),
+                v.lat_index,
+                v.lon_index,
+                v.decoded_latitude,
+                v.decoded_longitude
+            )
+        });
+        out.push_str(",\n");
+        write_array(&mut out, "cells", &self.cells, |v| {
+            format!(
+                "{{\"code\": {}, \"level\": {}, \"min_lat\": {:?}, \"max_lat\": {:?}, \"min_lon\": {:?}, \"max_lon\": {:?}}}",
+                json_string(&v.code),
+                v.level,
+                v.min_lat,
+                v.max_lat,
+                v.min_lon,
+                v.max_lon
+            )
+        });
+        out.push_str(",\n");
+        write_array(&mut out, "distances", &self.distances, |v| {
+            format!("{{\"from\": {}, \"to\": {}, \"meters\": {:?}}}", json_string(&v.from), json_string(&v.to), v.meters)
+        });
+        out.push_str(",\n");
+        write_array(&mut out, "parsing", &self.parsing, |v| {
+            let code = v.code.as_deref().map_or_else(|| "null".to_string(), json_string);
+            format!("{{\"input\": {}, \"code\": {}}}", json_string(&v.input), code)
+        });
+        out.push_str("\n}\n");
+        out
+    }
+}
+
+fn encode_vector(latitude: f64, longitude: f64) -> EncodeVector {
+    let code = encode(latitude, longitude, false).expect("vector coordinates are valid");
+    let human_readable = encode(latitude, longitude, true).expect("vector coordinates are valid");
+    let (lat_index, lon_index) = code.parse::<Grid9Code>().expect("generated code").indices();
+    let (decoded_latitude, decoded_longitude) = decode(&code).expect("generated code");
+
+    EncodeVector {
+        latitude,
+        longitude,
+        code,
+        human_readable,
+        lat_index,
+        lon_index,
+        decoded_latitude,
+        decoded_longitude,
+    }
+}
+
+fn write_array<T>(out: &mut String, name: &str, items: &[T], item_json: impl Fn(&T) -> String) {
+    let _ = write!(out, "  {}: [", json_string(name));
+    for (i, item) in items.iter().enumerate() {
+        out.push_str(if i == 0 { "\n    " } else { ",\n    " });
+        out.push_str(&item_json(item));
+    }
+    out.push_str(if items.is_empty() { "]" } else { "\n  ]" });
+}
+
+/// Quotes a string for JSON, escaping quotes, backslashes and control characters
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => {
+                let _ = write!(quoted, "\\u{:04x}", ch as u32);
+            }
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors_are_consistent() {
+        let vectors = TestVectors::generate();
+        assert_eq!(vectors.cells.len(), vectors.encode.len() * CELL_LEVELS.len());
+        assert_eq!(vectors.distances.len(), vectors.encode.len() - 1);
+
+        for vector in &vectors.encode {
+            let code = Grid9Code::from_indices(vector.lat_index, vector.lon_index).unwrap();
+            assert_eq!(code.to_string(), vector.code);
+            assert_eq!(code.to_code_string(true), vector.human_readable);
+        }
+
+        // Boundary points may fall on either side of the edge in floating
+        // point; the vectors pin down which one
+        let boundary = vectors.encode.iter().find(|v| v.latitude == lat_of_index(1.0)).unwrap();
+        assert!(boundary.lat_index <= 1);
+
+        let parsed: Vec<bool> = vectors.parsing.iter().map(|v| v.code.is_some()).collect();
+        assert_eq!(&parsed[..6], &[true; 6]);
+        assert!(parsed[6..].iter().all(|&valid| !valid));
+    }
+
+    #[test]
+    fn test_json_output() {
+        assert_eq!(json_string("a\"b\\c\t\u{1}"), "\"a\\\"b\\\\c\\t\\u0001\"");
+
+        let json = TestVectors::generate().to_json();
+        assert!(json.starts_with("{\n  \"format_version\": 1,"));
+        assert!(json.contains("\"input\": \"Q7KH2BBYU\", \"code\": null"));
+        assert!(json.contains("\"latitude\": -90.0, \"longitude\": -180.0, \"code\": \"000000000\""));
+        assert!(json.trim_end().ends_with("]\n}"));
+    }
+}
@@ -0,0 +1,217 @@
+//! Offline time zone lookup (feature `timezones`)
+//!
+//! Each country outline (see [`crate::country_of`]) maps to a default IANA
+//! zone (`data/country_zones.txt`); countries spanning several zones are
+//! split by coarse zone outlines (`data/timezones.txt`). Points outside
+//! every country get the nautical zone of their longitude (`Etc/GMT+5` for
+//! 67.5°W to 82.5°W). Zone borders inherit the ~20 km accuracy of the
+//! country outlines and are coarser still inside large countries.
+//!
+//! Only standard offsets are embedded. Daylight saving rules change too
+//! often to ship in a geocoding crate: pass [`TimeZone::name`] to a tz
+//! database when the exact local time matters.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::geodata::{parse_regions, smallest_containing, Region};
+use crate::{CountryCode, CountryIndex, Grid9Code, Grid9Error, Result, TimeBucketing};
+
+const EMBEDDED_COUNTRY_ZONES: &str = include_str!("../data/country_zones.txt");
+const EMBEDDED_ZONE_OUTLINES: &str = include_str!("../data/timezones.txt");
+
+/// Nautical zones from UTC-12 to UTC+12 (the POSIX-style names invert the sign)
+const NAUTICAL_ZONES: [&str; 25] = [
+    "Etc/GMT+12", "Etc/GMT+11", "Etc/GMT+10", "Etc/GMT+9", "Etc/GMT+8", "Etc/GMT+7", "Etc/GMT+6", "Etc/GMT+5",
+    "Etc/GMT+4", "Etc/GMT+3", "Etc/GMT+2", "Etc/GMT+1", "Etc/GMT", "Etc/GMT-1", "Etc/GMT-2", "Etc/GMT-3",
+    "Etc/GMT-4", "Etc/GMT-5", "Etc/GMT-6", "Etc/GMT-7", "Etc/GMT-8", "Etc/GMT-9", "Etc/GMT-10", "Etc/GMT-11",
+    "Etc/GMT-12",
+];
+
+/// An IANA time zone with its standard UTC offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimeZone {
+    name: &'static str,
+    utc_offset_seconds: i32,
+}
+
+impl TimeZone {
+    /// IANA zone name, e.g. `Europe/Paris`
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Standard (non-daylight-saving) offset east of UTC in seconds
+    pub fn utc_offset_seconds(&self) -> i32 {
+        self.utc_offset_seconds
+    }
+
+    /// Time windows of `width` aligned to this zone's standard time, for
+    /// [`crate::Track::bucket_by`].
+    pub fn bucketing(&self, width: Duration) -> TimeBucketing {
+        TimeBucketing::fixed_offset(width, self.utc_offset_seconds)
+    }
+
+    /// Nautical zone of a longitude.
+    fn nautical(longitude: f64) -> Self {
+        let hours = (longitude / 15.0).round().clamp(-12.0, 12.0) as i32;
+        Self { name: NAUTICAL_ZONES[(hours + 12) as usize], utc_offset_seconds: hours * 3600 }
+    }
+}
+
+impl fmt::Display for TimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+struct ZoneTable {
+    defaults: HashMap<CountryCode, TimeZone>,
+    outlines: Vec<Region<(CountryCode, TimeZone)>>,
+}
+
+impl ZoneTable {
+    fn embedded() -> &'static ZoneTable {
+        static TABLE: OnceLock<ZoneTable> = OnceLock::new();
+        TABLE.get_or_init(|| {
+            let defaults = EMBEDDED_COUNTRY_ZONES
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| parse_labels(&line.split_whitespace().collect::<Vec<_>>()))
+                .collect::<Result<HashMap<_, _>>>()
+                .expect("embedded country zones are valid");
+            let outlines =
+                parse_regions(EMBEDDED_ZONE_OUTLINES, parse_labels).expect("embedded zone outlines are valid");
+            ZoneTable { defaults, outlines }
+        })
+    }
+
+    fn lookup(&self, latitude: f64, longitude: f64) -> TimeZone {
+        let Some(country) = CountryIndex::embedded().lookup(latitude, longitude) else {
+            return TimeZone::nautical(longitude);
+        };
+
+        let in_country = self.outlines.iter().filter(|region| region.label.0 == country);
+        smallest_containing(in_country, latitude, longitude)
+            .map(|region| region.label.1)
+            .or_else(|| self.defaults.get(&country).copied())
+            .unwrap_or_else(|| TimeZone::nautical(longitude))
+    }
+}
+
+/// Parses `CC Zone/Name +HH:MM` labels.
+fn parse_labels(labels: &[&'static str]) -> Result<(CountryCode, TimeZone)> {
+    let &[country, name, offset] = labels else {
+        return Err(Grid9Error::InvalidInput("expected a country code, zone name and UTC offset".into()));
+    };
+    let invalid = || Grid9Error::InvalidInput(format!("invalid UTC offset '{}'", offset).into());
+
+    let (sign, hours_minutes) = if let Some(rest) = offset.strip_prefix('+') {
+        (1, rest)
+    } else if let Some(rest) = offset.strip_prefix('-') {
+        (-1, rest)
+    } else {
+        return Err(invalid());
+    };
+    let (hours, minutes) = hours_minutes.split_once(':').ok_or_else(invalid)?;
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+    if hours > 14 || minutes >= 60 {
+        return Err(invalid());
+    }
+
+    Ok((country.parse()?, TimeZone { name, utc_offset_seconds: sign * (hours * 3600 + minutes * 60) }))
+}
+
+/// Returns the time zone a Grid9 cell lies in, using the embedded datasets.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code (any accepted form)
+///
+/// # Returns
+/// The IANA zone and its standard UTC offset; over the ocean, the nautical
+/// `Etc/GMT±N` zone of the cell's longitude
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, timezone_of};
+///
+/// let chicago = timezone_of(&encode(41.8781, -87.6298, false)?)?;
+/// assert_eq!(chicago.name(), "America/Chicago");
+/// assert_eq!(chicago.utc_offset_seconds(), -6 * 3600);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn timezone_of(encoded: &str) -> Result<TimeZone> {
+    let code: Grid9Code = encoded.parse()?;
+    let (latitude, longitude) = code.coordinates();
+    Ok(ZoneTable::embedded().lookup(latitude, longitude))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encode;
+
+    fn zone(latitude: f64, longitude: f64) -> TimeZone {
+        timezone_of(&encode(latitude, longitude, false).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_cities() {
+        let cities = [
+            ("Europe/Lisbon", 0, 38.7223, -9.1393),
+            ("Atlantic/Azores", -3600, 37.7412, -25.6756),
+            ("Europe/Paris", 3600, 48.8566, 2.3522),
+            ("Europe/Kaliningrad", 7200, 54.7104, 20.4522),
+            ("Europe/Moscow", 10800, 55.7558, 37.6173),
+            ("Europe/Samara", 14400, 53.1959, 50.1002),
+            ("Asia/Yekaterinburg", 18000, 56.8389, 60.6057),
+            ("Asia/Novosibirsk", 25200, 55.0084, 82.9357),
+            ("Asia/Vladivostok", 36000, 43.1155, 131.8855),
+            ("Asia/Kolkata", 19800, 28.6139, 77.2090),
+            ("Asia/Kathmandu", 20700, 27.7172, 85.3240),
+            ("Asia/Makassar", 28800, -8.6500, 115.2167),
+            ("Australia/Perth", 28800, -31.9505, 115.8605),
+            ("Australia/Adelaide", 34200, -34.9285, 138.6007),
+            ("Australia/Sydney", 36000, -33.8688, 151.2093),
+            ("America/New_York", -18000, 40.7128, -74.0060),
+            ("America/Chicago", -21600, 29.7604, -95.3698),
+            ("America/Denver", -25200, 39.7392, -104.9903),
+            ("America/Phoenix", -25200, 33.4484, -112.0740),
+            ("America/Los_Angeles", -28800, 34.0522, -118.2437),
+            ("America/Anchorage", -32400, 61.2181, -149.9003),
+            ("America/St_Johns", -12600, 47.5615, -52.7126),
+            ("America/Vancouver", -28800, 49.2827, -123.1207),
+            ("America/Winnipeg", -21600, 49.8951, -97.1384),
+            ("America/Sao_Paulo", -10800, -23.5505, -46.6333),
+            ("America/Manaus", -14400, -3.1190, -60.0217),
+        ];
+
+        for (name, offset, lat, lon) in cities {
+            let tz = zone(lat, lon);
+            assert_eq!((tz.name(), tz.utc_offset_seconds()), (name, offset), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_nautical_zones() {
+        assert_eq!(zone(35.0, -40.0).name(), "Etc/GMT+3");
+        assert_eq!(zone(35.0, -40.0).utc_offset_seconds(), -3 * 3600);
+        assert_eq!(zone(-40.0, 90.0).name(), "Etc/GMT-6");
+        assert_eq!(zone(0.0, 179.9).name(), "Etc/GMT-12");
+        assert_eq!(zone(-85.0, 0.0).name(), "Etc/GMT");
+        assert!(timezone_of("INVALID").is_err());
+    }
+
+    #[test]
+    fn test_labels() {
+        let (country, tz) = parse_labels(&["IN", "Asia/Kolkata", "+05:30"]).unwrap();
+        assert_eq!((country.as_str(), tz.utc_offset_seconds()), ("IN", 19800));
+        assert_eq!(parse_labels(&["CA", "America/St_Johns", "-03:30"]).unwrap().1.utc_offset_seconds(), -12600);
+        assert!(parse_labels(&["IN", "Asia/Kolkata", "05:30"]).is_err());
+        assert!(parse_labels(&["IN", "Asia/Kolkata"]).is_err());
+    }
+}
@@ -0,0 +1,585 @@
+//! Timestamped tracks of Grid9 cells
+//!
+//! Tracks are generic over their timestamp type through [`Timestamp`], which
+//! is implemented for `std::time::SystemTime` and, behind the `time` and
+//! `chrono` features, for `time::OffsetDateTime` and `chrono::DateTime`.
+//! Durations are `std::time::Duration` throughout.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::uniform_precision_compressor::haversine_distance;
+use crate::{Fix, Grid9Bucket, Grid9Code, Grid9Error, Result};
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+/// An instant usable as a track timestamp
+pub trait Timestamp: Copy + Ord {
+    /// Nanoseconds since the Unix epoch (UTC)
+    fn unix_nanos(&self) -> i128;
+
+    /// Offset of the timestamp's time zone from UTC in seconds, used by
+    /// [`TimeBucketing::local`]; zero for types without a time zone.
+    fn utc_offset_seconds(&self) -> i32 {
+        0
+    }
+}
+
+impl Timestamp for SystemTime {
+    fn unix_nanos(&self) -> i128 {
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(after) => after.as_nanos() as i128,
+            Err(before) => -(before.duration().as_nanos() as i128),
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp for time::OffsetDateTime {
+    fn unix_nanos(&self) -> i128 {
+        self.unix_timestamp_nanos()
+    }
+
+    fn utc_offset_seconds(&self) -> i32 {
+        self.offset().whole_seconds()
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> Timestamp for chrono::DateTime<Tz>
+where
+    Tz::Offset: Copy,
+{
+    fn unix_nanos(&self) -> i128 {
+        self.timestamp() as i128 * NANOS_PER_SECOND + self.timestamp_subsec_nanos() as i128
+    }
+
+    fn utc_offset_seconds(&self) -> i32 {
+        use chrono::Offset;
+        self.offset().fix().local_minus_utc()
+    }
+}
+
+/// Elapsed time from `start` to `end` (zero if `end` is earlier)
+pub(crate) fn elapsed<T: Timestamp>(start: &T, end: &T) -> Duration {
+    let nanos = (end.unix_nanos() - start.unix_nanos()).max(0);
+    Duration::new((nanos / NANOS_PER_SECOND) as u64, (nanos % NANOS_PER_SECOND) as u32)
+}
+
+/// Converts nanoseconds since the Unix epoch to a `SystemTime`.
+pub(crate) fn system_time(unix_nanos: i128) -> SystemTime {
+    let magnitude = Duration::new(
+        (unix_nanos.unsigned_abs() / NANOS_PER_SECOND as u128) as u64,
+        (unix_nanos.unsigned_abs() % NANOS_PER_SECOND as u128) as u32,
+    );
+    if unix_nanos >= 0 { UNIX_EPOCH + magnitude } else { UNIX_EPOCH - magnitude }
+}
+
+/// Timestamp of a fix added to a track
+fn fix_time<T: Timestamp>(fix: &Fix<T>) -> Result<T> {
+    fix.timestamp.ok_or_else(|| Grid9Error::InvalidInput("track fixes must have a timestamp".into()))
+}
+
+/// A Grid9 cell visited at a point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrackPoint<T> {
+    /// Cell of the position fix
+    pub code: Grid9Code,
+    /// Time of the fix
+    pub time: T,
+}
+
+/// A continuous stay inside one bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dwell<T> {
+    /// Bucket the track stayed in
+    pub bucket: Grid9Bucket,
+    /// Time of the first fix inside the bucket
+    pub start: T,
+    /// Time of the last fix inside the bucket
+    pub end: T,
+    /// `end - start`
+    pub duration: Duration,
+}
+
+/// Time zone used to align time windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum WindowZone {
+    Utc,
+    Fixed(i32),
+    Local,
+}
+
+/// How timestamps are grouped into fixed-width time windows
+///
+/// Windows are aligned to the Unix epoch in the chosen time zone, so daily
+/// windows run from local midnight to midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimeBucketing {
+    width: Duration,
+    zone: WindowZone,
+}
+
+impl TimeBucketing {
+    /// Windows of `width` aligned in UTC
+    pub fn utc(width: Duration) -> Self {
+        Self { width, zone: WindowZone::Utc }
+    }
+
+    /// Windows of `width` aligned in a fixed time zone `utc_offset_seconds` east of UTC
+    pub fn fixed_offset(width: Duration, utc_offset_seconds: i32) -> Self {
+        Self { width, zone: WindowZone::Fixed(utc_offset_seconds) }
+    }
+
+    /// Windows of `width` aligned in each timestamp's own time zone (see
+    /// [`Timestamp::utc_offset_seconds`]), so daylight saving changes move
+    /// window boundaries with the local clock.
+    pub fn local(width: Duration) -> Self {
+        Self { width, zone: WindowZone::Local }
+    }
+
+    /// Window width
+    pub fn width(&self) -> Duration {
+        self.width
+    }
+
+    /// Returns the (start, end) instants of the window containing `time`.
+    pub fn window<T: Timestamp>(&self, time: &T) -> Result<(SystemTime, SystemTime)> {
+        let width = self.width.as_nanos() as i128;
+        if width == 0 {
+            return Err(Grid9Error::InvalidInput("time window width must be positive".into()));
+        }
+
+        let offset = match self.zone {
+            WindowZone::Utc => 0,
+            WindowZone::Fixed(seconds) => seconds as i128 * NANOS_PER_SECOND,
+            WindowZone::Local => time.utc_offset_seconds() as i128 * NANOS_PER_SECOND,
+        };
+        let start = (time.unix_nanos() + offset).div_euclid(width) * width - offset;
+
+        Ok((system_time(start), system_time(start + width)))
+    }
+}
+
+/// A spatial bucket during a time window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpaceTimeBucket {
+    /// Spatial bucket
+    pub cell: Grid9Bucket,
+    /// Start of the time window (inclusive)
+    pub start: SystemTime,
+    /// End of the time window (exclusive)
+    pub end: SystemTime,
+}
+
+/// A time-ordered sequence of visited cells
+///
+/// # Example
+/// ```rust
+/// use std::time::{Duration, SystemTime};
+/// use grid9::{Grid9Code, Track};
+///
+/// let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+/// let mut track = Track::new();
+/// track.push(Grid9Code::encode(40.7128, -74.0060)?, start)?;
+/// track.push(Grid9Code::encode(40.7129, -74.0061)?, start + Duration::from_secs(90))?;
+///
+/// assert_eq!(track.duration(), Duration::from_secs(90));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Track<T> {
+    points: Vec<TrackPoint<T>>,
+}
+
+impl<T: Timestamp> Default for Track<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Timestamp> Track<T> {
+    /// Creates an empty track
+    pub fn new() -> Self {
+        Self { points: Vec::new() }
+    }
+
+    /// Creates a track from fixes in any order; they are sorted by time.
+    pub fn from_points<I: IntoIterator<Item = TrackPoint<T>>>(points: I) -> Self {
+        let mut points: Vec<TrackPoint<T>> = points.into_iter().collect();
+        points.sort_by_key(|point| point.time);
+        Self { points }
+    }
+
+    /// Appends a fix; fixes must be pushed in time order.
+    pub fn push(&mut self, code: Grid9Code, time: T) -> Result<()> {
+        if self.points.last().is_some_and(|last| time < last.time) {
+            return Err(Grid9Error::InvalidInput(
+                "track fixes must be pushed in time order".into(),
+            ));
+        }
+
+        self.points.push(TrackPoint { code, time });
+        Ok(())
+    }
+
+    /// Creates a track from [`Fix`]es in any order, dropping fixes whose
+    /// horizontal accuracy is unknown or worse than `max_error_m`.
+    ///
+    /// Every fix must carry a timestamp.
+    pub fn from_fixes<I: IntoIterator<Item = Fix<T>>>(fixes: I, max_error_m: f64) -> Result<Self> {
+        let mut points = Vec::new();
+        for fix in fixes {
+            if fix.is_accurate_to(max_error_m) {
+                points.push(TrackPoint { code: fix.code()?, time: fix_time(&fix)? });
+            }
+        }
+        Ok(Self::from_points(points))
+    }
+
+    /// Appends a timestamped [`Fix`]; fixes must be pushed in time order.
+    pub fn push_fix(&mut self, fix: &Fix<T>) -> Result<()> {
+        self.push(fix.code()?, fix_time(fix)?)
+    }
+
+    /// Fixes in time order
+    pub fn points(&self) -> &[TrackPoint<T>] {
+        &self.points
+    }
+
+    /// Codes of the fixes in time order
+    pub fn codes(&self) -> impl Iterator<Item = Grid9Code> + '_ {
+        self.points.iter().map(|point| point.code)
+    }
+
+    /// Number of fixes
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// True if the track has no fixes
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Time between the first and the last fix
+    pub fn duration(&self) -> Duration {
+        match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => elapsed(&first.time, &last.time),
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Splits the track into consecutive stays inside buckets of `level`.
+    ///
+    /// A stay ends at the last fix before the track enters another bucket,
+    /// so single-fix stays have a zero duration.
+    pub fn dwell_durations(&self, level: u8) -> Result<Vec<Dwell<T>>> {
+        let mut dwells: Vec<Dwell<T>> = Vec::new();
+
+        for point in &self.points {
+            let (lat_index, lon_index) = point.code.indices();
+            let bucket = Grid9Bucket::from_indices(lat_index, lon_index, level)?;
+            match dwells.last_mut() {
+                Some(dwell) if dwell.bucket == bucket => {
+                    dwell.end = point.time;
+                    dwell.duration = elapsed(&dwell.start, &point.time);
+                }
+                _ => dwells.push(Dwell {
+                    bucket,
+                    start: point.time,
+                    end: point.time,
+                    duration: Duration::ZERO,
+                }),
+            }
+        }
+
+        Ok(dwells)
+    }
+
+    /// Groups fixes by spatial bucket of `level` and time window.
+    pub fn bucket_by(&self, level: u8, bucketing: &TimeBucketing) -> Result<HashMap<SpaceTimeBucket, Vec<TrackPoint<T>>>> {
+        let mut buckets: HashMap<SpaceTimeBucket, Vec<TrackPoint<T>>> = HashMap::new();
+
+        for point in &self.points {
+            let (start, end) = bucketing.window(&point.time)?;
+            let (lat_index, lon_index) = point.code.indices();
+            let cell = Grid9Bucket::from_indices(lat_index, lon_index, level)?;
+            buckets.entry(SpaceTimeBucket { cell, start, end }).or_default().push(*point);
+        }
+
+        Ok(buckets)
+    }
+
+    /// Discrete Fréchet distance to another track in meters, see
+    /// [`frechet_distance`]; timestamps are ignored.
+    pub fn frechet_distance<U: Timestamp>(&self, other: &Track<U>) -> Result<f64> {
+        frechet_distance(&self.codes().collect::<Vec<_>>(), &other.codes().collect::<Vec<_>>())
+    }
+
+    /// Hausdorff distance to another track in meters, see
+    /// [`hausdorff_distance`]; timestamps are ignored.
+    pub fn hausdorff_distance<U: Timestamp>(&self, other: &Track<U>) -> Result<f64> {
+        hausdorff_distance(&self.codes().collect::<Vec<_>>(), &other.codes().collect::<Vec<_>>())
+    }
+}
+
+/// Discrete Fréchet distance between two code sequences in meters.
+///
+/// The shortest leash that lets two walkers traverse the sequences from
+/// start to end, each only moving forward, measured at the codes. Unlike
+/// [`hausdorff_distance`] it respects order, so a route driven in reverse
+/// or with a loop the plan does not have scores far from the plan.
+/// Takes time proportional to the product of the lengths.
+///
+/// # Arguments
+/// * `a`, `b` - Codes in order of travel, e.g. a planned and a driven route
+///
+/// # Returns
+/// The distance between grid points, or `EmptyInput` if either sequence is
+/// empty
+///
+/// # Example
+/// ```rust
+/// use grid9::{frechet_distance, hausdorff_distance, Grid9Code};
+///
+/// let route = |points: &[(f64, f64)]| points.iter().map(|&(lat, lon)| Grid9Code::encode(lat, lon)).collect::<Result<Vec<_>, _>>();
+/// let planned = route(&[(52.50, 13.40), (52.51, 13.40), (52.52, 13.40)])?;
+/// let reversed: Vec<Grid9Code> = planned.iter().rev().copied().collect();
+///
+/// // Same places, opposite order
+/// assert_eq!(hausdorff_distance(&planned, &reversed)?, 0.0);
+/// assert!(frechet_distance(&planned, &reversed)? > 2_000.0);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn frechet_distance(a: &[Grid9Code], b: &[Grid9Code]) -> Result<f64> {
+    let (a, b) = (positions(a)?, positions(b)?);
+    let distance = |i: usize, j: usize| haversine_distance(a[i].0, a[i].1, b[j].0, b[j].1);
+
+    // Coupling distances for row i of the dynamic programme, one row at a time
+    let mut row: Vec<f64> = Vec::with_capacity(b.len());
+    for j in 0..b.len() {
+        let reach = if j == 0 { 0.0 } else { row[j - 1] };
+        row.push(distance(0, j).max(reach));
+    }
+    for i in 1..a.len() {
+        let mut diagonal = row[0];
+        row[0] = row[0].max(distance(i, 0));
+        for j in 1..b.len() {
+            let reach = diagonal.min(row[j]).min(row[j - 1]);
+            diagonal = row[j];
+            row[j] = distance(i, j).max(reach);
+        }
+    }
+    Ok(row[b.len() - 1])
+}
+
+/// Hausdorff distance between two code sets in meters.
+///
+/// The largest distance from a code in either sequence to the nearest code
+/// in the other, ignoring order: how far a driven route strays from the
+/// plan at its worst, or the plan from the driven route. Takes time
+/// proportional to the product of the lengths.
+///
+/// # Returns
+/// The distance between grid points, or `EmptyInput` if either sequence is
+/// empty
+pub fn hausdorff_distance(a: &[Grid9Code], b: &[Grid9Code]) -> Result<f64> {
+    let (a, b) = (positions(a)?, positions(b)?);
+    let directed = |from: &[(f64, f64)], to: &[(f64, f64)]| {
+        from.iter()
+            .map(|p| to.iter().map(|q| haversine_distance(p.0, p.1, q.0, q.1)).fold(f64::INFINITY, f64::min))
+            .fold(0.0, f64::max)
+    };
+    Ok(directed(&a, &b).max(directed(&b, &a)))
+}
+
+/// Decoded grid points of a non-empty code sequence
+fn positions(codes: &[Grid9Code]) -> Result<Vec<(f64, f64)>> {
+    if codes.is_empty() {
+        return Err(Grid9Error::EmptyInput);
+    }
+    Ok(codes.iter().map(|code| code.coordinates()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000 + seconds)
+    }
+
+    fn code(lat: f64, lon: f64) -> Grid9Code {
+        Grid9Code::encode(lat, lon).unwrap()
+    }
+
+    #[test]
+    fn test_push_requires_time_order() {
+        let mut track = Track::new();
+        track.push(code(0.0, 0.0), at(10)).unwrap();
+        assert!(track.push(code(0.0, 0.0), at(5)).is_err());
+
+        let sorted = Track::from_points([
+            TrackPoint { code: code(1.0, 1.0), time: at(20) },
+            TrackPoint { code: code(0.0, 0.0), time: at(10) },
+        ]);
+        assert_eq!(sorted.points()[0].time, at(10));
+        assert_eq!(sorted.duration(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_tracks_from_fixes() {
+        let fix = |lat: f64, accuracy: f64, seconds: u64| {
+            Fix::builder(lat, 0.0).horizontal_accuracy_m(accuracy).timestamp(at(seconds)).build().unwrap()
+        };
+
+        let track = Track::from_fixes([fix(1.0, 5.0, 20), fix(2.0, 80.0, 10), fix(0.0, 3.0, 0)], 10.0).unwrap();
+        assert_eq!(track.points().iter().map(|p| p.time).collect::<Vec<_>>(), vec![at(0), at(20)]);
+
+        let mut pushed = Track::new();
+        pushed.push_fix(&fix(0.0, 3.0, 0)).unwrap();
+        assert_eq!(pushed.points()[0].code, code(0.0, 0.0));
+
+        let untimed = Fix::from(crate::Coordinate::new(0.0, 0.0));
+        assert!(pushed.push_fix(&untimed).is_err());
+    }
+
+    #[test]
+    fn test_dwell_durations() {
+        let home = code(40.7128, -74.0060);
+        let work = code(40.7580, -73.9855);
+        let mut track = Track::new();
+        for (c, t) in [(home, 0), (home, 600), (work, 1800), (work, 5400), (home, 9000)] {
+            track.push(c, at(t)).unwrap();
+        }
+
+        let dwells = track.dwell_durations(4).unwrap();
+        let durations: Vec<u64> = dwells.iter().map(|d| d.duration.as_secs()).collect();
+        assert_eq!(durations, vec![600, 3600, 0]);
+        assert_eq!(dwells[1].start, at(1800));
+    }
+
+    #[test]
+    fn test_frechet_and_hausdorff() {
+        let line = |lats: &[f64]| lats.iter().map(|&lat| code(lat, 0.0)).collect::<Vec<_>>();
+        let meters = |degrees: f64| degrees.to_radians() * 6_371_000.0;
+        let planned = line(&[0.0, 0.01, 0.02, 0.03]);
+
+        assert_eq!(frechet_distance(&planned, &planned).unwrap(), 0.0);
+        // Sampling density does not matter, only the shape
+        let dense = line(&[0.0, 0.005, 0.01, 0.015, 0.02, 0.025, 0.03]);
+        assert!(frechet_distance(&planned, &dense).unwrap() < meters(0.0051));
+        // A detour back to the start shows in Fréchet but not in Hausdorff
+        let looped = line(&[0.0, 0.01, 0.02, 0.0, 0.03]);
+        assert!(hausdorff_distance(&planned, &looped).unwrap() < 1.0);
+        let frechet = frechet_distance(&planned, &looped).unwrap();
+        assert!((frechet - meters(0.01)).abs() < 1.0, "{}", frechet);
+
+        let offset: Vec<Grid9Code> = planned.iter().map(|c| code(c.coordinates().0, 0.02)).collect();
+        let hausdorff = hausdorff_distance(&planned, &offset).unwrap();
+        assert!((hausdorff - meters(0.02)).abs() < 1.0, "{}", hausdorff);
+        assert_eq!(hausdorff_distance(&offset, &planned).unwrap(), hausdorff);
+        assert!(frechet_distance(&planned, &[]).is_err());
+        assert_eq!(hausdorff_distance(&[], &planned), Err(Grid9Error::EmptyInput));
+
+        let mut driven = Track::new();
+        for (i, c) in dense.iter().enumerate() {
+            driven.push(*c, at(60 * i as u64)).unwrap();
+        }
+        let plan = Track::from_points(planned.iter().map(|&c| TrackPoint { code: c, time: at(0) }));
+        assert_eq!(driven.frechet_distance(&plan).unwrap(), frechet_distance(&dense, &planned).unwrap());
+        assert_eq!(driven.hausdorff_distance(&plan).unwrap(), hausdorff_distance(&dense, &planned).unwrap());
+    }
+
+    #[test]
+    fn test_time_windows_and_zones() {
+        let hour = Duration::from_secs(3600);
+        let time = UNIX_EPOCH + Duration::from_secs(3 * 3600 + 1234);
+
+        let (start, end) = TimeBucketing::utc(hour).window(&time).unwrap();
+        assert_eq!(start, UNIX_EPOCH + Duration::from_secs(3 * 3600));
+        assert_eq!(end, start + hour);
+
+        // Daily windows at UTC-5 start at 05:00 UTC
+        let day = Duration::from_secs(86_400);
+        let (start, _) = TimeBucketing::fixed_offset(day, -5 * 3600).window(&time).unwrap();
+        assert_eq!(start, UNIX_EPOCH - Duration::from_secs(19 * 3600));
+
+        assert!(TimeBucketing::utc(Duration::ZERO).window(&time).is_err());
+    }
+
+    #[test]
+    fn test_space_time_buckets() {
+        let mut track = Track::new();
+        for (lat, t) in [(10.0, 0), (10.00001, 60), (10.0, 4000), (20.0, 4100)] {
+            track.push(code(lat, 10.0), at(t)).unwrap();
+        }
+
+        let buckets = track.bucket_by(6, &TimeBucketing::utc(Duration::from_secs(3600))).unwrap();
+        let mut sizes: Vec<usize> = buckets.values().map(Vec::len).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![1, 1, 2]);
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time_crate_timestamps() {
+        use time::{OffsetDateTime, UtcOffset};
+
+        let utc = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let local = utc.to_offset(UtcOffset::from_hms(2, 0, 0).unwrap());
+        assert_eq!(local.unix_nanos(), utc.unix_nanos());
+
+        let day = Duration::from_secs(86_400);
+        let (start, _) = TimeBucketing::local(day).window(&local).unwrap();
+        assert_eq!(start.unix_nanos() % (86_400 * NANOS_PER_SECOND), 22 * 3600 * NANOS_PER_SECOND);
+
+        let mut track = Track::new();
+        track.push(code(0.0, 0.0), local).unwrap();
+        track.push(code(0.0, 0.0), local + time::Duration::minutes(5)).unwrap();
+        assert_eq!(track.duration(), Duration::from_secs(300));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_timestamps() {
+        use chrono::{FixedOffset, TimeZone, Utc};
+
+        let utc = Utc.timestamp_opt(1_700_000_000, 500).unwrap();
+        let local = utc.with_timezone(&FixedOffset::west_opt(5 * 3600).unwrap());
+        assert_eq!(local.unix_nanos(), 1_700_000_000 * NANOS_PER_SECOND + 500);
+        assert_eq!(local.utc_offset_seconds(), -5 * 3600);
+
+        let mut track = Track::new();
+        track.push(code(0.0, 0.0), utc).unwrap();
+        track.push(code(0.0, 0.0), utc + chrono::Duration::seconds(42)).unwrap();
+        assert_eq!(track.duration(), Duration::from_secs(42));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_postcard_size() {
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut track = Track::new();
+        for i in 0..100u64 {
+            let code = Grid9Code::encode(40.7128 + i as f64 * 1e-4, -74.0060).unwrap();
+            track.push(code, start + Duration::from_millis(i * 1_250)).unwrap();
+        }
+        let bytes = postcard::to_allocvec(&track).unwrap();
+
+        // 6-byte code plus varint seconds and nanoseconds per point
+        assert!(bytes.len() <= 2 + 16 * track.len(), "{} bytes", bytes.len());
+        assert_eq!(postcard::from_bytes::<Track<SystemTime>>(&bytes).unwrap(), track);
+
+        let bucketing = TimeBucketing::fixed_offset(Duration::from_secs(3_600), 7_200);
+        let bytes = postcard::to_allocvec(&bucketing).unwrap();
+        assert_eq!(postcard::from_bytes::<TimeBucketing>(&bytes).unwrap(), bucketing);
+    }
+}
@@ -1,17 +1,66 @@
 //! Uniform precision coordinate compression implementation
 
-use crate::{Grid9Error, Result};
+#[cfg(feature = "alloc")]
+use alloc::{format, string::{String, ToString}, vec::Vec};
 
-const BASE32_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
-const LAT_BITS: u32 = 22;
-const LON_BITS: u32 = 23;
-const LAT_MAX: u64 = (1u64 << LAT_BITS) - 1;
-const LON_MAX: u64 = (1u64 << LON_BITS) - 1;
-const EARTH_RADIUS_M: f64 = 6_371_000.0;
+use crate::version::strip_version_tag;
+#[cfg(feature = "std")]
+use crate::BoundingBox;
+use crate::{DefaultGrid, Grid9Code, Grid9Error, Result, Rounding};
+
+pub(crate) const BASE32_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Entry of the digit table for bytes that are not code characters
+pub(crate) const INVALID_DIGIT: u8 = 0xff;
+
+/// Value of each byte as a canonical (uppercase) code character
+pub(crate) const DIGIT_VALUES: [u8; 256] = {
+    let mut table = [INVALID_DIGIT; 256];
+    let mut value = 0;
+    while value < BASE32_ALPHABET.len() {
+        table[BASE32_ALPHABET[value] as usize] = value as u8;
+        value += 1;
+    }
+    table
+};
+
+// The table must invert the alphabet exactly
+const _: () = {
+    assert!(BASE32_ALPHABET.len() == 32);
+    let mut valid = 0;
+    let mut byte = 0;
+    while byte < 256 {
+        let value = DIGIT_VALUES[byte];
+        if value != INVALID_DIGIT {
+            assert!(BASE32_ALPHABET[value as usize] as usize == byte);
+            valid += 1;
+        }
+        byte += 1;
+    }
+    assert!(valid == 32);
+};
+pub(crate) const LAT_BITS: u32 = 22;
+pub(crate) const LON_BITS: u32 = 23;
+#[cfg(feature = "std")]
+pub(crate) const EARTH_RADIUS_M: f64 = 6_371_000.0;
+#[cfg(feature = "std")]
+pub(crate) const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// How strictly Grid9 codes are parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParseMode {
+    /// Ignores dashes anywhere and reads copy-pasted Unicode look-alikes as ASCII
+    #[default]
+    Lenient,
+    /// Accepts only `XXXXXXXXX` or `XXX-XXX-XXX` using uppercase alphabet characters
+    Strict,
+}
 
 /// Precision information for a coordinate
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct PrecisionInfo {
     /// Latitude error in meters
     pub lat_error_m: f64,
@@ -39,35 +88,142 @@ pub struct PrecisionInfo {
 /// let readable = encode(40.7128, -74.0060, true)?; // "Q7K-H2B-BYF"
 /// # Ok::<(), grid9::Grid9Error>(())
 /// ```
+#[cfg(feature = "alloc")]
 pub fn encode(latitude: f64, longitude: f64, human_readable: bool) -> Result<String> {
     validate_coordinates(latitude, longitude)?;
     
-    // Normalize coordinates to [0, 1] range
-    let norm_lat = (latitude + 90.0) / 180.0;
-    let norm_lon = (longitude + 180.0) / 360.0;
-    
-    // Quantize to bit precision
-    let lat_bits = ((norm_lat * LAT_MAX as f64) as u64).min(LAT_MAX);
-    let lon_bits = ((norm_lon * LON_MAX as f64) as u64).min(LON_MAX);
-    
-    // Pack into 45-bit value
-    let packed = (lat_bits << LON_BITS) | lon_bits;
-    
-    // Convert to base32 (9 characters for 45 bits)
-    let mut result = String::with_capacity(if human_readable { 11 } else { 9 });
-    let mut temp = packed;
+    let (lat_bits, lon_bits) = quantize(latitude, longitude);
     
-    for _ in 0..9 {
-        let index = (temp & 0x1F) as usize;
-        result.insert(0, BASE32_ALPHABET[index] as char);
-        temp >>= 5;
-    }
-    
-    if human_readable {
-        Ok(format_for_humans(&result))
+    Ok(format_packed(pack_indices(lat_bits, lon_bits), human_readable))
+}
+
+/// Encodes coordinates to the Grid9 code of the nearest grid point.
+///
+/// [`encode`] truncates, so [`decode`] lands up to a whole cell south-west
+/// of the original position. Rounding instead keeps the decoded grid point
+/// within half a cell (~2.4m × ~1.8m at the equator) of it, at the cost of
+/// codes that differ from [`encode`] for about three positions in four.
+/// Equivalent to a [`crate::Grid9Encoder`] with [`Rounding::Nearest`].
+///
+/// # Arguments
+/// * `latitude` - Latitude in degrees (-90 to 90)
+/// * `longitude` - Longitude in degrees (-180 to 180)
+/// * `human_readable` - If true, returns XXX-XXX-XXX format with dashes
+///
+/// # Example
+/// ```rust
+/// use grid9::{decode, encode_nearest};
+///
+/// let code = encode_nearest(40.7128, -74.0060, false)?;
+/// let (lat, lon) = decode(&code)?;
+/// assert!((lat - 40.7128).abs() < 2.2e-5 && (lon + 74.0060).abs() < 2.2e-5);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[cfg(feature = "alloc")]
+pub fn encode_nearest(latitude: f64, longitude: f64, human_readable: bool) -> Result<String> {
+    validate_coordinates(latitude, longitude)?;
+
+    let (lat_bits, lon_bits) = quantize_with(latitude, longitude, Rounding::Nearest);
+
+    Ok(format_packed(pack_indices(lat_bits, lon_bits), human_readable))
+}
+
+/// Encodes coordinates into the nine ASCII characters of a compact Grid9
+/// code, without allocating.
+///
+/// # Arguments
+/// * `latitude` - Latitude in degrees (-90 to 90)
+/// * `longitude` - Longitude in degrees (-180 to 180)
+///
+/// # Returns
+/// The code's characters, e.g. for copying straight into an output buffer
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, encode_ascii};
+///
+/// let mut line = Vec::with_capacity(64);
+/// line.extend_from_slice(&encode_ascii(40.7128, -74.0060)?);
+/// assert_eq!(line, encode(40.7128, -74.0060, false)?.as_bytes());
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn encode_ascii(latitude: f64, longitude: f64) -> Result<[u8; 9]> {
+    validate_coordinates(latitude, longitude)?;
+
+    let (lat_bits, lon_bits) = quantize(latitude, longitude);
+    Ok(packed_chars(pack_indices(lat_bits, lon_bits)))
+}
+
+/// Encodes coordinates to the 45-bit Grid9 payload as an integer, skipping
+/// the base32 step.
+///
+/// The value is `[22-bit lat index][23-bit lon index]`, the number the nine
+/// characters of [`encode`] spell, so it fits a fixed-width `BIGINT` column
+/// and converts to and from the string form losslessly.
+///
+/// # Arguments
+/// * `latitude` - Latitude in degrees (-90 to 90)
+/// * `longitude` - Longitude in degrees (-180 to 180)
+///
+/// # Returns
+/// The payload, below 2^45
+///
+/// # Example
+/// ```rust
+/// use grid9::{decode_from_u64, encode, encode_to_u64, Grid9Code};
+///
+/// let packed = encode_to_u64(40.7128, -74.0060)?;
+/// assert_eq!(Grid9Code::from_u64(packed)?.to_string(), encode(40.7128, -74.0060, false)?);
+/// assert_eq!(decode_from_u64(packed)?, Grid9Code::from_u64(packed)?.coordinates());
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn encode_to_u64(latitude: f64, longitude: f64) -> Result<u64> {
+    Ok(Grid9Code::encode(latitude, longitude)?.as_u64())
+}
+
+/// Brings coordinates from receivers that report unwrapped longitudes into
+/// range instead of rejecting them.
+///
+/// Longitudes outside -180..=180 are wrapped around the globe (190° becomes
+/// -170°, 720° becomes 0°); in-range values are returned unchanged.
+///
+/// # Arguments
+/// * `latitude` - Latitude in degrees
+/// * `longitude` - Longitude in degrees, any finite value
+/// * `clamp_latitude` - Clamp latitudes beyond the poles to ±90 instead of failing
+///
+/// # Returns
+/// The normalized pair, or `InvalidLatitude`/`InvalidLongitude` for values
+/// that cannot be normalized (NaN, infinities, and out-of-range latitudes
+/// unless clamped)
+///
+/// # Example
+/// ```rust
+/// use grid9::normalize_coordinates;
+///
+/// assert_eq!(normalize_coordinates(51.5, 190.0, false)?, (51.5, -170.0));
+/// assert_eq!(normalize_coordinates(90.2, 720.0, true)?, (90.0, 0.0));
+/// assert!(normalize_coordinates(90.2, 0.0, false).is_err());
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn normalize_coordinates(latitude: f64, longitude: f64, clamp_latitude: bool) -> Result<(f64, f64)> {
+    normalize_with(latitude, longitude, true, clamp_latitude)
+}
+
+/// Validates coordinates after optionally wrapping the longitude and
+/// clamping the latitude.
+#[cfg(feature = "std")]
+pub(crate) fn normalize_with(latitude: f64, longitude: f64, wrap_longitude: bool, clamp_latitude: bool) -> Result<(f64, f64)> {
+    let longitude = if wrap_longitude && longitude.is_finite() && !(-180.0..=180.0).contains(&longitude) {
+        (longitude + 180.0).rem_euclid(360.0) - 180.0
     } else {
-        Ok(result)
-    }
+        longitude
+    };
+    let latitude = if clamp_latitude && latitude.is_finite() { latitude.clamp(-90.0, 90.0) } else { latitude };
+
+    validate_coordinates(latitude, longitude)?;
+    Ok((latitude, longitude))
 }
 
 /// Decodes a Grid9 string to latitude and longitude coordinates.
@@ -87,30 +243,99 @@ pub fn encode(latitude: f64, longitude: f64, human_readable: bool) -> Result<Str
 /// # Ok::<(), grid9::Grid9Error>(())
 /// ```
 pub fn decode(encoded: &str) -> Result<(f64, f64)> {
-    let clean_encoded = remove_formatting(encoded);
-    validate_encoded_string(&clean_encoded)?;
-    
-    // Convert from base32 to 45-bit value
-    let mut packed = 0u64;
-    for ch in clean_encoded.chars() {
-        packed <<= 5;
-        let pos = BASE32_ALPHABET.iter().position(|&b| b as char == ch)
-            .ok_or(Grid9Error::InvalidCharacter(ch))?;
-        packed |= pos as u64;
-    }
+    let (lat_bits, lon_bits) = unpack_indices(parse_packed(encoded)?);
     
-    // Extract latitude and longitude bits
-    let lon_bits = packed & LON_MAX;
-    let lat_bits = (packed >> LON_BITS) & LAT_MAX;
-    
-    // Denormalize coordinates
-    let norm_lat = lat_bits as f64 / LAT_MAX as f64;
-    let norm_lon = lon_bits as f64 / LON_MAX as f64;
-    
-    let latitude = norm_lat * 180.0 - 90.0;
-    let longitude = norm_lon * 360.0 - 180.0;
+    Ok((lat_of_index(lat_bits as f64), lon_of_index(lon_bits as f64)))
+}
+
+/// Decodes a Grid9 string to the bounding box of its cell.
+///
+/// [`decode`] returns the cell's south-west corner; every position that
+/// encodes to the code lies in this box, which ends at the next grid line
+/// north and east (~4.8m × ~3.6m at the equator, narrower towards the
+/// poles). Cells in the northmost row and at longitude 180° are clamped to
+/// the valid range and so have zero height or width.
+///
+/// # Arguments
+/// * `encoded` - A 9-character Grid9 code (dashes are automatically removed)
+///
+/// # Returns
+/// The cell's extent in degrees
+///
+/// # Example
+/// ```rust
+/// use grid9::{decode, decode_bounds, encode};
+///
+/// let cell = decode_bounds("Q7KH2BBYF")?;
+/// assert_eq!((cell.min_lat, cell.min_lon), decode("Q7KH2BBYF")?);
+/// assert!(cell.contains(cell.max_lat - 1e-7, cell.max_lon - 1e-7));
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn decode_bounds(encoded: &str) -> Result<BoundingBox> {
+    Ok(encoded.parse::<Grid9Code>()?.bounds())
+}
+
+/// Decodes a Grid9 string to the center of its cell.
+///
+/// Codes from [`encode`] name the cell a position falls in, and [`decode`]
+/// returns that cell's south-west corner, biasing every round trip south
+/// and west. The center is within half a cell of every position in the
+/// cell, halving the worst-case error. Use [`decode`] for codes from
+/// [`encode_nearest`], whose grid point is already the nearest one.
+///
+/// # Arguments
+/// * `encoded` - A 9-character Grid9 code (dashes are automatically removed)
+///
+/// # Returns
+/// (latitude, longitude) of the cell center in degrees
+///
+/// # Example
+/// ```rust
+/// use grid9::{decode_bounds, decode_center};
+///
+/// let (lat, lon) = decode_center("Q7K-H2B-BYF")?;
+/// let cell = decode_bounds("Q7KH2BBYF")?;
+/// assert!(lat > cell.min_lat && lat < cell.max_lat);
+/// assert!(lon > cell.min_lon && lon < cell.max_lon);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn decode_center(encoded: &str) -> Result<(f64, f64)> {
+    Ok(encoded.parse::<Grid9Code>()?.center())
+}
+
+/// Decodes a Grid9 string using the given parsing mode.
+///
+/// `ParseMode::Lenient` behaves exactly like [`decode`]. `ParseMode::Strict`
+/// rejects anything that is not a canonical compact or XXX-XXX-XXX code, such
+/// as misplaced dashes or lowercase characters, instead of normalizing it.
+///
+/// # Example
+/// ```rust
+/// use grid9::{decode_with_mode, Grid9Error, ParseMode};
+/// 
+/// assert!(decode_with_mode("Q7K-H2B-BYF", ParseMode::Strict).is_ok());
+/// assert_eq!(
+///     decode_with_mode("Q-7KH2BB-YF", ParseMode::Strict),
+///     Err(Grid9Error::InvalidSeparator(1))
+/// );
+/// assert!(decode_with_mode("Q-7KH2BB-YF", ParseMode::Lenient).is_ok());
+/// ```
+#[cfg(feature = "alloc")]
+pub fn decode_with_mode(encoded: &str, mode: ParseMode) -> Result<(f64, f64)> {
+    let (lat_bits, lon_bits) = unpack_indices(parse_packed_with_mode(encoded, mode)?);
     
-    Ok((latitude, longitude))
+    Ok((lat_of_index(lat_bits as f64), lon_of_index(lon_bits as f64)))
+}
+
+/// Decodes a 45-bit Grid9 payload from [`encode_to_u64`] to coordinates.
+///
+/// # Returns
+/// The same (latitude, longitude) as [`decode`] of the matching code, or
+/// `InvalidIndex` for values of 2^45 and above
+pub fn decode_from_u64(packed: u64) -> Result<(f64, f64)> {
+    Ok(Grid9Code::from_u64(packed)?.coordinates())
 }
 
 /// Calculates the distance between two Grid9 codes in meters.
@@ -131,6 +356,7 @@ pub fn decode(encoded: &str) -> Result<(f64, f64)> {
 /// let distance = calculate_distance(&nyc, &london)?; // ~5,570,224 meters
 /// # Ok::<(), grid9::Grid9Error>(())
 /// ```
+#[cfg(feature = "std")]
 pub fn calculate_distance(encoded1: &str, encoded2: &str) -> Result<f64> {
     let (lat1, lon1) = decode(encoded1)?;
     let (lat2, lon2) = decode(encoded2)?;
@@ -138,6 +364,33 @@ pub fn calculate_distance(encoded1: &str, encoded2: &str) -> Result<f64> {
     Ok(haversine_distance(lat1, lon1, lat2, lon2))
 }
 
+/// Calculates the initial great-circle bearing from one Grid9 code to another.
+///
+/// # Arguments
+/// * `encoded1` - Starting Grid9 code
+/// * `encoded2` - Destination Grid9 code
+///
+/// # Returns
+/// True bearing in degrees clockwise from north, in `[0, 360)`
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, calculate_bearing};
+///
+/// let nyc = encode(40.7128, -74.0060, false)?;
+/// let london = encode(51.5074, -0.1278, false)?;
+/// let bearing = calculate_bearing(&nyc, &london)?; // ~51 degrees (north-east)
+/// assert!((bearing - 51.2).abs() < 0.5);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+#[cfg(feature = "std")]
+pub fn calculate_bearing(encoded1: &str, encoded2: &str) -> Result<f64> {
+    let (lat1, lon1) = decode(encoded1)?;
+    let (lat2, lon2) = decode(encoded2)?;
+
+    Ok(initial_bearing(lat1, lon1, lat2, lon2))
+}
+
 /// Validates if a string is a valid Grid9 encoding.
 ///
 /// # Arguments
@@ -155,8 +408,22 @@ pub fn calculate_distance(encoded1: &str, encoded2: &str) -> Result<f64> {
 /// assert!(!is_valid_encoding("INVALID"));      // Invalid
 /// ```
 pub fn is_valid_encoding(encoded: &str) -> bool {
-    let clean_encoded = remove_formatting(encoded);
-    validate_encoded_string(&clean_encoded).is_ok()
+    parse_packed(encoded).is_ok()
+}
+
+/// Validates if a string is a valid Grid9 encoding under the given parsing mode.
+///
+/// # Example
+/// ```rust
+/// use grid9::{is_valid_encoding_with_mode, ParseMode};
+/// 
+/// assert!(is_valid_encoding_with_mode("Q7K-H2B-BYF", ParseMode::Strict));
+/// assert!(!is_valid_encoding_with_mode("Q-7KH2BB-YF", ParseMode::Strict));
+/// assert!(is_valid_encoding_with_mode("Q-7KH2BB-YF", ParseMode::Lenient));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn is_valid_encoding_with_mode(encoded: &str, mode: ParseMode) -> bool {
+    parse_packed_with_mode(encoded, mode).is_ok()
 }
 
 /// Formats a compact Grid9 code with dashes for human readability.
@@ -173,6 +440,7 @@ pub fn is_valid_encoding(encoded: &str) -> bool {
 /// 
 /// let formatted = format_for_humans("Q7KH2BBYF"); // "Q7K-H2B-BYF"
 /// ```
+#[cfg(feature = "alloc")]
 pub fn format_for_humans(encoded: &str) -> String {
     if encoded.len() != 9 {
         return encoded.to_string();
@@ -195,6 +463,7 @@ pub fn format_for_humans(encoded: &str) -> String {
 /// 
 /// let clean = remove_formatting("Q7K-H2B-BYF"); // "Q7KH2BBYF"
 /// ```
+#[cfg(feature = "alloc")]
 pub fn remove_formatting(formatted: &str) -> String {
     formatted.chars().filter(|&c| c != '-').collect()
 }
@@ -216,28 +485,191 @@ pub fn remove_formatting(formatted: &str) -> String {
 /// println!("Total precision: {:.1}m", precision.total_error_m);
 /// # Ok::<(), grid9::Grid9Error>(())
 /// ```
+#[cfg(feature = "std")]
 pub fn get_actual_precision(latitude: f64, longitude: f64) -> Result<PrecisionInfo> {
-    validate_coordinates(latitude, longitude)?;
+    DefaultGrid::new().precision(latitude, longitude)
+}
+
+/// Quantizes validated coordinates to their (latitude, longitude) grid indices.
+pub(crate) fn quantize(latitude: f64, longitude: f64) -> (u32, u32) {
+    quantize_with(latitude, longitude, Rounding::Truncate)
+}
+
+/// Quantizes validated coordinates to grid indices using the given rounding.
+pub(crate) fn quantize_with(latitude: f64, longitude: f64, rounding: Rounding) -> (u32, u32) {
+    DefaultGrid::quantize_with(latitude, longitude, rounding)
+}
+
+/// Latitude in degrees of a (possibly fractional) latitude grid index.
+pub(crate) fn lat_of_index(index: f64) -> f64 {
+    DefaultGrid::lat_of_index(index)
+}
+
+/// Longitude in degrees of a (possibly fractional) longitude grid index.
+pub(crate) fn lon_of_index(index: f64) -> f64 {
+    DefaultGrid::lon_of_index(index)
+}
+
+/// Packs grid indices into the 45-bit payload: `[22-bit lat][23-bit lon]`.
+pub(crate) fn pack_indices(lat_bits: u32, lon_bits: u32) -> u64 {
+    DefaultGrid::pack_indices(lat_bits, lon_bits)
+}
+
+/// Splits a 45-bit payload into its (latitude, longitude) grid indices.
+pub(crate) fn unpack_indices(packed: u64) -> (u32, u32) {
+    DefaultGrid::unpack_indices(packed)
+}
+
+/// Parses a (possibly dash-formatted) Grid9 code into its 45-bit payload.
+pub(crate) fn parse_packed(encoded: &str) -> Result<u64> {
+    parse_digits(encoded, |len| len == 9).map(|(packed, _)| packed)
+}
+
+/// Reads the characters of a code as 5-bit digits, returning their value
+/// (the low 64 bits of it for codes over 12 characters) and the number of
+/// characters.
+///
+/// Applies the [`canonicalize`] rules character by character, without
+/// allocating. Errors are reported in the order of
+/// `validate_encoded_string`: empty input, then a length `valid_len`
+/// rejects, then the first character outside the alphabet.
+pub(crate) fn parse_digits(encoded: &str, valid_len: impl Fn(usize) -> bool) -> Result<(u64, usize)> {
+    let encoded = strip_version_tag(encoded, ParseMode::Lenient)?;
+
+    let mut value = 0u64;
+    let mut len = 0;
+    let mut invalid = None;
+    for original in encoded.trim().chars() {
+        let ch = match normalize_unicode(original) {
+            Some('-') | None => continue,
+            Some(ch) => ch,
+        };
+        match digit_value(ch) {
+            Some(digit) => value = (value << 5) | digit as u64,
+            None => {
+                invalid.get_or_insert(original);
+            }
+        }
+        len += 1;
+    }
+
+    if len == 0 {
+        return Err(Grid9Error::EmptyInput);
+    }
+    if !valid_len(len) {
+        return Err(Grid9Error::InvalidLength(len));
+    }
+    match invalid {
+        Some(ch) => Err(Grid9Error::InvalidCharacter(ch)),
+        None => Ok((value, len)),
+    }
+}
+
+/// Parses a Grid9 code into its 45-bit payload using the given parsing mode.
+#[cfg(feature = "alloc")]
+pub(crate) fn parse_packed_with_mode(encoded: &str, mode: ParseMode) -> Result<u64> {
+    match mode {
+        ParseMode::Lenient => parse_packed(encoded),
+        ParseMode::Strict => {
+            let encoded = strip_version_tag(encoded, ParseMode::Strict)?;
+            let clean_encoded = strip_separators_strict(encoded, false)?;
+            validate_encoded_string(&clean_encoded)?;
+            parse_packed(&clean_encoded)
+        }
+    }
+}
+
+/// Removes the dashes of a canonical XXX-XXX-XXX code (XXX-XXX-XXX-C when it
+/// carries a check character), rejecting dashes anywhere else.
+#[cfg(feature = "alloc")]
+pub(crate) fn strip_separators_strict(encoded: &str, with_check: bool) -> Result<String> {
+    let separators: &[usize] = if with_check { &[3, 7, 11] } else { &[3, 7] };
+    let chars: Vec<char> = encoded.chars().collect();
+    let formatted = chars.len() == if with_check { 13 } else { 11 }
+        && separators.iter().all(|&i| chars[i] == '-');
     
-    // Calculate precision based on latitude quantization
-    let lat_precision = 180.0 / (1u64 << LAT_BITS) as f64;
-    let lat_error_m = lat_precision * 111_320.0;
+    let mut clean = String::with_capacity(chars.len());
+    for (position, &ch) in chars.iter().enumerate() {
+        if ch != '-' {
+            clean.push(ch);
+        } else if !(formatted && separators.contains(&position)) {
+            return Err(Grid9Error::InvalidSeparator(position));
+        }
+    }
     
-    // Calculate precision based on longitude quantization and latitude
-    let lon_precision = 360.0 / (1u64 << LON_BITS) as f64;
-    let lon_error_m = lon_precision * 111_320.0 * latitude.to_radians().cos();
+    Ok(clean)
+}
+
+/// Formats a 45-bit payload as a 9-character Grid9 code, allocating once.
+#[cfg(feature = "alloc")]
+pub(crate) fn format_packed(packed: u64, human_readable: bool) -> String {
+    let mut result = String::with_capacity(11);
+    write_packed(packed, human_readable, &mut result);
+    result
+}
+
+/// Appends a 45-bit payload as a Grid9 code to `out`, reusing its capacity.
+#[cfg(feature = "alloc")]
+pub(crate) fn write_packed(packed: u64, human_readable: bool, out: &mut String) {
+    let chars = packed_chars(packed);
+    if !human_readable {
+        out.push_str(ascii_str(&chars));
+        return;
+    }
+
+    let mut formatted = [b'-'; 11];
+    formatted[..3].copy_from_slice(&chars[..3]);
+    formatted[4..7].copy_from_slice(&chars[3..6]);
+    formatted[8..].copy_from_slice(&chars[6..]);
+    out.push_str(ascii_str(&formatted));
+}
+
+/// Views alphabet characters as a string slice.
+pub(crate) fn ascii_str(chars: &[u8]) -> &str {
+    core::str::from_utf8(chars).expect("alphabet is ASCII")
+}
+
+/// Spells a 45-bit payload as its nine alphabet characters, without allocating.
+pub(crate) fn packed_chars(packed: u64) -> [u8; 9] {
+    let mut chars = [0u8; 9];
+    let mut temp = packed;
     
-    // Total error is the diagonal of the error rectangle
-    let total_error_m = (lat_error_m * lat_error_m + lon_error_m * lon_error_m).sqrt();
+    for slot in chars.iter_mut().rev() {
+        *slot = BASE32_ALPHABET[(temp & 0x1F) as usize];
+        temp >>= 5;
+    }
     
-    Ok(PrecisionInfo {
-        lat_error_m,
-        lon_error_m,
-        total_error_m,
-    })
+    chars
+}
+
+/// Removes dashes, accepting text copied from chat apps and PDFs:
+/// surrounding whitespace is trimmed, full-width forms read as their ASCII
+/// equivalents, Unicode dash variants act as dashes and zero-width
+/// characters are ignored.
+#[cfg(feature = "std")]
+pub(crate) fn canonicalize(encoded: &str) -> String {
+    encoded
+        .trim()
+        .chars()
+        .filter_map(normalize_unicode)
+        .filter(|&ch| ch != '-')
+        .collect()
 }
 
-fn validate_coordinates(latitude: f64, longitude: f64) -> Result<()> {
+/// Maps a character to its ASCII reading, or `None` if it should be ignored.
+pub(crate) fn normalize_unicode(ch: char) -> Option<char> {
+    match ch {
+        // Full-width ASCII variants (U+FF01..U+FF5E)
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(ch as u32 - 0xFEE0),
+        // Hyphens, dashes and minus signs
+        '\u{2010}'..='\u{2015}' | '\u{2212}' | '\u{FE58}' | '\u{FE63}' => Some('-'),
+        // Soft hyphen, zero-width spaces/joiners and byte order mark
+        '\u{00AD}' | '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' => None,
+        _ => Some(ch),
+    }
+}
+
+pub(crate) fn validate_coordinates(latitude: f64, longitude: f64) -> Result<()> {
     if !(-90.0..=90.0).contains(&latitude) {
         return Err(Grid9Error::InvalidLatitude(latitude));
     }
@@ -247,6 +679,20 @@ fn validate_coordinates(latitude: f64, longitude: f64) -> Result<()> {
     Ok(())
 }
 
+/// Returns true if the character is part of the canonical Grid9 alphabet.
+#[cfg(feature = "alloc")]
+pub(crate) fn is_alphabet_char(ch: char) -> bool {
+    digit_value(ch).is_some()
+}
+
+/// Value of a canonical code character
+pub(crate) fn digit_value(ch: char) -> Option<u8> {
+    let value = DIGIT_VALUES[u8::try_from(ch).ok()? as usize];
+    (value != INVALID_DIGIT).then_some(value)
+}
+
+
+#[cfg(feature = "alloc")]
 fn validate_encoded_string(encoded: &str) -> Result<()> {
     if encoded.is_empty() {
         return Err(Grid9Error::EmptyInput);
@@ -257,7 +703,7 @@ fn validate_encoded_string(encoded: &str) -> Result<()> {
     }
     
     for ch in encoded.chars() {
-        if !BASE32_ALPHABET.iter().any(|&b| b as char == ch) {
+        if !is_alphabet_char(ch) {
             return Err(Grid9Error::InvalidCharacter(ch));
         }
     }
@@ -265,7 +711,8 @@ fn validate_encoded_string(encoded: &str) -> Result<()> {
     Ok(())
 }
 
-fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+#[cfg(feature = "std")]
+pub(crate) fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     let d_lat = (lat2 - lat1).to_radians();
     let d_lon = (lon2 - lon1).to_radians();
     
@@ -278,10 +725,97 @@ fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
     EARTH_RADIUS_M * c
 }
 
+/// Unit vector (x towards 0°N 0°E, z towards the north pole) of a point.
+#[cfg(feature = "std")]
+pub(crate) fn unit_vector(lat: f64, lon: f64) -> [f64; 3] {
+    let (lat, lon) = (lat.to_radians(), lon.to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+/// Latitude and longitude of a (not necessarily unit length) vector.
+#[cfg(feature = "std")]
+pub(crate) fn from_vector([x, y, z]: [f64; 3]) -> (f64, f64) {
+    (z.atan2(x.hypot(y)).to_degrees(), y.atan2(x).to_degrees())
+}
+
+/// Initial great-circle bearing in degrees clockwise from north, in `[0, 360)`.
+#[cfg(feature = "std")]
+pub(crate) fn initial_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let y = d_lon.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * d_lon.cos();
+
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// Point reached by travelling `distance_m` meters from a start point along
+/// the great circle with the given initial bearing (degrees clockwise from north).
+#[cfg(any(test, feature = "rand", feature = "landmask"))]
+pub(crate) fn destination_point(lat: f64, lon: f64, bearing_deg: f64, distance_m: f64) -> (f64, f64) {
+    let delta = distance_m / EARTH_RADIUS_M;
+    let theta = bearing_deg.to_radians();
+    let phi1 = lat.to_radians();
+    let lambda1 = lon.to_radians();
+    
+    let sin_phi2 = phi1.sin() * delta.cos() + phi1.cos() * delta.sin() * theta.cos();
+    let phi2 = sin_phi2.clamp(-1.0, 1.0).asin();
+    let lambda2 = lambda1 + (theta.sin() * delta.sin() * phi1.cos())
+        .atan2(delta.cos() - phi1.sin() * sin_phi2);
+    
+    // Wrap longitude back into [-180, 180]
+    let lon2 = (lambda2.to_degrees() + 540.0).rem_euclid(360.0) - 180.0;
+    
+    (phi2.to_degrees().clamp(-90.0, 90.0), lon2)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_formatting_matches_encode_ascii() {
+        for (lat, lon) in [(-90.0, -180.0), (40.7128, -74.0060), (0.0, 0.0), (90.0, 180.0)] {
+            let ascii = encode_ascii(lat, lon).unwrap();
+            let compact = encode(lat, lon, false).unwrap();
+            assert_eq!(compact.as_bytes(), ascii);
+            assert_eq!(encode(lat, lon, true).unwrap(), format_for_humans(&compact));
+        }
+        assert_eq!(encode_ascii(91.0, 0.0), Err(Grid9Error::InvalidLatitude(91.0)));
+    }
+
+    #[test]
+    fn test_normalize_coordinates() {
+        let cases = [
+            ((10.0, 190.0), (10.0, -170.0)),
+            ((10.0, -190.0), (10.0, 170.0)),
+            ((10.0, 720.0), (10.0, 0.0)),
+            ((10.0, 540.0), (10.0, -180.0)),
+            ((10.0, 180.0), (10.0, 180.0)),
+            ((-10.0, -180.0), (-10.0, -180.0)),
+        ];
+        for ((lat, lon), expected) in cases {
+            assert_eq!(normalize_coordinates(lat, lon, false).unwrap(), expected, "{}", lon);
+        }
+        assert_eq!(normalize_coordinates(-95.0, 0.0, true), Ok((-90.0, 0.0)));
+        assert_eq!(normalize_coordinates(-95.0, 0.0, false), Err(Grid9Error::InvalidLatitude(-95.0)));
+        assert!(normalize_coordinates(f64::INFINITY, 0.0, true).is_err());
+        assert!(normalize_coordinates(0.0, f64::NAN, true).is_err());
+        assert_eq!(normalize_with(0.0, 190.0, false, true), Err(Grid9Error::InvalidLongitude(190.0)));
+    }
+
+    #[test]
+    fn test_digit_tables() {
+        for (value, &b) in BASE32_ALPHABET.iter().enumerate() {
+            assert_eq!(digit_value(b as char), Some(value as u8));
+        }
+        for ch in ['I', 'L', 'O', 'U', 'q', '-', ' ', '\0', 'é', '０'] {
+            assert_eq!(digit_value(ch), None, "{:?}", ch);
+        }
+        assert_eq!(DIGIT_VALUES.iter().filter(|&&v| v != INVALID_DIGIT).count(), 32);
+    }
+
     #[test]
     fn test_encode_decode_roundtrip() {
         let test_coords = [
@@ -304,6 +838,71 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_decode_bounds() {
+        for (lat, lon) in [(40.7128, -74.0060), (-33.8688, 151.2093), (0.0, 0.0), (-90.0, -180.0)] {
+            let code = encode(lat, lon, false).unwrap();
+            let cell = decode_bounds(&code).unwrap();
+            assert_eq!((cell.min_lat, cell.min_lon), decode(&code).unwrap());
+            assert!(cell.contains(lat, lon), "{} {}", lat, lon);
+            assert!(cell.max_lat - cell.min_lat > 4.0e-5 && cell.max_lon - cell.min_lon > 4.0e-5);
+            // The cell's far corner belongs to the next cells north and east
+            let (north, east) = (cell.max_lat + 1e-9, cell.max_lon + 1e-9);
+            assert_ne!(encode(north, lon, false).unwrap(), code);
+            assert_ne!(encode(lat, east, false).unwrap(), code);
+        }
+        assert_eq!(decode_bounds("Q7K-H2B-BYF"), decode_bounds("Q7KH2BBYF"));
+        let top = decode_bounds(&encode(90.0, 180.0, false).unwrap()).unwrap();
+        assert_eq!(top, BoundingBox::new(90.0, 90.0, 180.0, 180.0));
+        assert!(decode_bounds("Q7KH2BBY").is_err());
+    }
+
+    #[test]
+    fn test_round_trip_within_half_a_cell() {
+        let half_lat = 90.0 / crate::MAX_LAT_INDEX as f64 + 1e-12;
+        let half_lon = 180.0 / crate::MAX_LON_INDEX as f64 + 1e-12;
+        let mut worst_corner: f64 = 0.0;
+        for i in 0..=400 {
+            for j in 0..=200 {
+                // Irregular offsets so positions fall all over their cells
+                let lat = (-90.0 + i as f64 * 0.45 + (i * j) as f64 * 1.3e-7).min(90.0);
+                let lon = (-180.0 + j as f64 * 1.8 + (i * 7 + j) as f64 * 3.1e-7).min(180.0);
+
+                let (center_lat, center_lon) = decode_center(&encode(lat, lon, false).unwrap()).unwrap();
+                assert!((center_lat - lat).abs() <= half_lat && (center_lon - lon).abs() <= half_lon, "{} {}", lat, lon);
+                let (near_lat, near_lon) = decode(&encode_nearest(lat, lon, false).unwrap()).unwrap();
+                assert!((near_lat - lat).abs() <= half_lat && (near_lon - lon).abs() <= half_lon, "{} {}", lat, lon);
+
+                let (corner_lat, _) = decode(&encode(lat, lon, false).unwrap()).unwrap();
+                worst_corner = worst_corner.max(lat - corner_lat);
+            }
+        }
+        // The south-west corner is up to a whole cell away
+        assert!(worst_corner > 1.5 * half_lat);
+
+        assert_eq!(decode_center("Q7K-H2B-BYF"), decode_center("Q7KH2BBYF"));
+        assert_eq!(decode_center(&encode(90.0, 180.0, false).unwrap()).unwrap(), (90.0, 180.0));
+        assert_eq!(encode_nearest(90.0, 180.0, false), encode(90.0, 180.0, false));
+        assert!(encode_nearest(91.0, 0.0, false).is_err());
+        assert!(decode_center("Q7KH2BBY").is_err());
+    }
+
+    #[test]
+    fn test_u64_round_trip() {
+        for (lat, lon) in [(40.7128, -74.0060), (-90.0, -180.0), (90.0, 180.0), (0.0, 0.0)] {
+            let code = encode(lat, lon, false).unwrap();
+            let packed = encode_to_u64(lat, lon).unwrap();
+            assert!(packed < 1 << 45);
+            assert_eq!(packed, parse_packed(&code).unwrap());
+            assert_eq!(decode_from_u64(packed).unwrap(), decode(&code).unwrap());
+        }
+        assert_eq!(encode_to_u64(-90.0, -180.0), Ok(0));
+        assert_eq!(encode_to_u64(90.0, 180.0), Ok((1 << 45) - 1));
+        assert_eq!(decode_from_u64(1 << 45), Err(Grid9Error::InvalidIndex(1 << 22)));
+        assert_eq!(decode_from_u64(u64::MAX), Err(Grid9Error::InvalidIndex(u32::MAX)));
+        assert!(encode_to_u64(0.0, 180.5).is_err());
+    }
+
     #[test]
     fn test_human_readable_formatting() {
         let encoded = encode(40.7128, -74.0060, false).unwrap();
@@ -331,6 +930,24 @@ mod tests {
         assert!(distance > 5_500_000.0 && distance < 5_600_000.0);
     }
 
+    #[test]
+    fn test_packed_chars() {
+        let packed = parse_packed("Q7KH2BBYF").unwrap();
+        assert_eq!(&packed_chars(packed), b"Q7KH2BBYF");
+        assert_eq!(&packed_chars(0), b"000000000");
+        assert_eq!(format_packed(packed, true), "Q7K-H2B-BYF");
+    }
+
+    #[test]
+    fn test_bearing_calculation() {
+        let origin = encode(0.0, 0.0, false).unwrap();
+        for (lat, lon, expected) in [(1.0, 0.0, 0.0), (0.0, 1.0, 90.0), (-1.0, 0.0, 180.0), (0.0, -1.0, 270.0)] {
+            let bearing = calculate_bearing(&origin, &encode(lat, lon, false).unwrap()).unwrap();
+            assert!((bearing - expected).abs() < 0.01, "{} vs {}", bearing, expected);
+        }
+        assert!(calculate_bearing(&origin, "INVALID").is_err());
+    }
+
     #[test]
     fn test_precision_info() {
         let precision = get_actual_precision(40.7128, -74.0060).unwrap();
@@ -338,7 +955,8 @@ mod tests {
         assert!(precision.lat_error_m > 0.0);
         assert!(precision.lon_error_m > 0.0);
         assert!(precision.total_error_m > 0.0);
-        assert!(precision.total_error_m < 5.0); // Should be under 5 meters
+        assert!(precision.lat_error_m < 5.0 && precision.lon_error_m < 5.0); // One cell: under 5 meters a side
+        assert!(precision.total_error_m < 7.0); // Cell diagonal
     }
 
     #[test]
@@ -354,6 +972,54 @@ mod tests {
         assert!(!is_valid_encoding("Q7KH2BBY")); // Too short
     }
 
+    #[test]
+    fn test_unicode_normalization() {
+        let expected = decode("Q7KH2BBYF").unwrap();
+        
+        let inputs = [
+            "\u{FF31}\u{FF17}\u{FF2B}\u{FF28}\u{FF12}\u{FF22}\u{FF22}\u{FF39}\u{FF26}", // Full-width
+            "Q7K\u{2011}H2B\u{2011}BYF", // Non-breaking hyphens
+            "Q7K\u{2013}H2B\u{2014}BYF", // En and em dashes
+            "Q7K\u{2212}H2B\u{FF0D}BYF", // Minus sign and full-width hyphen
+            "\u{FEFF}Q7KH2B\u{200B}BYF\u{00A0}", // BOM, zero-width space, NBSP
+            "  Q7K-H2B-BYF\n",
+        ];
+        
+        for input in inputs {
+            assert_eq!(decode(input).unwrap(), expected, "failed for {:?}", input);
+            assert!(is_valid_encoding(input));
+        }
+        
+        // Strict mode does not normalize
+        assert!(!is_valid_encoding_with_mode("Q7K\u{2011}H2B\u{2011}BYF", ParseMode::Strict));
+        
+        // Characters outside the alphabet are reported as typed
+        assert_eq!(decode("Q7KH2BBY\u{00E9}"), Err(Grid9Error::InvalidCharacter('\u{00E9}')));
+        assert_eq!(decode("Q7KH2BBYu"), Err(Grid9Error::InvalidCharacter('u')));
+        assert_eq!(decode("Q7KH2BByf"), Err(Grid9Error::InvalidCharacter('y')));
+        assert_eq!(decode("Q7KH2BBYO"), Err(Grid9Error::InvalidCharacter('O')));
+        assert_eq!(decode("Q7KH2BBY\u{FF35}"), Err(Grid9Error::InvalidCharacter('\u{FF35}')));
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        assert!(is_valid_encoding_with_mode("Q7KH2BBYF", ParseMode::Strict));
+        assert!(is_valid_encoding_with_mode("Q7K-H2B-BYF", ParseMode::Strict));
+        
+        assert_eq!(decode_with_mode("Q-7KH2BB-YF", ParseMode::Strict), Err(Grid9Error::InvalidSeparator(1)));
+        assert_eq!(decode_with_mode("Q7K-H2BBYF", ParseMode::Strict), Err(Grid9Error::InvalidSeparator(3)));
+        assert_eq!(decode_with_mode("Q7KH2BBYF-", ParseMode::Strict), Err(Grid9Error::InvalidSeparator(9)));
+        assert_eq!(decode_with_mode("q7kh2bbyf", ParseMode::Strict), Err(Grid9Error::InvalidCharacter('q')));
+        assert_eq!(decode_with_mode("Q7KH2BBYO", ParseMode::Strict), Err(Grid9Error::InvalidCharacter('O')));
+        assert_eq!(decode_with_mode("", ParseMode::Strict), Err(Grid9Error::EmptyInput));
+        
+        // Strict and lenient agree on canonical input
+        assert_eq!(
+            decode_with_mode("Q7K-H2B-BYF", ParseMode::Strict).unwrap(),
+            decode_with_mode("Q7K-H2B-BYF", ParseMode::Lenient).unwrap()
+        );
+    }
+
     #[test]
     fn test_edge_cases() {
         // Test boundary coordinates
@@ -384,6 +1050,7 @@ mod tests {
         // Invalid encoded strings
         assert!(matches!(decode(""), Err(Grid9Error::EmptyInput)));
         assert!(matches!(decode("TOOLONG"), Err(Grid9Error::InvalidLength(_))));
-        assert!(matches!(decode("INVALID!"), Err(Grid9Error::InvalidCharacter(_))));
+        assert!(matches!(decode("INVALID!!"), Err(Grid9Error::InvalidCharacter(_))));
+        assert_eq!(decode("Q7KH2BBY!!"), Err(Grid9Error::InvalidLength(10)));
     }
 }
\ No newline at end of file
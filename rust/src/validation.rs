@@ -0,0 +1,188 @@
+//! Detailed validation reports for user-facing input checks
+
+use crate::version::split_version_tag;
+use crate::{Grid9Decoder, ParseMode, FORMAT_VERSION};
+
+/// A single problem found in a candidate Grid9 code
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ValidationIssue {
+    /// The input is empty
+    Empty,
+    /// Wrong number of code characters (separators excluded); 9 are required
+    InvalidLength { length: usize },
+    /// Character outside the Grid9 alphabet at the given 0-based character index
+    InvalidCharacter { position: usize, character: char },
+    /// Dash at a position other than the XXX-XXX-XXX separator positions
+    MisplacedSeparator { position: usize },
+    /// Check character does not match the code (decoders with checksums only)
+    ChecksumMismatch,
+    /// Code is tagged with a format version this library cannot read
+    UnsupportedVersion { version: u8 },
+}
+
+/// Every problem found in a candidate Grid9 code
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ValidationReport {
+    /// Problems in order of their position in the input
+    pub issues: Vec<ValidationIssue>,
+    /// Canonical compact code, if the input is accepted by lenient decoding
+    pub suggestion: Option<String>,
+}
+
+impl ValidationReport {
+    /// True if the input is a canonical Grid9 code
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Validates a candidate Grid9 code and reports every problem found.
+///
+/// The input is checked against the canonical `XXXXXXXXX` / `XXX-XXX-XXX`
+/// forms (the rules of `ParseMode::Strict`). Positions are 0-based character
+/// indices into the original input, so UIs can highlight exactly what to fix.
+/// When lenient decoding would still accept the input, the report carries the
+/// canonical code as a suggestion.
+///
+/// # Arguments
+/// * `encoded` - Candidate Grid9 code
+///
+/// # Returns
+/// A report listing all problems (empty if the code is valid)
+///
+/// # Example
+/// ```rust
+/// use grid9::{validate_detailed, ValidationIssue};
+///
+/// let report = validate_detailed("q7K-H2B-BY!");
+/// assert_eq!(report.issues, vec![
+///     ValidationIssue::InvalidCharacter { position: 0, character: 'q' },
+///     ValidationIssue::InvalidCharacter { position: 10, character: '!' },
+/// ]);
+/// assert!(validate_detailed("Q7K-H2B-BYF").is_valid());
+/// ```
+pub fn validate_detailed(encoded: &str) -> ValidationReport {
+    validate_with(encoded, &Grid9Decoder::default())
+}
+
+/// Detailed validation against a decoder's alphabet and checksum setting
+pub(crate) fn validate_with(encoded: &str, decoder: &Grid9Decoder) -> ValidationReport {
+    let mut report = ValidationReport {
+        issues: Vec::new(),
+        suggestion: decoder
+            .parse_packed_with_mode(encoded, ParseMode::Lenient)
+            .ok()
+            .map(|packed| decoder.canonical(packed)),
+    };
+
+    if encoded.is_empty() {
+        report.issues.push(ValidationIssue::Empty);
+        return report;
+    }
+
+    // Skip a version tag; positions stay relative to the full input
+    let mut offset = 0;
+    let mut body = encoded;
+    if decoder.reads_version_tags() {
+        if let Ok(Some((version, rest))) = split_version_tag(encoded) {
+            if version != FORMAT_VERSION {
+                report.issues.push(ValidationIssue::UnsupportedVersion { version });
+                return report;
+            }
+            offset = 2;
+            body = rest;
+        }
+    }
+
+    let chars: Vec<char> = body.chars().collect();
+    let separators: &[usize] = if decoder.checksum() { &[3, 7, 11] } else { &[3, 7] };
+    let code_length = if decoder.checksum() { 10 } else { 9 };
+    let formatted = chars.len() == code_length + separators.len()
+        && separators.iter().all(|&position| chars[position] == '-');
+
+    let mut length = 0;
+    for (index, &character) in chars.iter().enumerate() {
+        let position = offset + index;
+        if character == '-' {
+            if !(formatted && separators.contains(&index)) {
+                report.issues.push(ValidationIssue::MisplacedSeparator { position });
+            }
+            continue;
+        }
+
+        length += 1;
+        if decoder.alphabet().value(character).is_none() {
+            report.issues.push(ValidationIssue::InvalidCharacter { position, character });
+        }
+    }
+
+    if length != code_length {
+        report.issues.push(ValidationIssue::InvalidLength { length });
+    }
+
+    if report.issues.is_empty() && decoder.parse_packed_with_mode(encoded, ParseMode::Strict).is_err() {
+        // Well-formed, so the only remaining failure is the check character
+        report.issues.push(ValidationIssue::ChecksumMismatch);
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_codes() {
+        for code in ["Q7KH2BBYF", "Q7K-H2B-BYF"] {
+            let report = validate_detailed(code);
+            assert!(report.is_valid());
+            assert_eq!(report.suggestion.as_deref(), Some("Q7KH2BBYF"));
+        }
+    }
+
+    #[test]
+    fn test_reports_every_issue() {
+        let report = validate_detailed("Q-7KH2B!-YF-");
+
+        assert_eq!(report.issues, vec![
+            ValidationIssue::MisplacedSeparator { position: 1 },
+            ValidationIssue::InvalidCharacter { position: 7, character: '!' },
+            ValidationIssue::MisplacedSeparator { position: 8 },
+            ValidationIssue::MisplacedSeparator { position: 11 },
+        ]);
+        assert_eq!(report.suggestion, None);
+    }
+
+    #[test]
+    fn test_sloppy_input_gets_suggestion() {
+        let report = validate_detailed("Q-7KH2BB-YF");
+
+        assert!(!report.is_valid());
+        assert_eq!(report.suggestion.as_deref(), Some("Q7KH2BBYF"));
+    }
+
+    #[test]
+    fn test_length_and_empty() {
+        assert_eq!(validate_detailed("").issues, vec![ValidationIssue::Empty]);
+        assert_eq!(
+            validate_detailed("Q7KH2BB").issues,
+            vec![ValidationIssue::InvalidLength { length: 7 }]
+        );
+    }
+
+    #[test]
+    fn test_version_tags() {
+        assert!(validate_detailed("U1Q7K-H2B-BYF").is_valid());
+        assert_eq!(
+            validate_detailed("U1Q7KH2B!YF").issues,
+            vec![ValidationIssue::InvalidCharacter { position: 8, character: '!' }]
+        );
+        assert_eq!(
+            validate_detailed("U3Q7KH2BBYF").issues,
+            vec![ValidationIssue::UnsupportedVersion { version: 3 }]
+        );
+    }
+}
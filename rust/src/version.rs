@@ -0,0 +1,109 @@
+//! Format version tags for long-lived codes
+//!
+//! Untagged 9-character codes are implicitly [`FORMAT_VERSION`] 1. A code may
+//! also carry an explicit tag: the reserved prefix [`VERSION_PREFIX`] (`U`,
+//! which is not part of the Grid9 alphabet) followed by one alphabet
+//! character holding the version number, e.g. `U1Q7KH2BBYF`. Future
+//! revisions of the format will always be tagged, so decoders that predate
+//! them reject such codes with [`Grid9Error::UnsupportedVersion`] instead of
+//! misreading them.
+
+use crate::uniform_precision_compressor::{digit_value, parse_packed};
+use crate::{Grid9Error, ParseMode, Result};
+
+/// Format version written and read by this library
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Reserved first character of a version tag
+pub const VERSION_PREFIX: char = 'U';
+
+/// Returns the format version of a code.
+///
+/// Untagged codes are version 1. Codes tagged with a version this library
+/// does not understand are not validated further; their version is returned
+/// so callers can route them to a newer decoder.
+///
+/// # Arguments
+/// * `encoded` - Grid9 code, optionally prefixed with a version tag
+///
+/// # Returns
+/// The format version number
+///
+/// # Example
+/// ```rust
+/// use grid9::{format_version, FORMAT_VERSION};
+///
+/// assert_eq!(format_version("Q7KH2BBYF")?, FORMAT_VERSION);
+/// assert_eq!(format_version("U1Q7KH2BBYF")?, 1);
+/// assert_eq!(format_version("U2ANYTHING")?, 2);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn format_version(encoded: &str) -> Result<u8> {
+    match split_version_tag(encoded.trim_start())? {
+        Some((version, _)) if version != FORMAT_VERSION => Ok(version),
+        _ => parse_packed(encoded).map(|_| FORMAT_VERSION),
+    }
+}
+
+/// Splits a leading version tag off `encoded`, returning the tagged version
+/// and the remaining text, or `None` if the code is untagged.
+pub(crate) fn split_version_tag(encoded: &str) -> Result<Option<(u8, &str)>> {
+    let mut chars = encoded.chars();
+    if chars.next() != Some(VERSION_PREFIX) {
+        return Ok(None);
+    }
+
+    let tag = chars.next().ok_or(Grid9Error::InvalidLength(0))?;
+    let version = digit_value(tag).ok_or(Grid9Error::InvalidCharacter(tag))?;
+
+    Ok(Some((version, chars.as_str())))
+}
+
+/// Removes a supported version tag, rejecting tags of other versions.
+pub(crate) fn strip_version_tag(encoded: &str, mode: ParseMode) -> Result<&str> {
+    let start = match mode {
+        ParseMode::Lenient => encoded.trim_start(),
+        ParseMode::Strict => encoded,
+    };
+
+    match split_version_tag(start)? {
+        Some((FORMAT_VERSION, rest)) => Ok(rest),
+        Some((version, _)) => Err(Grid9Error::UnsupportedVersion(version)),
+        None => Ok(encoded),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decode, decode_with_mode, encode};
+
+    #[test]
+    fn test_tagged_codes_decode_like_untagged() {
+        let code = encode(40.7128, -74.0060, false).unwrap();
+        let expected = decode(&code).unwrap();
+
+        for tagged in [format!("U1{}", code), format!("U1{}", encode(40.7128, -74.0060, true).unwrap()), format!(" U1{}", code)] {
+            assert_eq!(decode(&tagged).unwrap(), expected);
+            assert_eq!(format_version(&tagged).unwrap(), 1);
+        }
+        assert_eq!(decode_with_mode(&format!("U1{}", code), ParseMode::Strict).unwrap(), expected);
+        assert!(decode_with_mode(&format!("u1{}", code), ParseMode::Strict).is_err());
+        assert!(decode(&format!("u1{}", code)).is_err());
+    }
+
+    #[test]
+    fn test_future_versions_are_rejected() {
+        assert_eq!(decode("U2Q7KH2BBYF"), Err(Grid9Error::UnsupportedVersion(2)));
+        assert_eq!(decode("U0Q7KH2BBYF"), Err(Grid9Error::UnsupportedVersion(0)));
+        assert_eq!(format_version("UZ-WHATEVER-COMES-NEXT").unwrap(), 31);
+    }
+
+    #[test]
+    fn test_malformed_tags() {
+        assert_eq!(format_version("U"), Err(Grid9Error::InvalidLength(0)));
+        assert_eq!(format_version("U!Q7KH2BBYF"), Err(Grid9Error::InvalidCharacter('!')));
+        assert_eq!(format_version("U1BAD"), Err(Grid9Error::InvalidLength(3)));
+        assert_eq!(format_version("Q7KH2BBYU"), Err(Grid9Error::InvalidCharacter('U')));
+    }
+}
@@ -0,0 +1,312 @@
+//! Well-Known Binary geometries
+//!
+//! Reads and writes the WKB points, LineStrings and Polygons that database
+//! drivers return for geometry columns, so they can be encoded without a
+//! round trip through WKT text. Both ISO WKB and PostGIS EWKB (with SRID,
+//! Z or M flags) are read; extra ordinates and the SRID are dropped.
+//! Output is little-endian 2D ISO WKB.
+
+use crate::{decode, encode, Coordinate, Grid9Bucket, Grid9Error, Grid9Region, Result};
+
+const POINT: u32 = 1;
+const LINE_STRING: u32 = 2;
+const POLYGON: u32 = 3;
+
+const EWKB_Z: u32 = 0x8000_0000;
+const EWKB_M: u32 = 0x4000_0000;
+const EWKB_SRID: u32 = 0x2000_0000;
+
+/// A geometry read from or written as WKB
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WkbGeometry {
+    /// A single position
+    Point(Coordinate),
+    /// A polyline
+    LineString(Vec<Coordinate>),
+    /// Outer ring followed by any holes, each closed
+    Polygon(Vec<Vec<Coordinate>>),
+}
+
+impl WkbGeometry {
+    /// Parses a WKB or EWKB blob.
+    ///
+    /// # Example
+    /// ```rust
+    /// use grid9::{Coordinate, WkbGeometry};
+    ///
+    /// let point = WkbGeometry::Point(Coordinate::new(40.7128, -74.0060));
+    /// assert_eq!(WkbGeometry::from_wkb(&point.to_wkb())?, point);
+    /// # Ok::<(), grid9::Grid9Error>(())
+    /// ```
+    pub fn from_wkb(wkb: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes: wkb, little_endian: true };
+        let geometry = reader.geometry()?;
+        if !reader.bytes.is_empty() {
+            return Err(invalid("trailing bytes after WKB geometry"));
+        }
+        Ok(geometry)
+    }
+
+    /// Serializes as little-endian 2D ISO WKB
+    pub fn to_wkb(&self) -> Vec<u8> {
+        let mut wkb = Vec::new();
+        wkb.push(1);
+        match self {
+            WkbGeometry::Point(point) => {
+                wkb.extend_from_slice(&POINT.to_le_bytes());
+                write_position(&mut wkb, point);
+            }
+            WkbGeometry::LineString(points) => {
+                wkb.extend_from_slice(&LINE_STRING.to_le_bytes());
+                write_positions(&mut wkb, points);
+            }
+            WkbGeometry::Polygon(rings) => {
+                wkb.extend_from_slice(&POLYGON.to_le_bytes());
+                wkb.extend_from_slice(&(rings.len() as u32).to_le_bytes());
+                for ring in rings {
+                    write_positions(&mut wkb, ring);
+                }
+            }
+        }
+        wkb
+    }
+}
+
+impl From<&Grid9Bucket> for WkbGeometry {
+    /// The bucket's outline as a closed polygon ring
+    fn from(bucket: &Grid9Bucket) -> Self {
+        let bounds = bucket.bounds();
+        WkbGeometry::Polygon(vec![vec![
+            Coordinate::new(bounds.min_lat, bounds.min_lon),
+            Coordinate::new(bounds.min_lat, bounds.max_lon),
+            Coordinate::new(bounds.max_lat, bounds.max_lon),
+            Coordinate::new(bounds.max_lat, bounds.min_lon),
+            Coordinate::new(bounds.min_lat, bounds.min_lon),
+        ]])
+    }
+}
+
+/// Encodes a WKB point.
+///
+/// # Arguments
+/// * `wkb` - WKB or EWKB point in longitude/latitude order (SRID 4326)
+/// * `human_readable` - Whether to format the code with dashes
+///
+/// # Example
+/// ```rust
+/// use grid9::{encode, encode_wkb_point, Coordinate, WkbGeometry};
+///
+/// let wkb = WkbGeometry::Point(Coordinate::new(40.7128, -74.0060)).to_wkb();
+/// assert_eq!(encode_wkb_point(&wkb, false)?, encode(40.7128, -74.0060, false)?);
+/// # Ok::<(), grid9::Grid9Error>(())
+/// ```
+pub fn encode_wkb_point(wkb: &[u8], human_readable: bool) -> Result<String> {
+    match WkbGeometry::from_wkb(wkb)? {
+        WkbGeometry::Point(point) => encode(point.lat, point.lon, human_readable),
+        _ => Err(invalid("expected a WKB point")),
+    }
+}
+
+/// Encodes a batch of WKB points, failing on the first invalid blob.
+pub fn batch_encode_wkb_points<B: AsRef<[u8]>>(wkbs: &[B], human_readable: bool) -> Result<Vec<String>> {
+    wkbs.iter().map(|wkb| encode_wkb_point(wkb.as_ref(), human_readable)).collect()
+}
+
+/// Decodes a Grid9 string to a WKB point
+pub fn decode_to_wkb(encoded: &str) -> Result<Vec<u8>> {
+    let (lat, lon) = decode(encoded)?;
+    Ok(WkbGeometry::Point(Coordinate::new(lat, lon)).to_wkb())
+}
+
+/// Buckets of `level` covered by a WKB polygon, leaving out any holes; see
+/// [`Grid9Region::from_polygon_with_holes`].
+pub fn wkb_region(wkb: &[u8], level: u8) -> Result<Grid9Region> {
+    match WkbGeometry::from_wkb(wkb)? {
+        WkbGeometry::Polygon(rings) => Grid9Region::from_polygon_with_holes(&rings, level),
+        _ => Err(invalid("expected a WKB polygon")),
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        if self.bytes.len() < N {
+            return Err(invalid("truncated WKB"));
+        }
+        let (head, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+        Ok(head.try_into().expect("split at N"))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take::<4>()?;
+        Ok(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        let bytes = self.take::<8>()?;
+        Ok(if self.little_endian { f64::from_le_bytes(bytes) } else { f64::from_be_bytes(bytes) })
+    }
+
+    fn geometry(&mut self) -> Result<WkbGeometry> {
+        self.little_endian = match self.take::<1>()? {
+            [0] => false,
+            [1] => true,
+            [other] => return Err(Grid9Error::InvalidInput(format!("invalid WKB byte order {}", other).into())),
+        };
+
+        let raw = self.u32()?;
+        if raw & EWKB_SRID != 0 {
+            self.u32()?;
+        }
+        let flags = [raw & EWKB_Z != 0, raw & EWKB_M != 0];
+        let code = raw & 0x0fff_ffff;
+        let (iso_z, iso_m) = match code / 1000 {
+            0 => (false, false),
+            1 => (true, false),
+            2 => (false, true),
+            3 => (true, true),
+            _ => return Err(Grid9Error::InvalidInput(format!("unsupported WKB geometry type {}", raw).into())),
+        };
+        let dimensions = 2 + (flags[0] || iso_z) as usize + (flags[1] || iso_m) as usize;
+
+        match code % 1000 {
+            POINT => Ok(WkbGeometry::Point(self.position(dimensions)?)),
+            LINE_STRING => Ok(WkbGeometry::LineString(self.positions(dimensions)?)),
+            POLYGON => {
+                let count = self.count(4)?;
+                let rings = (0..count).map(|_| self.positions(dimensions)).collect::<Result<_>>()?;
+                Ok(WkbGeometry::Polygon(rings))
+            }
+            _ => Err(Grid9Error::InvalidInput(format!("unsupported WKB geometry type {}", raw).into())),
+        }
+    }
+
+    /// Reads an element count, checking that enough bytes remain for
+    /// elements of at least `min_size` bytes before anything is allocated.
+    fn count(&mut self, min_size: usize) -> Result<usize> {
+        let count = self.u32()? as usize;
+        if count.saturating_mul(min_size) > self.bytes.len() {
+            return Err(invalid("truncated WKB"));
+        }
+        Ok(count)
+    }
+
+    fn positions(&mut self, dimensions: usize) -> Result<Vec<Coordinate>> {
+        let count = self.count(dimensions * 8)?;
+        (0..count).map(|_| self.position(dimensions)).collect()
+    }
+
+    fn position(&mut self, dimensions: usize) -> Result<Coordinate> {
+        let lon = self.f64()?;
+        let lat = self.f64()?;
+        for _ in 2..dimensions {
+            self.f64()?;
+        }
+        Ok(Coordinate::new(lat, lon))
+    }
+}
+
+fn write_position(wkb: &mut Vec<u8>, point: &Coordinate) {
+    wkb.extend_from_slice(&point.lon.to_le_bytes());
+    wkb.extend_from_slice(&point.lat.to_le_bytes());
+}
+
+fn write_positions(wkb: &mut Vec<u8>, points: &[Coordinate]) {
+    wkb.extend_from_slice(&(points.len() as u32).to_le_bytes());
+    for point in points {
+        write_position(wkb, point);
+    }
+}
+
+fn invalid(message: &str) -> Grid9Error {
+    Grid9Error::InvalidInput(message.to_string().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(text: &str) -> Vec<u8> {
+        (0..text.len()).step_by(2).map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_known_encodings() {
+        // POINT(1 2), little- and big-endian
+        let little = hex("0101000000000000000000F03F0000000000000040");
+        let big = hex("00000000013FF00000000000004000000000000000");
+        let expected = WkbGeometry::Point(Coordinate::new(2.0, 1.0));
+        assert_eq!(WkbGeometry::from_wkb(&little).unwrap(), expected);
+        assert_eq!(WkbGeometry::from_wkb(&big).unwrap(), expected);
+        assert_eq!(expected.to_wkb(), little);
+
+        // SRID=4326;POINT(1 2 3) as PostGIS EWKB, and ISO POINT Z(1 2 3)
+        let ewkb = hex("01010000A0E6100000000000000000F03F00000000000000400000000000000840");
+        let iso_z = hex("01E9030000000000000000F03F00000000000000400000000000000840");
+        assert_eq!(WkbGeometry::from_wkb(&ewkb).unwrap(), expected);
+        assert_eq!(WkbGeometry::from_wkb(&iso_z).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_round_trips() {
+        let line = WkbGeometry::LineString(vec![Coordinate::new(51.5, -0.12), Coordinate::new(48.85, 2.35)]);
+        assert_eq!(WkbGeometry::from_wkb(&line.to_wkb()).unwrap(), line);
+
+        let bucket = Grid9Bucket::from_coordinates(10.0, 20.0, 10).unwrap();
+        let outline = WkbGeometry::from(&bucket);
+        assert_eq!(WkbGeometry::from_wkb(&outline.to_wkb()).unwrap(), outline);
+
+        let cell = encode(35.6762, 139.6503, false).unwrap();
+        assert_eq!(encode_wkb_point(&decode_to_wkb(&cell).unwrap(), false).unwrap(), cell);
+    }
+
+    #[test]
+    fn test_region_and_batches() {
+        let square = WkbGeometry::Polygon(vec![vec![
+            Coordinate::new(0.0, 0.0),
+            Coordinate::new(0.0, 1.0),
+            Coordinate::new(1.0, 1.0),
+            Coordinate::new(1.0, 0.0),
+            Coordinate::new(0.0, 0.0),
+        ]]);
+        let region = wkb_region(&square.to_wkb(), 10).unwrap();
+        assert!(region.contains(&encode(0.5, 0.5, false).unwrap()).unwrap());
+        let WkbGeometry::Polygon(mut rings) = square else { unreachable!() };
+        rings.push(rings[0].iter().map(|point| Coordinate::new(point.lat * 0.5 + 0.25, point.lon * 0.5 + 0.25)).collect());
+        let holed = wkb_region(&WkbGeometry::Polygon(rings).to_wkb(), 10).unwrap();
+        assert!(holed.contains(&encode(0.1, 0.1, false).unwrap()).unwrap());
+        assert!(!holed.contains(&encode(0.5, 0.5, false).unwrap()).unwrap());
+
+        let points: Vec<Vec<u8>> = [(1.0, 2.0), (3.0, 4.0)]
+            .iter()
+            .map(|&(lat, lon)| WkbGeometry::Point(Coordinate::new(lat, lon)).to_wkb())
+            .collect();
+        assert_eq!(
+            batch_encode_wkb_points(&points, true).unwrap(),
+            vec![encode(1.0, 2.0, true).unwrap(), encode(3.0, 4.0, true).unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        let point = WkbGeometry::Point(Coordinate::new(2.0, 1.0)).to_wkb();
+        assert!(WkbGeometry::from_wkb(&point[..point.len() - 1]).is_err());
+        assert!(WkbGeometry::from_wkb(&[point.as_slice(), &[0]].concat()).is_err());
+        assert!(WkbGeometry::from_wkb(&[]).is_err());
+        assert!(WkbGeometry::from_wkb(&hex("02010000000000")).is_err());
+        assert!(WkbGeometry::from_wkb(&hex("0107000000")).is_err()); // GeometryCollection
+        // A huge count must not allocate
+        assert!(WkbGeometry::from_wkb(&hex("0102000000FFFFFFFF")).is_err());
+
+        let line = WkbGeometry::LineString(vec![Coordinate::new(0.0, 0.0)]).to_wkb();
+        assert!(encode_wkb_point(&line, false).is_err());
+        assert!(wkb_region(&point, 0).is_err());
+        assert!(encode_wkb_point(&WkbGeometry::Point(Coordinate::new(95.0, 0.0)).to_wkb(), false).is_err());
+    }
+}